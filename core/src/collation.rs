@@ -0,0 +1,175 @@
+//! Bangla dictionary order, as in the Bangla Academy dictionary: vowels
+//! before consonants, a consonant's bare form before its vowel signs in
+//! vowel order, and conjuncts after all of those (কত, কাজ, কি, ক্ষমা).
+//! Code point order gets much of this wrong, e.g. it puts ং before অ.
+//!
+//! ```
+//! use restro_keyboard_core::collation;
+//!
+//! let mut words = vec!["ক্ষমা", "কাজ", "আম", "কথা"];
+//! words.sort_by(|a, b| collation::compare(a, b));
+//! assert_eq!(words, ["আম", "কথা", "কাজ", "ক্ষমা"]);
+//! ```
+
+use std::cmp::Ordering;
+
+// Independent vowels and the signs that stand for them after a consonant.
+// অ has no sign; it is the vowel a bare consonant is read with.
+const VOWELS: [(char, Option<char>); 11] = [
+    ('অ', None),
+    ('আ', Some('া')),
+    ('ই', Some('ি')),
+    ('ঈ', Some('ী')),
+    ('উ', Some('ু')),
+    ('ঊ', Some('ূ')),
+    ('ঋ', Some('ৃ')),
+    ('এ', Some('ে')),
+    ('ঐ', Some('ৈ')),
+    ('ও', Some('ো')),
+    ('ঔ', Some('ৌ')),
+];
+
+const CONSONANTS: [char; 32] = [
+    'ক', 'খ', 'গ', 'ঘ', 'ঙ', 'চ', 'ছ', 'জ', 'ঝ', 'ঞ', 'ট', 'ঠ', 'ড', 'ঢ', 'ণ', 'ত', 'থ', 'দ', 'ধ',
+    'ন', 'প', 'ফ', 'ব', 'ভ', 'ম', 'য', 'র', 'ল', 'শ', 'ষ', 'স', 'হ',
+];
+
+const HASANT: char = '্';
+const NUKTA: char = '\u{09BC}';
+const KHANDA_TA: char = 'ৎ';
+const CHANDRABINDU: char = 'ঁ';
+
+// Weights: digits, then vowels, ং and ঃ, hasant, consonants, anything else
+const DIGIT_WEIGHT: u32 = 0;
+const VOWEL_WEIGHT: u32 = 10;
+const ANUSVARA_WEIGHT: u32 = VOWEL_WEIGHT + VOWELS.len() as u32;
+const VISARGA_WEIGHT: u32 = ANUSVARA_WEIGHT + 1;
+const HASANT_WEIGHT: u32 = VISARGA_WEIGHT + 1;
+const CONSONANT_WEIGHT: u32 = HASANT_WEIGHT + 1;
+const OTHER_WEIGHT: u32 = 0x1000;
+
+// Each consonant takes two places, the second for its nukta form
+// (ড then ড়), so ড় sorts right after ড as in dictionaries.
+fn consonant_weight(c: char) -> Option<u32> {
+    let (base, nukta) = match c {
+        '\u{09DC}' => ('ড', true),
+        '\u{09DD}' => ('ঢ', true),
+        '\u{09DF}' => ('য', true),
+        c => (c, false),
+    };
+    let index = CONSONANTS.iter().position(|&k| k == base)?;
+    Some(CONSONANT_WEIGHT + 2 * index as u32 + u32::from(nukta))
+}
+
+fn vowel_weight(c: char) -> Option<u32> {
+    VOWELS
+        .iter()
+        .position(|&(vowel, sign)| vowel == c || sign == Some(c))
+        .map(|index| VOWEL_WEIGHT + index as u32)
+}
+
+fn is_sign(c: char) -> bool {
+    c == HASANT || VOWELS.iter().any(|&(_, sign)| sign == Some(c))
+}
+
+/// The sort key of a text: one weight per letter, with the inherent vowel
+/// of bare consonants spelled out. Chandrabindu is left out, so it only
+/// breaks ties.
+pub fn key(text: &str) -> Vec<u32> {
+    let mut key = Vec::new();
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        if let Some(weight) = consonant_weight(c) {
+            // A nukta after ড, ঢ or য makes ড়, ঢ় or য়
+            let weight = match chars.peek() {
+                Some(&NUKTA) if matches!(c, 'ড' | 'ঢ' | 'য') => {
+                    chars.next();
+                    weight + 1
+                }
+                _ => weight,
+            };
+            key.push(weight);
+            if !chars.peek().is_some_and(|&next| is_sign(next)) {
+                key.push(VOWEL_WEIGHT);
+            }
+            continue;
+        }
+        match c {
+            '০'..='৯' => key.push(DIGIT_WEIGHT + (c as u32 - '০' as u32)),
+            // ৎ is ত without its vowel
+            KHANDA_TA => key.extend([
+                consonant_weight('ত').expect("ত is a consonant"),
+                HASANT_WEIGHT,
+            ]),
+            'ং' => key.push(ANUSVARA_WEIGHT),
+            'ঃ' => key.push(VISARGA_WEIGHT),
+            HASANT => key.push(HASANT_WEIGHT),
+            CHANDRABINDU | NUKTA => {}
+            c => key.push(vowel_weight(c).unwrap_or(OTHER_WEIGHT + c as u32)),
+        }
+    }
+    key
+}
+
+/// Compares in dictionary order. Texts that sort alike, such as those
+/// differing only in chandrabindu, fall back to code point order.
+pub fn compare(a: &str, b: &str) -> Ordering {
+    key(a).cmp(&key(b)).then_with(|| a.cmp(b))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sorted(words: &[&'static str]) -> Vec<&'static str> {
+        let mut words = words.to_vec();
+        words.sort_by(|a, b| compare(a, b));
+        words
+    }
+
+    #[test]
+    fn vowel_signs_follow_vowel_order() {
+        let order = ["ক", "কা", "কি", "কী", "কু", "কূ", "কৃ", "কে", "কৈ", "কো", "কৌ"];
+        let mut shuffled = order;
+        shuffled.reverse();
+        assert_eq!(sorted(&shuffled), order);
+    }
+
+    #[test]
+    fn a_bare_consonant_comes_before_its_signs() {
+        assert_eq!(sorted(&["কাজ", "কত", "কলম"]), ["কত", "কলম", "কাজ"]);
+    }
+
+    #[test]
+    fn conjuncts_come_after_every_vowel_sign() {
+        assert_eq!(
+            sorted(&["ক্ষমা", "কৌশল", "ক্কা", "কোন"]),
+            ["কোন", "কৌশল", "ক্কা", "ক্ষমা"]
+        );
+        // Three consonants after two, and the bare form between
+        assert_eq!(sorted(&["স্ত্রী", "স্তর", "স্তন"]), ["স্তন", "স্তর", "স্ত্রী"]);
+        assert_eq!(sorted(&["উৎস", "উত্তর", "উতল"]), ["উতল", "উত্তর", "উৎস"]);
+    }
+
+    #[test]
+    fn vowels_and_signs_sort_before_consonants() {
+        assert_eq!(
+            sorted(&["কলা", "অংক", "আম", "অক্ষর", "১০"]),
+            ["১০", "অংক", "অক্ষর", "আম", "কলা"]
+        );
+    }
+
+    #[test]
+    fn nukta_letters_follow_their_base() {
+        assert_eq!(sorted(&["ঢাক", "ড়", "ডাক"]), ["ডাক", "ড়", "ঢাক"]);
+        // Spelled with a separate nukta, the same place
+        assert_eq!(key("ড\u{09BC}"), key("\u{09DC}"));
+    }
+
+    #[test]
+    fn chandrabindu_only_breaks_ties() {
+        assert_eq!(key("চাঁদ"), key("চাদ"));
+        assert_ne!(compare("চাঁদ", "চাদ"), Ordering::Equal);
+        assert_eq!(sorted(&["চাঁদা", "চাদর"]), ["চাদর", "চাঁদা"]);
+    }
+}
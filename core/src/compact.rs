@@ -0,0 +1,390 @@
+use crate::dictionary::{edit_distance, Dictionary};
+use fst::Automaton;
+use fst::{IntoStreamer, Set, SetBuilder, Streamer};
+use std::collections::BTreeSet;
+use std::fmt;
+use std::ops::Range;
+use std::sync::Arc;
+
+// The file starts with this, then the byte lengths of the two sets as
+// little-endian u64s, the sets, and the source text
+const MAGIC: &[u8; 4] = b"RKD1";
+const HEADER_LEN: usize = 4 + 8 + 8;
+// Between a word's two spellings in a key; no spelling has it
+const SEPARATOR: u8 = 0;
+
+/// A large dictionary kept compact: its words are two finite-state sets,
+/// `roman\0bangla` and `bangla\0roman`, in one buffer that is usually a
+/// memory-mapped file. Words sharing beginnings and endings share their
+/// bytes, and of a mapped file only the pages searched are in memory.
+///
+/// ```
+/// use restro_keyboard_core::{CompactDictionary, Dictionary};
+///
+/// let words = Dictionary::parse("ami\tআমি\namar\tআমার\n");
+/// let compact = CompactDictionary::from_bytes(CompactDictionary::build(&words, "")).unwrap();
+/// assert_eq!(compact.get("Ami").as_deref(), Some("আমি"));
+/// assert!(compact.has_spelling("আমার"));
+/// ```
+#[derive(Clone)]
+pub struct CompactDictionary {
+    by_roman: Set<Bytes>,
+    by_bangla: Set<Bytes>,
+    source: Bytes,
+}
+
+// A range of the shared buffer, which the sets read through
+#[derive(Clone)]
+struct Bytes {
+    data: Arc<dyn AsRef<[u8]> + Send + Sync>,
+    range: Range<usize>,
+}
+
+impl AsRef<[u8]> for Bytes {
+    fn as_ref(&self) -> &[u8] {
+        &(*self.data).as_ref()[self.range.clone()]
+    }
+}
+
+// Matches words at most `max_distance` letter edits from `word`, a row of
+// the edit distance table per letter read. fst's own Levenshtein automaton
+// misses words in three-byte scripts like Bangla.
+struct Near {
+    word: Vec<char>,
+    max_distance: usize,
+}
+
+#[derive(Clone)]
+struct Row {
+    // From the letters read so far to each prefix of the word
+    distances: Vec<usize>,
+    // Bytes of a letter not yet read whole
+    pending: Vec<u8>,
+}
+
+impl Automaton for Near {
+    type State = Row;
+
+    fn start(&self) -> Row {
+        Row {
+            distances: (0..=self.word.len()).collect(),
+            pending: Vec::new(),
+        }
+    }
+
+    fn is_match(&self, row: &Row) -> bool {
+        row.pending.is_empty() && row.distances[self.word.len()] <= self.max_distance
+    }
+
+    fn can_match(&self, row: &Row) -> bool {
+        row.distances
+            .iter()
+            .min()
+            .is_some_and(|&d| d <= self.max_distance)
+    }
+
+    fn accept(&self, row: &Row, byte: u8) -> Row {
+        let mut pending = row.pending.clone();
+        pending.push(byte);
+        let letter = match std::str::from_utf8(&pending) {
+            Ok(text) => text.chars().next().expect("a byte was pushed"),
+            Err(err) if err.error_len().is_none() => {
+                return Row {
+                    distances: row.distances.clone(),
+                    pending,
+                }
+            }
+            // Not UTF-8, so no word
+            Err(_) => {
+                return Row {
+                    distances: vec![usize::MAX; row.distances.len()],
+                    pending: Vec::new(),
+                }
+            }
+        };
+        let mut distances = Vec::with_capacity(row.distances.len());
+        distances.push(row.distances[0].saturating_add(1));
+        for (j, c) in self.word.iter().enumerate() {
+            let substitution = row.distances[j].saturating_add(usize::from(*c != letter));
+            let insertion = row.distances[j + 1].saturating_add(1);
+            let deletion = distances[j].saturating_add(1);
+            distances.push(substitution.min(insertion).min(deletion));
+        }
+        Row {
+            distances,
+            pending: Vec::new(),
+        }
+    }
+}
+
+// Matches keys whose part before the separator the inner automaton
+// matches, whatever follows it
+struct ThenAnything<A>(A);
+
+#[derive(Clone)]
+enum Part<S> {
+    Word(S),
+    Rest,
+}
+
+impl<A: Automaton> Automaton for ThenAnything<A> {
+    type State = Option<Part<A::State>>;
+
+    fn start(&self) -> Self::State {
+        Some(Part::Word(self.0.start()))
+    }
+
+    fn is_match(&self, state: &Self::State) -> bool {
+        matches!(state, Some(Part::Rest))
+    }
+
+    fn can_match(&self, state: &Self::State) -> bool {
+        match state {
+            Some(Part::Word(state)) => self.0.can_match(state),
+            Some(Part::Rest) => true,
+            None => false,
+        }
+    }
+
+    fn accept(&self, state: &Self::State, byte: u8) -> Self::State {
+        match state {
+            Some(Part::Word(state)) if byte == SEPARATOR => {
+                self.0.is_match(state).then_some(Part::Rest)
+            }
+            Some(Part::Word(state)) => Some(Part::Word(self.0.accept(state, byte))),
+            Some(Part::Rest) => Some(Part::Rest),
+            None => None,
+        }
+    }
+}
+
+fn key(first: &str, second: &str) -> Vec<u8> {
+    let mut key = Vec::with_capacity(first.len() + 1 + second.len());
+    key.extend_from_slice(first.as_bytes());
+    key.push(SEPARATOR);
+    key.extend_from_slice(second.as_bytes());
+    key
+}
+
+fn split(key: &[u8]) -> Option<(String, String)> {
+    let at = key.iter().position(|&byte| byte == SEPARATOR)?;
+    let first = std::str::from_utf8(&key[..at]).ok()?;
+    let second = std::str::from_utf8(&key[at + 1..]).ok()?;
+    Some((first.to_string(), second.to_string()))
+}
+
+fn set(keys: BTreeSet<Vec<u8>>) -> Vec<u8> {
+    let mut builder = SetBuilder::memory();
+    for key in keys {
+        builder.insert(key).expect("keys are sorted and unique");
+    }
+    builder.into_inner().expect("writing to memory never fails")
+}
+
+impl CompactDictionary {
+    /// The bytes of a compact dictionary of `dictionary`'s words, with
+    /// `source` kept to say where they came from.
+    pub fn build(dictionary: &Dictionary, source: &str) -> Vec<u8> {
+        let mut by_roman = BTreeSet::new();
+        let mut by_bangla = BTreeSet::new();
+        for (roman, bangla) in dictionary.words() {
+            by_roman.insert(key(roman, bangla));
+            by_bangla.insert(key(bangla, roman));
+        }
+        let (by_roman, by_bangla) = (set(by_roman), set(by_bangla));
+        let mut bytes = Vec::with_capacity(HEADER_LEN + by_roman.len() + by_bangla.len());
+        bytes.extend_from_slice(MAGIC);
+        bytes.extend_from_slice(&(by_roman.len() as u64).to_le_bytes());
+        bytes.extend_from_slice(&(by_bangla.len() as u64).to_le_bytes());
+        bytes.extend_from_slice(&by_roman);
+        bytes.extend_from_slice(&by_bangla);
+        bytes.extend_from_slice(source.as_bytes());
+        bytes
+    }
+
+    /// Reads bytes made by [`build`](Self::build), without copying them.
+    pub fn from_bytes(data: impl AsRef<[u8]> + Send + Sync + 'static) -> Result<Self, String> {
+        let data: Arc<dyn AsRef<[u8]> + Send + Sync> = Arc::new(data);
+        let bytes = (*data).as_ref();
+        if bytes.len() < HEADER_LEN || &bytes[..4] != MAGIC {
+            return Err("not a compact dictionary".to_string());
+        }
+        let length = |at: usize| {
+            let mut word = [0; 8];
+            word.copy_from_slice(&bytes[at..at + 8]);
+            usize::try_from(u64::from_le_bytes(word)).unwrap_or(usize::MAX)
+        };
+        let roman_end = HEADER_LEN.saturating_add(length(4));
+        let bangla_end = roman_end.saturating_add(length(12));
+        if bangla_end > bytes.len() {
+            return Err("the compact dictionary is cut short".to_string());
+        }
+        let part = |range: Range<usize>| Bytes {
+            data: data.clone(),
+            range,
+        };
+        let total = bytes.len();
+        let open = |range| Set::new(part(range)).map_err(|err| err.to_string());
+        Ok(Self {
+            by_roman: open(HEADER_LEN..roman_end)?,
+            by_bangla: open(roman_end..bangla_end)?,
+            source: part(bangla_end..total),
+        })
+    }
+
+    // The other spellings of keys starting with `word`, in key order
+    fn others(set: &Set<Bytes>, word: &str) -> Vec<String> {
+        let mut end = word.as_bytes().to_vec();
+        end.push(SEPARATOR + 1);
+        let mut stream = set.range().ge(key(word, "")).lt(end).into_stream();
+        let mut others = Vec::new();
+        while let Some(key) = stream.next() {
+            others.extend(split(key).map(|(_, other)| other));
+        }
+        others
+    }
+
+    /// The spelling of a whole word, without regard to case.
+    pub fn get(&self, roman: &str) -> Option<String> {
+        Self::others(&self.by_roman, &roman.to_lowercase())
+            .into_iter()
+            .next()
+    }
+
+    /// Whether a word is spelled `bangla`.
+    pub fn has_spelling(&self, bangla: &str) -> bool {
+        self.roman_of(bangla).is_some()
+    }
+
+    /// See [`Dictionary::roman_of`].
+    pub fn roman_of(&self, bangla: &str) -> Option<String> {
+        Self::others(&self.by_bangla, bangla).into_iter().next()
+    }
+
+    // Keys whose first part is at most `max_distance` edits from `word`,
+    // closest first and then in key order, as (distance, first, second)
+    fn near(set: &Set<Bytes>, word: &str, max_distance: usize) -> Vec<(usize, String, String)> {
+        let automaton = Near {
+            word: word.chars().collect(),
+            max_distance,
+        };
+        let mut stream = set.search(ThenAnything(automaton)).into_stream();
+        let mut near = Vec::new();
+        while let Some(key) = stream.next() {
+            if let Some((first, second)) = split(key) {
+                near.push((edit_distance(word, &first), first, second));
+            }
+        }
+        near.sort();
+        near
+    }
+
+    /// See [`Dictionary::nearest`].
+    pub fn nearest(&self, roman: &str, max_distance: usize) -> Option<(String, String)> {
+        Self::near(&self.by_roman, &roman.to_lowercase(), max_distance)
+            .into_iter()
+            .next()
+            .map(|(_, roman, bangla)| (roman, bangla))
+    }
+
+    /// Up to `limit` spellings at most `max_distance` letter edits from
+    /// `bangla` other than itself, closest first, like
+    /// [`WordList::nearest`](crate::WordList::nearest).
+    pub fn nearest_spellings(
+        &self,
+        bangla: &str,
+        max_distance: usize,
+        limit: usize,
+    ) -> Vec<String> {
+        let mut spellings: Vec<String> = Vec::new();
+        for (_, spelling, _) in Self::near(&self.by_bangla, bangla, max_distance) {
+            if spelling != bangla && !spellings.contains(&spelling) {
+                spellings.push(spelling);
+            }
+        }
+        spellings.truncate(limit);
+        spellings
+    }
+
+    /// Every spelling once, in code point order.
+    pub fn spellings(&self) -> Vec<String> {
+        let mut stream = self.by_bangla.stream();
+        let mut spellings: Vec<String> = Vec::new();
+        while let Some(key) = stream.next() {
+            if let Some((spelling, _)) = split(key) {
+                if spellings.last() != Some(&spelling) {
+                    spellings.push(spelling);
+                }
+            }
+        }
+        spellings
+    }
+
+    /// Where the words came from, as given when it was built.
+    pub fn source(&self) -> String {
+        String::from_utf8_lossy(self.source.as_ref()).into_owned()
+    }
+
+    pub fn len(&self) -> usize {
+        self.by_roman.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.by_roman.is_empty()
+    }
+}
+
+impl fmt::Debug for CompactDictionary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CompactDictionary")
+            .field("words", &self.len())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn compact(text: &str) -> CompactDictionary {
+        CompactDictionary::from_bytes(CompactDictionary::build(&Dictionary::parse(text), "# test"))
+            .unwrap()
+    }
+
+    #[test]
+    fn looks_up_whole_words_only() {
+        let words = compact("ami\tআমি\namar\tআমার\namra\tআমরা\n");
+        assert_eq!(words.len(), 3);
+        assert_eq!(words.get("ami").as_deref(), Some("আমি"));
+        assert_eq!(words.get("am"), None);
+        assert_eq!(words.get("amis"), None);
+        assert!(words.has_spelling("আমরা"));
+        assert!(!words.has_spelling("আম"));
+        assert_eq!(words.roman_of("আমার").as_deref(), Some("amar"));
+        assert_eq!(words.spellings(), ["আমরা", "আমার", "আমি"]);
+        assert_eq!(words.source(), "# test");
+    }
+
+    #[test]
+    fn finds_near_words_like_the_map_does() {
+        let text = "ami\tআমি\namar\tআমার\ntumi\tতুমি\nbhalobasa\tভালোবাসা\n";
+        let words = compact(text);
+        let map = Dictionary::parse(text);
+        for roman in ["amj", "amr", "tumj", "bhalobsa", "xyz"] {
+            let expected = map
+                .nearest(roman, 2)
+                .map(|(roman, bangla)| (roman.to_string(), bangla.to_string()));
+            assert_eq!(words.nearest(roman, 2), expected, "{}", roman);
+        }
+        assert_eq!(words.nearest_spellings("ভালবাসা", 2, 3), ["ভালোবাসা"]);
+        assert!(words.nearest_spellings("আমি", 0, 3).is_empty());
+    }
+
+    #[test]
+    fn refuses_other_bytes() {
+        assert!(CompactDictionary::from_bytes(b"not a dictionary".to_vec()).is_err());
+        let mut cut = CompactDictionary::build(&Dictionary::parse("ami\tআমি\n"), "");
+        cut.truncate(cut.len() - 4);
+        assert!(CompactDictionary::from_bytes(cut).is_err());
+    }
+}
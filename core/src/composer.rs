@@ -0,0 +1,177 @@
+use crate::dictionary::Dictionary;
+use crate::transliterator::Transliterator;
+
+/// Adds `key` to the word buffer and converts the whole word again. Returns
+/// the text to type and the number of already emitted characters to erase
+/// first, or None when the key should reach the application unchanged.
+pub fn process_keyboard_input(
+    engine: &Transliterator,
+    key: &str,
+    buffer: &mut String,
+) -> Option<(String, usize)> {
+    let before = engine.transliterate(buffer);
+    buffer.push_str(key);
+    let after = engine.transliterate(buffer);
+
+    let (output, backspaces) = difference(&before, &after);
+    if backspaces == 0 && output == key {
+        return None;
+    }
+    Some((output, backspaces))
+}
+
+// Only what differs from the earlier text is retyped
+fn difference(before: &str, after: &str) -> (String, usize) {
+    let common = before
+        .chars()
+        .zip(after.chars())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let backspaces = before.chars().count() - common;
+    (after.chars().skip(common).collect(), backspaces)
+}
+
+const HASANT: char = '্';
+const NUKTA: char = '\u{09BC}';
+const ZWJ: char = '\u{200D}';
+
+// Where the last letter of the text starts, in chars. A letter is what one
+// backspace should take back: a consonant with the consonants joined to it
+// by hasant (ক্ষ, র‍্য), or a vowel, vowel sign or other single character.
+// Vowel signs stand alone, so erasing কি leaves ক.
+fn last_letter_start(text: &str) -> usize {
+    let chars: Vec<char> = text.chars().collect();
+    let mut start = chars.len().saturating_sub(1);
+    loop {
+        while start > 0 && matches!(chars[start], HASANT | NUKTA | ZWJ) {
+            start -= 1;
+        }
+        if start >= 2 && chars[start - 1] == HASANT {
+            start -= 2;
+            continue;
+        }
+        return start;
+    }
+}
+
+/// The word being typed, as the roman keys pressed so far. Each key
+/// reconverts the word, so later keys can change earlier output (k, kh).
+#[derive(Clone, Debug, Default)]
+pub struct Composer {
+    buffer: String,
+}
+
+impl Composer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn buffer(&self) -> &str {
+        &self.buffer
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.buffer.is_empty()
+    }
+
+    /// See [`process_keyboard_input`].
+    pub fn push(&mut self, engine: &Transliterator, key: &str) -> Option<(String, usize)> {
+        process_keyboard_input(engine, key, &mut self.buffer)
+    }
+
+    /// Ends the word. When the dictionary spells it differently from the
+    /// rules, returns the text to type and the characters to erase first.
+    pub fn finish(
+        &mut self,
+        engine: &Transliterator,
+        dictionary: &Dictionary,
+    ) -> Option<(String, usize)> {
+        let word = std::mem::take(&mut self.buffer);
+        let spelling = dictionary.get(&word)?;
+        let converted = engine.transliterate(&word);
+        (spelling != converted).then(|| difference(&converted, &spelling))
+    }
+
+    pub fn backspace(&mut self) {
+        self.buffer.pop();
+    }
+
+    /// Erases the last letter of the word being typed, conjuncts whole, and
+    /// drops the keys that made it. Returns the text to type and the number
+    /// of characters to erase first, or None when no word is being typed.
+    ///
+    /// ```
+    /// use restro_keyboard_core::{Composer, Layout, Transliterator};
+    ///
+    /// let layout = Layout::phonetic();
+    /// let engine = Transliterator::new(&layout);
+    /// let mut composer = Composer::new();
+    /// for key in ["a", "k", "S", "h"] {
+    ///     composer.push(&engine, key);
+    /// }
+    /// // অক্ষ loses ক্ষ, not just ষ
+    /// assert_eq!(composer.erase_letter(&engine), Some((String::new(), 3)));
+    /// assert_eq!(composer.buffer(), "a");
+    /// ```
+    pub fn erase_letter(&mut self, engine: &Transliterator) -> Option<(String, usize)> {
+        if self.buffer.is_empty() {
+            return None;
+        }
+        let before = engine.transliterate(&self.buffer);
+        let kept = last_letter_start(&before);
+        // Keys that changed earlier output, like the h of kh, go with it
+        let mut after = before.clone();
+        while after.chars().count() > kept {
+            self.buffer.pop();
+            after = engine.transliterate(&self.buffer);
+        }
+        Some(difference(&before, &after))
+    }
+
+    // The word ended or the caret moved away
+    pub fn clear(&mut self) {
+        self.buffer.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Layout;
+
+    fn typed(engine: &Transliterator, keys: &str) -> Composer {
+        let mut composer = Composer::new();
+        for key in keys.chars() {
+            composer.push(engine, &key.to_string());
+        }
+        composer
+    }
+
+    #[test]
+    fn backspace_takes_a_conjunct_whole() {
+        let layout = Layout::avro();
+        let engine = Transliterator::new(&layout);
+
+        // অস্ত্র loses স্ত্র and its three keys
+        let mut composer = typed(&engine, "ostr");
+        assert_eq!(composer.erase_letter(&engine), Some((String::new(), 5)));
+        assert_eq!(composer.buffer(), "o");
+
+        // A vowel sign goes on its own, leaving the conjunct
+        let mut composer = typed(&engine, "nti");
+        assert_eq!(composer.erase_letter(&engine), Some((String::new(), 1)));
+        assert_eq!(composer.buffer(), "nt");
+        assert_eq!(composer.erase_letter(&engine), Some((String::new(), 3)));
+        assert_eq!(composer.buffer(), "");
+        assert_eq!(composer.erase_letter(&engine), None);
+    }
+
+    #[test]
+    fn consonants_outside_a_cluster_go_one_by_one() {
+        let layout = Layout::avro();
+        let engine = Transliterator::new(&layout);
+        let mut composer = typed(&engine, "ekT");
+        assert_eq!(composer.erase_letter(&engine), Some((String::new(), 1)));
+        assert_eq!(composer.buffer(), "ek");
+    }
+}
@@ -0,0 +1,160 @@
+#[cfg(feature = "compact")]
+use crate::compact::CompactDictionary;
+use std::collections::HashMap;
+#[cfg(feature = "compact")]
+use std::sync::Arc;
+
+/// Whole-word spellings by their roman spelling, for words that converting
+/// rule by rule gets wrong (ami types অমি, the word is আমি).
+///
+/// With the `compact` feature a large [`CompactDictionary`] can sit under
+/// the words parsed or inserted, which win over it.
+#[derive(Clone, Debug, Default)]
+pub struct Dictionary {
+    // Keyed by the lowercase roman word
+    words: HashMap<String, String>,
+    #[cfg(feature = "compact")]
+    base: Option<Arc<CompactDictionary>>,
+}
+
+impl Dictionary {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Reads `roman<TAB>bangla` lines. Blank lines, lines starting with '#'
+    /// and lines without a tab are skipped; a later line for the same roman
+    /// word wins.
+    pub fn parse(text: &str) -> Self {
+        let mut dictionary = Self::new();
+        for line in text.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            if let Some((roman, bangla)) = line.split_once('\t') {
+                dictionary.insert(roman.trim(), bangla.trim());
+            }
+        }
+        dictionary
+    }
+
+    pub fn insert(&mut self, roman: &str, bangla: &str) {
+        self.words.insert(roman.to_lowercase(), bangla.to_string());
+    }
+
+    /// Puts `base` under the words, to be looked up when they don't have
+    /// a word.
+    #[cfg(feature = "compact")]
+    pub fn with_base(mut self, base: Arc<CompactDictionary>) -> Self {
+        self.base = Some(base);
+        self
+    }
+
+    /// The spelling of a whole word, without regard to case.
+    pub fn get(&self, roman: &str) -> Option<String> {
+        let own = self.words.get(&roman.to_lowercase()).cloned();
+        #[cfg(feature = "compact")]
+        let own = own.or_else(|| self.base.as_ref()?.get(roman));
+        own
+    }
+
+    /// The word closest to `roman` in spelling, at most `max_distance`
+    /// letter edits away, as its roman and Bangla spellings. Ties go to the
+    /// alphabetically first roman word.
+    pub fn nearest(&self, roman: &str, max_distance: usize) -> Option<(String, String)> {
+        let lowercase = roman.to_lowercase();
+        let own = self
+            .words
+            .iter()
+            .map(|(word, bangla)| (edit_distance(&lowercase, word), word, bangla))
+            .filter(|(distance, _, _)| *distance <= max_distance)
+            .min_by(|a, b| (a.0, a.1).cmp(&(b.0, b.1)))
+            .map(|(distance, word, bangla)| (distance, word.clone(), bangla.clone()));
+        #[cfg(feature = "compact")]
+        let own = {
+            let base = self.base.as_ref().and_then(|base| {
+                let (word, bangla) = base.nearest(roman, max_distance)?;
+                Some((edit_distance(&lowercase, &word), word, bangla))
+            });
+            own.into_iter().chain(base).min()
+        };
+        own.map(|(_, word, bangla)| (word, bangla))
+    }
+
+    /// Whether any word is spelled `bangla`.
+    pub fn has_spelling(&self, bangla: &str) -> bool {
+        self.roman_of(bangla).is_some()
+    }
+
+    /// The roman spelling of the word spelled `bangla`, which says how it
+    /// is pronounced. Of several, the alphabetically first.
+    ///
+    /// ```
+    /// use restro_keyboard_core::Dictionary;
+    ///
+    /// let words = Dictionary::parse("ami\tআমি\nshokal\tসকাল\nsokal\tসকাল\n");
+    /// assert_eq!(words.roman_of("সকাল").as_deref(), Some("shokal"));
+    /// assert_eq!(words.roman_of("বিকাল"), None);
+    /// ```
+    pub fn roman_of(&self, bangla: &str) -> Option<String> {
+        let own = self
+            .words
+            .iter()
+            .filter(|(_, spelling)| *spelling == bangla)
+            .map(|(roman, _)| roman)
+            .min()
+            .cloned();
+        #[cfg(feature = "compact")]
+        let own = own.or_else(|| self.base.as_ref()?.roman_of(bangla));
+        own
+    }
+
+    /// Every Bangla spelling once, a compact base's too, in code point
+    /// order.
+    pub fn spellings(&self) -> Vec<String> {
+        let mut spellings: Vec<String> = self.words.values().cloned().collect();
+        #[cfg(feature = "compact")]
+        spellings.extend(self.base.iter().flat_map(|base| base.spellings()));
+        spellings.sort();
+        spellings.dedup();
+        spellings
+    }
+
+    /// Every word as (roman, Bangla), in no particular order. Those of a
+    /// compact base aren't listed.
+    pub fn words(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.words
+            .iter()
+            .map(|(roman, bangla)| (roman.as_str(), bangla.as_str()))
+    }
+
+    /// How many words there are, counting those of a compact base that
+    /// the others replace twice.
+    pub fn len(&self) -> usize {
+        let own = self.words.len();
+        #[cfg(feature = "compact")]
+        let own = own + self.base.as_ref().map_or(0, |base| base.len());
+        own
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+// Levenshtein distance: insertions, deletions and substitutions
+pub(crate) fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = diagonal + usize::from(ca != *cb);
+            diagonal = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(diagonal + 1);
+        }
+    }
+    row[b.len()]
+}
@@ -0,0 +1,441 @@
+use std::collections::HashMap;
+
+/// Which characters of a key are typed: plain, with Shift, with AltGr, or
+/// with both.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Layer {
+    Base,
+    Shift,
+    AltGr,
+    ShiftAltGr,
+}
+
+impl Layer {
+    pub fn new(shift: bool, altgr: bool) -> Self {
+        match (shift, altgr) {
+            (false, false) => Layer::Base,
+            (true, false) => Layer::Shift,
+            (false, true) => Layer::AltGr,
+            (true, true) => Layer::ShiftAltGr,
+        }
+    }
+}
+
+// Keys are named by what they type on a US keyboard without Shift. Each row
+// is the key and its base, Shift, AltGr and Shift+AltGr text; empty layers
+// type nothing.
+type KeyRow = (char, &'static str, &'static str, &'static str, &'static str);
+
+const DIGITS: [KeyRow; 10] = [
+    ('1', "১", "!", "", ""),
+    ('2', "২", "@", "", ""),
+    ('3', "৩", "#", "", ""),
+    ('4', "৪", "৳", "", ""),
+    ('5', "৫", "%", "", ""),
+    ('6', "৬", "^", "", ""),
+    ('7', "৭", "&", "", ""),
+    ('8', "৮", "*", "", ""),
+    ('9', "৯", "(", "", ""),
+    ('0', "০", ")", "", ""),
+];
+
+const PROBHAT: [KeyRow; 36] = [
+    ('7', "৭", "ঞ", "", ""),
+    ('8', "৮", "ৎ", "", ""),
+    ('-', "-", "ঃ", "", ""),
+    ('q', "দ", "ধ", "", ""),
+    ('w', "ূ", "ঊ", "", ""),
+    ('e', "ী", "ঈ", "", ""),
+    ('r', "র", "ড়", "", ""),
+    ('t', "ট", "ঠ", "", ""),
+    ('y', "এ", "ঐ", "", ""),
+    ('u', "ু", "উ", "", ""),
+    ('i', "ি", "ই", "", ""),
+    ('o', "ও", "ঔ", "", ""),
+    ('p', "প", "ফ", "", ""),
+    ('[', "ে", "ৈ", "", ""),
+    (']', "ো", "ৌ", "", ""),
+    ('\\', "\u{200C}", "॥", "", ""),
+    ('a', "া", "অ", "", ""),
+    ('s', "স", "ষ", "", ""),
+    ('d', "ড", "ঢ", "", ""),
+    ('f', "ত", "থ", "", ""),
+    ('g', "গ", "ঘ", "", ""),
+    ('h', "হ", "ঃ", "", ""),
+    ('j', "জ", "ঝ", "", ""),
+    ('k', "ক", "খ", "", ""),
+    ('l', "ল", "ং", "", ""),
+    ('z', "য়", "য", "", ""),
+    ('x', "শ", "ঢ়", "", ""),
+    ('c', "চ", "ছ", "", ""),
+    ('v', "আ", "ঋ", "", ""),
+    ('b', "ব", "ভ", "", ""),
+    ('n', "ন", "ণ", "", ""),
+    ('m', "ম", "ঙ", "", ""),
+    (',', ",", "ৃ", "", ""),
+    ('.', "।", "ঁ", "", ""),
+    ('/', "্", "?", "", ""),
+    ('`', "\u{200D}", "~", "", ""),
+];
+
+// National (Jatiya) layout of Bangladesh: independent vowels on AltGr
+const JATIYA: [KeyRow; 31] = [
+    ('q', "ঙ", "ং", "", ""),
+    ('w', "য", "য়", "", ""),
+    ('e', "ড", "ঢ", "", ""),
+    ('r', "প", "ফ", "", ""),
+    ('t', "ট", "ঠ", "", ""),
+    ('y', "চ", "ছ", "", ""),
+    ('u', "জ", "ঝ", "", ""),
+    ('i', "হ", "ঞ", "", ""),
+    ('o', "গ", "ঘ", "", ""),
+    ('p', "ড়", "ঢ়", "", ""),
+    ('a', "ৃ", "ঋ", "", ""),
+    ('s', "ু", "ূ", "উ", "ঊ"),
+    ('d', "ি", "ী", "ই", "ঈ"),
+    ('f', "ব", "ভ", "", ""),
+    ('g', "্", "।", "", ""),
+    ('h', "া", "অ", "আ", ""),
+    ('j', "ক", "খ", "", ""),
+    ('k', "ত", "থ", "ৎ", ""),
+    ('l', "দ", "ধ", "", ""),
+    ('z', "্র", "্য", "", ""),
+    ('x', "ো", "ৌ", "ও", "ঔ"),
+    ('c', "ে", "ৈ", "এ", "ঐ"),
+    ('v', "র", "ল", "", ""),
+    ('b', "ন", "ণ", "", ""),
+    ('n', "স", "ষ", "", ""),
+    ('m', "ম", "শ", "", ""),
+    (',', ",", "ঁ", "", ""),
+    ('.', ".", "ঃ", "", ""),
+    ('/', "/", "?", "", ""),
+    ('\\', "\\", "ৎ", "", ""),
+    ('`', "\u{200D}", "~", "", ""),
+];
+
+// Bijoy, typed in visual order: ি, ে and ৈ before their consonant and reph
+// after it
+const BIJOY: [KeyRow; 30] = [
+    ('q', "ঙ", "ং", "", ""),
+    ('w', "য", "য়", "", ""),
+    ('e', "ড", "ঢ", "", ""),
+    ('r', "প", "ফ", "", ""),
+    ('t', "ট", "ঠ", "", ""),
+    ('y', "চ", "ছ", "", ""),
+    ('u', "জ", "ঝ", "", ""),
+    ('i', "হ", "ঞ", "", ""),
+    ('o', "গ", "ঘ", "", ""),
+    ('p', "ড়", "ঢ়", "", ""),
+    ('a', "ৃ", "র্", "", ""),
+    ('s', "ু", "ূ", "", ""),
+    ('d', "ি", "ী", "", ""),
+    ('f', "া", "অ", "", ""),
+    ('g', "্", "।", "", ""),
+    ('h', "ব", "ভ", "", ""),
+    ('j', "ক", "খ", "", ""),
+    ('k', "ত", "থ", "", ""),
+    ('l', "দ", "ধ", "", ""),
+    ('z', "্র", "্য", "", ""),
+    ('x', "ও", "ৗ", "", ""),
+    ('c', "ে", "ৈ", "", ""),
+    ('v', "র", "ল", "", ""),
+    ('b', "ন", "ণ", "", ""),
+    ('n', "স", "ষ", "", ""),
+    ('m', "ম", "শ", "", ""),
+    ('\\', "ৎ", "ঃ", "", ""),
+    ('.', ".", "ঁ", "", ""),
+    (',', ",", "<", "", ""),
+    ('`', "\u{200D}", "~", "", ""),
+];
+
+// Bijoy's independent vowels: hasant followed by a sign, and অ followed by া
+const BIJOY_COMBOS: [(&str, &str, &str); 10] = [
+    ("্", "ি", "ই"),
+    ("্", "ী", "ঈ"),
+    ("্", "ু", "উ"),
+    ("্", "ূ", "ঊ"),
+    ("্", "ৃ", "ঋ"),
+    ("্", "ে", "এ"),
+    ("্", "ৈ", "ঐ"),
+    ("্", "া", "আ"),
+    ("অ", "া", "আ"),
+    ("ও", "ৗ", "ঔ"),
+];
+
+// Signs written before the consonant they follow in Unicode
+const PRE_BASE_KARS: [char; 3] = ['ি', 'ে', 'ৈ'];
+
+const REPH: &str = "র্";
+
+const NUKTA: char = '\u{09BC}';
+
+fn is_consonant(c: char) -> bool {
+    matches!(c, 'ক'..='ন' | 'প'..='র' | 'ল' | 'শ'..='হ' | '\u{09DC}'..='\u{09DF}' | 'ৎ')
+}
+
+// The consonant the text ends with, with its nukta (য় is য and a nukta)
+fn last_consonant(text: &str) -> Option<&str> {
+    let mut chars = text.char_indices().rev();
+    let (mut start, mut last) = chars.next()?;
+    if last == NUKTA {
+        (start, last) = chars.next()?;
+    }
+    is_consonant(last).then(|| &text[start..])
+}
+
+/// A layout where every key types fixed Bangla text, as opposed to the
+/// phonetic layouts that convert roman spelling.
+#[derive(Clone, Debug)]
+pub struct FixedLayout {
+    name: String,
+    keys: HashMap<(char, Layer), String>,
+    // Kars and reph are typed in the order they are seen, as in Bijoy
+    visual_order: bool,
+    combos: HashMap<(String, String), String>,
+}
+
+impl FixedLayout {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            keys: HashMap::new(),
+            visual_order: false,
+            combos: HashMap::new(),
+        }
+    }
+
+    pub fn probhat() -> Self {
+        let mut layout = Self::new("Probhat");
+        layout.insert_rows(&DIGITS);
+        layout.insert_rows(&PROBHAT);
+        layout
+    }
+
+    pub fn jatiya() -> Self {
+        let mut layout = Self::new("Jatiya");
+        layout.insert_rows(&DIGITS);
+        layout.insert_rows(&JATIYA);
+        layout
+    }
+
+    pub fn bijoy() -> Self {
+        let mut layout = Self::new("Bijoy");
+        layout.insert_rows(&DIGITS);
+        layout.insert_rows(&BIJOY);
+        layout.visual_order = true;
+        for (first, second, combined) in BIJOY_COMBOS {
+            layout.combos.insert(
+                (first.to_string(), second.to_string()),
+                combined.to_string(),
+            );
+        }
+        layout
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn insert(&mut self, key: char, layer: Layer, text: &str) {
+        self.keys.insert((key, layer), text.to_string());
+    }
+
+    fn insert_rows(&mut self, rows: &[KeyRow]) {
+        for (key, base, shift, altgr, shift_altgr) in rows {
+            for (layer, text) in [
+                (Layer::Base, base),
+                (Layer::Shift, shift),
+                (Layer::AltGr, altgr),
+                (Layer::ShiftAltGr, shift_altgr),
+            ] {
+                if !text.is_empty() {
+                    self.insert(*key, layer, text);
+                }
+            }
+        }
+    }
+
+    /// The text `key` types on `layer`, if the layout has any.
+    pub fn get(&self, key: char, layer: Layer) -> Option<&str> {
+        self.keys.get(&(key, layer)).map(String::as_str)
+    }
+}
+
+/// The text typed in the current word with a fixed layout, so visual-order
+/// typing can be put in Unicode order as keys arrive.
+#[derive(Clone, Debug, Default)]
+pub struct FixedComposer {
+    typed: String,
+    // A pre-base kar waiting at the end of its cluster
+    pending_kar: Option<char>,
+}
+
+impl FixedComposer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The word typed so far, in Unicode order.
+    pub fn typed(&self) -> &str {
+        &self.typed
+    }
+
+    /// Returns the text to type and the number of already typed characters
+    /// to erase first, or None when the layout has nothing on the key.
+    pub fn push(
+        &mut self,
+        layout: &FixedLayout,
+        key: char,
+        layer: Layer,
+    ) -> Option<(String, usize)> {
+        let text = layout.get(key, layer)?;
+        if !layout.visual_order {
+            self.typed.push_str(text);
+            return Some((text.to_string(), 0));
+        }
+
+        let last = self.typed.chars().next_back();
+        let combo = last.and_then(|last| layout.combos.get(&(last.to_string(), text.to_string())));
+
+        let (output, backspaces) = if let Some(combined) = combo {
+            self.pending_kar = None;
+            (combined.clone(), 1)
+        } else if let Some(kar) = self.pending_kar.filter(|kar| last == Some(*kar)) {
+            // ি then ক types কি; the kar stays last while the cluster grows
+            let first = text.chars().next();
+            if text == "্" || first.is_some_and(is_consonant) {
+                (format!("{}{}", text, kar), 1)
+            } else {
+                self.pending_kar = None;
+                (text.to_string(), 0)
+            }
+        } else if text == REPH {
+            // Reph is typed after its consonant but comes first in Unicode
+            match last_consonant(&self.typed) {
+                Some(consonant) => (format!("{}{}", REPH, consonant), consonant.chars().count()),
+                None => (text.to_string(), 0),
+            }
+        } else {
+            let first = text.chars().next();
+            self.pending_kar =
+                first.filter(|c| PRE_BASE_KARS.contains(c) && text.chars().count() == 1);
+            (text.to_string(), 0)
+        };
+
+        for _ in 0..backspaces {
+            self.typed.pop();
+        }
+        self.typed.push_str(&output);
+        Some((output, backspaces))
+    }
+
+    pub fn backspace(&mut self) {
+        self.typed.pop();
+        self.pending_kar = None;
+    }
+
+    pub fn clear(&mut self) {
+        self.typed.clear();
+        self.pending_kar = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use Layer::{AltGr, Base, Shift, ShiftAltGr};
+
+    // The text on screen after typing `keys`
+    fn typed(layout: &FixedLayout, keys: &[(char, Layer)]) -> String {
+        let mut composer = FixedComposer::new();
+        let mut screen = String::new();
+        for (key, layer) in keys {
+            if let Some((output, backspaces)) = composer.push(layout, *key, *layer) {
+                for _ in 0..backspaces {
+                    screen.pop();
+                }
+                screen.push_str(&output);
+            }
+        }
+        screen
+    }
+
+    fn base(keys: &str) -> Vec<(char, Layer)> {
+        keys.chars().map(|key| (key, Base)).collect()
+    }
+
+    #[test]
+    fn keys_type_their_layer() {
+        let cases = [
+            (FixedLayout::probhat(), 'k', Base, Some("ক")),
+            (FixedLayout::probhat(), 'k', Shift, Some("খ")),
+            (FixedLayout::probhat(), 'f', Base, Some("ত")),
+            (FixedLayout::probhat(), '8', Shift, Some("ৎ")),
+            (FixedLayout::probhat(), '/', Base, Some("্")),
+            (FixedLayout::probhat(), '4', Shift, Some("৳")),
+            (FixedLayout::probhat(), 'k', AltGr, None),
+            (FixedLayout::jatiya(), 'j', Base, Some("ক")),
+            (FixedLayout::jatiya(), 'j', Shift, Some("খ")),
+            (FixedLayout::jatiya(), 'd', AltGr, Some("ই")),
+            (FixedLayout::jatiya(), 's', ShiftAltGr, Some("ঊ")),
+            (FixedLayout::jatiya(), 'g', Shift, Some("।")),
+            (FixedLayout::jatiya(), '0', Base, Some("০")),
+            (FixedLayout::bijoy(), 'j', Base, Some("ক")),
+            (FixedLayout::bijoy(), 'm', Shift, Some("শ")),
+            (FixedLayout::bijoy(), 'a', Shift, Some("র্")),
+            (FixedLayout::bijoy(), '\\', Base, Some("ৎ")),
+            (FixedLayout::bijoy(), 'd', AltGr, None),
+            (FixedLayout::bijoy(), ';', Base, None),
+        ];
+        for (layout, key, layer, text) in cases {
+            assert_eq!(
+                layout.get(key, layer),
+                text,
+                "{} {} {:?}",
+                layout.name(),
+                key,
+                layer
+            );
+        }
+    }
+
+    #[test]
+    fn logical_layouts_type_in_key_order() {
+        let probhat = FixedLayout::probhat();
+        assert_eq!(typed(&probhat, &base("kia")), "কিা");
+        assert_eq!(typed(&probhat, &base("k/s")), "ক্স");
+        let jatiya = FixedLayout::jatiya();
+        assert_eq!(typed(&jatiya, &base("jd")), "কি");
+        assert_eq!(typed(&jatiya, &[('d', AltGr), ('j', Base)]), "ইক");
+    }
+
+    #[test]
+    fn bijoy_puts_pre_base_kars_after_their_consonant() {
+        let bijoy = FixedLayout::bijoy();
+        assert_eq!(typed(&bijoy, &base("dj")), "কি");
+        assert_eq!(typed(&bijoy, &base("cj")), "কে");
+        assert_eq!(typed(&bijoy, &[('c', Shift), ('n', Base)]), "সৈ");
+        // The kar stays last while the cluster grows
+        assert_eq!(typed(&bijoy, &base("djgn")), "ক্সি");
+        // Anything else after the consonant ends the wait
+        assert_eq!(typed(&bijoy, &base("djf")), "কিা");
+    }
+
+    #[test]
+    fn bijoy_moves_reph_before_its_consonant() {
+        let bijoy = FixedLayout::bijoy();
+        assert_eq!(typed(&bijoy, &[('j', Base), ('a', Shift)]), "র্ক");
+        assert_eq!(typed(&bijoy, &[('w', Shift), ('a', Shift)]), "র্য়");
+        // With nothing to sit on it stays where it was typed
+        assert_eq!(typed(&bijoy, &[('a', Shift)]), "র্");
+    }
+
+    #[test]
+    fn bijoy_combines_independent_vowels() {
+        let bijoy = FixedLayout::bijoy();
+        assert_eq!(typed(&bijoy, &base("gd")), "ই");
+        assert_eq!(typed(&bijoy, &base("gc")), "এ");
+        assert_eq!(typed(&bijoy, &[('f', Shift), ('f', Base)]), "আ");
+        assert_eq!(typed(&bijoy, &[('x', Base), ('x', Shift)]), "ঔ");
+    }
+}
@@ -0,0 +1,435 @@
+use crate::rules::{Condition, ContextRule, Scope, Side};
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+
+/// What a roman sequence types, by the role the character plays in a word.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum BanglaChar {
+    Vowel(String),
+    Consonant(String),
+    VowelSign(String),
+    Number(String),
+    Special(String),
+}
+
+impl BanglaChar {
+    pub fn text(&self) -> &str {
+        match self {
+            BanglaChar::Vowel(c)
+            | BanglaChar::Consonant(c)
+            | BanglaChar::VowelSign(c)
+            | BanglaChar::Number(c)
+            | BanglaChar::Special(c) => c,
+        }
+    }
+}
+
+// Vowels (স্বরবর্ণ)
+const VOWELS: [(&str, &str); 16] = [
+    ("a", "অ"),
+    ("aa", "আ"),
+    ("A", "আ"),
+    ("i", "ই"),
+    ("ii", "ঈ"),
+    ("I", "ঈ"),
+    ("u", "উ"),
+    ("uu", "ঊ"),
+    ("U", "ঊ"),
+    ("rri", "ঋ"),
+    ("e", "এ"),
+    ("oi", "ঐ"),
+    ("OI", "ঐ"),
+    ("o", "ও"),
+    ("ou", "ঔ"),
+    ("OU", "ঔ"),
+];
+
+// Consonants (ব্যঞ্জনবর্ণ)
+const CONSONANTS: [(&str, &str); 37] = [
+    ("k", "ক"),
+    ("kh", "খ"),
+    ("g", "গ"),
+    ("gh", "ঘ"),
+    ("ng", "ঙ"),
+    ("c", "চ"),
+    ("ch", "ছ"),
+    ("j", "জ"),
+    ("jh", "ঝ"),
+    ("ny", "ঞ"),
+    ("t", "ট"),
+    ("th", "ঠ"),
+    ("d", "ড"),
+    ("dh", "ঢ"),
+    ("n", "ন"),
+    ("p", "প"),
+    ("ph", "ফ"),
+    ("f", "ফ"),
+    ("b", "ব"),
+    ("bh", "ভ"),
+    ("v", "ভ"),
+    ("m", "ম"),
+    ("z", "য"),
+    ("r", "র"),
+    ("l", "ল"),
+    ("sh", "শ"),
+    ("s", "স"),
+    ("h", "হ"),
+    ("y", "য়"),
+    ("tt", "ত্ত"),
+    // Case-sensitive: the capital takes the dental or the second letter
+    ("T", "ত"),
+    ("Th", "থ"),
+    ("D", "দ"),
+    ("Dh", "ধ"),
+    ("N", "ণ"),
+    ("S", "ষ"),
+    ("Sh", "ষ"),
+];
+
+// Vowel signs (কার)
+const VOWEL_SIGNS: [(&str, &str); 11] = [
+    ("E", "ে"),
+    ("O", "ো"),
+    ("kar_aa", "া"),
+    ("kar_i", "ি"),
+    ("kar_ii", "ী"),
+    ("kar_u", "ু"),
+    ("kar_uu", "ূ"),
+    ("kar_e", "ে"),
+    ("kar_oi", "ৈ"),
+    ("kar_o", "ো"),
+    ("kar_ou", "ৌ"),
+];
+
+// Vowel signs (কার) a vowel types after a consonant; অ is the inherent vowel
+// and adds nothing
+const KARS: [(&str, &str); 10] = [
+    ("অ", ""),
+    ("আ", "া"),
+    ("ই", "ি"),
+    ("ঈ", "ী"),
+    ("উ", "ু"),
+    ("ঊ", "ূ"),
+    ("এ", "ে"),
+    ("ঐ", "ৈ"),
+    ("ও", "ো"),
+    ("ঔ", "ৌ"),
+];
+
+const NUMBERS: [(&str, &str); 10] = [
+    ("0", "০"),
+    ("1", "১"),
+    ("2", "২"),
+    ("3", "৩"),
+    ("4", "৪"),
+    ("5", "৫"),
+    ("6", "৬"),
+    ("7", "৭"),
+    ("8", "৮"),
+    ("9", "৯"),
+];
+
+const SPECIALS: [(&str, &str); 5] = [
+    ("chandrabindu", "ঁ"),
+    ("anusvar", "ং"),
+    ("bisarga", "ঃ"),
+    ("hasant", "্"),
+    ("dari", "।"),
+];
+
+// Avro Phonetic vowels: roman, the vowel and its sign after a consonant
+const AVRO_VOWELS: [(&str, &str, &str); 15] = [
+    ("o", "অ", ""),
+    ("a", "আ", "া"),
+    ("i", "ই", "ি"),
+    ("I", "ঈ", "ী"),
+    ("ee", "ঈ", "ী"),
+    ("u", "উ", "ু"),
+    ("U", "ঊ", "ূ"),
+    ("oo", "উ", "ু"),
+    ("e", "এ", "ে"),
+    ("oi", "ঐ", "ৈ"),
+    ("OI", "ঐ", "ৈ"),
+    ("O", "ও", "ো"),
+    ("ou", "ঔ", "ৌ"),
+    ("OU", "ঔ", "ৌ"),
+    ("rri", "ঋ", "ৃ"),
+];
+
+// Avro Phonetic consonants; capitals are the retroflex and other second letters
+const AVRO_CONSONANTS: [(&str, &str); 37] = [
+    ("k", "ক"),
+    ("kh", "খ"),
+    ("g", "গ"),
+    ("gh", "ঘ"),
+    ("Ng", "ঙ"),
+    ("c", "চ"),
+    ("ch", "ছ"),
+    ("j", "জ"),
+    ("jh", "ঝ"),
+    ("NG", "ঞ"),
+    ("T", "ট"),
+    ("Th", "ঠ"),
+    ("D", "ড"),
+    ("Dh", "ঢ"),
+    ("N", "ণ"),
+    ("t", "ত"),
+    ("th", "থ"),
+    ("d", "দ"),
+    ("dh", "ধ"),
+    ("n", "ন"),
+    ("p", "প"),
+    ("ph", "ফ"),
+    ("f", "ফ"),
+    ("b", "ব"),
+    ("bh", "ভ"),
+    ("v", "ভ"),
+    ("m", "ম"),
+    ("z", "য"),
+    ("r", "র"),
+    ("l", "ল"),
+    ("sh", "শ"),
+    ("S", "ষ"),
+    ("s", "স"),
+    ("h", "হ"),
+    ("R", "ড়"),
+    ("Rh", "ঢ়"),
+    ("y", "য়"),
+];
+
+const AVRO_SPECIALS: [(&str, &str); 3] = [("ng", "ং"), ("x", "ক্স"), ("Z", "্য")];
+
+// Conjuncts (যুক্তাক্ষর): each consonant and the consonants it joins with
+// hasant when typed right after it. Longer clusters chain pairs: স+ত and ত+র
+// give স্ত্র. Pairs usually split by a dropped vowel (ekta, korbo) are left
+// out so those words still come out as typed.
+const CONJUNCTS: [(&str, &[&str]); 26] = [
+    ("ক", &["ক", "ত", "ব", "ম", "র", "ল", "ষ", "স", "য"]),
+    ("খ", &["য", "র"]),
+    ("গ", &["ধ", "ন", "ব", "ম", "র", "ল", "য"]),
+    ("ঘ", &["ন", "র", "য"]),
+    ("ঙ", &["ক", "খ", "গ", "ঘ"]),
+    ("চ", &["চ", "ছ", "ঞ", "য"]),
+    ("জ", &["জ", "ঝ", "ঞ", "ব", "র", "য"]),
+    ("ঞ", &["চ", "ছ", "জ", "ঝ"]),
+    ("ট", &["ট", "ব", "র", "য"]),
+    ("ড", &["ড", "র", "য"]),
+    ("ণ", &["ট", "ঠ", "ড", "ঢ", "ণ"]),
+    ("ত", &["ত", "থ", "ন", "ব", "ম", "র", "য"]),
+    ("থ", &["র", "য"]),
+    ("দ", &["দ", "ধ", "ব", "ভ", "ম", "র", "য"]),
+    ("ধ", &["ন", "ব", "র", "য"]),
+    (
+        "ন",
+        &["ট", "ঠ", "ড", "ত", "থ", "দ", "ধ", "ন", "ব", "ম", "স", "য"],
+    ),
+    ("প", &["ট", "ত", "ন", "প", "ল", "র", "স", "য"]),
+    ("ফ", &["ল", "র"]),
+    ("ব", &["জ", "দ", "ধ", "ব", "র", "য"]),
+    ("ভ", &["র", "য"]),
+    ("ম", &["ন", "প", "ফ", "ব", "ভ", "ম", "য"]),
+    // Reph before the letters it sits on most often
+    ("র", &["ত", "থ", "দ", "ধ", "ম", "শ", "ষ", "স", "য"]),
+    ("ল", &["ক", "গ", "ট", "ড", "প", "ফ", "ম", "ল"]),
+    ("শ", &["চ", "ছ", "ন", "ব", "ম", "র", "ল", "য"]),
+    ("ষ", &["ক", "ট", "ঠ", "ণ", "প", "ফ", "ম", "য"]),
+    (
+        "স",
+        &["ক", "খ", "ট", "ত", "থ", "ন", "প", "ফ", "ব", "ম", "ল", "য"],
+    ),
+];
+
+// Typed input is matched up to this many characters. Longer names such as
+// kar_aa and dari only label characters in the reference table.
+pub const MAX_TYPED_LEN: usize = 3;
+
+/// A set of roman sequences and the Bangla characters they type.
+#[derive(Clone, Debug)]
+pub struct Layout {
+    name: String,
+    rules: HashMap<String, BanglaChar>,
+    // (first, second) consonant pairs that join with hasant
+    conjuncts: HashSet<(String, String)>,
+    // Outputs that depend on the surrounding input, tried in order
+    context: HashMap<String, Vec<ContextRule>>,
+    // Whether a doubled consonant joins with hasant (kk → ক্ক)
+    gemination: bool,
+}
+
+impl Layout {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            rules: HashMap::new(),
+            conjuncts: HashSet::new(),
+            context: HashMap::new(),
+            gemination: true,
+        }
+    }
+
+    /// The built-in phonetic layout used by the keyboard.
+    pub fn phonetic() -> Self {
+        let mut layout = Self::new("Phonetic");
+        layout.insert_group(&VOWELS, BanglaChar::Vowel);
+        layout.insert_group(&CONSONANTS, BanglaChar::Consonant);
+        layout.insert_group(&VOWEL_SIGNS, BanglaChar::VowelSign);
+        layout.insert_group(&NUMBERS, BanglaChar::Number);
+        layout.insert_group(&SPECIALS, BanglaChar::Special);
+        for (roman, vowel) in VOWELS {
+            if let Some((_, kar)) = KARS.iter().find(|(v, _)| *v == vowel) {
+                let label = if kar.is_empty() {
+                    "inherent vowel after a consonant"
+                } else {
+                    "vowel sign after a consonant"
+                };
+                layout.insert_context_rule(roman, after_consonant(kar, label));
+            }
+        }
+        layout.insert_conjuncts();
+        layout
+    }
+
+    /// Avro Phonetic: case-sensitive where Avro is (t ত, T ট). Capitals of
+    /// the other letters type like the small ones, as in every layout.
+    pub fn avro() -> Self {
+        let mut layout = Self::new("Avro");
+        for (roman, vowel, kar) in AVRO_VOWELS {
+            layout.insert(roman, BanglaChar::Vowel(vowel.to_string()));
+            let label = if kar.is_empty() {
+                "inherent vowel after a consonant"
+            } else {
+                "vowel sign after a consonant"
+            };
+            layout.insert_context_rule(roman, after_consonant(kar, label));
+        }
+        layout.insert_group(&AVRO_CONSONANTS, BanglaChar::Consonant);
+        // য-ফলা: y after a consonant (kyi → ক্যি)
+        layout.insert_context_rule("y", after_consonant("্য", "য-ফলা after a consonant"));
+        layout.insert_group(&AVRO_SPECIALS, BanglaChar::Special);
+        layout.insert_group(&NUMBERS, BanglaChar::Number);
+        layout.insert_conjuncts();
+        layout
+    }
+
+    fn insert_conjuncts(&mut self) {
+        for (first, seconds) in CONJUNCTS {
+            for second in seconds {
+                self.insert_conjunct(first, second);
+            }
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Whether doubled consonants join in this layout unless the engine is
+    /// told otherwise. On for every built-in layout; layouts where doubling
+    /// a letter means something else turn it off.
+    pub fn gemination(&self) -> bool {
+        self.gemination
+    }
+
+    pub fn set_gemination(&mut self, gemination: bool) {
+        self.gemination = gemination;
+    }
+
+    pub fn insert(&mut self, roman: &str, bangla: BanglaChar) {
+        self.rules.insert(roman.to_string(), bangla);
+    }
+
+    fn insert_group(&mut self, rules: &[(&str, &str)], kind: fn(String) -> BanglaChar) {
+        for (roman, bangla) in rules {
+            self.insert(roman, kind(bangla.to_string()));
+        }
+    }
+
+    /// Lets `second` typed right after `first` join it as a conjunct.
+    pub fn insert_conjunct(&mut self, first: &str, second: &str) {
+        self.conjuncts
+            .insert((first.to_string(), second.to_string()));
+    }
+
+    /// Whether the consonant `second` joins `first` with hasant.
+    pub fn joins(&self, first: &str, second: &str) -> bool {
+        self.conjuncts
+            .contains(&(first.to_string(), second.to_string()))
+    }
+
+    /// Adds a rule tried before the plain output of `roman`. Rules added
+    /// first win.
+    pub fn insert_context_rule(&mut self, roman: &str, rule: ContextRule) {
+        self.context
+            .entry(roman.to_string())
+            .or_default()
+            .push(rule);
+    }
+
+    pub fn context_rules(&self, roman: &str) -> &[ContextRule] {
+        self.context
+            .get(self.key(roman).as_ref())
+            .map_or(&[], Vec::as_slice)
+    }
+
+    /// Every conjunct pair as (first, second), in no particular order.
+    pub fn conjuncts(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.conjuncts
+            .iter()
+            .map(|(first, second)| (first.as_str(), second.as_str()))
+    }
+
+    /// Whether some capital types something of its own (T ত, t ট), so a
+    /// word typed with capitals is another word.
+    pub fn is_case_sensitive(&self) -> bool {
+        self.rules
+            .keys()
+            .any(|roman| roman.contains(|c: char| c.is_ascii_uppercase()))
+    }
+
+    pub fn get(&self, roman: &str) -> Option<&BanglaChar> {
+        self.rules.get(self.key(roman).as_ref())
+    }
+
+    // The rule `roman` types with. A capital no rule starts with types like
+    // the small letter, as in Avro: Kh is kh, but Th keeps its own rule.
+    fn key<'r>(&self, roman: &'r str) -> Cow<'r, str> {
+        if self.rules.contains_key(roman) || !roman.contains(|c: char| c.is_ascii_uppercase()) {
+            return Cow::Borrowed(roman);
+        }
+        roman
+            .chars()
+            .map(|c| {
+                if c.is_ascii_uppercase() && !self.rules.keys().any(|key| key.starts_with(c)) {
+                    c.to_ascii_lowercase()
+                } else {
+                    c
+                }
+            })
+            .collect()
+    }
+
+    /// Every rule as (roman, Bangla character), in no particular order.
+    pub fn rules(&self) -> impl Iterator<Item = (&str, &BanglaChar)> {
+        self.rules
+            .iter()
+            .map(|(roman, bangla)| (roman.as_str(), bangla))
+    }
+
+    /// The longest rule `input` starts with, along with its roman sequence.
+    /// Capitals without rules of their own match as small letters.
+    pub fn longest_match<'a>(&self, input: &'a str) -> Option<(&'a str, &BanglaChar)> {
+        (1..=MAX_TYPED_LEN.min(input.len())).rev().find_map(|len| {
+            let roman = input.get(..len)?;
+            Some((roman, self.get(roman)?))
+        })
+    }
+}
+
+// What a sequence types right after a consonant
+fn after_consonant(output: &str, label: &'static str) -> ContextRule {
+    ContextRule::new(
+        vec![Condition::new(Side::Prefix, Scope::Consonant)],
+        output,
+        label,
+    )
+}
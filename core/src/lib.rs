@@ -0,0 +1,42 @@
+//! The phonetic Bangla engine of Restro Keyboard, without the GUI or the
+//! Windows hook.
+//!
+//! ```
+//! use restro_keyboard_core::{Composer, Layout, Transliterator};
+//!
+//! let layout = Layout::phonetic();
+//! let engine = Transliterator::new(&layout);
+//! assert_eq!(engine.transliterate("kk"), "ক্ক");
+//!
+//! // Typing key by key: "k" types ক, then "h" erases it and types খ
+//! let mut composer = Composer::new();
+//! assert_eq!(composer.push(&engine, "k"), Some(("ক".to_string(), 0)));
+//! assert_eq!(composer.push(&engine, "h"), Some(("খ".to_string(), 1)));
+//! ```
+//!
+//! The types re-exported here are the stable API and follow semver. Structs
+//! with public fields are `#[non_exhaustive]`, so fields can be added in a
+//! minor release.
+
+pub mod collation;
+#[cfg(feature = "compact")]
+mod compact;
+mod composer;
+mod dictionary;
+mod fixed;
+mod layout;
+pub mod reference;
+mod romanize;
+mod rules;
+mod transliterator;
+mod word_list;
+
+#[cfg(feature = "compact")]
+pub use compact::CompactDictionary;
+pub use composer::{process_keyboard_input, Composer};
+pub use dictionary::Dictionary;
+pub use fixed::{FixedComposer, FixedLayout, Layer};
+pub use layout::{BanglaChar, Layout, MAX_TYPED_LEN};
+pub use rules::{Condition, ContextRule, Previous, Scope, Side};
+pub use transliterator::{Candidate, Conversion, Step, Transliterator};
+pub use word_list::WordList;
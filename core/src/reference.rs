@@ -0,0 +1,234 @@
+use crate::collation;
+use crate::layout::{BanglaChar, Layout};
+use crate::transliterator::Transliterator;
+use std::collections::BTreeSet;
+
+// Section order and headings of the reference
+const CATEGORIES: [&str; 5] = ["Vowels", "Consonants", "Vowel signs", "Numbers", "Symbols"];
+
+// Whole words shown at the end, converted with the current options
+const SAMPLE_WORDS: [&str; 6] = ["ami", "bangla", "bhalo", "kotha", "pakka", "shompurno"];
+
+struct Row {
+    roman: String,
+    bangla: String,
+    // Typed input and its output, when a rule reads differently in a word
+    example: Option<(String, String)>,
+    typed: bool,
+}
+
+impl BanglaChar {
+    /// The reference section the character is listed under.
+    pub fn category(&self) -> &'static str {
+        match self {
+            BanglaChar::Vowel(_) => CATEGORIES[0],
+            BanglaChar::Consonant(_) => CATEGORIES[1],
+            BanglaChar::VowelSign(_) => CATEGORIES[2],
+            BanglaChar::Number(_) => CATEGORIES[3],
+            BanglaChar::Special(_) => CATEGORIES[4],
+        }
+    }
+}
+
+fn rows(engine: &Transliterator, category: &str) -> Vec<Row> {
+    let layout = engine.layout();
+    let mut rows: Vec<Row> = layout
+        .rules()
+        .filter(|(_, bangla)| bangla.category() == category)
+        .map(|(roman, bangla)| {
+            let example = match bangla {
+                // A vowel after a consonant becomes its sign: ki → কি
+                BanglaChar::Vowel(_) => Some(format!("k{}", roman)),
+                BanglaChar::Consonant(_) => Some(format!("{}o", roman)),
+                _ => None,
+            }
+            .map(|typed| {
+                let output = engine.transliterate(&typed);
+                (typed, output)
+            });
+            Row {
+                roman: roman.to_string(),
+                bangla: bangla.text().to_string(),
+                example,
+                // Names longer than the typed match only label the character
+                typed: layout
+                    .longest_match(roman)
+                    .is_some_and(|(matched, _)| matched == roman),
+            }
+        })
+        .collect();
+    // Dictionary order of the output; rules typing the same text by roman
+    rows.sort_by(|a, b| {
+        collation::compare(&a.bangla, &b.bangla).then_with(|| a.roman.cmp(&b.roman))
+    });
+    rows
+}
+
+// Conjuncts grouped by their first consonant, e.g. ("ক", "ক্ক, ক্ত")
+fn conjuncts(engine: &Transliterator) -> Vec<(String, String)> {
+    let mut pairs: Vec<(&str, &str)> = engine.layout().conjuncts().collect();
+    pairs.sort_by(|a, b| collation::compare(a.0, b.0).then_with(|| collation::compare(a.1, b.1)));
+    let mut groups: Vec<(String, String)> = Vec::new();
+    for (first, second) in pairs {
+        let cluster = format!("{}্{}", first, second);
+        match groups.last_mut() {
+            Some((group, clusters)) if group == first => {
+                clusters.push_str(", ");
+                clusters.push_str(&cluster);
+            }
+            _ => groups.push((first.to_string(), cluster)),
+        }
+    }
+    groups
+}
+
+fn samples(engine: &Transliterator) -> Vec<(&'static str, String)> {
+    SAMPLE_WORDS
+        .iter()
+        .map(|word| (*word, engine.transliterate(word)))
+        .collect()
+}
+
+/// A Markdown reference of every rule in the engine's layout, grouped by
+/// category, with examples converted by the engine itself.
+pub fn markdown(engine: &Transliterator) -> String {
+    let mut doc = format!("# {} layout\n", engine.layout().name());
+
+    for category in CATEGORIES {
+        let rows = rows(engine, category);
+        if rows.is_empty() {
+            continue;
+        }
+        doc.push_str(&format!("\n## {}\n\n", category));
+        doc.push_str("| Type | Bangla | Example |\n|---|---|---|\n");
+        for row in rows {
+            let roman = if row.typed {
+                format!("`{}`", row.roman)
+            } else {
+                format!("{} (name only)", row.roman)
+            };
+            let example = row
+                .example
+                .map(|(typed, output)| format!("`{}` → {}", typed, output))
+                .unwrap_or_default();
+            doc.push_str(&format!("| {} | {} | {} |\n", roman, row.bangla, example));
+        }
+    }
+
+    let conjuncts = conjuncts(engine);
+    if !conjuncts.is_empty() {
+        doc.push_str("\n## Conjuncts\n\n");
+        for (first, clusters) in conjuncts {
+            doc.push_str(&format!("- {}: {}\n", first, clusters));
+        }
+    }
+
+    doc.push_str("\n## Examples\n\n");
+    for (word, output) in samples(engine) {
+        doc.push_str(&format!("- `{}` → {}\n", word, output));
+    }
+    doc
+}
+
+/// The same reference as [`markdown`], as a standalone HTML page.
+pub fn html(engine: &Transliterator) -> String {
+    let name = escape(engine.layout().name());
+    let mut doc = format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n\
+         <title>{name} layout</title>\n</head>\n<body>\n<h1>{name} layout</h1>\n"
+    );
+
+    for category in CATEGORIES {
+        let rows = rows(engine, category);
+        if rows.is_empty() {
+            continue;
+        }
+        doc.push_str(&format!("<h2>{}</h2>\n<table>\n", category));
+        doc.push_str("<tr><th>Type</th><th>Bangla</th><th>Example</th></tr>\n");
+        for row in rows {
+            let roman = if row.typed {
+                format!("<code>{}</code>", escape(&row.roman))
+            } else {
+                format!("{} (name only)", escape(&row.roman))
+            };
+            let example = row
+                .example
+                .map(|(typed, output)| {
+                    format!("<code>{}</code> → {}", escape(&typed), escape(&output))
+                })
+                .unwrap_or_default();
+            doc.push_str(&format!(
+                "<tr><td>{}</td><td>{}</td><td>{}</td></tr>\n",
+                roman,
+                escape(&row.bangla),
+                example
+            ));
+        }
+        doc.push_str("</table>\n");
+    }
+
+    let conjuncts = conjuncts(engine);
+    if !conjuncts.is_empty() {
+        doc.push_str("<h2>Conjuncts</h2>\n<ul>\n");
+        for (first, clusters) in conjuncts {
+            doc.push_str(&format!(
+                "<li>{}: {}</li>\n",
+                escape(&first),
+                escape(&clusters)
+            ));
+        }
+        doc.push_str("</ul>\n");
+    }
+
+    doc.push_str("<h2>Examples</h2>\n<ul>\n");
+    for (word, output) in samples(engine) {
+        doc.push_str(&format!(
+            "<li><code>{}</code> → {}</li>\n",
+            word,
+            escape(&output)
+        ));
+    }
+    doc.push_str("</ul>\n</body>\n</html>\n");
+    doc
+}
+
+/// What a roman sequence types in two layouts, None where a layout has no
+/// rule for it.
+#[derive(Clone, PartialEq, Eq, Debug)]
+#[non_exhaustive]
+pub struct Comparison {
+    pub roman: String,
+    pub left: Option<String>,
+    pub right: Option<String>,
+}
+
+impl Comparison {
+    pub fn differs(&self) -> bool {
+        self.left != self.right
+    }
+}
+
+/// Every roman sequence of either layout in sorted order, with what it
+/// types in each, for viewing two layouts or two revisions side by side.
+pub fn compare(left: &Layout, right: &Layout) -> Vec<Comparison> {
+    let romans: BTreeSet<&str> = left
+        .rules()
+        .chain(right.rules())
+        .map(|(roman, _)| roman)
+        .collect();
+    romans
+        .into_iter()
+        .map(|roman| Comparison {
+            roman: roman.to_string(),
+            left: left.get(roman).map(|bangla| bangla.text().to_string()),
+            right: right.get(roman).map(|bangla| bangla.text().to_string()),
+        })
+        .collect()
+}
+
+// Custom layouts may use markup characters in their rules
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
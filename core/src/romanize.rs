@@ -0,0 +1,191 @@
+use crate::layout::MAX_TYPED_LEN;
+use crate::transliterator::Transliterator;
+use std::collections::HashMap;
+
+const HASANT: char = '্';
+
+// Roman sequences by the Bangla they type, plainest first: no capitals,
+// then shortest. Context outputs count too, so vowel signs are found.
+fn typings(engine: &Transliterator) -> HashMap<String, Vec<String>> {
+    let layout = engine.layout();
+    let mut typings: HashMap<String, Vec<String>> = HashMap::new();
+    for (roman, bangla) in layout.rules() {
+        // Longer names only label characters in the reference table
+        if roman.len() > MAX_TYPED_LEN {
+            continue;
+        }
+        let outputs = std::iter::once(bangla.text()).chain(
+            layout
+                .context_rules(roman)
+                .iter()
+                .map(|rule| rule.output.as_str()),
+        );
+        for output in outputs {
+            let romans = typings.entry(output.to_string()).or_default();
+            if !romans.iter().any(|r| r == roman) {
+                romans.push(roman.to_string());
+            }
+        }
+    }
+    for romans in typings.values_mut() {
+        romans.sort_by_key(|roman| {
+            (
+                roman.chars().any(|c| c.is_ascii_uppercase()),
+                roman.len(),
+                roman.clone(),
+            )
+        });
+    }
+    typings
+}
+
+fn is_bangla(c: char) -> bool {
+    matches!(c, '\u{0980}'..='\u{09FF}' | '\u{200C}' | '\u{200D}')
+}
+
+impl Transliterator<'_> {
+    /// The keys that type a Bangla word with this engine, so pasted text
+    /// shows how to type it. Each piece of the word takes the plainest keys
+    /// that give the word back so far, with the inherent vowel typed in
+    /// between where keys would otherwise join (কহ is kah, not kh). Where no
+    /// keys give the word back, such as a vowel letter after a consonant
+    /// (কই), the plainest keys are used anyway.
+    ///
+    /// ```
+    /// use restro_keyboard_core::{Layout, Transliterator};
+    ///
+    /// let layout = Layout::phonetic();
+    /// let engine = Transliterator::new(&layout);
+    /// assert_eq!(engine.romanize("আমি"), "aami");
+    /// assert_eq!(engine.transliterate(&engine.romanize("কথা")), "কথা");
+    /// ```
+    pub fn romanize(&self, bangla: &str) -> String {
+        self.romanize_with(&typings(self), bangla)
+    }
+
+    fn romanize_with(&self, typings: &HashMap<String, Vec<String>>, bangla: &str) -> String {
+        let longest = typings
+            .keys()
+            .map(|text| text.chars().count())
+            .max()
+            .unwrap_or(1);
+        // Keys that type the inherent vowel, to keep consonants apart
+        let inherent = typings.get("").map_or(&[][..], Vec::as_slice);
+
+        let chars: Vec<char> = bangla.chars().collect();
+        let mut roman = String::new();
+        let mut start = 0;
+        while start < chars.len() {
+            // A consonant after hasant is typed on its own and joins
+            let skip = usize::from(chars[start] == HASANT && start + 1 < chars.len());
+            let piece = (1..=longest.min(chars.len() - start - skip))
+                .rev()
+                .find_map(|len| {
+                    let text: String = chars[start + skip..start + skip + len].iter().collect();
+                    typings.get(&text).map(|romans| (skip + len, romans))
+                });
+            let Some((len, romans)) = piece else {
+                roman.push(chars[start]);
+                start += 1;
+                continue;
+            };
+            start += len;
+
+            let target: String = chars[..start].iter().collect();
+            let gives_target = |keys: &str| self.transliterate(keys) == target;
+            let separated = inherent
+                .iter()
+                .flat_map(|separator| romans.iter().map(move |r| format!("{}{}", separator, r)));
+            let keys = romans
+                .iter()
+                .cloned()
+                .chain(separated)
+                .find(|keys| gives_target(&format!("{}{}", roman, keys)))
+                .unwrap_or_else(|| romans[0].clone());
+            roman.push_str(&keys);
+        }
+        roman
+    }
+
+    /// Romanizes running text: Bangla words go through [`Self::romanize`],
+    /// everything else is kept as is.
+    pub fn romanize_text(&self, text: &str) -> String {
+        let typings = typings(self);
+        let mut output = String::new();
+        let mut word = String::new();
+        for c in text.chars() {
+            if is_bangla(c) {
+                word.push(c);
+                continue;
+            }
+            output.push_str(&self.romanize_with(&typings, &word));
+            word.clear();
+            output.push(c);
+        }
+        output.push_str(&self.romanize_with(&typings, &word));
+        output
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Layout, Transliterator};
+
+    // Not বই: no keys type a vowel letter after a consonant
+    const WORDS: [&str; 13] = [
+        "আমি",
+        "তুমি",
+        "কথা",
+        "ভালো",
+        "মানুষ",
+        "ঘর",
+        "কহ",
+        "পাখি",
+        "দেশ",
+        "নদী",
+        "রাস্তা",
+        "ইচ্ছা",
+        "চাকরি",
+    ];
+
+    #[test]
+    fn romanized_words_type_back_the_same() {
+        for layout in [Layout::phonetic(), Layout::avro()] {
+            let engine = Transliterator::new(&layout);
+            for word in WORDS {
+                let roman = engine.romanize(word);
+                assert_eq!(
+                    engine.transliterate(&roman),
+                    word,
+                    "{} in {}",
+                    roman,
+                    layout.name()
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn romanized_keys_are_the_plainest() {
+        let layout = Layout::avro();
+        let engine = Transliterator::new(&layout);
+        assert_eq!(engine.romanize("আমি"), "ami");
+        // No vowel where the consonants don't join anyway
+        assert_eq!(engine.romanize("কথা"), "ktha");
+        assert_eq!(engine.romanize("কহ"), "koh");
+        assert!(!engine
+            .romanize("পাখি")
+            .contains(|c: char| c.is_ascii_uppercase()));
+    }
+
+    #[test]
+    fn running_text_keeps_what_isnt_bangla() {
+        let layout = Layout::phonetic();
+        let engine = Transliterator::new(&layout);
+        let text = "আমি, তুমি (দেশ)!";
+        let roman = engine.romanize_text(text);
+        assert_eq!(roman.matches(|c: char| c.is_ascii_punctuation()).count(), 4);
+        assert!(roman.is_ascii());
+        assert_eq!(engine.transliterate_text(&roman), text);
+    }
+}
@@ -0,0 +1,254 @@
+/// What a condition looks for in the typed input, like the scopes of Avro
+/// Phonetic's rules. After the sequence, vowels are a, e, i, o and u in
+/// either case and consonants are the other letters. Before it, they are
+/// what the previous sequence typed, so a letter the layout has no rule for
+/// (w) or the ং of ng is no consonant to put a vowel sign on.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum Scope {
+    Vowel,
+    Consonant,
+    // Anything but a letter, or the edge of the word
+    Punctuation,
+    Exact(String),
+}
+
+/// Whether a condition looks at the input before the sequence or after it.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Side {
+    Prefix,
+    Suffix,
+}
+
+/// What the sequence before the one being matched typed.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Previous {
+    Vowel,
+    Consonant,
+    // Anything else, or nothing at the start of the word
+    Other,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+#[non_exhaustive]
+pub struct Condition {
+    pub side: Side,
+    pub scope: Scope,
+    pub negated: bool,
+}
+
+/// Output for a sequence that replaces the plain rule when all conditions
+/// hold, e.g. "i" after a consonant types ি instead of ই.
+#[derive(Clone, PartialEq, Eq, Debug)]
+#[non_exhaustive]
+pub struct ContextRule {
+    pub conditions: Vec<Condition>,
+    pub output: String,
+    // Shown in the conversion steps
+    pub label: &'static str,
+}
+
+fn is_vowel(c: char) -> bool {
+    matches!(c.to_ascii_lowercase(), 'a' | 'e' | 'i' | 'o' | 'u')
+}
+
+impl Condition {
+    pub fn new(side: Side, scope: Scope) -> Self {
+        Self {
+            side,
+            scope,
+            negated: false,
+        }
+    }
+
+    pub fn not(side: Side, scope: Scope) -> Self {
+        Self {
+            negated: true,
+            ..Self::new(side, scope)
+        }
+    }
+
+    /// Checks the condition against the input around a matched sequence
+    /// and what the sequence before it typed.
+    pub fn holds(&self, before: &str, after: &str, previous: Previous) -> bool {
+        let next = match self.side {
+            Side::Prefix => before.chars().next_back(),
+            Side::Suffix => after.chars().next(),
+        };
+        let found = match (&self.scope, self.side) {
+            (Scope::Vowel, Side::Prefix) => previous == Previous::Vowel,
+            (Scope::Consonant, Side::Prefix) => previous == Previous::Consonant,
+            (Scope::Vowel, Side::Suffix) => {
+                next.is_some_and(|c| c.is_ascii_alphabetic() && is_vowel(c))
+            }
+            (Scope::Consonant, Side::Suffix) => {
+                next.is_some_and(|c| c.is_ascii_alphabetic() && !is_vowel(c))
+            }
+            (Scope::Punctuation, _) => !next.is_some_and(|c| c.is_ascii_alphabetic()),
+            (Scope::Exact(text), _) => match self.side {
+                Side::Prefix => before.ends_with(text.as_str()),
+                Side::Suffix => after.starts_with(text.as_str()),
+            },
+        };
+        found != self.negated
+    }
+}
+
+impl ContextRule {
+    pub fn new(conditions: Vec<Condition>, output: &str, label: &'static str) -> Self {
+        Self {
+            conditions,
+            output: output.to_string(),
+            label,
+        }
+    }
+
+    pub fn applies(&self, before: &str, after: &str, previous: Previous) -> bool {
+        self.conditions
+            .iter()
+            .all(|condition| condition.holds(before, after, previous))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BanglaChar, Layout, Transliterator};
+
+    #[test]
+    fn scopes_look_at_the_next_letter_on_their_side() {
+        let vowel_before = Condition::new(Side::Prefix, Scope::Vowel);
+        assert!(vowel_before.holds("ka", "t", Previous::Vowel));
+        assert!(vowel_before.holds("kA", "", Previous::Vowel));
+        assert!(!vowel_before.holds("k", "a", Previous::Consonant));
+        assert!(!vowel_before.holds("", "a", Previous::Other));
+
+        let consonant_after = Condition::new(Side::Suffix, Scope::Consonant);
+        assert!(consonant_after.holds("", "ta", Previous::Other));
+        assert!(!consonant_after.holds("t", "a", Previous::Other));
+        assert!(!consonant_after.holds("t", "1", Previous::Other));
+        assert!(!consonant_after.holds("t", "", Previous::Other));
+
+        let exact_after = Condition::new(Side::Suffix, Scope::Exact("h".to_string()));
+        assert!(exact_after.holds("", "ha", Previous::Other));
+        assert!(!exact_after.holds("h", "a", Previous::Other));
+        let exact_before = Condition::new(Side::Prefix, Scope::Exact("k".to_string()));
+        assert!(exact_before.holds("ok", "", Previous::Other));
+        assert!(!exact_before.holds("ko", "", Previous::Other));
+    }
+
+    #[test]
+    fn the_edge_of_the_word_counts_as_punctuation() {
+        let start = Condition::new(Side::Prefix, Scope::Punctuation);
+        assert!(start.holds("", "ami", Previous::Other));
+        assert!(start.holds("ki ", "o", Previous::Other));
+        assert!(start.holds("2", "o", Previous::Other));
+        assert!(!start.holds("k", "o", Previous::Consonant));
+
+        let end = Condition::new(Side::Suffix, Scope::Punctuation);
+        assert!(end.holds("k", "", Previous::Other));
+        assert!(end.holds("k", ", ", Previous::Other));
+        assert!(!end.holds("k", "a", Previous::Other));
+    }
+
+    #[test]
+    fn negated_conditions_hold_where_the_plain_ones_dont() {
+        let not_vowel_before = Condition::not(Side::Prefix, Scope::Vowel);
+        assert!(not_vowel_before.holds("", "", Previous::Other));
+        assert!(not_vowel_before.holds("k", "", Previous::Consonant));
+        assert!(!not_vowel_before.holds("a", "", Previous::Vowel));
+    }
+
+    #[test]
+    fn prefix_scopes_follow_what_was_typed() {
+        let consonant_before = Condition::new(Side::Prefix, Scope::Consonant);
+        assert!(consonant_before.holds("k", "", Previous::Consonant));
+        // A letter with no rule, or ং, is no consonant
+        assert!(!consonant_before.holds("w", "", Previous::Other));
+        assert!(!consonant_before.holds("ng", "", Previous::Other));
+        // An inherent vowel types nothing but is still a vowel
+        let vowel_before = Condition::new(Side::Prefix, Scope::Vowel);
+        assert!(vowel_before.holds("ko", "", Previous::Vowel));
+    }
+
+    #[test]
+    fn a_rule_applies_only_when_every_condition_holds() {
+        let rule = ContextRule::new(
+            vec![
+                Condition::new(Side::Prefix, Scope::Consonant),
+                Condition::new(Side::Suffix, Scope::Punctuation),
+            ],
+            "",
+            "inherent vowel at the end of a word",
+        );
+        assert!(rule.applies("k", "", Previous::Consonant));
+        assert!(rule.applies("k", " ", Previous::Consonant));
+        assert!(!rule.applies("k", "t", Previous::Consonant));
+        assert!(!rule.applies("a", "", Previous::Vowel));
+        // No conditions: always
+        assert!(ContextRule::new(Vec::new(), "", "always").applies("", "", Previous::Other));
+    }
+
+    #[test]
+    fn vowels_turn_into_signs_after_consonants() {
+        let layout = Layout::avro();
+        let engine = Transliterator::new(&layout);
+        assert_eq!(engine.transliterate("i"), "ই");
+        assert_eq!(engine.transliterate("ki"), "কি");
+        assert_eq!(engine.transliterate("aI"), "আঈ");
+        assert_eq!(engine.transliterate("kaI"), "কাঈ");
+        // য-ফলা only after a consonant
+        assert_eq!(engine.transliterate("y"), "য়");
+        assert_eq!(engine.transliterate("ky"), "ক্য");
+        assert_eq!(engine.transliterate("ay"), "আয়");
+    }
+
+    #[test]
+    fn vowels_after_unknown_letters_and_signs_stay_vowels() {
+        let layout = Layout::phonetic();
+        let engine = Transliterator::new(&layout);
+        assert_eq!(engine.transliterate("wa"), "wঅ");
+        assert_eq!(engine.transliterate("qi"), "qই");
+        assert_eq!(engine.transliterate("xo"), "xও");
+
+        let layout = Layout::avro();
+        let engine = Transliterator::new(&layout);
+        assert_eq!(engine.transliterate("wa"), "wআ");
+        assert_eq!(engine.transliterate("nga"), "ংআ");
+        assert_eq!(engine.transliterate("koe"), "কএ");
+        // Signs that end in a consonant still take a vowel sign
+        assert_eq!(engine.transliterate("xa"), "ক্সা");
+        assert_eq!(engine.transliterate("kZa"), "ক্যা");
+        assert_eq!(engine.transliterate("Ra"), "ড়া");
+    }
+
+    #[test]
+    fn rules_can_apply_at_the_start_of_a_word() {
+        let mut layout = Layout::new("Test");
+        layout.insert("o", BanglaChar::Vowel("অ".to_string()));
+        layout.insert("k", BanglaChar::Consonant("ক".to_string()));
+        layout.insert_context_rule(
+            "o",
+            ContextRule::new(
+                vec![Condition::new(Side::Prefix, Scope::Punctuation)],
+                "ও",
+                "o at the start of a word",
+            ),
+        );
+        layout.insert_context_rule(
+            "o",
+            ContextRule::new(
+                vec![Condition::new(Side::Prefix, Scope::Consonant)],
+                "",
+                "inherent vowel after a consonant",
+            ),
+        );
+        let engine = Transliterator::new(&layout);
+        assert_eq!(engine.transliterate("o"), "ও");
+        assert_eq!(engine.transliterate("ko"), "ক");
+        // Neither: after a vowel the plain rule types
+        assert_eq!(engine.transliterate("oo"), "ওঅ");
+        let conversion = engine.convert("ok");
+        assert_eq!(conversion.steps[0].rule, "o at the start of a word");
+        assert_eq!(conversion.output, "ওক");
+    }
+}
@@ -0,0 +1,342 @@
+use crate::layout::{BanglaChar, Layout};
+use crate::rules::Previous;
+
+// Clusters longer than this (স্ত্র) start over with a plain consonant
+const MAX_CLUSTER: usize = 3;
+
+const KEPT_AS_TYPED: &str = "not in the layout, kept as typed";
+
+/// One matched piece of the input, what it became and the rule that applied.
+#[derive(Clone, PartialEq, Eq, Debug)]
+#[non_exhaustive]
+pub struct Step {
+    pub roman: String,
+    pub output: String,
+    pub rule: &'static str,
+}
+
+/// The result of converting a roman word.
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+#[non_exhaustive]
+pub struct Conversion {
+    pub output: String,
+    pub steps: Vec<Step>,
+}
+
+/// A layout rule offered for a search, e.g. "kh" → "খ" for "k".
+#[derive(Clone, PartialEq, Eq, Debug)]
+#[non_exhaustive]
+pub struct Candidate {
+    pub roman: String,
+    pub bangla: String,
+}
+
+/// Converts roman text to Bangla with a layout.
+#[derive(Clone, Copy, Debug)]
+pub struct Transliterator<'a> {
+    layout: &'a Layout,
+    gemination: bool,
+}
+
+impl<'a> Transliterator<'a> {
+    pub fn new(layout: &'a Layout) -> Self {
+        Self {
+            layout,
+            gemination: layout.gemination(),
+        }
+    }
+
+    /// Whether a doubled consonant joins with hasant (kk → ক্ক). The
+    /// layout's own choice by default.
+    pub fn with_gemination(mut self, gemination: bool) -> Self {
+        self.gemination = gemination;
+        self
+    }
+
+    pub fn layout(&self) -> &'a Layout {
+        self.layout
+    }
+
+    /// Splits a word into the longest known sequences and records how each
+    /// one was converted.
+    pub fn convert(&self, input: &str) -> Conversion {
+        let mut conversion = Conversion::default();
+        let mut prev_consonant: Option<(&str, &str)> = None;
+        let mut geminated = false;
+        // Consonants in the cluster being typed
+        let mut cluster = 0;
+        let mut rest = input;
+
+        while let Some(ch) = rest.chars().next() {
+            let Some((roman, bangla_char)) = self.layout.longest_match(rest) else {
+                // Unknown input passes through as typed
+                conversion.push(Step {
+                    roman: ch.to_string(),
+                    output: ch.to_string(),
+                    rule: KEPT_AS_TYPED,
+                });
+                prev_consonant = None;
+                rest = &rest[ch.len_utf8()..];
+                continue;
+            };
+
+            // The layout's context rules decide what the sequence types here
+            let before = &input[..input.len() - rest.len()];
+            let after = &rest[roman.len()..];
+            let previous = conversion.steps.last().map_or(Previous::Other, Step::typed);
+            let context = self
+                .layout
+                .context_rules(roman)
+                .iter()
+                .find(|rule| rule.applies(before, after, previous));
+
+            let (mut output, mut rule) = match bangla_char {
+                BanglaChar::Consonant(c) => {
+                    let step = match prev_consonant {
+                        // Gemination: a doubled consonant joins with hasant (kk → ক্ক)
+                        Some((prev, _))
+                            if prev == roman
+                                && self.gemination
+                                && !geminated
+                                && cluster < MAX_CLUSTER =>
+                        {
+                            geminated = true;
+                            cluster += 1;
+                            (format!("্{}", c), "doubled consonant, joined with hasant")
+                        }
+                        // Pairs the layout lists join the same way (mp → ম্প);
+                        // doubled ones are left to the gemination setting
+                        Some((prev_roman, prev))
+                            if prev_roman != roman
+                                && cluster < MAX_CLUSTER
+                                && self.layout.joins(prev, c) =>
+                        {
+                            geminated = false;
+                            cluster += 1;
+                            (format!("্{}", c), "conjunct, joined with hasant")
+                        }
+                        _ => {
+                            geminated = false;
+                            cluster = 1;
+                            (c.clone(), "consonant")
+                        }
+                    };
+                    prev_consonant = Some((roman, c));
+                    step
+                }
+                BanglaChar::Vowel(c) => {
+                    prev_consonant = None;
+                    (c.clone(), "vowel")
+                }
+                BanglaChar::VowelSign(c) => {
+                    prev_consonant = None;
+                    (c.clone(), "vowel sign")
+                }
+                BanglaChar::Number(c) => {
+                    prev_consonant = None;
+                    (c.clone(), "digit")
+                }
+                BanglaChar::Special(c) => {
+                    prev_consonant = None;
+                    (c.clone(), "symbol")
+                }
+            };
+            if let Some(context) = context {
+                output = context.output.clone();
+                rule = context.label;
+            }
+            conversion.push(Step {
+                roman: roman.to_string(),
+                output,
+                rule,
+            });
+            rest = &rest[roman.len()..];
+        }
+
+        conversion
+    }
+
+    pub fn transliterate(&self, input: &str) -> String {
+        self.convert(input).output
+    }
+
+    /// Converts running text: letters and digits go through the engine word
+    /// by word, everything else is kept as is. Case matters, as when typing.
+    pub fn transliterate_text(&self, text: &str) -> String {
+        let mut output = String::new();
+        let mut word = String::new();
+
+        for c in text.chars() {
+            if c.is_ascii_alphanumeric() {
+                word.push(c);
+                continue;
+            }
+            output.push_str(&self.transliterate(&word));
+            word.clear();
+            output.push(c);
+        }
+        output.push_str(&self.transliterate(&word));
+
+        output
+    }
+
+    /// Layout rules whose roman sequence contains `query`, shortest first so
+    /// the closest match is on top.
+    pub fn candidates(&self, query: &str) -> Vec<Candidate> {
+        let query = query.to_lowercase();
+        let mut candidates: Vec<Candidate> = self
+            .layout
+            .rules()
+            .filter(|(roman, _)| roman.to_lowercase().contains(&query))
+            .map(|(roman, bangla)| Candidate {
+                roman: roman.to_string(),
+                bangla: bangla.text().to_string(),
+            })
+            .collect();
+        candidates.sort_by(|a, b| {
+            a.roman
+                .len()
+                .cmp(&b.roman.len())
+                .then_with(|| a.roman.cmp(&b.roman))
+        });
+        candidates
+    }
+}
+
+impl Conversion {
+    fn push(&mut self, step: Step) {
+        self.output.push_str(&step.output);
+        self.steps.push(step);
+    }
+
+    /// Steps whose input the layout has no rule for and which were kept as
+    /// typed, e.g. the w in "bishw".
+    pub fn unmatched(&self) -> impl Iterator<Item = &Step> {
+        self.steps.iter().filter(|step| step.is_unmatched())
+    }
+}
+
+impl Step {
+    pub fn is_unmatched(&self) -> bool {
+        self.rule == KEPT_AS_TYPED
+    }
+
+    // What the step leaves a following vowel after. Nothing typed is the
+    // inherent vowel; ্য of য-ফলা and a nukta (ড়) end in a consonant.
+    fn typed(&self) -> Previous {
+        if self.is_unmatched() {
+            return Previous::Other;
+        }
+        match self.output.chars().next_back() {
+            None => Previous::Vowel,
+            Some('\u{0995}'..='\u{09B9}' | '\u{09BC}' | 'ৎ' | '\u{09DC}'..='\u{09DF}') => {
+                Previous::Consonant
+            }
+            Some('\u{0985}'..='\u{0994}' | '\u{09BE}'..='\u{09CC}' | 'ৗ') => Previous::Vowel,
+            Some(_) => Previous::Other,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn doubled_consonants_join_by_default() {
+        let layout = Layout::phonetic();
+        let engine = Transliterator::new(&layout);
+        assert_eq!(engine.transliterate("kk"), "ক্ক");
+        assert_eq!(engine.transliterate("ll"), "ল্ল");
+        assert_eq!(engine.transliterate("mm"), "ম্ম");
+        // A third one starts over
+        assert_eq!(engine.transliterate("kkk"), "ক্কক");
+    }
+
+    #[test]
+    fn gemination_follows_the_layout() {
+        let mut layout = Layout::phonetic();
+        layout.set_gemination(false);
+        let engine = Transliterator::new(&layout);
+        assert_eq!(engine.transliterate("kk"), "কক");
+        // Listed pairs still join
+        assert_eq!(engine.transliterate("mp"), "ম্প");
+        assert_eq!(engine.with_gemination(true).transliterate("kk"), "ক্ক");
+    }
+
+    #[test]
+    fn listed_pairs_join_with_hasant() {
+        let layout = Layout::avro();
+        let engine = Transliterator::new(&layout);
+        assert_eq!(engine.transliterate("kS"), "ক্ষ");
+        assert_eq!(engine.transliterate("jNG"), "জ্ঞ");
+        assert_eq!(engine.transliterate("nt"), "ন্ত");
+        assert_eq!(engine.transliterate("mp"), "ম্প");
+        assert_eq!(engine.transliterate("rk"), "রক");
+    }
+
+    #[test]
+    fn three_consonants_chain_pairs() {
+        let layout = Layout::avro();
+        let engine = Transliterator::new(&layout);
+        assert_eq!(engine.transliterate("str"), "স্ত্র");
+        assert_eq!(engine.transliterate("ntr"), "ন্ত্র");
+        assert_eq!(engine.transliterate("mpr"), "ম্প্র");
+        assert_eq!(engine.transliterate("ostro"), "অস্ত্র");
+    }
+
+    #[test]
+    fn clusters_break_where_no_hasant_belongs() {
+        let layout = Layout::avro();
+        let engine = Transliterator::new(&layout);
+        // Pairs split by a dropped vowel aren't listed
+        assert_eq!(engine.transliterate("ekTa"), "একটা");
+        assert_eq!(engine.transliterate("korbo"), "করব");
+        // A vowel ends the cluster
+        assert_eq!(engine.transliterate("sot"), "সত");
+        assert_eq!(engine.transliterate("kak"), "কাক");
+        // A fourth consonant starts over
+        assert_eq!(engine.transliterate("nstr"), "ন্স্তর");
+        // So does a key the layout doesn't know
+        assert_eq!(engine.transliterate("s-t"), "স-ত");
+    }
+
+    #[test]
+    fn capitals_keep_their_own_rules() {
+        let layout = Layout::phonetic();
+        let engine = Transliterator::new(&layout);
+        assert_eq!(engine.transliterate("t"), "ট");
+        assert_eq!(engine.transliterate("T"), "ত");
+        assert_eq!(engine.transliterate("Dh"), "ধ");
+        assert_eq!(engine.transliterate("Sh"), "ষ");
+    }
+
+    #[test]
+    fn capitals_without_a_rule_type_like_small_letters() {
+        let layout = Layout::phonetic();
+        let engine = Transliterator::new(&layout);
+        // Shift or Caps Lock on letters the layout keeps lowercase
+        assert_eq!(engine.transliterate("K"), "ক");
+        assert_eq!(engine.transliterate("Kh"), "খ");
+        assert_eq!(engine.transliterate("KH"), "খ");
+        assert_eq!(engine.transliterate("GH"), "ঘ");
+        assert_eq!(engine.transliterate("PLM"), engine.transliterate("plm"));
+        assert!(engine.convert("BOLLO").unmatched().next().is_none());
+        // Mixed case still respects the capitals that have rules
+        assert_eq!(engine.transliterate("SH"), "ষ");
+
+        let layout = Layout::avro();
+        let engine = Transliterator::new(&layout);
+        assert_eq!(engine.transliterate("Kh"), "খ");
+        assert_eq!(engine.transliterate("Ka"), "কা");
+        assert_eq!(engine.transliterate("NG"), "ঞ");
+        assert_eq!(engine.transliterate("Ng"), "ঙ");
+    }
+
+    #[test]
+    fn the_engine_can_turn_gemination_off() {
+        let layout = Layout::avro();
+        let engine = Transliterator::new(&layout).with_gemination(false);
+        assert_eq!(engine.transliterate("ll"), "লল");
+    }
+}
@@ -0,0 +1,69 @@
+use crate::dictionary::edit_distance;
+use std::collections::HashSet;
+
+/// Bangla words known to be spelled right, for checking finished words.
+///
+/// ```
+/// use restro_keyboard_core::WordList;
+///
+/// let words = WordList::parse("# common words\nভালো\nভালোবাসা\nবাসা\n");
+/// assert!(words.contains("ভালো"));
+/// assert!(!words.contains("ভালবাসা"));
+/// assert_eq!(words.nearest("ভালবাসা", 2, 3), ["ভালোবাসা"]);
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct WordList {
+    words: HashSet<String>,
+}
+
+impl WordList {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Reads one word per line. Blank lines and lines starting with '#'
+    /// are skipped.
+    pub fn parse(text: &str) -> Self {
+        let mut list = Self::new();
+        for line in text.lines() {
+            let line = line.trim();
+            if !line.is_empty() && !line.starts_with('#') {
+                list.insert(line);
+            }
+        }
+        list
+    }
+
+    pub fn insert(&mut self, word: &str) {
+        self.words.insert(word.to_string());
+    }
+
+    pub fn contains(&self, word: &str) -> bool {
+        self.words.contains(word)
+    }
+
+    /// Up to `limit` words at most `max_distance` letter edits from `word`,
+    /// closest first. A vowel sign or hasanta is a letter of its own, so
+    /// ভালবাসা is one edit from ভালোবাসা. Ties are in code point order.
+    pub fn nearest(&self, word: &str, max_distance: usize, limit: usize) -> Vec<&str> {
+        let mut near: Vec<(usize, &str)> = self
+            .words
+            .iter()
+            .map(|known| (edit_distance(word, known), known.as_str()))
+            .filter(|(distance, known)| *distance <= max_distance && *known != word)
+            .collect();
+        near.sort();
+        near.into_iter()
+            .take(limit)
+            .map(|(_, known)| known)
+            .collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.words.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.words.is_empty()
+    }
+}
@@ -0,0 +1,35 @@
+/* C interface to the Restro Keyboard Bangla engine (restro_keyboard_ffi.dll).
+ * All strings are NUL-terminated UTF-8. */
+
+#ifndef RESTRO_KEYBOARD_H
+#define RESTRO_KEYBOARD_H
+
+#include <stdbool.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+typedef struct RestroEngine RestroEngine;
+
+/* Engine with the phonetic layout and gemination on. Free with
+ * restro_engine_free. */
+RestroEngine *restro_engine_create(void);
+
+/* Whether doubled consonants join with hasant (kk -> ক্ক). */
+void restro_engine_set_gemination(RestroEngine *engine, bool enabled);
+
+/* Converts roman text to Bangla, keeping punctuation and spacing. Returns
+ * NULL on a NULL argument or invalid UTF-8; free the result with
+ * restro_string_free. */
+char *restro_transliterate(const RestroEngine *engine, const char *roman);
+
+void restro_string_free(char *text);
+
+void restro_engine_free(RestroEngine *engine);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* RESTRO_KEYBOARD_H */
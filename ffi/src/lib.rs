@@ -0,0 +1,85 @@
+//! C ABI for the engine, declared in `include/restro_keyboard.h`. Strings
+//! cross the boundary as NUL-terminated UTF-8.
+
+use restro_keyboard_core::{Layout, Transliterator};
+use std::ffi::{c_char, CStr, CString};
+use std::ptr;
+
+/// Opaque to C: the layout and the options conversions run with.
+pub struct RestroEngine {
+    layout: Layout,
+    gemination: bool,
+}
+
+/// Creates an engine with the phonetic layout and gemination on. Free it with
+/// `restro_engine_free`.
+#[no_mangle]
+pub extern "C" fn restro_engine_create() -> *mut RestroEngine {
+    Box::into_raw(Box::new(RestroEngine {
+        layout: Layout::phonetic(),
+        gemination: true,
+    }))
+}
+
+/// # Safety
+///
+/// `engine` must come from `restro_engine_create` and not be freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn restro_engine_set_gemination(engine: *mut RestroEngine, enabled: bool) {
+    if let Some(engine) = engine.as_mut() {
+        engine.gemination = enabled;
+    }
+}
+
+/// Converts roman text to Bangla. Returns NULL if an argument is NULL or the
+/// text isn't valid UTF-8; otherwise free the result with `restro_string_free`.
+///
+/// # Safety
+///
+/// `engine` must come from `restro_engine_create` and not be freed yet, and
+/// `roman` must point to a NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn restro_transliterate(
+    engine: *const RestroEngine,
+    roman: *const c_char,
+) -> *mut c_char {
+    let Some(engine) = engine.as_ref() else {
+        return ptr::null_mut();
+    };
+    if roman.is_null() {
+        return ptr::null_mut();
+    }
+    let Ok(roman) = CStr::from_ptr(roman).to_str() else {
+        return ptr::null_mut();
+    };
+
+    let output = Transliterator::new(&engine.layout)
+        .with_gemination(engine.gemination)
+        .transliterate_text(roman);
+    // Output can't contain NUL unless the input did, and CStr stops at the first
+    CString::new(output)
+        .map(CString::into_raw)
+        .unwrap_or(ptr::null_mut())
+}
+
+/// # Safety
+///
+/// `text` must come from `restro_transliterate` and not be freed yet. NULL is
+/// ignored.
+#[no_mangle]
+pub unsafe extern "C" fn restro_string_free(text: *mut c_char) {
+    if !text.is_null() {
+        drop(CString::from_raw(text));
+    }
+}
+
+/// # Safety
+///
+/// `engine` must come from `restro_engine_create` and not be freed yet. NULL
+/// is ignored.
+#[no_mangle]
+pub unsafe extern "C" fn restro_engine_free(engine: *mut RestroEngine) {
+    if !engine.is_null() {
+        drop(Box::from_raw(engine));
+    }
+}
@@ -0,0 +1,79 @@
+//! Python module `restro_keyboard`, converting with the same rules as the
+//! live keyboard:
+//!
+//! ```python
+//! import restro_keyboard
+//!
+//! restro_keyboard.transliterate("ami bhalo")
+//! engine = restro_keyboard.Engine(gemination=False)
+//! engine.transliterate_batch(["kk", "tumi"])
+//! ```
+
+use pyo3::prelude::*;
+use restro_keyboard_core::{Layout, Transliterator};
+
+/// The phonetic layout with the conversion options. Reuse one engine for a
+/// corpus instead of calling `transliterate` per line.
+#[pyclass]
+struct Engine {
+    layout: Layout,
+    gemination: bool,
+}
+
+impl Engine {
+    fn transliterator(&self) -> Transliterator<'_> {
+        Transliterator::new(&self.layout).with_gemination(self.gemination)
+    }
+}
+
+#[pymethods]
+impl Engine {
+    #[new]
+    #[pyo3(signature = (gemination = true))]
+    fn new(gemination: bool) -> Self {
+        Self {
+            layout: Layout::phonetic(),
+            gemination,
+        }
+    }
+
+    /// Converts running text; punctuation and spacing are kept.
+    fn transliterate(&self, text: &str) -> String {
+        self.transliterator().transliterate_text(text)
+    }
+
+    /// Converts every text of a list without holding the GIL.
+    fn transliterate_batch(&self, py: Python<'_>, texts: Vec<String>) -> Vec<String> {
+        py.allow_threads(|| {
+            let transliterator = self.transliterator();
+            texts
+                .iter()
+                .map(|text| transliterator.transliterate_text(text))
+                .collect()
+        })
+    }
+
+    /// How a single word was converted, as (roman, output, rule) tuples.
+    fn steps(&self, word: &str) -> Vec<(String, String, String)> {
+        self.transliterator()
+            .convert(word)
+            .steps
+            .into_iter()
+            .map(|step| (step.roman, step.output, step.rule.to_string()))
+            .collect()
+    }
+}
+
+/// One-off conversion with a fresh engine.
+#[pyfunction]
+#[pyo3(signature = (text, gemination = true))]
+fn transliterate(text: &str, gemination: bool) -> String {
+    Engine::new(gemination).transliterate(text)
+}
+
+#[pymodule]
+fn restro_keyboard(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<Engine>()?;
+    m.add_function(wrap_pyfunction!(transliterate, m)?)?;
+    Ok(())
+}
@@ -0,0 +1,114 @@
+use crate::settings::KeyboardSettings;
+use once_cell::sync::OnceCell;
+use restro_keyboard_core::Dictionary;
+use std::sync::{Arc, Mutex};
+
+// Shortened and misspelled Banglish, amr for আমার, put right when the word
+// ends. Checked before dictionary mode and the phonetic rules, whatever the
+// dictionary setting. The bundled list comes with the app; the user's
+// additions live in the settings and win over it.
+
+/// The bundled corrections, in the dictionary's `roman<TAB>bangla` format.
+pub const BUNDLED_FILE: &str = "assets/autocorrect/corrections.tsv";
+
+static BUNDLED: OnceCell<Dictionary> = OnceCell::new();
+// The corrections in use and the settings version they were made for
+static CURRENT: Mutex<Option<(u64, Arc<Dictionary>)>> = Mutex::new(None);
+
+// Added as "roman bangla" lines, since a tab can't be typed in the settings
+fn with_additions(mut corrections: Dictionary, additions: &str) -> Dictionary {
+    for line in additions.lines() {
+        let line = line.trim();
+        if line.starts_with('#') {
+            continue;
+        }
+        if let Some((roman, bangla)) = line.split_once(char::is_whitespace) {
+            corrections.insert(roman, bangla.trim());
+        }
+    }
+    corrections
+}
+
+fn make(settings: &KeyboardSettings) -> Dictionary {
+    if !settings.autocorrect {
+        return Dictionary::new();
+    }
+    let bundled = BUNDLED.get_or_init(|| {
+        Dictionary::parse(&std::fs::read_to_string(BUNDLED_FILE).unwrap_or_default())
+    });
+    with_additions(bundled.clone(), &settings.autocorrect_additions)
+}
+
+/// The corrections in use: the bundled ones with the user's on top. Empty
+/// when autocorrect is off. Made again only when the settings change.
+pub fn corrections() -> Arc<Dictionary> {
+    let version = crate::SETTINGS.version();
+    let mut current = CURRENT.lock().unwrap();
+    if let Some((made_for, corrections)) = current.as_ref() {
+        if *made_for == version {
+            return corrections.clone();
+        }
+    }
+    // The snapshot is at least as new as `version`
+    let corrections = Arc::new(make(&crate::SETTINGS.snapshot()));
+    *current = Some((version, corrections.clone()));
+    corrections
+}
+
+/// Adds imported corrections after the user's, as lines at the end. Words
+/// the user already corrects keep their correction. Returns how many were
+/// added.
+pub fn add(additions: &mut String, corrections: &[(String, String)]) -> usize {
+    let mut known = with_additions(Dictionary::new(), additions);
+    let mut added = 0;
+    for (roman, bangla) in corrections {
+        if known.get(roman).is_some() {
+            continue;
+        }
+        if !additions.is_empty() && !additions.ends_with('\n') {
+            additions.push('\n');
+        }
+        additions.push_str(&format!("{} {}\n", roman, bangla));
+        known.insert(roman, bangla);
+        added += 1;
+    }
+    added
+}
+
+/// How many corrections the user added.
+pub fn addition_count(settings: &KeyboardSettings) -> usize {
+    with_additions(Dictionary::new(), &settings.autocorrect_additions).len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn additions_win_over_bundled_corrections() {
+        let bundled = Dictionary::parse("amr\tআমার\nkmn\tকেমন\n");
+        let corrections = with_additions(bundled, "# mine\nKMN  কেমনে\n\nbad\nvlo ভালো\n");
+        assert_eq!(corrections.get("amr").as_deref(), Some("আমার"));
+        assert_eq!(corrections.get("kmn").as_deref(), Some("কেমনে"));
+        assert_eq!(corrections.get("vlo").as_deref(), Some("ভালো"));
+        assert_eq!(corrections.len(), 3);
+    }
+
+    #[test]
+    fn imports_keep_the_users_corrections() {
+        let mut additions = "vlo ভালো".to_string();
+        let imported = [
+            ("vlo".to_string(), "ভাল".to_string()),
+            ("bd".to_string(), "বাংলাদেশ".to_string()),
+            ("bd".to_string(), "বদ".to_string()),
+        ];
+        assert_eq!(add(&mut additions, &imported), 1);
+        assert_eq!(additions, "vlo ভালো\nbd বাংলাদেশ\n");
+    }
+
+    #[test]
+    fn the_bundled_list_reads() {
+        let bundled = Dictionary::parse(&std::fs::read_to_string(BUNDLED_FILE).unwrap());
+        assert_eq!(bundled.get("amr").as_deref(), Some("আমার"));
+    }
+}
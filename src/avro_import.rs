@@ -0,0 +1,165 @@
+use restro_keyboard_core::{Layout, Transliterator};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// Avro Keyboard keeps its word lists as text files, one `word replacement`
+// pair per line, in its folder under %APPDATA% or next to the program. A
+// replacement is either Bangla or Avro Phonetic spelling (e.g. "bangla"
+// → "ba`Nla"), which is converted with our Avro layout. Its autocorrect
+// list, where users keep their macros, becomes autocorrections; its user
+// words go to the dictionary.
+const AUTOCORRECT_FILE: &str = "autodict.dct";
+const DICTIONARY_FILE: &str = "userdict.dct";
+
+// Avro's own fixed layouts, which can't be loaded here
+const LAYOUT_EXTENSION: &str = "avrolayout";
+
+/// What an import found and added.
+#[derive(Debug, Default)]
+pub struct Report {
+    pub folders: Vec<PathBuf>,
+    pub words: usize,
+    pub added: usize,
+    pub corrections: usize,
+    pub corrections_added: usize,
+    // Custom layouts that were found but not imported
+    pub layouts: Vec<String>,
+}
+
+impl Report {
+    pub fn summary(&self) -> String {
+        let mut summary = format!(
+            "Found {} words in {}, {} added to your dictionary, and {} autocorrections, {} added.",
+            self.words,
+            self.folders
+                .iter()
+                .map(|folder| folder.display().to_string())
+                .collect::<Vec<_>>()
+                .join(" and "),
+            self.added,
+            self.corrections,
+            self.corrections_added
+        );
+        if !self.layouts.is_empty() {
+            summary.push_str(&format!(
+                " Custom layouts aren't supported yet and were skipped: {}.",
+                self.layouts.join(", ")
+            ));
+        }
+        summary
+    }
+}
+
+// The user data folder first, then the installation folders
+fn candidate_folders() -> Vec<PathBuf> {
+    ["APPDATA", "ProgramFiles(x86)", "ProgramFiles"]
+        .into_iter()
+        .filter_map(std::env::var_os)
+        .map(|base| PathBuf::from(base).join("Avro Keyboard"))
+        .filter(|folder| folder.is_dir())
+        .collect()
+}
+
+fn is_bangla(text: &str) -> bool {
+    text.chars().any(|c| ('\u{0980}'..='\u{09FF}').contains(&c))
+}
+
+// Pairs of a roman word and its Bangla spelling. Lines whose word isn't
+// roman letters are skipped.
+fn parse_words(text: &str, engine: &Transliterator) -> Vec<(String, String)> {
+    text.trim_start_matches('\u{FEFF}')
+        .lines()
+        .filter_map(|line| {
+            let line = line.trim();
+            if line.starts_with('#') || line.starts_with("//") {
+                return None;
+            }
+            let (word, replacement) = line.split_once(char::is_whitespace)?;
+            let replacement = replacement.trim();
+            if replacement.is_empty() || !word.chars().all(|c| c.is_ascii_alphabetic()) {
+                return None;
+            }
+            let bangla = if is_bangla(replacement) {
+                replacement.to_string()
+            } else {
+                // A backtick only separates letters, as in Avro
+                replacement
+                    .split('`')
+                    .map(|part| engine.transliterate_text(part))
+                    .collect()
+            };
+            Some((word.to_lowercase(), bangla))
+        })
+        .collect()
+}
+
+// The files called `name` in a folder and its Data subfolder
+fn word_files(folder: &Path, name: &str) -> Vec<PathBuf> {
+    [folder.to_path_buf(), folder.join("Data")]
+        .iter()
+        .map(|dir| dir.join(name))
+        .filter(|path| path.is_file())
+        .collect()
+}
+
+fn read_words(files: &[PathBuf], engine: &Transliterator) -> Vec<(String, String)> {
+    let mut words = Vec::new();
+    for file in files {
+        match fs::read(file) {
+            Ok(bytes) => words.extend(parse_words(&String::from_utf8_lossy(&bytes), engine)),
+            Err(err) => eprintln!("Could not read {}: {}", file.display(), err),
+        }
+    }
+    words
+}
+
+fn layout_names(folder: &Path) -> Vec<String> {
+    let Ok(entries) = fs::read_dir(folder.join("Keyboard Layouts")) else {
+        return Vec::new();
+    };
+    entries
+        .filter_map(|entry| Some(entry.ok()?.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == LAYOUT_EXTENSION))
+        .filter_map(|path| Some(path.file_stem()?.to_string_lossy().into_owned()))
+        .collect()
+}
+
+/// Reads the user words of an Avro Keyboard installation into the user
+/// dictionary, and its autocorrect list into `additions`, the user's
+/// autocorrections. Fails when no installation is found or the dictionary
+/// can't be written.
+pub fn import(additions: &mut String) -> Result<Report, String> {
+    let folders = candidate_folders();
+    if folders.is_empty() {
+        return Err("No Avro Keyboard installation was found".to_string());
+    }
+
+    let layout = Layout::avro();
+    let engine = Transliterator::new(&layout);
+    let mut report = Report::default();
+    let mut words = Vec::new();
+    let mut corrections = Vec::new();
+    for folder in folders {
+        let dictionary = word_files(&folder, DICTIONARY_FILE);
+        let autocorrect = word_files(&folder, AUTOCORRECT_FILE);
+        words.extend(read_words(&dictionary, &engine));
+        corrections.extend(read_words(&autocorrect, &engine));
+        let layouts = layout_names(&folder);
+        if !dictionary.is_empty() || !autocorrect.is_empty() || !layouts.is_empty() {
+            report.folders.push(folder);
+        }
+        report.layouts.extend(layouts);
+    }
+    if report.folders.is_empty() {
+        return Err("Avro Keyboard was found, but none of its word lists or layouts".to_string());
+    }
+
+    report.corrections = corrections.len();
+    report.corrections_added = crate::autocorrect::add(additions, &corrections);
+    report.words = words.len();
+    report.added = crate::user_dictionary::add(&words)
+        .map_err(|err| format!("Could not save the dictionary: {}", err))?;
+    report.layouts.sort();
+    report.layouts.dedup();
+    Ok(report)
+}
@@ -0,0 +1,265 @@
+use crate::settings::{self, KeyboardSettings};
+use std::fs;
+use std::io::{self, Cursor, Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use windows::Win32::System::SystemInformation::GetLocalTime;
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
+
+// Backups of what the user made: the settings, their dictionary, the
+// learned words and custom mappings, zipped into the backups folder with
+// the time in the name. When automatic backups are on one is made once a
+// day, and the oldest beyond `KeyboardSettings::backups_kept` are deleted.
+// Restoring first backs up what it replaces, so a restore can be undone.
+// An installed full dictionary is left out: it is large and can be
+// installed again.
+
+/// The files backed up, all next to the settings.
+pub const FILES: [&str; 4] = [
+    "settings.json",
+    "dictionary.tsv",
+    "learned.json",
+    "mappings.json",
+];
+
+// How often an automatic backup is made, and checked for
+const INTERVAL: Duration = Duration::from_secs(24 * 60 * 60);
+const CHECK_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// A backup in the backups folder.
+pub struct Backup {
+    pub path: PathBuf,
+    // When it was made, as 2024-03-01 14:05
+    pub made: String,
+    pub files: Vec<String>,
+}
+
+/// `%APPDATA%\RestroKeyboard\backups`, if APPDATA is set.
+pub fn dir() -> Option<PathBuf> {
+    Some(KeyboardSettings::path()?.parent()?.join("backups"))
+}
+
+fn data_dir() -> io::Result<PathBuf> {
+    KeyboardSettings::path()
+        .and_then(|path| Some(path.parent()?.to_path_buf()))
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "APPDATA is not set"))
+}
+
+// The local time as 2024-03-01-140502, which sorts in time order
+fn timestamp() -> String {
+    let now = unsafe { GetLocalTime() };
+    format!(
+        "{:04}-{:02}-{:02}-{:02}{:02}{:02}",
+        now.wYear, now.wMonth, now.wDay, now.wHour, now.wMinute, now.wSecond
+    )
+}
+
+// backup-2024-03-01-140502.zip made 2024-03-01 14:05
+fn made(path: &Path) -> Option<String> {
+    let stamp = path
+        .file_name()?
+        .to_str()?
+        .strip_prefix("backup-")?
+        .strip_suffix(".zip")?;
+    let (date, time) = stamp.rsplit_once('-')?;
+    (date.len() == 10 && time.len() == 6)
+        .then(|| format!("{} {}:{}", date, &time[..2], &time[2..4]))
+}
+
+// The files of `folder` that are backed up, zipped
+fn archive(folder: &Path) -> io::Result<Vec<u8>> {
+    let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+    let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+    for name in FILES {
+        let Ok(contents) = fs::read(folder.join(name)) else {
+            continue;
+        };
+        zip.start_file(name, options)?;
+        zip.write_all(&contents)?;
+    }
+    Ok(zip.finish()?.into_inner())
+}
+
+// Writes the backed up files of an archive into `folder`, returning their
+// names. Anything else in the archive is ignored.
+fn unpack(archive: &[u8], folder: &Path) -> io::Result<Vec<String>> {
+    let mut zip = ZipArchive::new(Cursor::new(archive))?;
+    let mut restored = Vec::new();
+    for name in FILES {
+        let Ok(mut file) = zip.by_name(name) else {
+            continue;
+        };
+        let mut contents = Vec::new();
+        file.read_to_end(&mut contents)?;
+        settings::write_atomically(&folder.join(name), contents)?;
+        restored.push(name.to_string());
+    }
+    Ok(restored)
+}
+
+fn files_in(path: &Path) -> Vec<String> {
+    let Ok(zip) = fs::File::open(path).and_then(|file| Ok(ZipArchive::new(file)?)) else {
+        return Vec::new();
+    };
+    zip.file_names().map(str::to_string).collect()
+}
+
+/// The backups, newest first.
+pub fn list() -> Vec<Backup> {
+    let Some(entries) = dir().and_then(|dir| fs::read_dir(dir).ok()) else {
+        return Vec::new();
+    };
+    let mut backups: Vec<Backup> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter_map(|path| {
+            Some(Backup {
+                made: made(&path)?,
+                files: files_in(&path),
+                path,
+            })
+        })
+        .collect();
+    backups.sort_by(|a, b| b.path.cmp(&a.path));
+    backups
+}
+
+// Deletes the oldest backups in `dir` beyond `kept`
+fn prune(dir: &Path, kept: usize) -> io::Result<()> {
+    let mut backups: Vec<PathBuf> = fs::read_dir(dir)?
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| made(path).is_some())
+        .collect();
+    backups.sort();
+    let excess = backups.len().saturating_sub(kept.max(1));
+    for path in &backups[..excess] {
+        fs::remove_file(path)?;
+    }
+    Ok(())
+}
+
+/// Backs up the user's files now, then deletes the oldest backups beyond
+/// the number kept. Returns the new backup.
+pub fn create(kept: usize) -> io::Result<PathBuf> {
+    let dir = dir().ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "APPDATA is not set"))?;
+    let path = dir.join(format!("backup-{}.zip", timestamp()));
+    settings::write_atomically(&path, archive(&data_dir()?)?)?;
+    prune(&dir, kept)?;
+    Ok(path)
+}
+
+/// Puts a backup's files in place of the current ones, after backing
+/// those up. Returns the names of the files restored; the caller reloads
+/// them.
+pub fn restore(backup: &Path, kept: usize) -> io::Result<Vec<String>> {
+    let archive = fs::read(backup)?;
+    // Checked before anything is replaced
+    ZipArchive::new(Cursor::new(&archive))?;
+    // One more is kept, so making room doesn't delete the backup restored
+    create(kept + 1)?;
+    unpack(&archive, &data_dir()?)
+}
+
+pub fn count() -> usize {
+    dir()
+        .and_then(|dir| fs::read_dir(dir).ok())
+        .map_or(0, |entries| {
+            entries
+                .flatten()
+                .filter(|entry| made(&entry.path()).is_some())
+                .count()
+        })
+}
+
+pub fn delete() -> io::Result<()> {
+    match dir() {
+        Some(dir) if dir.exists() => fs::remove_dir_all(dir),
+        _ => Ok(()),
+    }
+}
+
+// Whether the newest backup is older than the interval, or there is none
+fn due() -> bool {
+    let newest = list().into_iter().next();
+    let age = newest
+        .and_then(|backup| fs::metadata(backup.path).ok())
+        .and_then(|metadata| metadata.modified().ok())
+        .and_then(|modified| modified.elapsed().ok());
+    age.is_none_or(|age| age >= INTERVAL)
+}
+
+/// Makes the daily backup while automatic backups are on, checking every
+/// hour.
+pub fn spawn() {
+    std::thread::spawn(|| loop {
+        let settings = crate::SETTINGS.snapshot();
+        if settings.automatic_backups && due() {
+            if let Err(err) = create(settings.backups_kept) {
+                eprintln!("Could not back up: {}", err);
+            }
+        }
+        std::thread::sleep(CHECK_INTERVAL);
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn folder(name: &str) -> PathBuf {
+        let folder = std::env::temp_dir().join(format!("restro-backup-{}", name));
+        let _ = fs::remove_dir_all(&folder);
+        fs::create_dir_all(&folder).unwrap();
+        folder
+    }
+
+    #[test]
+    fn backups_restore_the_files_they_were_made_from() {
+        let from = folder("from");
+        fs::write(from.join("settings.json"), "{}").unwrap();
+        fs::write(from.join("dictionary.tsv"), "ami\tআমি\n").unwrap();
+        fs::write(from.join("statistics.json"), "[]").unwrap();
+        let archive = archive(&from).unwrap();
+
+        let to = folder("to");
+        fs::write(to.join("dictionary.tsv"), "tumi\tতুমি\n").unwrap();
+        let restored = unpack(&archive, &to).unwrap();
+        assert_eq!(restored, ["settings.json", "dictionary.tsv"]);
+        assert_eq!(
+            fs::read_to_string(to.join("dictionary.tsv")).unwrap(),
+            "ami\tআমি\n"
+        );
+        // Only the files backed up
+        assert!(!to.join("statistics.json").exists());
+        let _ = fs::remove_dir_all(from);
+        let _ = fs::remove_dir_all(to);
+    }
+
+    #[test]
+    fn the_oldest_backups_go_first() {
+        let dir = folder("prune");
+        for stamp in [
+            "2024-03-02-090000",
+            "2024-03-01-120000",
+            "2024-03-03-080000",
+        ] {
+            fs::write(dir.join(format!("backup-{}.zip", stamp)), "").unwrap();
+        }
+        fs::write(dir.join("notes.txt"), "").unwrap();
+        prune(&dir, 2).unwrap();
+        assert!(!dir.join("backup-2024-03-01-120000.zip").exists());
+        assert!(dir.join("backup-2024-03-02-090000.zip").exists());
+        assert!(dir.join("notes.txt").exists());
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn backup_names_tell_when_they_were_made() {
+        let path = Path::new("backup-2024-03-01-140502.zip");
+        assert_eq!(made(path).as_deref(), Some("2024-03-01 14:05"));
+        assert_eq!(made(Path::new("backup-old.zip")), None);
+        assert_eq!(made(Path::new("notes.txt")), None);
+    }
+}
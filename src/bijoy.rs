@@ -0,0 +1,807 @@
+use crate::injection::EditPlan;
+use crate::settings::KeyboardSettings;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+// Older apps and print shops still use Bijoy fonts such as SutonnyMJ, which
+// draw Bangla glyphs in place of Latin-1 characters. Text for them is typed
+// in the order the glyphs are drawn: vowel signs that come before their
+// consonant in print come first, and reph comes after its consonant.
+// Decoding old documents undoes both.
+
+/// Choices for `KeyboardSettings::output_encoding`, the first is the default.
+pub const ENCODINGS: [&str; 3] = ["Unicode", "Bijoy (ANSI)", "Bijoy (ANSI) in listed apps"];
+
+const HASANT: char = '্';
+const NUKTA: char = '\u{09BC}';
+const RA: char = 'র';
+
+// Vowel signs drawn before the consonant
+const PRE_BASE: [(char, &str); 3] = [('ি', "w"), ('ে', "‡"), ('ৈ', "‰")];
+// Vowel signs drawn on both sides: before, after
+const SPLIT: [(char, &str, &str); 2] = [('ো', "‡", "v"), ('ৌ', "‡", "Š")];
+
+// Conjuncts and phala with their own glyphs, longest first. Others are
+// typed with a visible hasant, as Bijoy users do.
+const CLUSTERS: [(&str, &str); 28] = [
+    ("ক্ষ", "ÿ"),
+    ("ক্ক", "°"),
+    ("ক্ত", "³"),
+    ("ক্র", "µ"),
+    ("ঙ্ক", "¼"),
+    ("ঙ্গ", "½"),
+    ("জ্জ", "¾"),
+    ("জ্ঞ", "Á"),
+    ("ঞ্চ", "Â"),
+    ("ঞ্জ", "Ä"),
+    ("ট্ট", "Æ"),
+    ("ড্ড", "Ç"),
+    ("ত্ত", "Ë"),
+    ("ত্র", "Î"),
+    ("দ্দ", "Ï"),
+    ("দ্ধ", "×"),
+    ("দ্ব", "Ø"),
+    ("ন্ত", "šÍ"),
+    ("ন্দ", "›`"),
+    ("ন্ধ", "Ü"),
+    ("প্ত", "ß"),
+    ("প্প", "à"),
+    ("ম্প", "¤c"),
+    ("স্ত", "¯Í"),
+    ("স্থ", "¯’"),
+    ("্র", "Ö"),
+    ("্য", "¨"),
+    ("্", "&"),
+];
+
+// A consonant and u or uu drawn as one glyph
+const WITH_U: [(&str, &str); 5] = [("রু", "iæ"), ("রূ", "iƒ"), ("হু", "û"), ("গু", "¸"), ("শু", "ï")];
+
+fn consonant(c: char) -> Option<&'static str> {
+    Some(match c {
+        'ক' => "K",
+        'খ' => "L",
+        'গ' => "M",
+        'ঘ' => "N",
+        'ঙ' => "O",
+        'চ' => "P",
+        'ছ' => "Q",
+        'জ' => "R",
+        'ঝ' => "S",
+        'ঞ' => "T",
+        'ট' => "U",
+        'ঠ' => "V",
+        'ড' => "W",
+        'ঢ' => "X",
+        'ণ' => "Y",
+        'ত' => "Z",
+        'থ' => "_",
+        'দ' => "`",
+        'ধ' => "a",
+        'ন' => "b",
+        'প' => "c",
+        'ফ' => "d",
+        'ব' => "e",
+        'ভ' => "f",
+        'ম' => "g",
+        'য' => "h",
+        'র' => "i",
+        'ল' => "j",
+        'শ' => "k",
+        'ষ' => "l",
+        'স' => "m",
+        'হ' => "n",
+        '\u{09DC}' => "o",
+        '\u{09DD}' => "p",
+        '\u{09DF}' => "q",
+        _ => return None,
+    })
+}
+
+// Everything drawn where it stands
+fn other(c: char) -> Option<&'static str> {
+    Some(match c {
+        'অ' => "A",
+        'আ' => "Av",
+        'ই' => "B",
+        'ঈ' => "C",
+        'উ' => "D",
+        'ঊ' => "E",
+        'ঋ' => "F",
+        'এ' => "G",
+        'ঐ' => "H",
+        'ও' => "I",
+        'ঔ' => "J",
+        'া' => "v",
+        'ী' => "x",
+        'ু' => "y",
+        'ূ' => "~",
+        'ৃ' => "…",
+        'ৗ' => "Š",
+        'ৎ' => "r",
+        'ং' => "s",
+        'ঃ' => "t",
+        'ঁ' => "u",
+        '।' => "|",
+        '০' => "0",
+        '১' => "1",
+        '২' => "2",
+        '৩' => "3",
+        '৪' => "4",
+        '৫' => "5",
+        '৬' => "6",
+        '৭' => "7",
+        '৮' => "8",
+        '৯' => "9",
+        // Joiners have no glyphs
+        '\u{200C}' | '\u{200D}' => "",
+        _ => return None,
+    })
+}
+
+// The glyphs for consonants joined by hasant, conjuncts where there are
+fn cluster(chars: &[char]) -> String {
+    let mut glyphs = String::new();
+    let mut start = 0;
+    'outer: while start < chars.len() {
+        for (unicode, bijoy) in CLUSTERS {
+            let len = unicode.chars().count();
+            if chars[start..].len() >= len
+                && chars[start..start + len]
+                    .iter()
+                    .copied()
+                    .eq(unicode.chars())
+            {
+                glyphs.push_str(bijoy);
+                start += len;
+                continue 'outer;
+            }
+        }
+        match consonant(chars[start]) {
+            Some(glyph) => glyphs.push_str(glyph),
+            None => glyphs.push(chars[start]),
+        }
+        start += 1;
+    }
+    glyphs
+}
+
+// ড, ঢ and য with a nukta as the single characters Bijoy has glyphs for
+fn compose(text: &str) -> Vec<char> {
+    let mut chars: Vec<char> = Vec::new();
+    for c in text.chars() {
+        let composed = match (chars.last(), c) {
+            (Some('ড'), NUKTA) => '\u{09DC}',
+            (Some('ঢ'), NUKTA) => '\u{09DD}',
+            (Some('য'), NUKTA) => '\u{09DF}',
+            _ => {
+                chars.push(c);
+                continue;
+            }
+        };
+        *chars.last_mut().unwrap() = composed;
+    }
+    chars
+}
+
+/// Unicode Bangla as Bijoy text, in glyph order. Characters Bijoy has no
+/// glyph for, such as Latin letters, are kept.
+pub fn encode(text: &str) -> String {
+    let chars = compose(text);
+    let mut bijoy = String::new();
+    let mut i = 0;
+    while i < chars.len() {
+        if consonant(chars[i]).is_none() {
+            match other(chars[i]) {
+                Some(glyph) => bijoy.push_str(glyph),
+                None => bijoy.push(chars[i]),
+            }
+            i += 1;
+            continue;
+        }
+
+        // Reph: র and hasant before another consonant
+        let reph = chars[i] == RA
+            && chars.get(i + 1) == Some(&HASANT)
+            && chars.get(i + 2).is_some_and(|&c| consonant(c).is_some());
+        if reph {
+            i += 2;
+        }
+        // Consonants joined by hasant, with a phala or a hasant ending it
+        let start = i;
+        i += 1;
+        while chars.get(i) == Some(&HASANT) {
+            match chars.get(i + 1) {
+                Some(&c) if consonant(c).is_some() => i += 2,
+                _ => {
+                    i += 1;
+                    break;
+                }
+            }
+        }
+        let consonants = &chars[start..i];
+
+        let sign = chars.get(i).copied();
+        let pre = PRE_BASE.iter().find(|(c, _)| Some(*c) == sign);
+        let split = SPLIT.iter().find(|(c, _, _)| Some(*c) == sign);
+        let with_u = sign.and_then(|sign| {
+            let pair: String = consonants.iter().chain([&sign]).collect();
+            WITH_U
+                .iter()
+                .find(|(unicode, _)| *unicode == pair)
+                .map(|(_, bijoy)| *bijoy)
+        });
+
+        if let Some((_, glyph)) = pre {
+            bijoy.push_str(glyph);
+        }
+        if let Some((_, before, _)) = split {
+            bijoy.push_str(before);
+        }
+        match with_u {
+            Some(glyph) => bijoy.push_str(glyph),
+            None => bijoy.push_str(&cluster(consonants)),
+        }
+        if reph {
+            bijoy.push('©');
+        }
+        if let Some((_, _, after)) = split {
+            bijoy.push_str(after);
+        }
+        if pre.is_some() || split.is_some() || with_u.is_some() {
+            i += 1;
+        }
+    }
+    bijoy
+}
+
+// A glyph of Bijoy text by the part it plays in a syllable
+#[derive(Clone, PartialEq, Eq, Debug)]
+enum Piece {
+    // A consonant or conjunct, which signs are drawn around
+    Consonant(String),
+    // Hasant or a phala, joining what comes before to the cluster
+    Join(String),
+    // A vowel sign drawn before the consonant
+    Pre(char),
+    Reph,
+    Other(String),
+}
+
+// Other glyphs old documents use for the same letters
+fn aliases() -> [(&'static str, Piece); 5] {
+    [
+        ("†", Piece::Pre('ে')),
+        ("ˆ", Piece::Pre('ৈ')),
+        ("æ", Piece::Other("ু".to_string())),
+        ("ƒ", Piece::Other("ূ".to_string())),
+        ("ª", Piece::Join("্র".to_string())),
+    ]
+}
+
+// Every glyph with what it stands for, longest first
+fn pieces_by_glyph() -> Vec<(&'static str, Piece)> {
+    let mut table = Vec::new();
+    for c in ('\u{0980}'..='\u{09FF}').chain(['।', '\u{200C}', '\u{200D}']) {
+        if let Some(glyph) = consonant(c) {
+            // ড় and the like as the layouts type them, with a nukta
+            let text = match c {
+                '\u{09DC}' => "ড\u{09BC}".to_string(),
+                '\u{09DD}' => "ঢ\u{09BC}".to_string(),
+                '\u{09DF}' => "য\u{09BC}".to_string(),
+                _ => c.to_string(),
+            };
+            table.push((glyph, Piece::Consonant(text)));
+        } else if let Some(glyph) = other(c).filter(|glyph| !glyph.is_empty()) {
+            table.push((glyph, Piece::Other(c.to_string())));
+        }
+    }
+    for (text, glyph) in CLUSTERS {
+        let piece = if text.starts_with(HASANT) {
+            Piece::Join(text.to_string())
+        } else {
+            Piece::Consonant(text.to_string())
+        };
+        table.push((glyph, piece));
+    }
+    for (text, glyph) in WITH_U {
+        table.push((glyph, Piece::Other(text.to_string())));
+    }
+    for (sign, glyph) in PRE_BASE {
+        table.push((glyph, Piece::Pre(sign)));
+    }
+    table.push(("©", Piece::Reph));
+    table.extend(aliases());
+    // Stable, so the first of two spellings of a glyph wins
+    table.sort_by_key(|(glyph, _)| std::cmp::Reverse(glyph.chars().count()));
+    table
+}
+
+fn split_pieces(bijoy: &str) -> Vec<Piece> {
+    let table = pieces_by_glyph();
+    let mut pieces = Vec::new();
+    let mut rest = bijoy;
+    while let Some(c) = rest.chars().next() {
+        let (len, piece) = table
+            .iter()
+            .find(|(glyph, _)| rest.starts_with(glyph))
+            .map_or(
+                (c.len_utf8(), Piece::Other(c.to_string())),
+                |(glyph, piece)| (glyph.len(), piece.clone()),
+            );
+        pieces.push(piece);
+        rest = &rest[len..];
+    }
+    pieces
+}
+
+// The consonants starting at `start` joined into one cluster, with the reph
+// drawn after them moved back in front, and where the cluster ends
+fn cluster_at(pieces: &[Piece], start: usize) -> Option<(String, usize)> {
+    let Some(Piece::Consonant(first)) = pieces.get(start) else {
+        return None;
+    };
+    let mut text = first.clone();
+    let mut i = start + 1;
+    loop {
+        match (pieces.get(i), pieces.get(i + 1)) {
+            (Some(Piece::Join(hasant)), Some(Piece::Consonant(next))) if hasant.as_str() == "্" =>
+            {
+                text.push_str(hasant);
+                text.push_str(next);
+                i += 2;
+            }
+            (Some(Piece::Join(phala)), _) if phala.as_str() != "্" => {
+                text.push_str(phala);
+                i += 1;
+            }
+            _ => break,
+        }
+    }
+    if pieces.get(i) == Some(&Piece::Reph) {
+        text.insert_str(0, "র্");
+        i += 1;
+    }
+    Some((text, i))
+}
+
+/// Bijoy text as Unicode Bangla, with vowel signs and reph moved back to
+/// where Unicode keeps them. Every character is read as a Bijoy glyph, so
+/// English in the same text comes out as Bangla too.
+pub fn decode(bijoy: &str) -> String {
+    let pieces = split_pieces(bijoy);
+    let mut unicode = String::new();
+    let mut i = 0;
+    while i < pieces.len() {
+        match &pieces[i] {
+            Piece::Pre(sign) => {
+                let Some((cluster, mut end)) = cluster_at(&pieces, i + 1) else {
+                    unicode.push(*sign);
+                    i += 1;
+                    continue;
+                };
+                unicode.push_str(&cluster);
+                // E with aa or au length mark after the consonant is O or AU
+                let sign = match (sign, pieces.get(end)) {
+                    ('ে', Some(Piece::Other(after))) if after == "া" => 'ো',
+                    ('ে', Some(Piece::Other(after))) if after == "ৗ" => 'ৌ',
+                    _ => {
+                        end -= 1;
+                        *sign
+                    }
+                };
+                unicode.push(sign);
+                i = end + 1;
+            }
+            Piece::Consonant(_) => {
+                let (cluster, end) = cluster_at(&pieces, i).unwrap_or_default();
+                unicode.push_str(&cluster);
+                i = end;
+            }
+            Piece::Reph => {
+                unicode.push_str("র্");
+                i += 1;
+            }
+            Piece::Join(text) | Piece::Other(text) => {
+                unicode.push_str(text);
+                i += 1;
+            }
+        }
+    }
+    unicode
+}
+
+// Glyphs of signs and conjuncts at codes English text doesn't use, not
+// even for quotes, dashes or accented letters
+const MARKERS: &str = "†‡‰Š¨Ö©ÿ³µ¼¾ÁÂÄÆËÎÏØšÍ›¤¯æƒû¸ï";
+
+/// Whether copied text looks like Bijoy rather than English or Banglish:
+/// no Unicode Bangla, and enough signs of glyph order. Those are the
+/// glyphs above, the dari typed as | and capitals inside words, which
+/// Bijoy's consonants are (wjwL for লিখি). At least one of the first two
+/// is needed, since names like iPhone have capitals inside too.
+pub fn looks_like_bijoy(text: &str) -> bool {
+    if text.chars().any(|c| ('\u{0980}'..='\u{09FF}').contains(&c)) {
+        return false;
+    }
+    let glyphs = text
+        .chars()
+        .filter(|c| MARKERS.contains(*c) || *c == '|')
+        .count();
+    let words: Vec<&str> = text.split_whitespace().collect();
+    let capitals_inside = words
+        .iter()
+        .filter(|word| {
+            let chars: Vec<char> = word.chars().collect();
+            chars
+                .windows(2)
+                .any(|pair| pair[0].is_ascii_lowercase() && pair[1].is_ascii_uppercase())
+        })
+        .count();
+    let signs = glyphs + capitals_inside;
+    glyphs > 0 && signs >= 2 && signs * 4 >= words.len()
+}
+
+// Windows-1252 puts typographic marks at 0x80-0x9F, where Latin-1 has
+// control characters; unused bytes keep their Latin-1 meaning
+const ANSI_HIGH: [char; 32] = [
+    '€', '\u{81}', '‚', 'ƒ', '„', '…', '†', '‡', 'ˆ', '‰', 'Š', '‹', 'Œ', '\u{8D}', 'Ž', '\u{8F}',
+    '\u{90}', '‘', '’', '“', '”', '•', '–', '—', '˜', '™', 'š', '›', 'œ', '\u{9D}', 'ž', 'Ÿ',
+];
+
+fn from_ansi(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .map(|&byte| match byte {
+            0x80..=0x9F => ANSI_HIGH[byte as usize - 0x80],
+            _ => byte as char,
+        })
+        .collect()
+}
+
+// Characters with no byte, such as Bangla that had no glyph, become ?
+fn to_ansi(text: &str) -> Vec<u8> {
+    text.chars()
+        .map(|c| match ANSI_HIGH.iter().position(|&high| high == c) {
+            Some(index) => 0x80 + index as u8,
+            None if (c as u32) < 0x100 => c as u8,
+            None => b'?',
+        })
+        .collect()
+}
+
+/// How a text file to convert is read, the first works it out from the
+/// file.
+pub const FILE_ENCODINGS: [&str; 5] = [
+    "Detect",
+    "UTF-8",
+    "UTF-16 LE",
+    "UTF-16 BE",
+    "ANSI (Windows-1252)",
+];
+
+// Byte order marks, which Notepad and Word write in front of Unicode text
+const UTF8_BOM: &[u8] = &[0xEF, 0xBB, 0xBF];
+const UTF16LE_BOM: &[u8] = &[0xFF, 0xFE];
+const UTF16BE_BOM: &[u8] = &[0xFE, 0xFF];
+
+/// The encoding a file's bytes are most likely in, one of FILE_ENCODINGS
+/// other than the first: its byte order mark if it has one, UTF-16 when
+/// zero bytes fall mostly on one side of each pair, as they do in mostly
+/// ASCII or Bangla text, UTF-8 when the bytes are valid UTF-8, and
+/// otherwise ANSI, the way Bijoy-era editors save.
+pub fn detect_encoding(bytes: &[u8]) -> &'static str {
+    if bytes.starts_with(UTF8_BOM) {
+        return FILE_ENCODINGS[1];
+    }
+    if bytes.starts_with(UTF16LE_BOM) {
+        return FILE_ENCODINGS[2];
+    }
+    if bytes.starts_with(UTF16BE_BOM) {
+        return FILE_ENCODINGS[3];
+    }
+    // The start of the file is enough to tell
+    let sample = &bytes[..bytes.len().min(4096) & !1];
+    let pairs = sample.len() / 2;
+    let zeros = |side: usize| {
+        sample
+            .iter()
+            .skip(side)
+            .step_by(2)
+            .filter(|&&byte| byte == 0)
+            .count()
+    };
+    let (even, odd) = (zeros(0), zeros(1));
+    if pairs > 0 && odd * 4 >= pairs && even * 10 < odd {
+        return FILE_ENCODINGS[2];
+    }
+    if pairs > 0 && even * 4 >= pairs && odd * 10 < even {
+        return FILE_ENCODINGS[3];
+    }
+    if std::str::from_utf8(bytes).is_ok() {
+        FILE_ENCODINGS[1]
+    } else {
+        FILE_ENCODINGS[4]
+    }
+}
+
+fn from_utf16(bytes: &[u8], little_endian: bool) -> io::Result<String> {
+    let units = bytes.chunks(2).map(|pair| match (pair, little_endian) {
+        ([low, high], true) | ([high, low], false) => u16::from_le_bytes([*low, *high]),
+        // An odd byte at the end
+        _ => 0xFFFD,
+    });
+    char::decode_utf16(units)
+        .collect::<Result<String, _>>()
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+}
+
+/// A file's bytes as text, read in `encoding`, one of FILE_ENCODINGS. A
+/// byte order mark is dropped.
+pub fn read_text(bytes: &[u8], encoding: &str) -> io::Result<String> {
+    let encoding = match encoding {
+        "Detect" => detect_encoding(bytes),
+        encoding => encoding,
+    };
+    match encoding {
+        "UTF-16 LE" => from_utf16(bytes.strip_prefix(UTF16LE_BOM).unwrap_or(bytes), true),
+        "UTF-16 BE" => from_utf16(bytes.strip_prefix(UTF16BE_BOM).unwrap_or(bytes), false),
+        "UTF-8" => String::from_utf8(bytes.strip_prefix(UTF8_BOM).unwrap_or(bytes).to_vec())
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err)),
+        _ => Ok(from_ansi(bytes)),
+    }
+}
+
+/// The abbreviations of a Bijoy abbreviation list, as a short word and its
+/// Unicode expansion. Lines are `short=expansion` or `short<TAB>expansion`,
+/// the expansion in Bijoy text or already Unicode; lines whose short form
+/// isn't one roman word are skipped.
+pub fn parse_abbreviations(text: &str) -> Vec<(String, String)> {
+    text.lines()
+        .filter_map(|line| {
+            let line = line.trim();
+            let (short, expansion) = line.split_once('=').or_else(|| line.split_once('\t'))?;
+            let (short, expansion) = (short.trim(), expansion.trim());
+            if short.is_empty()
+                || expansion.is_empty()
+                || !short.chars().all(|c| c.is_ascii_alphanumeric())
+            {
+                return None;
+            }
+            let bangla = expansion
+                .chars()
+                .any(|c| ('\u{0980}'..='\u{09FF}').contains(&c));
+            let expansion = match bangla {
+                true => expansion.to_string(),
+                false => decode(expansion),
+            };
+            Some((short.to_lowercase(), expansion))
+        })
+        .collect()
+}
+
+/// Converts a plain-text file between Bijoy and Unicode, writing the result
+/// next to it as `<name> (Unicode).txt` or `<name> (Bijoy).txt`. The file
+/// is read in `encoding`, one of FILE_ENCODINGS; Unicode is written as
+/// UTF-8 and Bijoy as ANSI, the way Bijoy-era editors save it. Digits and
+/// the dari going to Bijoy follow `policy`, one of numerals::POLICIES.
+pub fn convert_file(
+    path: &Path,
+    to_unicode: bool,
+    policy: &str,
+    encoding: &str,
+) -> io::Result<PathBuf> {
+    let text = read_text(&fs::read(path)?, encoding)?;
+    let (suffix, converted) = if to_unicode {
+        ("Unicode", decode(&text).into_bytes())
+    } else {
+        ("Bijoy", to_ansi(&crate::numerals::to_bijoy(&text, policy)))
+    };
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let target = path.with_file_name(format!("{} ({}).txt", stem, suffix));
+    fs::write(&target, converted)?;
+    Ok(target)
+}
+
+/// The plan that makes the same change to Bijoy text, given the Unicode
+/// text typed before it. Reordering can move glyphs well before the
+/// changed characters, so both versions are encoded and compared.
+pub fn translate(before: &str, plan: &EditPlan) -> EditPlan {
+    let chars: Vec<char> = before.chars().collect();
+    let Some(kept) = chars.len().checked_sub(plan.backspaces) else {
+        // The erased text isn't known, so neither is its length in Bijoy
+        return EditPlan {
+            backspaces: plan.backspaces,
+            text: encode(&plan.text),
+        };
+    };
+    let after: String = chars[..kept].iter().collect::<String>() + &plan.text;
+    let (old, new) = (encode(before), encode(&after));
+    let same = old
+        .chars()
+        .zip(new.chars())
+        .take_while(|(old, new)| old == new)
+        .count();
+    EditPlan {
+        backspaces: old.chars().count() - same,
+        text: new.chars().skip(same).collect(),
+    }
+}
+
+/// Whether text for the app in front goes out as Bijoy.
+pub fn applies(settings: &KeyboardSettings) -> bool {
+    match settings.output_encoding.as_str() {
+        "Bijoy (ANSI)" => true,
+        "Bijoy (ANSI) in listed apps" => {
+            let app = crate::problems::foreground_app();
+            settings
+                .bijoy_apps
+                .lines()
+                .any(|listed| listed.trim().eq_ignore_ascii_case(&app))
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn plan(backspaces: usize, text: &str) -> EditPlan {
+        EditPlan {
+            backspaces,
+            text: text.to_string(),
+        }
+    }
+
+    #[test]
+    fn letters_map_to_their_glyphs() {
+        assert_eq!(encode("আমার"), "Avgvi");
+        assert_eq!(encode("২০২৫।"), "2025|");
+        assert_eq!(encode("Bangla বাংলা"), "Bangla evsjv");
+    }
+
+    #[test]
+    fn vowel_signs_before_the_consonant_come_first() {
+        assert_eq!(encode("কি"), "wK");
+        assert_eq!(encode("দেশ"), "‡`k");
+        assert_eq!(encode("কৈ"), "‰K");
+        // O and au are drawn on both sides
+        assert_eq!(encode("বোন"), "‡evb");
+        assert_eq!(encode("মৌ"), "‡gŠ");
+    }
+
+    #[test]
+    fn signs_go_before_the_whole_conjunct() {
+        assert_eq!(encode("প্রতি"), "cÖwZ");
+        assert_eq!(encode("ক্ষেত"), "‡ÿZ");
+        assert_eq!(encode("স্তোত্র"), "‡¯ÍvÎ");
+    }
+
+    #[test]
+    fn reph_follows_its_consonant() {
+        assert_eq!(encode("কর্ম"), "Kg©");
+        assert_eq!(encode("ধর্মে"), "a‡g©");
+    }
+
+    #[test]
+    fn conjuncts_without_a_glyph_keep_the_hasant() {
+        assert_eq!(encode("ক্খ"), "K&L");
+        assert_eq!(encode("ক্"), "K&");
+    }
+
+    #[test]
+    fn consonants_with_u_can_be_one_glyph() {
+        assert_eq!(encode("রুমাল"), "iægvj");
+        assert_eq!(encode("কুল"), "Kyj");
+    }
+
+    #[test]
+    fn nukta_letters_are_one_glyph() {
+        assert_eq!(encode("বড়"), "eo");
+        assert_eq!(encode("\u{09AF}\u{09BC}\u{09BE}"), "qv");
+    }
+
+    #[test]
+    fn decoding_undoes_encoding() {
+        for word in [
+            "আমি বাংলায় লিখি।",
+            "প্রতি",
+            "ক্ষেত",
+            "স্তোত্র",
+            "ধর্মে",
+            "কৌতুক",
+            "রুমাল",
+            "বড়",
+            "ক্খ",
+            "২০২৫",
+        ] {
+            assert_eq!(decode(&encode(word)), word);
+        }
+    }
+
+    #[test]
+    fn old_documents_decode_with_other_glyphs() {
+        assert_eq!(decode("†`k"), "দেশ");
+        assert_eq!(decode("Avgiv"), "আমরা");
+        // A sign with no consonant after it stays where it is
+        assert_eq!(decode("w"), "ি");
+    }
+
+    #[test]
+    fn copied_bijoy_is_told_from_english() {
+        assert!(looks_like_bijoy("Avwg evsjvq wjwL|"));
+        assert!(looks_like_bijoy(&encode("আমার সোনার বাংলা, আমি তোমায় ভালোবাসি।")));
+        assert!(!looks_like_bijoy("He said “hello” — it’s fine…"));
+        assert!(!looks_like_bijoy("My iPhone and MacBook"));
+        assert!(!looks_like_bijoy("Café au lait, 20°C"));
+        assert!(!looks_like_bijoy("আমি | তুমি ‡"));
+    }
+
+    #[test]
+    fn file_encodings_are_detected() {
+        let bangla = "আমি ami";
+        let utf16 = |little: bool| -> Vec<u8> {
+            bangla
+                .encode_utf16()
+                .flat_map(|unit| match little {
+                    true => unit.to_le_bytes(),
+                    false => unit.to_be_bytes(),
+                })
+                .collect()
+        };
+        let with_bom = [UTF16LE_BOM, &utf16(true)].concat();
+        assert_eq!(detect_encoding(&with_bom), "UTF-16 LE");
+        assert_eq!(read_text(&with_bom, "Detect").unwrap(), bangla);
+        // Without a mark, from where the zero bytes fall
+        assert_eq!(detect_encoding(&utf16(true)), "UTF-16 LE");
+        assert_eq!(detect_encoding(&utf16(false)), "UTF-16 BE");
+        assert_eq!(read_text(&utf16(false), "Detect").unwrap(), bangla);
+        let utf8 = [UTF8_BOM, bangla.as_bytes()].concat();
+        assert_eq!(read_text(&utf8, "Detect").unwrap(), bangla);
+        assert_eq!(detect_encoding(bangla.as_bytes()), "UTF-8");
+        // Bijoy saved by an old editor
+        let ansi = to_ansi("Avwg †Zvgv‡K");
+        assert_eq!(detect_encoding(&ansi), "ANSI (Windows-1252)");
+        assert_eq!(read_text(&ansi, "Detect").unwrap(), "Avwg †Zvgv‡K");
+        // The choice made wins
+        assert_eq!(read_text(b"ab", "UTF-16 LE").unwrap(), "\u{6261}");
+    }
+
+    #[test]
+    fn abbreviations_are_decoded() {
+        let list = "; Bijoy abbreviations\nbd=evsjv‡`k\nami\tAvwg\nok=ঠিক\ntwo words=Avwg\n";
+        assert_eq!(
+            parse_abbreviations(list),
+            [
+                ("bd".to_string(), "বাংলাদেশ".to_string()),
+                ("ami".to_string(), "আমি".to_string()),
+                ("ok".to_string(), "ঠিক".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn ansi_bytes_round_trip() {
+        let bijoy = encode("স্তোত্র ক্ষেত");
+        assert_eq!(from_ansi(&to_ansi(&bijoy)), bijoy);
+        assert_eq!(to_ansi("‡"), [0x87]);
+        assert_eq!(from_ansi(&[0xFF]), "ÿ");
+    }
+
+    #[test]
+    fn translated_plans_retype_reordered_glyphs() {
+        // ক is on screen as K; typing ি puts w before it
+        assert_eq!(translate("আমি ক", &plan(0, "ি")), plan(1, "wK"));
+        // কি to কী: w K becomes K x
+        assert_eq!(translate("কি", &plan(1, "ী")), plan(2, "Kx"));
+        assert_eq!(translate("ক", &plan(1, "খ")), plan(1, "L"));
+        assert_eq!(translate("", &plan(0, "বাংলা")), plan(0, "evsjv"));
+    }
+
+    #[test]
+    fn unknown_erased_text_is_counted_as_is() {
+        assert_eq!(translate("", &plan(2, "কি")), plan(2, "wK"));
+    }
+}
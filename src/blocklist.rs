@@ -0,0 +1,37 @@
+use std::fs;
+use std::io;
+
+// Words that never show up as suggestions, one per line. Roman words match
+// without regard to case; '#' starts a comment line.
+
+fn entries(list: &str) -> impl Iterator<Item = &str> {
+    list.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+}
+
+pub fn count(list: &str) -> usize {
+    entries(list).count()
+}
+
+pub fn is_blocked(list: &str, word: &str) -> bool {
+    entries(list).any(|entry| entry.to_lowercase() == word.to_lowercase())
+}
+
+/// Appends the words of a list file that aren't blocked yet, returning how
+/// many were added.
+pub fn import(list: &mut String, path: &str) -> io::Result<usize> {
+    let contents = fs::read_to_string(path.trim())?;
+    let mut added = 0;
+    for word in entries(&contents) {
+        if is_blocked(list, word) {
+            continue;
+        }
+        if !list.is_empty() && !list.ends_with('\n') {
+            list.push('\n');
+        }
+        list.push_str(word);
+        added += 1;
+    }
+    Ok(added)
+}
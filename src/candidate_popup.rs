@@ -0,0 +1,200 @@
+use crate::caret::{self, Caret};
+use crate::mask;
+use crate::overlay::{self, Side};
+use crate::settings::KeyboardSettings;
+use egui::{RichText, ViewportId};
+use once_cell::sync::OnceCell;
+use restro_keyboard_core::Transliterator;
+use std::sync::Mutex;
+
+// One per number key
+const MAX_CANDIDATES: usize = 9;
+
+#[derive(Clone)]
+struct Popup {
+    // Where the word started, so the popup stays put while it grows
+    caret: Caret,
+    // The word's text as typed on screen first, then the others
+    candidates: Vec<String>,
+    // How each candidate is said, when hints are on and the dictionary knows
+    hints: Vec<Option<String>>,
+    selected: usize,
+}
+
+// Updated by the hook thread, drawn by the UI on its next frame
+static CURRENT: Mutex<Option<Popup>> = Mutex::new(None);
+static UI_CONTEXT: OnceCell<egui::Context> = OnceCell::new();
+
+/// Lets the hook wake the UI when the candidates change.
+pub fn attach(ctx: egui::Context) {
+    let _ = UI_CONTEXT.set(ctx);
+}
+
+fn repaint() {
+    if let Some(ctx) = UI_CONTEXT.get() {
+        ctx.request_repaint();
+    }
+}
+
+/// Spellings of a roman word: the conversion typed on screen, then the
+/// user's own, most typed first, the dictionary's, the one with gemination
+/// switched, and each phonetic layout's. One number key is left for the
+/// word as typed.
+fn candidates(roman: &str, settings: &KeyboardSettings) -> Vec<String> {
+    let layouts = crate::layouts();
+    let engine = crate::engine(&layouts, settings);
+
+    let mut candidates = vec![engine.transliterate(roman)];
+    let others = crate::learned_words::spellings(roman)
+        .into_iter()
+        .chain(crate::dictionary().get(roman))
+        .chain(std::iter::once(
+            Transliterator::new(engine.layout())
+                .with_gemination(!settings.gemination(engine.layout()))
+                .transliterate(roman),
+        ))
+        .chain(layouts.iter().map(|layout| {
+            Transliterator::new(layout)
+                .with_gemination(settings.gemination(layout))
+                .transliterate(roman)
+        }));
+    for candidate in others {
+        if !candidates.contains(&candidate) {
+            candidates.push(candidate);
+        }
+    }
+    candidates.truncate(MAX_CANDIDATES - 1);
+    candidates
+}
+
+/// Shows the candidates for the word being typed, or hides the popup when
+/// there is no word or only one spelling. Called from the hook thread.
+pub fn update(roman: &str, settings: &KeyboardSettings) {
+    let mut candidates = candidates(roman, settings);
+    if roman.is_empty() || candidates.len() < 2 {
+        close();
+        return;
+    }
+    // Last, the word left in English. The keys keep the case they were
+    // typed in, so iPhone comes back as iPhone.
+    if !candidates.iter().any(|candidate| candidate == roman) {
+        candidates.push(roman.to_string());
+    }
+    // A hint that is the word as typed says nothing new
+    let dictionary = crate::dictionary();
+    let typed = roman.to_lowercase();
+    let hints = candidates
+        .iter()
+        .map(|candidate| match settings.pronunciation_hints {
+            true => dictionary.roman_of(candidate).filter(|hint| *hint != typed),
+            false => None,
+        })
+        .collect();
+    let mut current = CURRENT.lock().unwrap();
+    let caret = match current.as_ref() {
+        Some(popup) => popup.caret,
+        None => match caret::locate() {
+            Some(caret) => caret,
+            None => return,
+        },
+    };
+    *current = Some(Popup {
+        caret,
+        candidates,
+        hints,
+        selected: 0,
+    });
+    drop(current);
+    repaint();
+}
+
+pub fn close() {
+    if CURRENT.lock().unwrap().take().is_some() {
+        repaint();
+    }
+}
+
+/// The highlighted candidate's index, while the popup shows.
+pub fn selected() -> Option<usize> {
+    CURRENT.lock().unwrap().as_ref().map(|popup| popup.selected)
+}
+
+/// Moves the highlight, wrapping around. Returns false when the popup is
+/// hidden.
+pub fn move_selection(down: bool) -> bool {
+    let mut current = CURRENT.lock().unwrap();
+    let Some(popup) = current.as_mut() else {
+        return false;
+    };
+    let count = popup.candidates.len();
+    popup.selected = if down {
+        (popup.selected + 1) % count
+    } else {
+        (popup.selected + count - 1) % count
+    };
+    drop(current);
+    repaint();
+    true
+}
+
+/// Highlights a candidate by index. Returns false when there is none.
+pub fn select(index: usize) -> bool {
+    let mut current = CURRENT.lock().unwrap();
+    match current.as_mut() {
+        Some(popup) if index < popup.candidates.len() => {
+            popup.selected = index;
+            true
+        }
+        _ => false,
+    }
+}
+
+/// Closes the popup, returning the text on screen and the highlighted
+/// candidate to replace it with.
+pub fn take() -> Option<(String, String)> {
+    let popup = CURRENT.lock().unwrap().take()?;
+    repaint();
+    let typed = popup.candidates.first()?.clone();
+    let chosen = popup.candidates.get(popup.selected)?.clone();
+    Some((typed, chosen))
+}
+
+/// Draws the popup below the caret. It never takes focus or clicks, so
+/// typing goes on in the app underneath.
+pub fn show(ctx: &egui::Context, font_size: f32) {
+    let Some(popup) = CURRENT.lock().unwrap().clone() else {
+        return;
+    };
+    let settings = crate::SETTINGS.snapshot();
+
+    let width = if popup.hints.iter().any(Option::is_some) {
+        260.0
+    } else {
+        180.0
+    };
+    let size = egui::vec2(
+        width,
+        (font_size + 12.0) * popup.candidates.len() as f32 + 8.0,
+    );
+    let position = overlay::position(ctx, &popup.caret, font_size, size, Side::Below);
+
+    ctx.show_viewport_immediate(
+        ViewportId::from_hash_of("candidate_popup"),
+        overlay::builder("Candidates", position, size),
+        |ctx, _class| {
+            egui::CentralPanel::default().show(ctx, |ui| {
+                for (index, candidate) in popup.candidates.iter().enumerate() {
+                    let candidate = mask::shown(&settings, candidate);
+                    let text =
+                        RichText::new(format!("{} {}", index + 1, candidate)).size(font_size + 2.0);
+                    ui.horizontal(|ui| {
+                        ui.add(egui::SelectableLabel::new(index == popup.selected, text));
+                        if let Some(hint) = &popup.hints[index] {
+                            ui.label(RichText::new(mask::shown(&settings, hint)).weak().italics());
+                        }
+                    });
+                }
+            });
+        },
+    );
+}
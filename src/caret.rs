@@ -0,0 +1,70 @@
+use windows::Win32::Foundation::{HWND, POINT};
+use windows::Win32::Graphics::Gdi::ClientToScreen;
+use windows::Win32::UI::WindowsAndMessaging::{
+    GetClassNameW, GetCursorPos, GetForegroundWindow, GetGUIThreadInfo, GetWindowLongW,
+    GetWindowThreadProcessId, ES_PASSWORD, GUITHREADINFO, GWL_STYLE,
+};
+
+/// Where typed text goes: the foreground window and the caret's bottom-left
+/// corner in screen pixels.
+#[derive(Clone, Copy, Debug)]
+pub struct Caret {
+    pub window: HWND,
+    pub position: POINT,
+}
+
+/// Finds the caret of the foreground app. Apps that draw their own caret
+/// (browsers, Electron) don't report it, so the mouse pointer stands in.
+pub fn locate() -> Option<Caret> {
+    unsafe {
+        let window = GetForegroundWindow();
+        if window.0 == 0 {
+            return None;
+        }
+
+        let thread = GetWindowThreadProcessId(window, None);
+        let mut info = GUITHREADINFO {
+            cbSize: std::mem::size_of::<GUITHREADINFO>() as u32,
+            ..Default::default()
+        };
+        if GetGUIThreadInfo(thread, &mut info).is_ok() && info.hwndCaret.0 != 0 {
+            let mut position = POINT {
+                x: info.rcCaret.left,
+                y: info.rcCaret.bottom,
+            };
+            if ClientToScreen(info.hwndCaret, &mut position).as_bool() {
+                return Some(Caret { window, position });
+            }
+        }
+
+        let mut position = POINT::default();
+        GetCursorPos(&mut position).ok()?;
+        Some(Caret { window, position })
+    }
+}
+
+/// Whether keyboard focus is in a password box. Only edit controls say so
+/// (Edit, RichEdit, WinForms text boxes); browsers and apps that draw their
+/// own fields don't.
+pub fn in_password_field() -> bool {
+    unsafe {
+        let window = GetForegroundWindow();
+        if window.0 == 0 {
+            return false;
+        }
+
+        let thread = GetWindowThreadProcessId(window, None);
+        let mut info = GUITHREADINFO {
+            cbSize: std::mem::size_of::<GUITHREADINFO>() as u32,
+            ..Default::default()
+        };
+        if GetGUIThreadInfo(thread, &mut info).is_err() || info.hwndFocus.0 == 0 {
+            return false;
+        }
+        // Other controls use the same style bit for something else
+        let mut class = [0u16; 64];
+        let len = GetClassNameW(info.hwndFocus, &mut class) as usize;
+        let class = String::from_utf16_lossy(&class[..len]).to_lowercase();
+        class.contains("edit") && GetWindowLongW(info.hwndFocus, GWL_STYLE) & ES_PASSWORD != 0
+    }
+}
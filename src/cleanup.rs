@@ -0,0 +1,143 @@
+use crate::settings::KeyboardSettings;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use windows::core::w;
+use windows::Win32::Foundation::ERROR_FILE_NOT_FOUND;
+use windows::Win32::System::Registry::{RegDeleteKeyValueW, HKEY_CURRENT_USER};
+
+// Removes what the keyboard leaves on the computer, for people who copied
+// it somewhere instead of installing it: a startup entry in the Run key or
+// the Startup folder, the words shared with Windows spelling, and the app
+// data folder, optionally keeping the user's dictionary. The keyboard
+// registers no file associations, and its windows find each other by
+// title, so no endpoints outlive it. The app exits afterwards, before a
+// saver can write anything back.
+
+// Startup entries the keyboard may have been given, by the names it goes by
+const RUN_KEY: windows::core::PCWSTR = w!("Software\\Microsoft\\Windows\\CurrentVersion\\Run");
+const RUN_VALUES: [windows::core::PCWSTR; 2] = [w!("Restro Keyboard"), w!("RestroKeyboard")];
+const STARTUP_SHORTCUTS: [&str; 2] = ["Restro Keyboard.lnk", "RestroKeyboard.lnk"];
+
+/// The files kept when the user keeps their dictionary.
+pub const DICTIONARY_FILES: [&str; 2] = ["dictionary.tsv", "full_dictionary.fst"];
+
+fn startup_folder() -> Option<PathBuf> {
+    let appdata = std::env::var_os("APPDATA")?;
+    Some(
+        PathBuf::from(appdata)
+            .join("Microsoft")
+            .join("Windows")
+            .join("Start Menu")
+            .join("Programs")
+            .join("Startup"),
+    )
+}
+
+fn remove_startup_entries() -> io::Result<()> {
+    for value in RUN_VALUES {
+        match unsafe { RegDeleteKeyValueW(HKEY_CURRENT_USER, RUN_KEY, value) } {
+            Err(err) if err.code() != ERROR_FILE_NOT_FOUND.to_hresult() => return Err(err.into()),
+            _ => {}
+        }
+    }
+    let Some(folder) = startup_folder() else {
+        return Ok(());
+    };
+    for name in STARTUP_SHORTCUTS {
+        let shortcut = folder.join(name);
+        if shortcut.exists() {
+            fs::remove_file(shortcut)?;
+        }
+    }
+    Ok(())
+}
+
+// Deletes everything in `dir` but the files named in `kept`, and `dir`
+// itself when nothing is kept
+fn clear_folder(dir: &Path, kept: &[&str]) -> io::Result<()> {
+    if !dir.exists() {
+        return Ok(());
+    }
+    let mut left = 0;
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        let name = path.file_name().and_then(|name| name.to_str());
+        if name.is_some_and(|name| kept.contains(&name)) {
+            left += 1;
+        } else if path.is_dir() {
+            fs::remove_dir_all(&path)?;
+        } else {
+            fs::remove_file(&path)?;
+        }
+    }
+    if left == 0 {
+        fs::remove_dir(dir)?;
+    }
+    Ok(())
+}
+
+/// Removes the startup entries, the Windows spelling words and the app
+/// data, keeping the user's dictionary when asked. Every step is tried;
+/// the ones that failed come back described.
+pub fn run(keep_dictionary: bool) -> Vec<String> {
+    let mut failed = Vec::new();
+    if let Err(err) = remove_startup_entries() {
+        failed.push(format!("Startup entries: {}", err));
+    }
+    if let Err(err) = crate::windows_spelling::uninstall() {
+        failed.push(format!("Windows spelling words: {}", err));
+    }
+    // Mapped while installed, and Windows won't delete a mapped file
+    if !keep_dictionary {
+        if let Err(err) = crate::full_dictionary::remove() {
+            failed.push(format!("Installed dictionary: {}", err));
+        }
+    }
+    let kept: &[&str] = if keep_dictionary {
+        &DICTIONARY_FILES
+    } else {
+        &[]
+    };
+    let data = KeyboardSettings::path().and_then(|path| Some(path.parent()?.to_path_buf()));
+    if let Some(Err(err)) = data.map(|dir| clear_folder(&dir, kept)) {
+        failed.push(format!("App data: {}", err));
+    }
+    failed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn folder(name: &str) -> PathBuf {
+        let folder = std::env::temp_dir().join(format!("restro-cleanup-{}", name));
+        let _ = fs::remove_dir_all(&folder);
+        fs::create_dir_all(folder.join("transcripts")).unwrap();
+        fs::write(folder.join("settings.json"), "{}").unwrap();
+        fs::write(folder.join("dictionary.tsv"), "ami\tআমি\n").unwrap();
+        fs::write(folder.join("transcripts").join("1.txt"), "").unwrap();
+        folder
+    }
+
+    #[test]
+    fn the_dictionary_can_be_kept() {
+        let dir = folder("keep");
+        clear_folder(&dir, &DICTIONARY_FILES).unwrap();
+        let left: Vec<_> = fs::read_dir(&dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name())
+            .collect();
+        assert_eq!(left, ["dictionary.tsv"]);
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn without_it_the_folder_goes() {
+        let dir = folder("all");
+        clear_folder(&dir, &[]).unwrap();
+        assert!(!dir.exists());
+        // Nothing to do the second time
+        clear_folder(&dir, &[]).unwrap();
+    }
+}
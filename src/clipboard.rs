@@ -0,0 +1,400 @@
+use crate::notification::{self, Icon};
+use std::ffi::c_void;
+use std::time::{Duration, Instant};
+use windows::Win32::Foundation::{HANDLE, HGLOBAL, HWND};
+use windows::Win32::System::DataExchange::{
+    CloseClipboard, EmptyClipboard, EnumClipboardFormats, GetClipboardData,
+    GetClipboardSequenceNumber, IsClipboardFormatAvailable, OpenClipboard, SetClipboardData,
+};
+use windows::Win32::System::Memory::{
+    GlobalAlloc, GlobalLock, GlobalSize, GlobalUnlock, GMEM_MOVEABLE,
+};
+use windows::Win32::System::Ole::{
+    CF_BITMAP, CF_DSPBITMAP, CF_DSPENHMETAFILE, CF_DSPMETAFILEPICT, CF_ENHMETAFILE, CF_GDIOBJLAST,
+    CF_METAFILEPICT, CF_OWNERDISPLAY, CF_PALETTE, CF_PRIVATEFIRST, CF_UNICODETEXT,
+};
+use windows::Win32::UI::Input::KeyboardAndMouse::{SendInput, INPUT};
+
+const MONITOR_INTERVAL: Duration = Duration::from_millis(500);
+// Characters of the converted text the confirmation shows
+const PREVIEW_LEN: usize = 60;
+// How long the hotkey's modifiers may stay held, and the app may take to
+// copy and to paste
+const RELEASE_TIMEOUT: Duration = Duration::from_secs(1);
+const COPY_TIMEOUT: Duration = Duration::from_millis(500);
+const PASTE_TIME: Duration = Duration::from_millis(300);
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+pub fn sequence_number() -> u32 {
+    unsafe { GetClipboardSequenceNumber() }
+}
+
+pub fn read_text() -> Option<String> {
+    unsafe {
+        IsClipboardFormatAvailable(CF_UNICODETEXT.0 as u32).ok()?;
+        OpenClipboard(HWND(0)).ok()?;
+        let text = read_unicode_text();
+        let _ = CloseClipboard();
+        text
+    }
+}
+
+pub fn write_text(text: &str) -> bool {
+    unsafe {
+        if OpenClipboard(HWND(0)).is_err() {
+            return false;
+        }
+        let written = write_unicode_text(text).is_some();
+        let _ = CloseClipboard();
+        written
+    }
+}
+
+// Caller must have the clipboard open
+unsafe fn read_unicode_text() -> Option<String> {
+    let handle = GetClipboardData(CF_UNICODETEXT.0 as u32).ok()?;
+    let memory = HGLOBAL(handle.0 as *mut c_void);
+    let data = GlobalLock(memory) as *const u16;
+    if data.is_null() {
+        return None;
+    }
+
+    // Another app's data need not end in a NUL, so stop at the allocation
+    let capacity = GlobalSize(memory) / 2;
+    let mut len = 0;
+    while len < capacity && *data.add(len) != 0 {
+        len += 1;
+    }
+    let text = String::from_utf16_lossy(std::slice::from_raw_parts(data, len));
+    let _ = GlobalUnlock(memory);
+    Some(text)
+}
+
+// Caller must have the clipboard open
+unsafe fn write_unicode_text(text: &str) -> Option<()> {
+    let bytes: Vec<u8> = text
+        .encode_utf16()
+        .chain(Some(0))
+        .flat_map(u16::to_ne_bytes)
+        .collect();
+
+    EmptyClipboard().ok()?;
+    set_memory(CF_UNICODETEXT.0 as u32, &bytes)
+}
+
+// Caller must have the clipboard open
+unsafe fn set_memory(format: u32, bytes: &[u8]) -> Option<()> {
+    let memory = GlobalAlloc(GMEM_MOVEABLE, bytes.len().max(1)).ok()?;
+    let data = GlobalLock(memory) as *mut u8;
+    if data.is_null() {
+        return None;
+    }
+    std::ptr::copy_nonoverlapping(bytes.as_ptr(), data, bytes.len());
+    let _ = GlobalUnlock(memory);
+
+    // The clipboard owns the memory from here on
+    SetClipboardData(format, HANDLE(memory.0 as isize)).ok()?;
+    Some(())
+}
+
+// Formats held in GDI objects or drawn by their owner, which can't be
+// copied as memory
+fn is_handle_format(format: u32) -> bool {
+    [
+        CF_METAFILEPICT,
+        CF_PALETTE,
+        CF_ENHMETAFILE,
+        CF_OWNERDISPLAY,
+        CF_DSPBITMAP,
+        CF_DSPMETAFILEPICT,
+        CF_DSPENHMETAFILE,
+    ]
+    .iter()
+    .any(|handle| handle.0 as u32 == format)
+        || (CF_PRIVATEFIRST.0 as u32..=CF_GDIOBJLAST.0 as u32).contains(&format)
+}
+
+/// Everything on the clipboard, format by format, or None when part of it
+/// can't be saved, like a metafile or an app's private handle.
+fn save_all() -> Option<Vec<(u32, Vec<u8>)>> {
+    unsafe {
+        OpenClipboard(HWND(0)).ok()?;
+        let saved = save_formats();
+        let _ = CloseClipboard();
+        saved
+    }
+}
+
+// Caller must have the clipboard open
+unsafe fn save_formats() -> Option<Vec<(u32, Vec<u8>)>> {
+    let mut saved = Vec::new();
+    let mut format = 0;
+    loop {
+        format = EnumClipboardFormats(format);
+        if format == 0 {
+            return Some(saved);
+        }
+        // Windows makes a bitmap again from the DIB saved with it
+        if format == CF_BITMAP.0 as u32 {
+            continue;
+        }
+        if is_handle_format(format) {
+            return None;
+        }
+        let handle = GetClipboardData(format).ok()?;
+        let memory = HGLOBAL(handle.0 as *mut c_void);
+        let data = GlobalLock(memory) as *const u8;
+        if data.is_null() {
+            return None;
+        }
+        saved.push((
+            format,
+            std::slice::from_raw_parts(data, GlobalSize(memory)).to_vec(),
+        ));
+        let _ = GlobalUnlock(memory);
+    }
+}
+
+fn restore_all(saved: &[(u32, Vec<u8>)]) -> bool {
+    unsafe {
+        if OpenClipboard(HWND(0)).is_err() {
+            return false;
+        }
+        let restored = EmptyClipboard().is_ok()
+            && saved
+                .iter()
+                .all(|(format, bytes)| set_memory(*format, bytes).is_some());
+        let _ = CloseClipboard();
+        restored
+    }
+}
+
+// Common English words, which a copied English sentence is full of and
+// Banglish hardly uses. Loanwords the dictionary lists count as English.
+const ENGLISH_WORDS: [&str; 122] = [
+    "the", "be", "to", "of", "and", "a", "in", "that", "have", "i", "it", "for", "not", "on",
+    "with", "he", "as", "you", "do", "at", "this", "but", "his", "by", "from", "they", "we", "say",
+    "her", "she", "or", "an", "will", "my", "one", "all", "would", "there", "their", "what", "so",
+    "up", "out", "if", "about", "who", "get", "which", "go", "me", "when", "make", "can", "like",
+    "time", "no", "just", "him", "know", "take", "people", "into", "year", "your", "good", "some",
+    "could", "them", "see", "other", "than", "then", "now", "look", "only", "come", "its", "over",
+    "think", "also", "back", "after", "use", "two", "how", "our", "work", "first", "well", "way",
+    "even", "new", "want", "because", "any", "these", "give", "day", "most", "us", "is", "are",
+    "was", "were", "am", "has", "had", "been", "love", "much", "hello", "world", "thanks", "thank",
+    "please", "yes", "okay", "ok", "hi", "school", "office", "computer",
+];
+
+// Plain Banglish: letters, spaces and sentence punctuation only, so URLs,
+// code and numbers are left alone. Of the words, at least a third must be
+// Banglish the keyboard knows, two of them in longer text, and at most a
+// quarter common English, so English sentences stay as copied.
+fn looks_romanized(text: &str, is_banglish: impl Fn(&str) -> bool) -> bool {
+    let plain = text.chars().any(|c| c.is_ascii_alphabetic())
+        && text.chars().all(|c| {
+            c.is_ascii_alphabetic() || c.is_ascii_whitespace() || ".,;:!?'\"-()".contains(c)
+        });
+    if !plain {
+        return false;
+    }
+
+    let words: Vec<String> = text
+        .split(|c: char| !c.is_ascii_alphabetic())
+        .filter(|word| !word.is_empty())
+        .map(str::to_ascii_lowercase)
+        .collect();
+    let english = words
+        .iter()
+        .filter(|word| ENGLISH_WORDS.contains(&word.as_str()))
+        .count();
+    let banglish = words
+        .iter()
+        .filter(|word| !ENGLISH_WORDS.contains(&word.as_str()) && is_banglish(word))
+        .count();
+    banglish >= words.len().min(2) && banglish * 3 >= words.len() && english * 4 <= words.len()
+}
+
+// Whether the keyboard knows `word` as Banglish: from the dictionary, the
+// autocorrect list or the words learned from typing
+fn known_banglish() -> impl Fn(&str) -> bool {
+    let dictionary = crate::dictionary();
+    let corrections = crate::autocorrect::corrections();
+    let learned = crate::learned_words::words();
+    move |word| {
+        dictionary.get(word).is_some()
+            || corrections.get(word).is_some()
+            || learned
+                .iter()
+                .any(|learned| learned.roman.eq_ignore_ascii_case(word))
+    }
+}
+
+/// Polls the clipboard and, while the monitor is switched on, replaces
+/// copied Bijoy or Banglish text with Unicode Bangla.
+pub fn spawn_monitor() {
+    std::thread::spawn(|| {
+        let mut last_seen = sequence_number();
+        loop {
+            std::thread::sleep(MONITOR_INTERVAL);
+
+            let sequence = sequence_number();
+            if sequence == last_seen {
+                continue;
+            }
+            last_seen = sequence;
+
+            let settings = crate::SETTINGS.snapshot();
+            if !settings.monitors_clipboard() {
+                continue;
+            }
+            let Some(text) = read_text() else {
+                continue;
+            };
+            // Bijoy first, as its glyphs can be plain letters too
+            let converted = if crate::bijoy::looks_like_bijoy(&text) {
+                crate::bijoy::decode(&text)
+            } else if looks_romanized(&text, known_banglish()) {
+                crate::engine(&crate::layouts(), &settings).transliterate_text(&text)
+            } else {
+                continue;
+            };
+
+            if write_text(&converted) {
+                // Don't pick up our own write as a new copy
+                last_seen = sequence_number();
+            }
+        }
+    });
+}
+
+/// The conversion hotkey: replaces the text on the clipboard with its
+/// Bangla conversion, whatever it looks like, and confirms with a
+/// notification. Blocks while that shows.
+pub fn convert() {
+    let Some(text) = read_text().filter(|text| !text.trim().is_empty()) else {
+        notification::show(
+            "Nothing to convert",
+            "The clipboard has no text.",
+            Icon::Info,
+        );
+        return;
+    };
+    let settings = crate::SETTINGS.snapshot();
+    let converted = crate::engine(&crate::layouts(), &settings).transliterate_text(&text);
+    if !write_text(&converted) {
+        notification::show(
+            "The clipboard couldn't be converted",
+            "Another app is using the clipboard. Try again.",
+            Icon::Warning,
+        );
+        return;
+    }
+    let mut preview: String = converted.chars().take(PREVIEW_LEN).collect();
+    if converted.chars().count() > PREVIEW_LEN {
+        preview.push('…');
+    }
+    notification::show("Clipboard converted to Bangla", &preview, Icon::Info);
+}
+
+// Waits up to `timeout` for `done`
+fn wait_for(timeout: Duration, done: impl Fn() -> bool) -> bool {
+    let start = Instant::now();
+    while !done() {
+        if start.elapsed() > timeout {
+            return false;
+        }
+        std::thread::sleep(POLL_INTERVAL);
+    }
+    true
+}
+
+fn send_ctrl(key: u8) {
+    let inputs = crate::injection::ctrl_shortcut(key);
+    unsafe {
+        SendInput(&inputs, std::mem::size_of::<INPUT>() as i32);
+    }
+}
+
+/// The selection hotkey: copies the selected text, pastes its Bangla
+/// conversion over it, then puts back everything the clipboard held before,
+/// in every format. When part of that can't be saved, such as a metafile,
+/// nothing is converted so the clipboard isn't lost. Blocks while the app
+/// copies and pastes.
+pub fn convert_selection() {
+    // Ctrl+C with Shift or Alt still held is another shortcut
+    if !wait_for(RELEASE_TIMEOUT, crate::hotkey::modifiers_released) {
+        return;
+    }
+    let Some(saved) = save_all() else {
+        notification::show(
+            "The selection wasn't converted",
+            "The clipboard holds a drawing that couldn't be put back afterwards.",
+            Icon::Warning,
+        );
+        return;
+    };
+    let before = sequence_number();
+    send_ctrl(b'C');
+    let copied = wait_for(COPY_TIMEOUT, || sequence_number() != before);
+    let Some(text) = read_text().filter(|text| copied && !text.trim().is_empty()) else {
+        notification::show(
+            "Nothing to convert",
+            "Select some Banglish text first.",
+            Icon::Info,
+        );
+        return;
+    };
+    let settings = crate::SETTINGS.snapshot();
+    let converted = crate::engine(&crate::layouts(), &settings).transliterate_text(&text);
+    if !write_text(&converted) {
+        notification::show(
+            "The selection couldn't be converted",
+            "Another app is using the clipboard. Try again.",
+            Icon::Warning,
+        );
+        return;
+    }
+    send_ctrl(b'V');
+    // The caret is somewhere else now
+    crate::clear_composition();
+    crate::HISTORY.lock().unwrap().clear();
+    std::thread::sleep(PASTE_TIME);
+    restore_all(&saved);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn known(word: &str) -> bool {
+        [
+            "ami", "tumi", "kemon", "acho", "bhalo", "valo", "office", "rat", "din", "jabo",
+        ]
+        .contains(&word)
+    }
+
+    #[test]
+    fn banglish_is_converted() {
+        assert!(looks_romanized("ami tomake bhalo bashi", known));
+        assert!(looks_romanized("Kemon acho?", known));
+        assert!(looks_romanized("tumi valo", known));
+        assert!(looks_romanized("ami", known));
+    }
+
+    #[test]
+    fn english_is_left_alone() {
+        assert!(!looks_romanized("I love you so much", known));
+        assert!(!looks_romanized("Hello world!", known));
+        assert!(!looks_romanized("I am at the office today.", known));
+        assert!(!looks_romanized("The rat ran all day.", known));
+        assert!(!looks_romanized("Rat race", known));
+        assert!(!looks_romanized("office", known));
+    }
+
+    #[test]
+    fn unknown_words_and_code_are_left_alone() {
+        assert!(!looks_romanized("lorem ipsum dolor", known));
+        assert!(!looks_romanized("https://example.com/ami", known));
+        assert!(!looks_romanized("ami 2", known));
+        assert!(!looks_romanized("", known));
+    }
+}
@@ -0,0 +1,506 @@
+use crate::bijoy;
+use crate::find_replace;
+use crate::numerals;
+use crate::script_stats::{self, Counts};
+use crate::settings::{self, KeyboardSettings};
+use crate::text_counts::TextCounts;
+use crate::web_copy;
+use egui::text::{CCursor, CCursorRange};
+use egui::{Id, RichText, TextStyle};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+/// What the converter turns into what, the first is the default.
+pub const MODES: [&str; 4] = [
+    "Banglish → Bangla",
+    "Bangla → Banglish",
+    "Unicode → Bijoy",
+    "Bijoy → Unicode",
+];
+
+// The text is saved this long after the last change, or when the window
+// closes, so a crash loses a moment's work at most
+const SAVE_DELAY: Duration = Duration::from_secs(2);
+// Longer text is converted but not kept across restarts
+const MAX_SAVED_BYTES: usize = 1 << 20;
+
+// What is kept in converter.json
+#[derive(Serialize, Deserialize, Clone, Default, PartialEq)]
+struct Saved {
+    mode: String,
+    text: String,
+}
+
+/// `%APPDATA%\RestroKeyboard\converter.json`, next to the settings.
+pub fn path() -> Option<PathBuf> {
+    Some(KeyboardSettings::path()?.parent()?.join("converter.json"))
+}
+
+fn delete() -> io::Result<()> {
+    match path() {
+        Some(path) if path.exists() => fs::remove_file(path),
+        _ => Ok(()),
+    }
+}
+
+/// Text converted without typing into another app: Banglish to Bangla or
+/// back, or old Bijoy documents to Unicode and back. Bijoy text files can
+/// be converted whole.
+pub struct Converter {
+    pub open: bool,
+    mode: String,
+    text: String,
+    file_path: String,
+    // One of bijoy::FILE_ENCODINGS, and what the file at file_path looks
+    // like it is in
+    file_encoding: String,
+    file_detected: Option<&'static str>,
+    file_status: Option<String>,
+    find: String,
+    replacement: String,
+    regex: bool,
+    // Where Find next looks from, a byte offset in the text
+    find_from: usize,
+    replaced: Option<usize>,
+    // As last written to converter.json
+    saved: Saved,
+    // When the text first differed from what was saved
+    changed_at: Option<Instant>,
+    too_long: bool,
+    // Characters in converter.json, which keeps older text when too long
+    kept_chars: usize,
+}
+
+impl Default for Converter {
+    fn default() -> Self {
+        Self {
+            open: false,
+            mode: MODES[0].to_string(),
+            text: String::new(),
+            file_path: String::new(),
+            file_encoding: bijoy::FILE_ENCODINGS[0].to_string(),
+            file_detected: None,
+            file_status: None,
+            find: String::new(),
+            replacement: String::new(),
+            regex: false,
+            find_from: 0,
+            replaced: None,
+            saved: Saved::default(),
+            changed_at: None,
+            too_long: false,
+            kept_chars: 0,
+        }
+    }
+}
+
+impl Converter {
+    /// The converter as it was left, text and mode.
+    pub fn load() -> Self {
+        let saved: Saved = path()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default();
+        let mut converter = Self::default();
+        if MODES.contains(&saved.mode.as_str()) {
+            converter.mode = saved.mode.clone();
+        }
+        converter.text = saved.text.clone();
+        converter.kept_chars = saved.text.chars().count();
+        converter.saved = saved;
+        converter
+    }
+
+    /// Characters kept in converter.json.
+    pub fn saved_chars(&self) -> usize {
+        self.kept_chars
+    }
+
+    /// Empties the converter and deletes its saved text.
+    pub fn clear(&mut self) -> io::Result<()> {
+        self.text.clear();
+        self.find_from = 0;
+        self.save()
+    }
+
+    // Writes the text and mode, or deletes the file when there is no text
+    fn save(&mut self) -> io::Result<()> {
+        self.changed_at = None;
+        let current = Saved {
+            mode: self.mode.clone(),
+            text: self.text.clone(),
+        };
+        self.too_long = current.text.len() > MAX_SAVED_BYTES;
+        if self.too_long {
+            // What was saved before stays; it's only retried on a change
+            self.saved = current;
+            return Ok(());
+        }
+        if current.text.is_empty() {
+            delete()?;
+        } else {
+            let path = path()
+                .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "APPDATA is not set"))?;
+            settings::save_json_atomically(&path, &current)?;
+        }
+        self.kept_chars = current.text.chars().count();
+        self.saved = current;
+        Ok(())
+    }
+
+    // Saves once the text has rested, or at once when the window closed
+    fn autosave(&mut self, ctx: &egui::Context) {
+        if self.mode == self.saved.mode && self.text == self.saved.text {
+            self.changed_at = None;
+            return;
+        }
+        let changed_at = *self.changed_at.get_or_insert_with(Instant::now);
+        let waited = changed_at.elapsed();
+        if self.open && waited < SAVE_DELAY {
+            ctx.request_repaint_after(SAVE_DELAY - waited);
+            return;
+        }
+        if let Err(err) = self.save() {
+            eprintln!("Could not save the converter's text: {}", err);
+        }
+    }
+
+    pub fn show(&mut self, ctx: &egui::Context, font_size: f32) {
+        let mut open = self.open;
+        egui::Window::new("Converter")
+            .open(&mut open)
+            .show(ctx, |ui| {
+                let settings = crate::SETTINGS.snapshot();
+                ui.horizontal(|ui| {
+                    for mode in MODES {
+                        ui.radio_value(&mut self.mode, mode.to_string(), mode);
+                    }
+                });
+                let layouts = crate::layouts();
+                let engine = crate::engine(&layouts, &settings);
+                let mode = self.mode.clone();
+                let (from, to) = mode.split_once(" → ").unwrap_or_default();
+                let (hint, converted, bangla_in) = match self.mode.as_str() {
+                    "Bangla → Banglish" => (
+                        "আমি বাংলায় লিখি",
+                        numerals::romanize(&engine, &self.text, &settings.reverse_numbers),
+                        true,
+                    ),
+                    "Unicode → Bijoy" => (
+                        "আমি বাংলায় লিখি",
+                        numerals::to_bijoy(&self.text, &settings.reverse_numbers),
+                        true,
+                    ),
+                    "Bijoy → Unicode" => ("Avwg evsjvq wjwL", bijoy::decode(&self.text), false),
+                    _ => (
+                        "ami banglay likhi",
+                        engine.transliterate_text(&self.text),
+                        false,
+                    ),
+                };
+                // Numbers, dates and the dari going out of Bangla
+                if to == "Banglish" || to == "Bijoy" {
+                    let mut policy = settings.reverse_numbers.clone();
+                    ui.horizontal(|ui| {
+                        ui.label("Numbers and dari:");
+                        egui::ComboBox::from_id_source("reverse_numbers")
+                            .selected_text(&policy)
+                            .show_ui(ui, |ui| {
+                                for option in numerals::POLICIES {
+                                    ui.selectable_value(&mut policy, option.to_string(), option);
+                                }
+                            });
+                    })
+                    .response
+                    .on_hover_text("Bijoy fonts draw digits as Bangla either way");
+                    if policy != settings.reverse_numbers {
+                        crate::SETTINGS
+                            .update(|settings| settings.reverse_numbers = policy.clone());
+                    }
+                }
+                let font = egui::FontId::proportional(font_size + 4.0);
+                let (from_font, to_font) = if bangla_in {
+                    (font, TextStyle::Body.resolve(ui.style()))
+                } else {
+                    (TextStyle::Body.resolve(ui.style()), font)
+                };
+                ui.columns(2, |columns| {
+                    columns[0].label(from);
+                    columns[0].add(
+                        egui::TextEdit::multiline(&mut self.text)
+                            .id(input_id())
+                            .font(from_font)
+                            .hint_text(hint)
+                            .desired_rows(10),
+                    );
+                    columns[1].label(to);
+                    columns[1].add(
+                        egui::TextEdit::multiline(&mut converted.as_str())
+                            .font(to_font)
+                            .desired_rows(10),
+                    );
+                    // For word limits, on either side
+                    for (column, text) in columns.iter_mut().zip([&self.text, &converted]) {
+                        if !text.is_empty() {
+                            column.label(
+                                RichText::new(TextCounts::of(text).summary())
+                                    .weak()
+                                    .size(12.0),
+                            );
+                        }
+                    }
+                });
+                ui.horizontal(|ui| {
+                    if ui
+                        .add_enabled(
+                            !converted.is_empty(),
+                            egui::Button::new(format!("Copy {}", to)),
+                        )
+                        .clicked()
+                    {
+                        ctx.output_mut(|o| o.copied_text = converted.clone());
+                    }
+                    let mut profile = settings.web_profile.clone();
+                    egui::ComboBox::from_id_source("web_profile")
+                        .selected_text(&profile)
+                        .show_ui(ui, |ui| {
+                            for option in web_copy::PROFILES {
+                                ui.selectable_value(&mut profile, option.to_string(), option);
+                            }
+                        });
+                    if profile != settings.web_profile {
+                        crate::SETTINGS.update(|settings| settings.web_profile = profile.clone());
+                    }
+                    if ui
+                        .add_enabled(!converted.is_empty(), egui::Button::new("Copy for web"))
+                        .on_hover_text(
+                            "Cleans invisible characters and punctuation that the site mangles",
+                        )
+                        .clicked()
+                    {
+                        let prepared = web_copy::prepare(&converted, &profile);
+                        ctx.output_mut(|o| o.copied_text = prepared);
+                    }
+                    if ui
+                        .button("Clear")
+                        .on_hover_text("Also deletes the text kept for next time")
+                        .clicked()
+                    {
+                        if let Err(err) = self.clear() {
+                            eprintln!("Could not delete the converter's text: {}", err);
+                        }
+                    }
+                    ui.label(
+                        RichText::new(format!("{} layout", settings.state.layout))
+                            .weak()
+                            .size(12.0),
+                    );
+                    if self.too_long {
+                        ui.label(
+                            RichText::new("Too long to keep for next time")
+                                .weak()
+                                .size(12.0),
+                        );
+                    }
+                });
+
+                egui::CollapsingHeader::new("Find and replace").show(ui, |ui| {
+                    self.find_and_replace(ui);
+                });
+
+                let issues = script_stats::issues(&self.text, from != "Bijoy");
+                if !issues.is_empty() {
+                    egui::CollapsingHeader::new(format!("Issues ({})", issues.len())).show(
+                        ui,
+                        |ui| {
+                            egui::ScrollArea::vertical()
+                                .id_source("issues")
+                                .max_height(120.0)
+                                .show(ui, |ui| {
+                                    for (line, range, issue) in issues {
+                                        let link = ui
+                                            .link(format!("Line {}: {}", line, issue.describe()))
+                                            .on_hover_text(&self.text[range.clone()]);
+                                        if link.clicked() {
+                                            select(ui.ctx(), &self.text, range);
+                                        }
+                                    }
+                                });
+                        },
+                    );
+                }
+
+                // Scripts on the Bangla side, to find what is left in English
+                let document = if bangla_in { &self.text } else { &converted };
+                if !document.trim().is_empty() {
+                    egui::CollapsingHeader::new("Scripts").show(ui, |ui| {
+                        script_stats::legend(ui);
+                        let counts = Counts::of(document);
+                        ui.horizontal(|ui| {
+                            ui.label("Whole text");
+                            script_stats::bar(ui, &counts, 160.0);
+                            ui.label(script_stats::summary(&counts));
+                        });
+                        egui::ScrollArea::vertical()
+                            .max_height(160.0)
+                            .show(ui, |ui| {
+                                let paragraphs = script_stats::paragraphs(document);
+                                for (index, (paragraph, counts)) in paragraphs.iter().enumerate() {
+                                    ui.horizontal(|ui| {
+                                        ui.label(format!("¶ {}", index + 1))
+                                            .on_hover_text(*paragraph);
+                                        script_stats::bar(ui, counts, 160.0);
+                                        ui.label(
+                                            RichText::new(script_stats::summary(counts)).size(12.0),
+                                        );
+                                    });
+                                }
+                            });
+                    });
+                }
+
+                // Whole documents, saved as plain text from the old editor
+                if from == "Bijoy" || to == "Bijoy" {
+                    ui.separator();
+                    ui.horizontal(|ui| {
+                        ui.label("Text file:");
+                        if ui.text_edit_singleline(&mut self.file_path).changed() {
+                            self.file_detected = fs::read(self.file_path.trim())
+                                .ok()
+                                .map(|bytes| bijoy::detect_encoding(&bytes));
+                        }
+                        if ui.button("Convert file").clicked() {
+                            let path = Path::new(self.file_path.trim());
+                            self.file_status = Some(
+                                match bijoy::convert_file(
+                                    path,
+                                    to == "Unicode",
+                                    &settings.reverse_numbers,
+                                    &self.file_encoding,
+                                ) {
+                                    Ok(saved) => format!("Saved {}", saved.display()),
+                                    Err(err) => format!("Could not convert: {}", err),
+                                },
+                            );
+                        }
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("Read as:");
+                        let shown = |encoding: &str| match (encoding, self.file_detected) {
+                            ("Detect", Some(detected)) => format!("Detect ({})", detected),
+                            _ => encoding.to_string(),
+                        };
+                        egui::ComboBox::from_id_source("file_encoding")
+                            .selected_text(shown(&self.file_encoding))
+                            .show_ui(ui, |ui| {
+                                for encoding in bijoy::FILE_ENCODINGS {
+                                    let label = shown(encoding);
+                                    ui.selectable_value(
+                                        &mut self.file_encoding,
+                                        encoding.to_string(),
+                                        label,
+                                    );
+                                }
+                            })
+                            .response
+                            .on_hover_text(
+                                "Pick another encoding if the converted text comes out garbled.",
+                            );
+                    });
+                    ui.label(
+                        RichText::new(
+                            "Word documents need saving as plain text (.txt) first; \
+                             the result is saved next to the file.",
+                        )
+                        .weak()
+                        .size(12.0),
+                    );
+                    if let Some(status) = &self.file_status {
+                        ui.label(RichText::new(status).weak().size(12.0));
+                    }
+                }
+            });
+        self.open = open;
+        self.autosave(ctx);
+    }
+
+    // On the text being converted. Letters with two Unicode spellings match
+    // either way, see find_replace.
+    fn find_and_replace(&mut self, ui: &mut egui::Ui) {
+        egui::Grid::new("find_replace")
+            .num_columns(2)
+            .show(ui, |ui| {
+                ui.label("Find:");
+                if ui.text_edit_singleline(&mut self.find).changed() {
+                    self.find_from = 0;
+                    self.replaced = None;
+                }
+                ui.end_row();
+                ui.label("Replace with:");
+                ui.text_edit_singleline(&mut self.replacement);
+                ui.end_row();
+            });
+        ui.checkbox(&mut self.regex, "Regular expression")
+            .on_hover_text("Groups can be used in the replacement as $1");
+        let found = find_replace::find(&self.text, &self.find, self.regex);
+        ui.horizontal(|ui| {
+            let matches = found.as_ref().map_or(0, Vec::len);
+            if ui
+                .add_enabled(matches > 0, egui::Button::new("Find next"))
+                .clicked()
+            {
+                let found = found.as_deref().unwrap_or_default();
+                let next = found
+                    .iter()
+                    .find(|range| range.start >= self.find_from)
+                    .or(found.first());
+                if let Some(range) = next {
+                    self.find_from = range.end;
+                    select(ui.ctx(), &self.text, range.clone());
+                }
+            }
+            if ui
+                .add_enabled(matches > 0, egui::Button::new("Replace all"))
+                .clicked()
+            {
+                if let Ok((text, count)) =
+                    find_replace::replace_all(&self.text, &self.find, &self.replacement, self.regex)
+                {
+                    self.text = text;
+                    self.find_from = 0;
+                    self.replaced = Some(count);
+                }
+            }
+            let status = match (&found, self.replaced) {
+                (Err(err), _) => err.clone(),
+                (Ok(_), Some(count)) => format!("Replaced {}", count),
+                (Ok(_), None) if self.find.is_empty() => String::new(),
+                (Ok(found), None) => match found.len() {
+                    1 => "1 match".to_string(),
+                    count => format!("{} matches", count),
+                },
+            };
+            ui.label(RichText::new(status).weak().size(12.0));
+        });
+    }
+}
+
+fn input_id() -> Id {
+    Id::new("converter_input")
+}
+
+// Selects a byte range of the input box's text and focuses it
+fn select(ctx: &egui::Context, text: &str, range: std::ops::Range<usize>) {
+    let start = text[..range.start].chars().count();
+    let end = start + text[range].chars().count();
+    let mut state = egui::text_edit::TextEditState::load(ctx, input_id()).unwrap_or_default();
+    state.cursor.set_char_range(Some(CCursorRange::two(
+        CCursor::new(start),
+        CCursor::new(end),
+    )));
+    state.store(ctx, input_id());
+    ctx.memory_mut(|memory| memory.request_focus(input_id()));
+}
@@ -0,0 +1,94 @@
+use std::time::{Duration, Instant};
+use windows::Win32::Foundation::FILETIME;
+use windows::Win32::System::ProcessStatus::{GetProcessMemoryInfo, PROCESS_MEMORY_COUNTERS};
+use windows::Win32::System::Threading::{
+    GetCurrentProcess, GetProcessHandleCount, GetProcessTimes,
+};
+
+const SAMPLE_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Clone, Copy, Default)]
+pub struct Footprint {
+    pub working_set_bytes: usize,
+    pub cpu_percent: f32,
+    pub handles: u32,
+}
+
+/// CPU usage is the share of all cores used since the previous sample,
+/// so readings are taken at most once per `SAMPLE_INTERVAL`.
+#[derive(Default)]
+pub struct FootprintSampler {
+    last_sample: Option<(Instant, u64)>,
+    footprint: Footprint,
+}
+
+impl FootprintSampler {
+    pub fn sample(&mut self) -> Footprint {
+        let now = Instant::now();
+        if let Some((taken, _)) = self.last_sample {
+            if now.duration_since(taken) < SAMPLE_INTERVAL {
+                return self.footprint;
+            }
+        }
+
+        let cpu_time = process_cpu_time_100ns();
+        if let Some((taken, last_cpu_time)) = self.last_sample {
+            let wall = now.duration_since(taken).as_secs_f32();
+            let cpu = cpu_time.saturating_sub(last_cpu_time) as f32 / 10_000_000.0;
+            let cores = std::thread::available_parallelism().map_or(1, |n| n.get()) as f32;
+            self.footprint.cpu_percent = cpu / wall / cores * 100.0;
+        }
+        self.last_sample = Some((now, cpu_time));
+        self.footprint.working_set_bytes = working_set_bytes();
+        self.footprint.handles = handle_count();
+
+        self.footprint
+    }
+}
+
+fn filetime_to_u64(time: FILETIME) -> u64 {
+    (u64::from(time.dwHighDateTime) << 32) | u64::from(time.dwLowDateTime)
+}
+
+// Kernel plus user time of this process, in 100 ns units
+fn process_cpu_time_100ns() -> u64 {
+    let mut creation = FILETIME::default();
+    let mut exit = FILETIME::default();
+    let mut kernel = FILETIME::default();
+    let mut user = FILETIME::default();
+    unsafe {
+        if GetProcessTimes(
+            GetCurrentProcess(),
+            &mut creation,
+            &mut exit,
+            &mut kernel,
+            &mut user,
+        )
+        .is_err()
+        {
+            return 0;
+        }
+    }
+    filetime_to_u64(kernel) + filetime_to_u64(user)
+}
+
+fn working_set_bytes() -> usize {
+    let mut counters = PROCESS_MEMORY_COUNTERS {
+        cb: std::mem::size_of::<PROCESS_MEMORY_COUNTERS>() as u32,
+        ..Default::default()
+    };
+    unsafe {
+        if GetProcessMemoryInfo(GetCurrentProcess(), &mut counters, counters.cb).is_err() {
+            return 0;
+        }
+    }
+    counters.WorkingSetSize
+}
+
+fn handle_count() -> u32 {
+    let mut handles = 0;
+    unsafe {
+        let _ = GetProcessHandleCount(GetCurrentProcess(), &mut handles);
+    }
+    handles
+}
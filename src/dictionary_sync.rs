@@ -0,0 +1,399 @@
+use crate::learned_words::{self, LearnedWord};
+use crate::settings::{self, KeyboardSettings};
+use crate::user_dictionary;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
+
+// The user's dictionary and learned words kept alike on several computers
+// through a folder they all see, such as one in Dropbox or OneDrive. Each
+// computer only ever appends to its own log there, so the sync service
+// never has two versions of a file to choose between:
+//
+//   word<TAB>ami<TAB>আমি          ami was added to the dictionary as আমি
+//   typed<TAB>ami<TAB>আমি<TAB>3   আমি was typed for ami three more times
+//
+// Merging reads every log. The words are the union of all of them and the
+// uses of a spelling are summed; a word added with different Bangla on two
+// computers gets the spelling typed most. What the last merge came to is
+// kept in sync.json, so only what changed since is appended, and words
+// removed here since aren't brought back.
+
+// Under the chosen folder, so the logs don't mix with the user's files
+const LOG_FOLDER: &str = "Restro Keyboard";
+const INTERVAL: Duration = Duration::from_secs(5 * 60);
+
+// Syncs take turns, so a log never gets the same change twice
+static SYNCING: Mutex<()> = Mutex::new(());
+
+// A roman word and its Bangla
+type Spelling = (String, String);
+
+#[derive(Default, Debug, PartialEq)]
+struct Logs {
+    words: BTreeSet<Spelling>,
+    typed: BTreeMap<Spelling, u32>,
+}
+
+impl Logs {
+    // Lines cut short by a sync in progress, or from a newer version, are
+    // skipped
+    fn read(&mut self, text: &str) {
+        for line in text.lines() {
+            let fields: Vec<&str> = line.split('\t').collect();
+            match fields.as_slice() {
+                ["word", roman, bangla] => {
+                    self.words.insert((roman.to_string(), bangla.to_string()));
+                }
+                ["typed", roman, bangla, count] => {
+                    if let Ok(count) = count.parse::<u32>() {
+                        let uses = self
+                            .typed
+                            .entry((roman.to_string(), bangla.to_string()))
+                            .or_default();
+                        *uses = uses.saturating_add(count);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+/// What the last merge came to, for the folder it was made with.
+#[derive(Serialize, Deserialize, Default)]
+struct State {
+    folder: String,
+    words: Vec<Spelling>,
+    typed: Vec<LearnedWord>,
+}
+
+/// What a sync brought in from the other computers.
+#[derive(Debug, Default, PartialEq)]
+pub struct Report {
+    pub words: Vec<Spelling>,
+    pub uses: Vec<LearnedWord>,
+}
+
+impl Report {
+    pub fn summary(&self) -> String {
+        let uses: u32 = self.uses.iter().map(|word| word.count).sum();
+        format!(
+            "Synced: {} words and {} uses of spellings came from your other computers.",
+            self.words.len(),
+            uses
+        )
+    }
+}
+
+/// `%APPDATA%\RestroKeyboard\sync.json`, next to the settings.
+fn state_path() -> Option<PathBuf> {
+    Some(KeyboardSettings::path()?.parent()?.join("sync.json"))
+}
+
+fn read_state(folder: &str) -> Option<State> {
+    let json = fs::read_to_string(state_path()?).ok()?;
+    let state: State = serde_json::from_str(&json).ok()?;
+    (state.folder == folder).then_some(state)
+}
+
+// This computer's log, named after it
+fn log_name() -> String {
+    let computer = std::env::var("COMPUTERNAME").unwrap_or_else(|_| "computer".to_string());
+    let computer: String = computer
+        .chars()
+        .map(|c| if c.is_alphanumeric() { c } else { '_' })
+        .collect();
+    format!("{}.log", computer)
+}
+
+fn read_logs(dir: &Path) -> io::Result<Logs> {
+    let mut logs = Logs::default();
+    if !dir.exists() {
+        return Ok(logs);
+    }
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.extension().is_some_and(|ext| ext == "log") {
+            logs.read(&String::from_utf8_lossy(&fs::read(path)?));
+        }
+    }
+    Ok(logs)
+}
+
+fn read_log(path: &Path) -> Logs {
+    let mut log = Logs::default();
+    if let Ok(bytes) = fs::read(path) {
+        log.read(&String::from_utf8_lossy(&bytes));
+    }
+    log
+}
+
+// Appends what changed here to the log at `log`, merges every log in its
+// folder and works out what to bring in. Without a last merge, what this
+// computer's log already has counts as sent.
+fn merge(
+    log: &Path,
+    last: Option<&State>,
+    words: &BTreeMap<String, String>,
+    typed: &BTreeMap<Spelling, u32>,
+) -> io::Result<(Report, Logs)> {
+    let (sent_words, sent_typed) = match last {
+        Some(last) => (
+            last.words.iter().cloned().collect(),
+            last.typed
+                .iter()
+                .map(|word| ((word.roman.clone(), word.bangla.clone()), word.count))
+                .collect(),
+        ),
+        None => {
+            let own = read_log(log);
+            (own.words, own.typed)
+        }
+    };
+
+    let mut lines = String::new();
+    for (roman, bangla) in words {
+        if !sent_words.contains(&(roman.clone(), bangla.clone())) {
+            lines.push_str(&format!("word\t{}\t{}\n", roman, bangla));
+        }
+    }
+    for ((roman, bangla), count) in typed {
+        let sent = sent_typed
+            .get(&(roman.clone(), bangla.clone()))
+            .copied()
+            .unwrap_or(0);
+        if *count > sent {
+            lines.push_str(&format!("typed\t{}\t{}\t{}\n", roman, bangla, count - sent));
+        }
+    }
+    if !lines.is_empty() {
+        if let Some(dir) = log.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(log)?
+            .write_all(lines.as_bytes())?;
+    }
+
+    let merged = read_logs(log.parent().unwrap_or(Path::new(".")))?;
+    let mut report = Report::default();
+    // Removed here when the last merge had it and this computer doesn't
+    let removed = |seen: bool, here: bool| last.is_some() && seen && !here;
+
+    let mut chosen: BTreeMap<&str, (&str, u32)> = BTreeMap::new();
+    for (roman, bangla) in &merged.words {
+        let uses = merged
+            .typed
+            .get(&(roman.clone(), bangla.clone()))
+            .copied()
+            .unwrap_or(0);
+        // Ties go to the first in order, the same on every computer
+        match chosen.get(roman.as_str()) {
+            Some((_, most)) if *most >= uses => {}
+            _ => {
+                chosen.insert(roman, (bangla, uses));
+            }
+        }
+    }
+    for (roman, (bangla, _)) in chosen {
+        let here = words.get(roman);
+        let seen = sent_words.iter().any(|(sent, _)| sent == roman);
+        if here.map(String::as_str) != Some(bangla) && !removed(seen, here.is_some()) {
+            report.words.push((roman.to_string(), bangla.to_string()));
+        }
+    }
+    for ((roman, bangla), total) in &merged.typed {
+        let spelling = (roman.clone(), bangla.clone());
+        let here = typed.get(&spelling).copied();
+        if removed(sent_typed.contains_key(&spelling), here.is_some()) {
+            continue;
+        }
+        let here = here.unwrap_or(0);
+        if *total > here {
+            report.uses.push(LearnedWord {
+                roman: roman.clone(),
+                bangla: bangla.clone(),
+                count: total - here,
+            });
+        }
+    }
+    Ok((report, merged))
+}
+
+// The user's dictionary, where later lines win
+fn dictionary_words() -> BTreeMap<String, String> {
+    user_dictionary::load()
+        .lines()
+        .filter_map(|line| {
+            let (roman, bangla) = line.split_once('\t')?;
+            Some((roman.trim().to_string(), bangla.trim().to_string()))
+        })
+        .collect()
+}
+
+/// Merges this computer's dictionary and learned words with those of the
+/// others syncing through `folder`, and brings in what they added.
+pub fn sync(folder: &str) -> io::Result<Report> {
+    let _turn = SYNCING.lock().unwrap();
+    let folder = folder.trim();
+    if !Path::new(folder).is_dir() {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("{} isn't a folder", folder),
+        ));
+    }
+    let state_path = state_path()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "APPDATA is not set"))?;
+    let log = Path::new(folder).join(LOG_FOLDER).join(log_name());
+    let typed = learned_words::words()
+        .into_iter()
+        .map(|word| ((word.roman, word.bangla), word.count))
+        .collect();
+    let last = read_state(folder);
+    let (report, merged) = merge(&log, last.as_ref(), &dictionary_words(), &typed)?;
+
+    if !report.words.is_empty() {
+        user_dictionary::add(&report.words)?;
+        crate::reload_dictionary();
+    }
+    learned_words::add_uses(&report.uses);
+    let state = State {
+        folder: folder.to_string(),
+        words: merged.words.into_iter().collect(),
+        typed: merged
+            .typed
+            .into_iter()
+            .map(|((roman, bangla), count)| LearnedWord {
+                roman,
+                bangla,
+                count,
+            })
+            .collect(),
+    };
+    settings::save_json_atomically(&state_path, &state)?;
+    Ok(report)
+}
+
+/// Syncs every few minutes while a sync folder is chosen.
+pub fn spawn() {
+    std::thread::spawn(|| loop {
+        let folder = crate::SETTINGS.snapshot().sync_folder.clone();
+        if !folder.trim().is_empty() {
+            if let Err(err) = sync(&folder) {
+                eprintln!("Could not sync the dictionary: {}", err);
+            }
+        }
+        std::thread::sleep(INTERVAL);
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn folder(name: &str) -> PathBuf {
+        let folder = std::env::temp_dir().join(format!("restro-sync-{}", name));
+        let _ = fs::remove_dir_all(&folder);
+        folder
+    }
+
+    fn words(pairs: &[(&str, &str)]) -> BTreeMap<String, String> {
+        pairs
+            .iter()
+            .map(|(roman, bangla)| (roman.to_string(), bangla.to_string()))
+            .collect()
+    }
+
+    fn typed(counts: &[(&str, &str, u32)]) -> BTreeMap<Spelling, u32> {
+        counts
+            .iter()
+            .map(|(roman, bangla, count)| ((roman.to_string(), bangla.to_string()), *count))
+            .collect()
+    }
+
+    fn state(logs: Logs) -> State {
+        State {
+            folder: String::new(),
+            words: logs.words.into_iter().collect(),
+            typed: logs
+                .typed
+                .into_iter()
+                .map(|((roman, bangla), count)| LearnedWord {
+                    roman,
+                    bangla,
+                    count,
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn logs_merge_by_union_and_summed_uses() {
+        let dir = folder("union");
+        let (home, work) = (dir.join("HOME.log"), dir.join("WORK.log"));
+        let home_words = words(&[("ami", "আমি")]);
+        let home_typed = typed(&[("ami", "আমি", 2)]);
+        let (report, merged) = merge(&home, None, &home_words, &home_typed).unwrap();
+        assert_eq!(report, Report::default());
+        let home_state = state(merged);
+
+        let work_words = words(&[("tumi", "তুমি")]);
+        let work_typed = typed(&[("ami", "আমি", 3)]);
+        let (report, merged) = merge(&work, None, &work_words, &work_typed).unwrap();
+        assert_eq!(report.words, [("ami".to_string(), "আমি".to_string())]);
+        // Its own 3 and the other computer's 2
+        assert_eq!(merged.typed[&("ami".to_string(), "আমি".to_string())], 5);
+        assert_eq!(report.uses[0].count, 2);
+
+        // Typed twice more at home since
+        let home_typed = typed(&[("ami", "আমি", 4)]);
+        let (report, merged) = merge(&home, Some(&home_state), &home_words, &home_typed).unwrap();
+        assert_eq!(report.words, [("tumi".to_string(), "তুমি".to_string())]);
+        assert_eq!(report.uses[0].count, 3);
+        assert_eq!(merged.typed[&("ami".to_string(), "আমি".to_string())], 7);
+        assert_eq!(
+            fs::read_to_string(&home).unwrap(),
+            "word\tami\tআমি\ntyped\tami\tআমি\t2\ntyped\tami\tআমি\t2\n"
+        );
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn the_spelling_typed_most_wins() {
+        let dir = folder("conflict");
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(
+            dir.join("WORK.log"),
+            "word\tami\tআমী\ntyped\tami\tআমী\t5\nhalf a li",
+        )
+        .unwrap();
+        let here = words(&[("ami", "আমি")]);
+        let (report, _) = merge(&dir.join("HOME.log"), None, &here, &typed(&[])).unwrap();
+        assert_eq!(report.words, [("ami".to_string(), "আমী".to_string())]);
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn words_removed_here_stay_removed() {
+        let dir = folder("removed");
+        let log = dir.join("HOME.log");
+        let (_, merged) = merge(
+            &log,
+            None,
+            &words(&[("ami", "আমি")]),
+            &typed(&[("ami", "আমি", 1)]),
+        )
+        .unwrap();
+        let last = state(merged);
+        let (report, _) = merge(&log, Some(&last), &words(&[]), &typed(&[])).unwrap();
+        assert_eq!(report, Report::default());
+        let _ = fs::remove_dir_all(dir);
+    }
+}
@@ -0,0 +1,40 @@
+use crate::settings::KeyboardSettings;
+use restro_keyboard_core::{reference, Transliterator};
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+/// `%APPDATA%\RestroKeyboard\docs`, if APPDATA is set.
+pub fn dir() -> Option<PathBuf> {
+    Some(KeyboardSettings::path()?.parent()?.join("docs"))
+}
+
+/// Writes the Markdown and HTML reference of the engine's layout to the
+/// docs folder, returning it. Files of an earlier run are replaced so they
+/// always match the current rules.
+pub fn generate(engine: &Transliterator) -> io::Result<PathBuf> {
+    let dir = dir().ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "APPDATA is not set"))?;
+    fs::create_dir_all(&dir)?;
+
+    let name = engine.layout().name().to_lowercase().replace(' ', "-");
+    fs::write(
+        dir.join(format!("{}.md", name)),
+        reference::markdown(engine),
+    )?;
+    fs::write(dir.join(format!("{}.html", name)), reference::html(engine))?;
+    Ok(dir)
+}
+
+/// How many files the docs folder holds; none when it doesn't exist.
+pub fn file_count() -> usize {
+    dir()
+        .and_then(|dir| fs::read_dir(dir).ok())
+        .map_or(0, |entries| entries.count())
+}
+
+pub fn delete() -> io::Result<()> {
+    match dir() {
+        Some(dir) if dir.exists() => fs::remove_dir_all(dir),
+        _ => Ok(()),
+    }
+}
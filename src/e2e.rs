@@ -0,0 +1,338 @@
+// End-to-end tests: keys go through the real hook procedure and the text
+// it injects lands in a plain Edit control, whose text is then checked.
+// They need an interactive desktop, since injected input goes to the
+// foreground window:
+//
+//     cargo test --features e2e
+//
+// SendInput marks every event as injected, which the hook ignores, so key
+// presses are handed to the hook procedure directly, the way Windows would
+// call it. Keys the hook lets through are then sent to the Edit control.
+
+use crate::settings::KeyboardSettings;
+use crate::state::{Event, Language};
+use crate::{
+    clear_composition, clipboard, keyboard_hook_proc, simulate_unicode_input, spell_check, HISTORY,
+    SETTINGS,
+};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use windows::core::w;
+use windows::Win32::Foundation::{HWND, LPARAM, WPARAM};
+use windows::Win32::UI::Input::KeyboardAndMouse::{
+    SendInput, SetFocus, INPUT, INPUT_0, INPUT_KEYBOARD, KEYBDINPUT, KEYBD_EVENT_FLAGS,
+    KEYEVENTF_KEYUP, VIRTUAL_KEY, VK_BACK, VK_OEM_PERIOD, VK_SPACE,
+};
+use windows::Win32::UI::WindowsAndMessaging::{
+    CreateWindowExW, DestroyWindow, DispatchMessageW, GetWindowTextW, PeekMessageW, SendMessageW,
+    SetForegroundWindow, TranslateMessage, KBDLLHOOKSTRUCT, MSG, PM_REMOVE, WINDOW_EX_STYLE,
+    WM_KEYDOWN, WS_OVERLAPPEDWINDOW, WS_VISIBLE,
+};
+
+// The hook state and the foreground window are shared, so tests take turns
+static SERIAL: Mutex<()> = Mutex::new(());
+
+// Long enough for the Edit control to handle injected input
+const SETTLE_TIME: Duration = Duration::from_millis(50);
+
+// Selects text in an Edit control; its module isn't a feature we build with
+const EM_SETSEL: u32 = 0x00B1;
+
+struct EditWindow {
+    hwnd: HWND,
+}
+
+impl EditWindow {
+    fn open() -> Self {
+        let hwnd = unsafe {
+            CreateWindowExW(
+                WINDOW_EX_STYLE::default(),
+                w!("EDIT"),
+                w!(""),
+                WS_OVERLAPPEDWINDOW | WS_VISIBLE,
+                100,
+                100,
+                400,
+                100,
+                None,
+                None,
+                None,
+                None,
+            )
+        };
+        assert_ne!(hwnd.0, 0, "could not create the Edit control");
+        unsafe {
+            let _ = SetForegroundWindow(hwnd);
+            let _ = SetFocus(hwnd);
+        }
+        settle();
+        Self { hwnd }
+    }
+
+    fn select_all(&self) {
+        unsafe {
+            SendMessageW(self.hwnd, EM_SETSEL, WPARAM(0), LPARAM(-1));
+        }
+    }
+
+    fn text(&self) -> String {
+        let mut buffer = [0u16; 512];
+        let len = unsafe { GetWindowTextW(self.hwnd, &mut buffer) };
+        String::from_utf16_lossy(&buffer[..len as usize])
+    }
+}
+
+impl Drop for EditWindow {
+    fn drop(&mut self) {
+        unsafe {
+            let _ = DestroyWindow(self.hwnd);
+        }
+    }
+}
+
+// Lets the Edit control handle what was sent to it
+fn settle() {
+    let start = Instant::now();
+    let mut msg = MSG::default();
+    while start.elapsed() < SETTLE_TIME {
+        unsafe {
+            while PeekMessageW(&mut msg, None, 0, 0, PM_REMOVE).as_bool() {
+                let _ = TranslateMessage(&msg);
+                DispatchMessageW(&msg);
+            }
+        }
+        std::thread::sleep(Duration::from_millis(1));
+    }
+}
+
+fn send_key(vk: VIRTUAL_KEY) {
+    let input = |flags| INPUT {
+        r#type: INPUT_KEYBOARD,
+        Anonymous: INPUT_0 {
+            ki: KEYBDINPUT {
+                wVk: vk,
+                wScan: 0,
+                dwFlags: flags,
+                time: 0,
+                dwExtraInfo: 0,
+            },
+        },
+    };
+    unsafe {
+        SendInput(
+            &[input(KEYBD_EVENT_FLAGS(0)), input(KEYEVENTF_KEYUP)],
+            std::mem::size_of::<INPUT>() as i32,
+        );
+    }
+}
+
+// A physical key press as the hook sees it; passed keys reach the Edit
+// control like they would from the keyboard
+fn press(vk: VIRTUAL_KEY) {
+    let event = KBDLLHOOKSTRUCT {
+        vkCode: u32::from(vk.0),
+        ..Default::default()
+    };
+    let result = unsafe {
+        keyboard_hook_proc(
+            0,
+            WPARAM(WM_KEYDOWN as usize),
+            LPARAM(&event as *const KBDLLHOOKSTRUCT as isize),
+        )
+    };
+    if result.0 == 0 {
+        send_key(vk);
+    }
+    settle();
+}
+
+// Lowercase letters, digits, spaces and periods
+fn type_text(text: &str) {
+    for c in text.chars() {
+        let vk = match c {
+            ' ' => VK_SPACE,
+            '.' => VK_OEM_PERIOD,
+            c => VIRTUAL_KEY(c.to_ascii_uppercase() as u16),
+        };
+        press(vk);
+    }
+}
+
+// Bangla mode with the phonetic layout and default options
+fn start(configure: impl FnOnce(&mut KeyboardSettings)) -> EditWindow {
+    let mut settings = KeyboardSettings::default();
+    configure(&mut settings);
+    SETTINGS.publish(settings);
+    clear_composition();
+    HISTORY.lock().unwrap().clear();
+    EditWindow::open()
+}
+
+#[test]
+fn converts_as_keys_are_typed() {
+    let _serial = SERIAL.lock().unwrap_or_else(|err| err.into_inner());
+    let edit = start(|_| {});
+    type_text("ami");
+    assert_eq!(edit.text(), "অমি");
+}
+
+#[test]
+fn later_keys_replace_earlier_output() {
+    let _serial = SERIAL.lock().unwrap_or_else(|err| err.into_inner());
+    let edit = start(|_| {});
+    type_text("kh");
+    assert_eq!(edit.text(), "খ");
+    type_text("k");
+    assert_eq!(edit.text(), "খক");
+}
+
+#[test]
+fn doubled_consonants_join() {
+    let _serial = SERIAL.lock().unwrap_or_else(|err| err.into_inner());
+    let edit = start(|settings| {
+        settings
+            .layout_gemination
+            .insert("Phonetic".to_string(), true);
+    });
+    type_text("kk");
+    assert_eq!(edit.text(), "ক্ক");
+}
+
+#[test]
+fn backspace_keeps_the_word_in_step() {
+    let _serial = SERIAL.lock().unwrap_or_else(|err| err.into_inner());
+    let edit = start(|_| {});
+    type_text("ki");
+    press(VK_BACK);
+    assert_eq!(edit.text(), "ক");
+    type_text("h");
+    assert_eq!(edit.text(), "খ");
+}
+
+#[test]
+fn backspace_erases_a_conjunct_whole() {
+    let _serial = SERIAL.lock().unwrap_or_else(|err| err.into_inner());
+    let edit = start(|settings| {
+        settings
+            .layout_gemination
+            .insert("Phonetic".to_string(), true);
+    });
+    type_text("akk");
+    assert_eq!(edit.text(), "অক্ক");
+    press(VK_BACK);
+    assert_eq!(edit.text(), "অ");
+    type_text("m");
+    assert_eq!(edit.text(), "অম");
+}
+
+#[test]
+fn period_after_bangla_is_a_dari() {
+    let _serial = SERIAL.lock().unwrap_or_else(|err| err.into_inner());
+    let edit = start(|settings| settings.dari_on_period = true);
+    type_text("ki.");
+    assert_eq!(edit.text(), "কি।");
+}
+
+#[test]
+fn dari_spacing_tidies_spaces_around_a_dari() {
+    let _serial = SERIAL.lock().unwrap_or_else(|err| err.into_inner());
+    let edit = start(|settings| settings.dari_spacing = true);
+    type_text("ki .  ki");
+    assert_eq!(edit.text(), "কি। কি");
+}
+
+#[test]
+fn dictionary_respells_before_the_space() {
+    let _serial = SERIAL.lock().unwrap_or_else(|err| err.into_inner());
+    let edit = start(|settings| settings.dictionary_mode = true);
+    type_text("ami ");
+    assert_eq!(edit.text(), "আমি ");
+}
+
+#[test]
+fn autocorrect_fixes_shortened_words_before_the_space() {
+    let _serial = SERIAL.lock().unwrap_or_else(|err| err.into_inner());
+    let edit = start(|settings| settings.autocorrect_additions = "vlbs ভালোবাসি".to_string());
+    type_text("kmn vlbs ");
+    assert_eq!(edit.text(), "কেমন ভালোবাসি ");
+}
+
+#[test]
+fn misspelled_words_are_corrected_with_the_hotkey() {
+    let _serial = SERIAL.lock().unwrap_or_else(|err| err.into_inner());
+    let edit = start(|settings| settings.spell_check = true);
+    type_text("valbasa ");
+    assert_eq!(edit.text(), "ভালবাসা ");
+    // As the hotkey does; the correction is typed from a thread of its own
+    assert!(spell_check::correct());
+    let start = Instant::now();
+    while edit.text() != "ভালোবাসা " && start.elapsed() < Duration::from_secs(1) {
+        settle();
+    }
+    assert_eq!(edit.text(), "ভালোবাসা ");
+}
+
+#[test]
+fn english_mode_passes_keys_through() {
+    let _serial = SERIAL.lock().unwrap_or_else(|err| err.into_inner());
+    let edit = start(|settings| {
+        settings
+            .state
+            .apply(Event::SelectLanguage(Language::English))
+    });
+    type_text("ami");
+    assert_eq!(edit.text(), "ami");
+}
+
+#[test]
+fn english_mode_capitalizes_sentences() {
+    let _serial = SERIAL.lock().unwrap_or_else(|err| err.into_inner());
+    let edit = start(|settings| {
+        settings
+            .state
+            .apply(Event::SelectLanguage(Language::English));
+        settings.capitalize_english = true;
+    });
+    type_text("ok. so i am");
+    assert_eq!(edit.text(), "ok. So I am");
+}
+
+#[test]
+fn characters_outside_the_bmp_arrive_whole() {
+    let _serial = SERIAL.lock().unwrap_or_else(|err| err.into_inner());
+    let edit = start(|_| {});
+    // Both halves of each surrogate pair go out in the same batch
+    simulate_unicode_input("ক😀𑄃");
+    settle();
+    assert_eq!(edit.text(), "ক😀𑄃");
+}
+
+#[test]
+fn bijoy_output_puts_vowel_signs_first() {
+    let _serial = SERIAL.lock().unwrap_or_else(|err| err.into_inner());
+    let edit = start(|settings| settings.output_encoding = "Bijoy (ANSI)".to_string());
+    type_text("ki");
+    assert_eq!(edit.text(), "wK");
+    type_text("i");
+    assert_eq!(edit.text(), "Kx");
+}
+
+#[test]
+fn selection_is_converted_in_place() {
+    let _serial = SERIAL.lock().unwrap_or_else(|err| err.into_inner());
+    let edit = start(|settings| {
+        settings
+            .state
+            .apply(Event::SelectLanguage(Language::English))
+    });
+    type_text("ami");
+    edit.select_all();
+    // The Edit control copies and pastes on this thread, so it keeps
+    // handling messages while the conversion waits for it
+    let converting = std::thread::spawn(clipboard::convert_selection);
+    while !converting.is_finished() {
+        settle();
+    }
+    settle();
+    assert_eq!(edit.text(), "আমি");
+}
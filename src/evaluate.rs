@@ -0,0 +1,91 @@
+use crate::settings::KeyboardSettings;
+use std::fs;
+
+// Parallel corpus: one line per sample, romanized text and gold Bangla
+// separated by a tab. Empty lines and lines starting with '#' are skipped.
+
+pub struct Mismatch {
+    pub roman: String,
+    pub expected: String,
+    pub actual: String,
+}
+
+pub struct Report {
+    pub words: usize,
+    pub correct: usize,
+    pub mismatches: Vec<Mismatch>,
+}
+
+impl Report {
+    pub fn accuracy(&self) -> f64 {
+        if self.words == 0 {
+            return 0.0;
+        }
+        self.correct as f64 * 100.0 / self.words as f64
+    }
+}
+
+/// Runs every corpus word through the engine and compares it word by word
+/// with the gold text. Missing or extra words count as mismatches.
+pub fn evaluate(corpus: &str, settings: &KeyboardSettings) -> Report {
+    let mut report = Report {
+        words: 0,
+        correct: 0,
+        mismatches: Vec::new(),
+    };
+    let layouts = crate::layouts();
+    let engine = crate::engine(&layouts, settings);
+
+    for line in corpus.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let Some((roman, gold)) = line.split_once('\t') else {
+            continue;
+        };
+
+        let roman_words: Vec<&str> = roman.split_whitespace().collect();
+        let gold_words: Vec<&str> = gold.split_whitespace().collect();
+
+        for i in 0..roman_words.len().max(gold_words.len()) {
+            let roman = roman_words.get(i).copied().unwrap_or_default();
+            let expected = gold_words.get(i).copied().unwrap_or_default();
+            let actual = engine.transliterate(roman);
+
+            report.words += 1;
+            if actual == expected {
+                report.correct += 1;
+            } else {
+                report.mismatches.push(Mismatch {
+                    roman: roman.to_string(),
+                    expected: expected.to_string(),
+                    actual,
+                });
+            }
+        }
+    }
+
+    report
+}
+
+/// `--evaluate <corpus>`: prints word-level accuracy and the mismatches.
+pub fn run(path: &str, settings: &KeyboardSettings) -> Result<(), Box<dyn std::error::Error>> {
+    let corpus = fs::read_to_string(path)?;
+    let report = evaluate(&corpus, settings);
+
+    for mismatch in &report.mismatches {
+        println!(
+            "{}\texpected {}\tgot {}",
+            mismatch.roman, mismatch.expected, mismatch.actual
+        );
+    }
+    println!(
+        "{} / {} words correct ({:.1}%)",
+        report.correct,
+        report.words,
+        report.accuracy()
+    );
+
+    Ok(())
+}
@@ -0,0 +1,48 @@
+/// Experimental subsystems that ship dark. Each one stays off until the user
+/// turns it on from the Advanced settings tab; the choice is stored in
+/// `KeyboardSettings::feature_flags` under the flag's key.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Feature {
+    ClipboardMonitor,
+    CandidatePopup,
+    PreeditOverlay,
+}
+
+impl Feature {
+    pub const ALL: [Feature; 3] = [
+        Feature::ClipboardMonitor,
+        Feature::CandidatePopup,
+        Feature::PreeditOverlay,
+    ];
+
+    // Name in the settings file, kept stable across renames of the variant
+    pub fn key(self) -> &'static str {
+        match self {
+            Feature::ClipboardMonitor => "clipboard_monitor",
+            Feature::CandidatePopup => "candidate_popup",
+            Feature::PreeditOverlay => "preedit_overlay",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Feature::ClipboardMonitor => "Clipboard monitor",
+            Feature::CandidatePopup => "Candidate popup",
+            Feature::PreeditOverlay => "Word preview",
+        }
+    }
+
+    pub fn description(self) -> &'static str {
+        match self {
+            Feature::ClipboardMonitor => {
+                "Offers to convert copied Banglish text to Bangla. Watches the clipboard in the background."
+            }
+            Feature::CandidatePopup => {
+                "Lists other spellings of the word being typed next to the caret. Up and Down or 1-9 pick one; Space or Enter types the highlighted one."
+            }
+            Feature::PreeditOverlay => {
+                "Shows the keys of the word being typed and their Bangla above the caret, so a mistyped key shows before the word ends."
+            }
+        }
+    }
+}
@@ -0,0 +1,160 @@
+use regex::Regex;
+use std::ops::Range;
+use unicode_normalization::char::decompose_canonical;
+use unicode_normalization::UnicodeNormalization;
+
+// Find and replace in the converter. Some Bangla letters have two
+// spellings in Unicode: য় as one code point or as য and a nukta, ো as one
+// or as ে and া. Text and query are compared decomposed, so either spelling
+// finds both, and matches are replaced in the text as it was written.
+
+// The text decomposed, and for each of its characters where it came from
+struct Decomposed {
+    text: String,
+    // (offset in `text`, byte range in the original), in order
+    origins: Vec<(usize, Range<usize>)>,
+}
+
+impl Decomposed {
+    fn of(original: &str) -> Self {
+        let mut text = String::with_capacity(original.len());
+        let mut origins = Vec::new();
+        for (offset, c) in original.char_indices() {
+            let origin = offset..offset + c.len_utf8();
+            decompose_canonical(c, |part| {
+                origins.push((text.len(), origin.clone()));
+                text.push(part);
+            });
+        }
+        Self { text, origins }
+    }
+
+    // The original characters a match in `text` covers, whole
+    fn original(&self, found: Range<usize>) -> Range<usize> {
+        let origin = |offset: usize| {
+            let index = self.origins.partition_point(|(start, _)| *start <= offset);
+            self.origins[index - 1].1.clone()
+        };
+        origin(found.start).start..origin(found.end - 1).end
+    }
+}
+
+// A literal query is escaped; either way it is decomposed like the text
+fn pattern(query: &str, regex: bool) -> Result<Regex, String> {
+    let query: String = query.nfd().collect();
+    let pattern = if regex { query } else { regex::escape(&query) };
+    Regex::new(&pattern).map_err(|err| err.to_string())
+}
+
+/// Byte ranges of the matches of `query` in `text`, a regular expression if
+/// `regex` is set. Empty matches are skipped.
+pub fn find(text: &str, query: &str, regex: bool) -> Result<Vec<Range<usize>>, String> {
+    if query.is_empty() {
+        return Ok(Vec::new());
+    }
+    let pattern = pattern(query, regex)?;
+    let decomposed = Decomposed::of(text);
+    Ok(pattern
+        .find_iter(&decomposed.text)
+        .filter(|found| !found.is_empty())
+        .map(|found| decomposed.original(found.range()))
+        .collect())
+}
+
+/// `text` with every match replaced, and how many there were. With `regex`
+/// the replacement may use groups as $1 or ${name}.
+pub fn replace_all(
+    text: &str,
+    query: &str,
+    replacement: &str,
+    regex: bool,
+) -> Result<(String, usize), String> {
+    if query.is_empty() {
+        return Ok((text.to_string(), 0));
+    }
+    let pattern = pattern(query, regex)?;
+    let decomposed = Decomposed::of(text);
+    let mut replaced = String::with_capacity(text.len());
+    let mut last = 0;
+    let mut count = 0;
+    for captures in pattern.captures_iter(&decomposed.text) {
+        let found = captures.get(0).expect("group 0 is the match");
+        if found.is_empty() {
+            continue;
+        }
+        let original = decomposed.original(found.range());
+        // A match that starts inside the last one's character is skipped
+        if original.start < last {
+            continue;
+        }
+        replaced.push_str(&text[last..original.start]);
+        if regex {
+            captures.expand(replacement, &mut replaced);
+        } else {
+            replaced.push_str(replacement);
+        }
+        last = original.end;
+        count += 1;
+    }
+    replaced.push_str(&text[last..]);
+    Ok((replaced, count))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // য় as one code point, and as য with a nukta
+    const COMPOSED: &str = "\u{09DF}";
+    const DECOMPOSED: &str = "\u{09AF}\u{09BC}";
+
+    #[test]
+    fn either_spelling_finds_both() {
+        let text = format!("আমা{}, তোমা{}", COMPOSED, DECOMPOSED);
+        for query in [COMPOSED, DECOMPOSED] {
+            let query = format!("মা{}", query);
+            let found = find(&text, &query, false).unwrap();
+            assert_eq!(found.len(), 2);
+            assert_eq!(&text[found[0].clone()], format!("মা{}", COMPOSED));
+            assert_eq!(&text[found[1].clone()], format!("মা{}", DECOMPOSED));
+        }
+        // ো written as ে and া
+        assert_eq!(find("কে\u{09BE}ন", "কোন", false).unwrap(), vec![0..12]);
+    }
+
+    #[test]
+    fn literal_queries_match_regex_characters_as_typed() {
+        assert_eq!(find("দাম (৳)", "(৳)", false).unwrap().len(), 1);
+        assert_eq!(find("a.b", ".", false).unwrap(), vec![1..2]);
+        assert!(find("anything", "", false).unwrap().is_empty());
+    }
+
+    #[test]
+    fn regular_expressions_can_use_groups() {
+        let (text, count) =
+            replace_all("১২-০৩-২০২৪", r"(\d+)-(\d+)-(\d+)", "$3/$2/$1", true).unwrap();
+        assert_eq!(text, "২০২৪/০৩/১২");
+        assert_eq!(count, 1);
+        assert!(find("text", "(", true).is_err());
+        // Empty matches are no matches
+        assert!(find("abc", "x*", true).unwrap().is_empty());
+    }
+
+    #[test]
+    fn replacing_keeps_the_rest_as_written() {
+        let text = format!("{}া{}া", DECOMPOSED, COMPOSED);
+        let (replaced, count) = replace_all(&text, COMPOSED, "ক", false).unwrap();
+        assert_eq!(replaced, "কাকা");
+        assert_eq!(count, 2);
+        let (replaced, count) = replace_all("আমি যাব", "তুমি", "সে", false).unwrap();
+        assert_eq!((replaced.as_str(), count), ("আমি যাব", 0));
+    }
+
+    #[test]
+    fn a_match_inside_a_letter_takes_the_whole_letter() {
+        // য finds the য in য়, in both spellings
+        let text = format!("{} {}", COMPOSED, DECOMPOSED);
+        let found = find(&text, "য", false).unwrap();
+        assert_eq!(found, [0..3, 4..7]);
+    }
+}
@@ -0,0 +1,115 @@
+use crate::problems::{self, Problem};
+use windows::Win32::Foundation::{HMODULE, HWND, LPARAM, LRESULT, WPARAM};
+use windows::Win32::UI::Accessibility::{SetWinEventHook, UnhookWinEvent, HWINEVENTHOOK};
+use windows::Win32::UI::WindowsAndMessaging::{
+    CallNextHookEx, SetWindowsHookExW, UnhookWindowsHookEx, EVENT_OBJECT_FOCUS,
+    EVENT_SYSTEM_FOREGROUND, HHOOK, WH_MOUSE_LL, WINEVENT_OUTOFCONTEXT, WM_LBUTTONDOWN,
+    WM_MBUTTONDOWN, WM_NCLBUTTONDOWN, WM_RBUTTONDOWN,
+};
+
+// A click or a focus change may move the caret, so the word being composed
+// and the text before the caret are no longer where we left them. A new
+// foreground window may also have a language of its own. These hooks run
+// on the keyboard hook's thread, which pumps their messages.
+
+/// The installed hooks; dropping it removes them.
+pub struct FocusWatch {
+    events: Vec<HWINEVENTHOOK>,
+    mouse: Option<HHOOK>,
+}
+
+// Forgets the word so the next key starts a new one, and notes whether it
+// goes to a password box
+fn caret_moved() {
+    crate::clear_composition();
+    let mut history = crate::HISTORY.lock().unwrap();
+    history.clear();
+    history.set_secret(crate::caret::in_password_field());
+}
+
+unsafe extern "system" fn focus_event_proc(
+    _hook: HWINEVENTHOOK,
+    event: u32,
+    window: HWND,
+    _object: i32,
+    _child: i32,
+    _thread: u32,
+    _time: u32,
+) {
+    caret_moved();
+    if event == EVENT_SYSTEM_FOREGROUND {
+        crate::window_language::foreground_changed(window);
+    }
+}
+
+unsafe extern "system" fn mouse_hook_proc(code: i32, wparam: WPARAM, lparam: LPARAM) -> LRESULT {
+    if code >= 0 {
+        crate::hook_health::record_mouse();
+        if crate::mouse_toggle::handle(wparam, lparam) {
+            return LRESULT(1);
+        }
+    }
+    if code >= 0
+        && matches!(
+            wparam.0 as u32,
+            WM_LBUTTONDOWN | WM_RBUTTONDOWN | WM_MBUTTONDOWN | WM_NCLBUTTONDOWN
+        )
+    {
+        caret_moved();
+    }
+    unsafe { CallNextHookEx(None, code, wparam, lparam) }
+}
+
+/// Watches for foreground and focus changes and mouse clicks. Must be
+/// called on a thread with a message loop. A hook that can't be installed
+/// is reported and skipped; typing still works without it.
+pub fn install() -> FocusWatch {
+    let events = [EVENT_SYSTEM_FOREGROUND, EVENT_OBJECT_FOCUS]
+        .into_iter()
+        .filter_map(|event| {
+            let hook = unsafe {
+                SetWinEventHook(
+                    event,
+                    event,
+                    HMODULE::default(),
+                    Some(focus_event_proc),
+                    0,
+                    0,
+                    WINEVENT_OUTOFCONTEXT,
+                )
+            };
+            if hook.is_invalid() {
+                problems::report(Problem::FocusWatchFailed, &format!("event {:#x}", event));
+                return None;
+            }
+            Some(hook)
+        })
+        .collect();
+
+    let mouse_hook =
+        unsafe { SetWindowsHookExW(WH_MOUSE_LL, Some(mouse_hook_proc), HMODULE::default(), 0) };
+    let mouse = match mouse_hook {
+        Ok(hook) => Some(hook),
+        Err(err) => {
+            problems::report(Problem::MouseWatchFailed, &err.to_string());
+            None
+        }
+    };
+
+    crate::hook_health::set_mouse_hooked(mouse.is_some());
+    FocusWatch { events, mouse }
+}
+
+impl Drop for FocusWatch {
+    fn drop(&mut self) {
+        unsafe {
+            for hook in self.events.drain(..) {
+                let _ = UnhookWinEvent(hook);
+            }
+            if let Some(hook) = self.mouse.take() {
+                let _ = UnhookWindowsHookEx(hook);
+                crate::hook_health::set_mouse_hooked(false);
+            }
+        }
+    }
+}
@@ -0,0 +1,96 @@
+use egui::{FontData, FontDefinitions, FontFamily};
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver};
+
+// Fonts shipped next to the executable. Noto fonts are picked up when dropped in.
+const BUNDLED_FONT_DIR: &str = "assets/fonts";
+const BUNDLED_FONTS: [&str; 3] = [
+    "Nirmala.ttf",
+    "NotoSansBengali-Regular.ttf",
+    "NotoSansDevanagari-Regular.ttf",
+];
+
+// Windows fonts covering Bangla (Nirmala, Vrinda, Shonar) and Devanagari (Mangal)
+const SYSTEM_FONTS: [&str; 4] = ["Nirmala.ttf", "Vrinda.ttf", "Shonar.ttf", "Mangal.ttf"];
+
+// (source path, file contents) in fallback order
+pub type FontChain = Vec<(String, Vec<u8>)>;
+
+/// Font files in fallback order: user font, bundled fonts, then system fonts.
+/// A file name already taken by an earlier source is skipped.
+pub fn font_chain_paths(user_font: &str) -> Vec<PathBuf> {
+    let mut paths = Vec::new();
+
+    if !user_font.trim().is_empty() {
+        paths.push(PathBuf::from(user_font.trim()));
+    }
+
+    for name in BUNDLED_FONTS {
+        paths.push(Path::new(BUNDLED_FONT_DIR).join(name));
+    }
+
+    if let Ok(windir) = std::env::var("WINDIR") {
+        for name in SYSTEM_FONTS {
+            paths.push(Path::new(&windir).join("Fonts").join(name));
+        }
+    }
+
+    let mut seen = HashSet::new();
+    paths
+        .into_iter()
+        .filter(|path| path.exists())
+        .filter(|path| {
+            let name = path
+                .file_name()
+                .map(|n| n.to_string_lossy().to_lowercase())
+                .unwrap_or_default();
+            seen.insert(name)
+        })
+        .collect()
+}
+
+/// Reads every font of the chain, skipping files that fail to load.
+pub fn load_font_chain(user_font: &str) -> FontChain {
+    font_chain_paths(user_font)
+        .into_iter()
+        .filter_map(|path| {
+            let data = fs::read(&path).ok()?;
+            Some((path.to_string_lossy().into_owned(), data))
+        })
+        .collect()
+}
+
+/// egui font definitions with the chain placed ahead of egui's own fonts, so
+/// each glyph is taken from the first font in the chain that has it.
+pub fn font_definitions(chain: &[(String, Vec<u8>)]) -> FontDefinitions {
+    let mut fonts = FontDefinitions::default();
+
+    for (index, (name, data)) in chain.iter().enumerate() {
+        fonts
+            .font_data
+            .insert(name.clone(), FontData::from_owned(data.clone()));
+
+        for family in [FontFamily::Proportional, FontFamily::Monospace] {
+            fonts
+                .families
+                .entry(family)
+                .or_default()
+                .insert(index, name.clone());
+        }
+    }
+
+    fonts
+}
+
+/// Reads the chain on a worker thread so the window can show right away.
+/// The UI is woken up once the fonts are ready to install.
+pub fn load_in_background(user_font: String, ctx: egui::Context) -> Receiver<FontChain> {
+    let (sender, receiver) = mpsc::channel();
+    std::thread::spawn(move || {
+        let _ = sender.send(load_font_chain(&user_font));
+        ctx.request_repaint();
+    });
+    receiver
+}
@@ -0,0 +1,170 @@
+use crate::settings::{self, KeyboardSettings};
+use egui::RichText;
+use memmap2::Mmap;
+use restro_keyboard_core::{CompactDictionary, Dictionary};
+use std::fs::{self, File};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+
+// A full word dictionary the user installs, such as one exported from
+// another phonetic keyboard, in the bundled sample's `roman<TAB>bangla`
+// format. No full dictionary ships with the keyboard yet; the sample in
+// assets/dictionary covers common words only. The file's leading comment
+// lines should say where it came from and under what license, and are
+// shown in the settings.
+//
+// Installing turns the file into a CompactDictionary, which is mapped into
+// memory rather than read, so a dictionary of hundreds of thousands of
+// words doesn't sit in a background app's memory as strings.
+
+// The mapped dictionary, and whether the file should be mapped again
+// before it is next asked for
+static OPEN: Mutex<Option<Arc<CompactDictionary>>> = Mutex::new(None);
+static STALE: AtomicBool = AtomicBool::new(true);
+
+/// `%APPDATA%\RestroKeyboard\full_dictionary.fst`, if APPDATA is set.
+pub fn path() -> Option<PathBuf> {
+    Some(
+        KeyboardSettings::path()?
+            .parent()?
+            .join("full_dictionary.fst"),
+    )
+}
+
+fn map() -> Option<Arc<CompactDictionary>> {
+    let file = File::open(path()?).ok()?;
+    // Safety: the file is only replaced or removed once it is unmapped,
+    // see `replace`; other programs have no reason to write to it
+    let bytes = unsafe { Mmap::map(&file) }.ok()?;
+    match CompactDictionary::from_bytes(bytes) {
+        Ok(dictionary) => Some(Arc::new(dictionary)),
+        Err(err) => {
+            eprintln!("Installed dictionary is unreadable, ignoring it: {}", err);
+            None
+        }
+    }
+}
+
+/// The installed dictionary, mapped the first time it is asked for.
+pub fn open() -> Option<Arc<CompactDictionary>> {
+    let mut open = OPEN.lock().unwrap();
+    if STALE.swap(false, Ordering::SeqCst) {
+        *open = map();
+    }
+    open.clone()
+}
+
+// Windows can't replace or delete a mapped file, so the dictionary is
+// dropped from use while `change` runs, then mapped again
+fn replace<T>(change: impl FnOnce(&Path) -> io::Result<T>) -> io::Result<T> {
+    let path =
+        path().ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "APPDATA is not set"))?;
+    *OPEN.lock().unwrap() = None;
+    STALE.store(false, Ordering::SeqCst);
+    crate::reload_dictionary();
+    let result = change(&path);
+    STALE.store(true, Ordering::SeqCst);
+    crate::reload_dictionary();
+    result
+}
+
+/// Compacts a dictionary file into place, replacing any installed before.
+/// Returns how many words it has; a file without any is refused.
+pub fn install(from: &Path) -> io::Result<usize> {
+    let text = fs::read_to_string(from)?;
+    let dictionary = Dictionary::parse(&text);
+    if dictionary.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "no roman<TAB>bangla lines in the file",
+        ));
+    }
+    let source = source_of(&text).unwrap_or_default();
+    let bytes = CompactDictionary::build(&dictionary, &source);
+    replace(|path| settings::write_atomically(path, &bytes))?;
+    Ok(dictionary.len())
+}
+
+/// The comment lines the installed file started with, without their '#':
+/// its source and license, when it named them.
+pub fn source() -> Option<String> {
+    Some(open()?.source()).filter(|source| !source.is_empty())
+}
+
+fn source_of(text: &str) -> Option<String> {
+    let lines: Vec<&str> = text
+        .lines()
+        .map(str::trim)
+        .take_while(|line| line.starts_with('#'))
+        .map(|line| line.trim_start_matches('#').trim())
+        .collect();
+    Some(lines.join("\n")).filter(|source| !source.is_empty())
+}
+
+pub fn remove() -> io::Result<()> {
+    replace(|path| match path.exists() {
+        true => fs::remove_file(path),
+        false => Ok(()),
+    })
+}
+
+/// How many words dictionary mode and spell checking know and from where, with a file field
+/// to install a full dictionary or remove it.
+pub fn settings(ui: &mut egui::Ui, file: &mut String, status: &mut Option<String>) {
+    let source = source();
+    let words = crate::dictionary().len();
+    let note = match (&source, open().is_some()) {
+        (_, false) => format!(
+            "{} words: the bundled sample and yours. Install a full dictionary for the rest.",
+            words
+        ),
+        (Some(_), true) => format!("{} words, with the installed dictionary", words),
+        (None, true) => format!(
+            "{} words, with the installed dictionary, which doesn't name its source",
+            words
+        ),
+    };
+    let label = ui.label(RichText::new(note).weak().size(12.0));
+    if let Some(source) = source {
+        label.on_hover_text(source);
+    }
+    ui.horizontal(|ui| {
+        ui.label("Dictionary file:");
+        ui.text_edit_singleline(file).on_hover_text(
+            "roman<TAB>bangla lines; comment lines at the top should name its source and license",
+        );
+        if ui.button("Install").clicked() {
+            let path = Path::new(file.trim());
+            *status = Some(match install(path) {
+                Ok(count) => format!("{} words installed", count),
+                Err(err) => format!("Could not install: {}", err),
+            });
+        }
+        if ui.button("Remove").clicked() {
+            *status = Some(match remove() {
+                Ok(()) => "Installed dictionary removed".to_string(),
+                Err(err) => format!("Could not remove: {}", err),
+            });
+        }
+    });
+    if let Some(status) = status {
+        ui.label(RichText::new(status.as_str()).weak().size(12.0));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_leading_comments_name_the_source() {
+        let text = "# Words from Example Keyboard\n# License: CC BY-SA 4.0\nami\tআমি\n# later\n";
+        assert_eq!(
+            source_of(text).as_deref(),
+            Some("Words from Example Keyboard\nLicense: CC BY-SA 4.0")
+        );
+        assert_eq!(source_of("ami\tআমি\n"), None);
+    }
+}
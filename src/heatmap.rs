@@ -0,0 +1,218 @@
+use crate::statistics;
+use egui::{Align2, Color32, ColorImage, FontId, Rect, RichText, Sense, Vec2, ViewportCommand};
+use std::collections::BTreeMap;
+use std::io;
+use std::path::{Path, PathBuf};
+
+// The heatmap in the Statistics window: how often each key was pressed in
+// Bangla mode and each Bangla character typed, redder for more. Saving it
+// as a PNG asks egui for a screenshot and crops it to the heatmap, so the
+// file looks exactly like the window.
+
+// US keyboard rows and how far each is indented, in keys
+const ROWS: [(&str, f32); 4] = [
+    ("`1234567890-=", 0.0),
+    ("qwertyuiop[]\\", 1.5),
+    ("asdfghjkl;'", 1.75),
+    ("zxcvbnm,./", 2.25),
+];
+// Letters, kars, signs and digits, in the order of the alphabet
+const CHARACTERS: &str = "অআইঈউঊঋএঐওঔকখগঘঙচছজঝঞটঠডঢণতথদধনপফবভমযরলশষসহড়ঢ়য়ৎংঃঁািীুূৃেৈোৌ্০১২৩৪৫৬৭৮৯";
+const KEY: f32 = 30.0;
+// Characters per row of their grid
+const PER_ROW: usize = 12;
+
+/// The colour of a count as a share of the largest: pale for none, red for
+/// the most. The square root keeps rarely typed keys from all looking alike.
+pub fn heat(share: f64) -> Color32 {
+    let share = share.clamp(0.0, 1.0).sqrt() as f32;
+    let mix = |from: u8, to: u8| (from as f32 + (to as f32 - from as f32) * share).round() as u8;
+    Color32::from_rgb(mix(238, 215), mix(238, 48), mix(238, 39))
+}
+
+// A cell of the heatmap, with its label, and its count when hovered
+fn cell(
+    painter: &egui::Painter,
+    rect: Rect,
+    label: &str,
+    count: u64,
+    most: u64,
+    hovered: &mut Option<String>,
+    pointer: Option<egui::Pos2>,
+) {
+    let share = if most == 0 {
+        0.0
+    } else {
+        count as f64 / most as f64
+    };
+    let rect = rect.shrink(1.5);
+    painter.rect_filled(rect, 3.0, heat(share));
+    let text = if share > 0.5 {
+        Color32::WHITE
+    } else {
+        Color32::BLACK
+    };
+    painter.text(
+        rect.center(),
+        Align2::CENTER_CENTER,
+        label,
+        FontId::proportional(15.0),
+        text,
+    );
+    if pointer.is_some_and(|pointer| rect.contains(pointer)) {
+        *hovered = Some(format!("{}: {}", label, statistics::grouped(count)));
+    }
+}
+
+// Draws one grid of cells, returning where it went
+fn grid(ui: &mut egui::Ui, rows: &[(Vec<char>, f32)], counts: &BTreeMap<char, u64>) -> Rect {
+    let most = counts.values().copied().max().unwrap_or(0);
+    let widest = rows
+        .iter()
+        .map(|(cells, indent)| cells.len() as f32 + indent)
+        .fold(0.0, f32::max);
+    let size = egui::vec2(widest * KEY, rows.len() as f32 * KEY);
+    let (rect, response) = ui.allocate_exact_size(size, Sense::hover());
+    let painter = ui.painter_at(rect);
+    let pointer = response.hover_pos();
+    let mut hovered = None;
+    for (row, (cells, indent)) in rows.iter().enumerate() {
+        for (column, c) in cells.iter().enumerate() {
+            let min = rect.min + egui::vec2((indent + column as f32) * KEY, row as f32 * KEY);
+            let count = counts.get(c).copied().unwrap_or(0);
+            let label = c.to_uppercase().to_string();
+            let place = Rect::from_min_size(min, Vec2::splat(KEY));
+            cell(&painter, place, &label, count, most, &mut hovered, pointer);
+        }
+    }
+    if let Some(hovered) = hovered {
+        response.on_hover_text(hovered);
+    }
+    rect
+}
+
+/// Writes a screenshot's pixels as a PNG.
+pub fn save_png(image: &ColorImage, path: &Path) -> io::Result<()> {
+    let bytes: Vec<u8> = image
+        .pixels
+        .iter()
+        .flat_map(|pixel| pixel.to_array())
+        .collect();
+    let [width, height] = image.size;
+    image::save_buffer(
+        path,
+        &bytes,
+        width as u32,
+        height as u32,
+        image::ExtendedColorType::Rgba8,
+    )
+    .map_err(io::Error::other)
+}
+
+#[derive(Default)]
+pub struct Heatmap {
+    // Where to save the PNG
+    pub path: String,
+    // Where the heatmap was drawn, and the file, while a screenshot for it
+    // is on its way
+    pending: Option<(Rect, PathBuf)>,
+    status: Option<String>,
+}
+
+impl Heatmap {
+    pub fn show(&mut self, ui: &mut egui::Ui) {
+        let keys: Vec<_> = ROWS
+            .iter()
+            .map(|(keys, indent)| (keys.chars().collect(), *indent))
+            .collect();
+        let characters: Vec<char> = CHARACTERS.chars().collect();
+        let characters: Vec<_> = characters
+            .chunks(PER_ROW)
+            .map(|row| (row.to_vec(), 0.0))
+            .collect();
+
+        let drawn = ui
+            .vertical(|ui| {
+                ui.label(RichText::new("Keys pressed in Bangla mode").weak());
+                let keys = grid(ui, &keys, &statistics::key_counts());
+                ui.add_space(6.0);
+                ui.label(RichText::new("Bangla characters typed").weak());
+                keys.union(grid(ui, &characters, &statistics::character_counts()))
+            })
+            .inner;
+
+        ui.horizontal(|ui| {
+            ui.label("PNG file:");
+            ui.text_edit_singleline(&mut self.path);
+            let chosen = !self.path.trim().is_empty() && self.pending.is_none();
+            if ui.add_enabled(chosen, egui::Button::new("Save")).clicked() {
+                let path = PathBuf::from(self.path.trim());
+                self.pending = Some((drawn.expand(4.0), path));
+                ui.ctx().send_viewport_cmd(ViewportCommand::Screenshot);
+            }
+        });
+        if let Some(status) = &self.status {
+            ui.label(RichText::new(status).weak().size(12.0));
+        }
+    }
+
+    /// Saves the PNG once the screenshot asked for arrives. Called every
+    /// frame.
+    pub fn screenshot_arrived(&mut self, ctx: &egui::Context) {
+        if self.pending.is_none() {
+            return;
+        }
+        let image = ctx.input(|input| {
+            input.events.iter().find_map(|event| match event {
+                egui::Event::Screenshot { image, .. } => Some(image.clone()),
+                _ => None,
+            })
+        });
+        let (Some(image), Some((rect, path))) = (image, self.pending.take()) else {
+            return;
+        };
+        let cropped = image.region(&rect, Some(ctx.pixels_per_point()));
+        self.status = Some(match save_png(&cropped, &path) {
+            Ok(()) => format!("Saved {}", path.display()),
+            Err(err) => format!("Could not save the heatmap: {}", err),
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use windows::Win32::UI::Input::KeyboardAndMouse::VIRTUAL_KEY;
+
+    #[test]
+    fn more_typing_is_redder() {
+        assert_eq!(heat(0.0), Color32::from_rgb(238, 238, 238));
+        assert_eq!(heat(1.0), Color32::from_rgb(215, 48, 39));
+        assert!(heat(0.2).g() > heat(0.8).g());
+        assert_eq!(heat(2.0), heat(1.0));
+    }
+
+    #[test]
+    fn every_counted_key_has_a_place() {
+        for code in 0..=0xFF {
+            if let Some(key) = crate::key_position(VIRTUAL_KEY(code)) {
+                assert!(
+                    ROWS.iter().any(|(keys, _)| keys.contains(key)),
+                    "{:?} is not on the heatmap",
+                    key
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn screenshots_are_saved_as_png() {
+        let path = std::env::temp_dir().join("restro-heatmap-test.png");
+        let image = ColorImage::new([3, 2], heat(1.0));
+        save_png(&image, &path).unwrap();
+        let read = image::open(&path).unwrap().to_rgba8();
+        let _ = std::fs::remove_file(&path);
+        assert_eq!(read.dimensions(), (3, 2));
+        assert_eq!(read.get_pixel(0, 0).0, [215, 48, 39, 255]);
+    }
+}
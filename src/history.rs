@@ -0,0 +1,102 @@
+use crate::transcript::Transcript;
+
+// Recently emitted text near the caret, used for context decisions such as
+// whether a period should become a dari. Only the tail is kept, except by
+// a transcript while one is recorded.
+const HISTORY_LIMIT: usize = 64;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Script {
+    Bangla,
+    Latin,
+    Other,
+}
+
+pub fn script_of(c: char) -> Script {
+    match c {
+        '\u{0980}'..='\u{09FF}' => Script::Bangla,
+        'a'..='z' | 'A'..='Z' => Script::Latin,
+        _ => Script::Other,
+    }
+}
+
+#[derive(Default)]
+pub struct EmissionHistory {
+    text: String,
+    // Everything emitted while a transcript is being recorded
+    transcript: Option<Transcript>,
+    // Moves on whenever the transcript changes, so the UI copies it only then
+    transcript_generation: u64,
+    // Typing goes to a password box, which a transcript never records
+    secret: bool,
+}
+
+impl EmissionHistory {
+    pub fn push(&mut self, text: &str) {
+        if let Some(transcript) = self.transcript.as_mut().filter(|_| !self.secret) {
+            transcript.push(text);
+            self.transcript_generation += 1;
+        }
+        self.text.push_str(text);
+        let excess = self.text.chars().count().saturating_sub(HISTORY_LIMIT);
+        if excess > 0 {
+            self.text = self.text.chars().skip(excess).collect();
+        }
+    }
+
+    // Mirrors backspaces sent to (or typed into) the application
+    pub fn erase(&mut self, count: usize) {
+        if let Some(transcript) = self.transcript.as_mut().filter(|_| !self.secret) {
+            transcript.erase(count);
+            self.transcript_generation += 1;
+        }
+        for _ in 0..count {
+            self.text.pop();
+        }
+    }
+
+    // The caret moved somewhere we can't follow
+    pub fn clear(&mut self) {
+        if let Some(transcript) = &mut self.transcript {
+            transcript.break_paragraph();
+            self.transcript_generation += 1;
+        }
+        self.text.clear();
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Whether typing goes to a password box, set as focus changes.
+    pub fn set_secret(&mut self, secret: bool) {
+        self.secret = secret;
+    }
+
+    pub fn start_transcript(&mut self) {
+        self.transcript = Some(Transcript::start());
+        self.transcript_generation += 1;
+    }
+
+    pub fn stop_transcript(&mut self) -> Option<Transcript> {
+        self.transcript_generation += 1;
+        self.transcript.take()
+    }
+
+    pub fn transcript(&self) -> Option<&Transcript> {
+        self.transcript.as_ref()
+    }
+
+    pub fn transcript_generation(&self) -> u64 {
+        self.transcript_generation
+    }
+
+    /// Script of the last non-whitespace character, if any.
+    pub fn last_script(&self) -> Option<Script> {
+        self.text
+            .chars()
+            .rev()
+            .find(|c| !c.is_whitespace())
+            .map(script_of)
+    }
+}
@@ -0,0 +1,173 @@
+use crate::notification::{self, Icon};
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+use windows::Win32::Foundation::{LPARAM, WPARAM};
+use windows::Win32::System::SystemInformation::GetTickCount;
+use windows::Win32::UI::Input::KeyboardAndMouse::{GetLastInputInfo, LASTINPUTINFO};
+use windows::Win32::UI::WindowsAndMessaging::{PostThreadMessageW, WM_APP};
+
+// Another low-level hook installed after ours sees keys first and may never
+// pass them on. Windows doesn't report that, so the watch looks for the
+// symptom: the user keeps using the machine while our hook hears nothing.
+
+const CHECK_INTERVAL: Duration = Duration::from_secs(5);
+// Checks with recent input while the hook heard nothing, two minutes of use
+const ACTIVE_CHECKS: u32 = 24;
+
+// Windows silently removes a low-level hook whose callback takes too long.
+// The watchdog spots keys typed since: input newer than anything the
+// keyboard and mouse hooks heard. The margin leaves room for the input we
+// inject right after a key.
+const WATCHDOG_INTERVAL: Duration = Duration::from_secs(1);
+const MISSED_MARGIN: Duration = Duration::from_millis(500);
+// Checks in a row with missed input before the hooks are reinstalled
+const MISSED_CHECKS: u32 = 2;
+// Keys typed into elevated windows never reach us either, so reinstalls
+// are spaced out
+const REINSTALL_GAP: Duration = Duration::from_secs(30);
+
+/// Posted to the hook thread to have it install its hooks again.
+pub const WM_REINSTALL_HOOKS: u32 = WM_APP + 1;
+
+// Physical keys the hook has seen, and the tick of the last one
+static KEYS_SEEN: AtomicU64 = AtomicU64::new(0);
+static LAST_KEY_TICK: AtomicU32 = AtomicU32::new(0);
+static SUSPECTED: AtomicBool = AtomicBool::new(false);
+static LAST_MOUSE_TICK: AtomicU32 = AtomicU32::new(0);
+// Without the mouse hook, mouse input can't be told from missed keys
+static MOUSE_HOOKED: AtomicBool = AtomicBool::new(false);
+static REINSTALLS: AtomicU32 = AtomicU32::new(0);
+
+/// Called by the hook for every physical key event.
+pub fn record_key() {
+    KEYS_SEEN.fetch_add(1, Ordering::Relaxed);
+    LAST_KEY_TICK.store(unsafe { GetTickCount() }, Ordering::Relaxed);
+}
+
+/// Called by the mouse hook for every mouse event.
+pub fn record_mouse() {
+    LAST_MOUSE_TICK.store(unsafe { GetTickCount() }, Ordering::Relaxed);
+}
+
+pub fn set_mouse_hooked(hooked: bool) {
+    MOUSE_HOOKED.store(hooked, Ordering::SeqCst);
+}
+
+pub fn keys_seen() -> u64 {
+    KEYS_SEEN.load(Ordering::Relaxed)
+}
+
+/// Time since the hook last saw a physical key.
+pub fn since_last_key() -> Duration {
+    let elapsed = unsafe { GetTickCount() }.wrapping_sub(LAST_KEY_TICK.load(Ordering::Relaxed));
+    Duration::from_millis(u64::from(elapsed))
+}
+
+/// Whether keys seem to be taken by another app before they reach us.
+pub fn is_suspected() -> bool {
+    SUSPECTED.load(Ordering::SeqCst)
+}
+
+/// How many times the watchdog reinstalled the hooks this run.
+pub fn reinstalls() -> u32 {
+    REINSTALLS.load(Ordering::SeqCst)
+}
+
+// Tick of the last keyboard or mouse input to any app
+fn last_input_tick() -> Option<u32> {
+    let mut info = LASTINPUTINFO {
+        cbSize: std::mem::size_of::<LASTINPUTINFO>() as u32,
+        dwTime: 0,
+    };
+    unsafe { GetLastInputInfo(&mut info) }
+        .as_bool()
+        .then_some(info.dwTime)
+}
+
+// Mouse use counts as input too, so the warning needs minutes of activity
+fn input_is_recent() -> bool {
+    last_input_tick().is_some_and(|tick| {
+        unsafe { GetTickCount() }.wrapping_sub(tick) < CHECK_INTERVAL.as_millis() as u32
+    })
+}
+
+// Input came after anything our hooks heard, by more than the margin
+fn input_was_missed() -> bool {
+    let Some(input) = last_input_tick() else {
+        return false;
+    };
+    [&LAST_KEY_TICK, &LAST_MOUSE_TICK].iter().all(|heard| {
+        let since = input.wrapping_sub(heard.load(Ordering::Relaxed));
+        // Ticks wrap; a huge difference means the hook heard it later
+        since > MISSED_MARGIN.as_millis() as u32 && since < u32::MAX / 2
+    })
+}
+
+/// Asks the hook thread to reinstall its hooks when input keeps arriving
+/// that they didn't hear, and says so once with a notification.
+pub fn spawn_watchdog(hook_thread: u32) {
+    std::thread::spawn(move || {
+        let mut missed = 0;
+        let mut last_reinstall: Option<Instant> = None;
+        loop {
+            std::thread::sleep(WATCHDOG_INTERVAL);
+            if !MOUSE_HOOKED.load(Ordering::SeqCst) || !input_was_missed() {
+                missed = 0;
+                continue;
+            }
+            missed += 1;
+            let too_soon = last_reinstall.is_some_and(|at| at.elapsed() < REINSTALL_GAP);
+            if missed < MISSED_CHECKS || too_soon {
+                continue;
+            }
+
+            missed = 0;
+            last_reinstall = Some(Instant::now());
+            let posted = unsafe {
+                PostThreadMessageW(hook_thread, WM_REINSTALL_HOOKS, WPARAM(0), LPARAM(0))
+            };
+            if posted.is_err() {
+                continue;
+            }
+            if REINSTALLS.fetch_add(1, Ordering::SeqCst) == 0 {
+                notification::show(
+                    "Restro Keyboard restarted its keyboard hook",
+                    "Windows had stopped sending keys to it. Typing should work again.",
+                    Icon::Warning,
+                );
+            }
+        }
+    });
+}
+
+/// Watches for the hook going quiet while the machine is in use and shows a
+/// notification once per episode, pointing to the Diagnostics window.
+pub fn spawn_watch() {
+    LAST_KEY_TICK.store(unsafe { GetTickCount() }, Ordering::Relaxed);
+    std::thread::spawn(|| {
+        let mut seen = keys_seen();
+        let mut active_checks = 0;
+        loop {
+            std::thread::sleep(CHECK_INTERVAL);
+
+            let now_seen = keys_seen();
+            if now_seen != seen {
+                seen = now_seen;
+                active_checks = 0;
+                SUSPECTED.store(false, Ordering::SeqCst);
+                continue;
+            }
+            if input_is_recent() {
+                active_checks += 1;
+            }
+            if active_checks == ACTIVE_CHECKS {
+                SUSPECTED.store(true, Ordering::SeqCst);
+                notification::show(
+                    "Restro Keyboard isn't receiving keys",
+                    "Another app may be intercepting the keyboard. Open Diagnostics in Restro Keyboard for help.",
+                    Icon::Warning,
+                );
+            }
+        }
+    });
+}
@@ -0,0 +1,231 @@
+use serde::{Deserialize, Serialize};
+use windows::Win32::UI::Input::KeyboardAndMouse::{
+    GetAsyncKeyState, VIRTUAL_KEY, VK_CONTROL, VK_MENU, VK_OEM_2, VK_OEM_COMMA, VK_OEM_MINUS,
+    VK_OEM_PERIOD, VK_SHIFT, VK_SPACE, VK_TAB,
+};
+
+// Shortcuts Windows or nearly every app already uses
+const RESERVED: [(&str, Hotkey); 10] = [
+    ("Copy", Hotkey::new(true, false, false, b'C' as u16)),
+    ("Paste", Hotkey::new(true, false, false, b'V' as u16)),
+    ("Cut", Hotkey::new(true, false, false, b'X' as u16)),
+    ("Undo", Hotkey::new(true, false, false, b'Z' as u16)),
+    ("Redo", Hotkey::new(true, false, false, b'Y' as u16)),
+    ("Select all", Hotkey::new(true, false, false, b'A' as u16)),
+    ("Save", Hotkey::new(true, false, false, b'S' as u16)),
+    ("Find", Hotkey::new(true, false, false, b'F' as u16)),
+    ("Close window", Hotkey::new(false, true, false, 0x73)),
+    ("Switch window", Hotkey::new(false, true, false, VK_TAB.0)),
+];
+
+/// A modifier+key combination, matched by the hook against the real key state.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Hotkey {
+    pub ctrl: bool,
+    pub alt: bool,
+    pub shift: bool,
+    // Windows virtual-key code
+    pub key: u16,
+}
+
+impl Default for Hotkey {
+    fn default() -> Self {
+        Self::new(true, false, false, VK_SPACE.0)
+    }
+}
+
+fn is_down(vk: VIRTUAL_KEY) -> bool {
+    unsafe { GetAsyncKeyState(vk.0 as i32) < 0 }
+}
+
+impl Hotkey {
+    pub const fn new(ctrl: bool, alt: bool, shift: bool, key: u16) -> Self {
+        Self {
+            ctrl,
+            alt,
+            shift,
+            key,
+        }
+    }
+
+    /// Ctrl+Shift+. opens the quick-insert panel unless changed.
+    pub fn quick_insert() -> Self {
+        Self::new(true, false, true, VK_OEM_PERIOD.0)
+    }
+
+    /// Ctrl+Alt+B converts the clipboard unless changed.
+    pub fn convert_clipboard() -> Self {
+        Self::new(true, true, false, b'B' as u16)
+    }
+
+    /// Ctrl+Shift+B converts the selected text unless changed.
+    pub fn convert_selection() -> Self {
+        Self::new(true, false, true, b'B' as u16)
+    }
+
+    /// Ctrl+F7 swaps a flagged word for its corrections unless changed; F7
+    /// is the spelling key of word processors.
+    pub fn correct_spelling() -> Self {
+        Self::new(true, false, false, 0x76)
+    }
+
+    /// Whether `vk_code` together with the modifiers held right now is this
+    /// combination. Extra modifiers don't count, so Ctrl+Shift+Space isn't
+    /// Ctrl+Space.
+    pub fn matches(&self, vk_code: VIRTUAL_KEY) -> bool {
+        vk_code.0 == self.key
+            && is_down(VK_CONTROL) == self.ctrl
+            && is_down(VK_MENU) == self.alt
+            && is_down(VK_SHIFT) == self.shift
+    }
+
+    /// The combination pressed in our own window. Keys the hook can't name
+    /// and keys without Ctrl or Alt are refused, so plain typing can't
+    /// become the hotkey.
+    pub fn from_egui(modifiers: egui::Modifiers, key: egui::Key) -> Option<Self> {
+        if !modifiers.ctrl && !modifiers.alt {
+            return None;
+        }
+        Some(Self {
+            ctrl: modifiers.ctrl,
+            alt: modifiers.alt,
+            shift: modifiers.shift,
+            key: virtual_key(key)?,
+        })
+    }
+
+    pub fn label(&self) -> String {
+        let mut label = String::new();
+        for (held, name) in [
+            (self.ctrl, "Ctrl+"),
+            (self.alt, "Alt+"),
+            (self.shift, "Shift+"),
+        ] {
+            if held {
+                label.push_str(name);
+            }
+        }
+        label.push_str(&key_name(self.key));
+        label
+    }
+}
+
+/// Whether Ctrl, Alt and Shift are all up, so keys we send don't pick
+/// them up from a hotkey still held.
+pub fn modifiers_released() -> bool {
+    !is_down(VK_CONTROL) && !is_down(VK_MENU) && !is_down(VK_SHIFT)
+}
+
+/// Why `hotkey` can't be used, if it can't: another of our hotkeys, named in
+/// `others`, or a shortcut everyone relies on already has it.
+pub fn conflict(hotkey: &Hotkey, others: &[(&str, Hotkey)]) -> Option<String> {
+    if let Some((name, _)) = others.iter().find(|(_, other)| other == hotkey) {
+        return Some(format!("{} is already used for {}", hotkey.label(), name));
+    }
+    RESERVED
+        .iter()
+        .find(|(_, reserved)| reserved == hotkey)
+        .map(|(name, _)| format!("{} is the {} shortcut of other apps", hotkey.label(), name))
+}
+
+/// A button showing the combination; clicked, it records the next one
+/// pressed in our window. Escape keeps the old combination, and one that
+/// conflicts with `others` or common shortcuts is refused with the reason
+/// shown. Returns whether `hotkey` changed.
+pub fn recorder(
+    ui: &mut egui::Ui,
+    id: &str,
+    hotkey: &mut Hotkey,
+    others: &[(&str, Hotkey)],
+) -> bool {
+    let capturing_id = egui::Id::new(id).with("capturing");
+    let problem_id = egui::Id::new(id).with("problem");
+    let mut capturing = ui.data(|d| d.get_temp::<bool>(capturing_id).unwrap_or(false));
+    let mut problem = ui.data(|d| d.get_temp::<String>(problem_id));
+    let mut changed = false;
+
+    let label = if capturing {
+        "Press a combination…".to_string()
+    } else {
+        hotkey.label()
+    };
+    if ui.button(label).clicked() {
+        capturing = true;
+        problem = None;
+    }
+
+    if capturing {
+        // Ctrl or Alt plus a key
+        let pressed = ui.input(|i| {
+            i.events.iter().find_map(|event| match event {
+                egui::Event::Key {
+                    key,
+                    pressed: true,
+                    modifiers,
+                    ..
+                } => Some((*key, *modifiers)),
+                _ => None,
+            })
+        });
+        if let Some((key, modifiers)) = pressed {
+            if key == egui::Key::Escape {
+                capturing = false;
+            } else if let Some(recorded) = Hotkey::from_egui(modifiers, key) {
+                capturing = false;
+                problem = conflict(&recorded, others);
+                if problem.is_none() && recorded != *hotkey {
+                    *hotkey = recorded;
+                    changed = true;
+                }
+            }
+        }
+    }
+    if let Some(problem) = &problem {
+        ui.label(
+            egui::RichText::new(problem)
+                .color(ui.visuals().warn_fg_color)
+                .size(12.0),
+        );
+    }
+
+    ui.data_mut(|d| {
+        d.insert_temp(capturing_id, capturing);
+        match problem {
+            Some(problem) => d.insert_temp(problem_id, problem),
+            None => d.remove::<String>(problem_id),
+        }
+    });
+    changed
+}
+
+// Letters, digits, Space, some punctuation and function keys
+fn virtual_key(key: egui::Key) -> Option<u16> {
+    let name = key.name();
+    match name.as_bytes() {
+        [c] if c.is_ascii_uppercase() || c.is_ascii_digit() => Some(*c as u16),
+        _ if key == egui::Key::Space => Some(VK_SPACE.0),
+        _ if key == egui::Key::Period => Some(VK_OEM_PERIOD.0),
+        _ if key == egui::Key::Comma => Some(VK_OEM_COMMA.0),
+        _ if key == egui::Key::Minus => Some(VK_OEM_MINUS.0),
+        _ if key == egui::Key::Slash => Some(VK_OEM_2.0),
+        [b'F', ..] => {
+            let n: u16 = name[1..].parse().ok()?;
+            (1..=24).contains(&n).then_some(0x6F + n)
+        }
+        _ => None,
+    }
+}
+
+fn key_name(vk: u16) -> String {
+    match vk {
+        0x20 => "Space".to_string(),
+        0x09 => "Tab".to_string(),
+        0xBC => ",".to_string(),
+        0xBD => "-".to_string(),
+        0xBE => ".".to_string(),
+        0xBF => "/".to_string(),
+        0x30..=0x39 | 0x41..=0x5A => (vk as u8 as char).to_string(),
+        0x70..=0x87 => format!("F{}", vk - 0x6F),
+        _ => format!("Key {:#04X}", vk),
+    }
+}
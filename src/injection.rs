@@ -0,0 +1,194 @@
+use crate::history::EmissionHistory;
+use windows::Win32::UI::Input::KeyboardAndMouse::{
+    INPUT, INPUT_0, INPUT_KEYBOARD, KEYBDINPUT, KEYBD_EVENT_FLAGS, KEYEVENTF_KEYUP,
+    KEYEVENTF_UNICODE, VIRTUAL_KEY, VK_BACK, VK_CONTROL,
+};
+
+/// What to send to the application: backspaces erasing characters before
+/// the caret, then text to type. Planned apart from sending so the
+/// replacement logic can be tested without a Windows session.
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct EditPlan {
+    pub backspaces: usize,
+    pub text: String,
+}
+
+impl EditPlan {
+    /// Replaces the last `backspaces` characters of the emitted text with
+    /// `output`. Erased characters the output starts with are kept instead
+    /// of typed again. When less text is known than is erased, because the
+    /// history was cleared or trimmed, everything is erased and retyped.
+    pub fn replace(emitted: &str, backspaces: usize, output: &str) -> Self {
+        let known: Vec<char> = emitted.chars().collect();
+        let erased = known
+            .len()
+            .checked_sub(backspaces)
+            .map_or(&[][..], |start| &known[start..]);
+        let kept = erased
+            .iter()
+            .zip(output.chars())
+            .take_while(|(erased, typed)| **erased == *typed)
+            .count();
+        Self {
+            backspaces: backspaces - kept,
+            text: output.chars().skip(kept).collect(),
+        }
+    }
+
+    // Mirrors the plan in the history before the keys are sent
+    pub fn apply(&self, history: &mut EmissionHistory) {
+        history.erase(self.backspaces);
+        history.push(&self.text);
+    }
+
+    /// The key presses for the whole plan, backspaces first, to be sent in
+    /// one SendInput call. Windows queues them together, so keys the user
+    /// types meanwhile can't land in the middle of the replacement.
+    pub fn inputs(&self) -> Vec<INPUT> {
+        let backspaces =
+            (0..self.backspaces).flat_map(|_| key_press(VK_BACK, 0, KEYBD_EVENT_FLAGS(0)));
+        // Characters outside the BMP are sent as their two UTF-16 halves
+        let text = self
+            .text
+            .encode_utf16()
+            .flat_map(|unit| key_press(VIRTUAL_KEY(0), unit, KEYEVENTF_UNICODE));
+        backspaces.chain(text).collect()
+    }
+}
+
+/// Ctrl+`key`, as for copying and pasting.
+pub fn ctrl_shortcut(key: u8) -> Vec<INPUT> {
+    let ctrl = key_press(VK_CONTROL, 0, KEYBD_EVENT_FLAGS(0));
+    let key = key_press(VIRTUAL_KEY(key as u16), 0, KEYBD_EVENT_FLAGS(0));
+    vec![ctrl[0], key[0], key[1], ctrl[1]]
+}
+
+// Key down and key up
+fn key_press(vk: VIRTUAL_KEY, scan: u16, flags: KEYBD_EVENT_FLAGS) -> [INPUT; 2] {
+    let input = |flags| INPUT {
+        r#type: INPUT_KEYBOARD,
+        Anonymous: INPUT_0 {
+            ki: KEYBDINPUT {
+                wVk: vk,
+                wScan: scan,
+                dwFlags: flags,
+                time: 0,
+                dwExtraInfo: 0,
+            },
+        },
+    };
+    [input(flags), input(flags | KEYEVENTF_KEYUP)]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn plan(backspaces: usize, text: &str) -> EditPlan {
+        EditPlan {
+            backspaces,
+            text: text.to_string(),
+        }
+    }
+
+    #[test]
+    fn replaces_the_erased_tail() {
+        assert_eq!(EditPlan::replace("আমি ক", 1, "খ"), plan(1, "খ"));
+    }
+
+    #[test]
+    fn keeps_what_the_output_starts_with() {
+        assert_eq!(EditPlan::replace("কি", 2, "কী"), plan(1, "ী"));
+        assert_eq!(EditPlan::replace("আমি ক", 1, "ক্ষ"), plan(0, "্ষ"));
+    }
+
+    #[test]
+    fn only_looks_at_erased_characters() {
+        // The ক before the erased text is not part of the replacement
+        assert_eq!(EditPlan::replace("কখ", 1, "কখ"), plan(1, "কখ"));
+    }
+
+    #[test]
+    fn same_text_needs_no_keys() {
+        assert_eq!(EditPlan::replace("অমি", 3, "অমি"), EditPlan::default());
+        assert_eq!(EditPlan::replace("", 0, ""), EditPlan::default());
+    }
+
+    #[test]
+    fn erasing_only() {
+        assert_eq!(EditPlan::replace("কখ", 1, ""), plan(1, ""));
+    }
+
+    #[test]
+    fn typing_only() {
+        assert_eq!(EditPlan::replace("আমি", 0, " "), plan(0, " "));
+    }
+
+    #[test]
+    fn counts_characters_not_bytes() {
+        assert_eq!(EditPlan::replace("অমি", 3, "আমি"), plan(3, "আমি"));
+    }
+
+    #[test]
+    fn unknown_text_is_retyped_in_full() {
+        assert_eq!(EditPlan::replace("", 2, "কী"), plan(2, "কী"));
+        assert_eq!(EditPlan::replace("ি", 2, "কী"), plan(2, "কী"));
+    }
+
+    // (virtual key, UTF-16 unit, key up) for each input
+    fn keys(plan: &EditPlan) -> Vec<(u16, u16, bool)> {
+        plan.inputs()
+            .iter()
+            .map(|input| {
+                let ki = unsafe { input.Anonymous.ki };
+                (ki.wVk.0, ki.wScan, ki.dwFlags.contains(KEYEVENTF_KEYUP))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn inputs_erase_then_type() {
+        let back = VK_BACK.0;
+        assert_eq!(
+            keys(&plan(2, "কী")),
+            vec![
+                (back, 0, false),
+                (back, 0, true),
+                (back, 0, false),
+                (back, 0, true),
+                (0, 0x0995, false),
+                (0, 0x0995, true),
+                (0, 0x09C0, false),
+                (0, 0x09C0, true),
+            ]
+        );
+        assert!(EditPlan::default().inputs().is_empty());
+    }
+
+    #[test]
+    fn inputs_split_characters_outside_the_bmp() {
+        assert_eq!(
+            keys(&plan(0, "😀")),
+            vec![
+                (0, 0xD83D, false),
+                (0, 0xD83D, true),
+                (0, 0xDE00, false),
+                (0, 0xDE00, true),
+            ]
+        );
+    }
+
+    #[test]
+    fn apply_mirrors_the_application() {
+        let mut history = EmissionHistory::default();
+        history.push("আমি কি");
+
+        let edit = EditPlan::replace(history.text(), 1, "ী");
+        edit.apply(&mut history);
+        assert_eq!(history.text(), "আমি কী");
+
+        let edit = EditPlan::replace(history.text(), 2, "তুমি");
+        edit.apply(&mut history);
+        assert_eq!(history.text(), "আমি তুমি");
+    }
+}
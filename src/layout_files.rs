@@ -0,0 +1,146 @@
+use crate::mappings::Mapping;
+use crate::settings;
+use restro_keyboard_core::{Condition, ContextRule, Layout, Scope, Side};
+use serde::Deserialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// Phonetic layouts shipped or dropped next to the executable, one JSON file
+// each:
+//
+// {
+//   "name": "My layout",
+//   "rules": [
+//     { "roman": "k", "bangla": "ক", "category": "Consonants" },
+//     { "roman": "i", "bangla": "ই", "category": "Vowels", "after_consonant": "ি" }
+//   ],
+//   "conjuncts": [["ক", "ষ"]],
+//   "gemination": false
+// }
+//
+// Categories are those of the mappings editor. after_consonant is what the
+// sequence types right after a consonant, e.g. a vowel's sign. gemination
+// is left out unless doubling a letter means something else in the layout,
+// and the user can still turn it on.
+const LAYOUT_DIR: &str = "assets/layouts";
+
+#[derive(Deserialize)]
+struct LayoutFile {
+    name: String,
+    rules: Vec<Rule>,
+    #[serde(default)]
+    conjuncts: Vec<(String, String)>,
+    #[serde(default = "joins_doubled")]
+    gemination: bool,
+}
+
+fn joins_doubled() -> bool {
+    true
+}
+
+#[derive(Deserialize)]
+struct Rule {
+    #[serde(flatten)]
+    mapping: Mapping,
+    #[serde(default)]
+    after_consonant: Option<String>,
+}
+
+fn parse(json: &str) -> Result<Layout, String> {
+    let file: LayoutFile = serde_json::from_str(json).map_err(|err| err.to_string())?;
+    if file.name.trim().is_empty() {
+        return Err("the layout has no name".to_string());
+    }
+
+    let mut layout = Layout::new(file.name.trim());
+    layout.set_gemination(file.gemination);
+    for rule in file.rules {
+        if let Some(problem) = rule.mapping.problem() {
+            return Err(problem);
+        }
+        layout.insert(&rule.mapping.roman, rule.mapping.bangla_char());
+        if let Some(output) = rule.after_consonant {
+            layout.insert_context_rule(
+                &rule.mapping.roman,
+                ContextRule::new(
+                    vec![Condition::new(Side::Prefix, Scope::Consonant)],
+                    &output,
+                    "layout file rule after a consonant",
+                ),
+            );
+        }
+    }
+    for (first, second) in file.conjuncts {
+        layout.insert_conjunct(&first, &second);
+    }
+    Ok(layout)
+}
+
+// The layout files, in file name order
+fn paths() -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(LAYOUT_DIR) else {
+        return Vec::new();
+    };
+    let mut paths: Vec<_> = entries
+        .filter_map(|entry| Some(entry.ok()?.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+        .collect();
+    paths.sort();
+    paths
+}
+
+/// Every layout in the layouts folder, in file name order. Files that fail
+/// to parse are skipped.
+pub fn load_all() -> Vec<Layout> {
+    paths()
+        .iter()
+        .filter_map(|path| match load(path) {
+            Ok(layout) => Some(layout),
+            Err(err) => {
+                eprintln!("Skipping layout file {}: {}", path.display(), err);
+                None
+            }
+        })
+        .collect()
+}
+
+fn load(path: &Path) -> Result<Layout, String> {
+    let json = fs::read_to_string(path).map_err(|err| err.to_string())?;
+    parse(&json)
+}
+
+/// The layouts of the layouts folder by name, with the JSON of their file.
+pub fn sources() -> Vec<(String, String)> {
+    paths()
+        .iter()
+        .filter_map(|path| {
+            let json = fs::read_to_string(path).ok()?;
+            Some((parse(&json).ok()?.name().to_string(), json))
+        })
+        .collect()
+}
+
+/// Adds a layout file to the layouts folder, replacing the file of a
+/// layout with the same name. Returns the layout's name.
+pub fn install(json: &str) -> Result<String, String> {
+    let layout = parse(json)?;
+    let name = layout.name().to_string();
+    if [Layout::phonetic(), Layout::avro()]
+        .iter()
+        .any(|builtin| builtin.name() == name)
+    {
+        return Err(format!("{} is a built-in layout", name));
+    }
+    let existing = paths()
+        .into_iter()
+        .find(|path| load(path).is_ok_and(|layout| layout.name() == name));
+    let path = existing.unwrap_or_else(|| {
+        let stem: String = name
+            .chars()
+            .map(|c| if c.is_alphanumeric() { c } else { '_' })
+            .collect();
+        Path::new(LAYOUT_DIR).join(format!("{}.json", stem))
+    });
+    settings::write_atomically(&path, json).map_err(|err| err.to_string())?;
+    Ok(name)
+}
@@ -0,0 +1,181 @@
+use crate::layout_files;
+use crate::mappings::Mapping;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use egui::{Color32, Rect, RichText, Sense, Vec2};
+use flate2::read::DeflateDecoder;
+use flate2::write::DeflateEncoder;
+use flate2::Compression;
+use qrcodegen::{QrCode, QrCodeEcc};
+use serde::{Deserialize, Serialize};
+use std::io::{Read, Write};
+
+// Layouts shared as text, so a team can pass tweaks around in a chat
+// instead of files: a layout file and the user's mappings as JSON,
+// deflated and written in URL-safe base64 after a version tag. Short codes
+// can also be shown as a QR code. Importing installs the layout file and
+// puts the mappings over the user's own.
+
+const PREFIX: &str = "RK1-";
+// Codes open to at most this much JSON, so a pasted code can't fill memory
+const MAX_JSON: u64 = 1 << 20;
+// White modules around a QR code, which scanners need
+const QUIET_ZONE: i32 = 4;
+
+#[derive(Serialize, Deserialize, Default, Debug, PartialEq)]
+struct Shared {
+    // The layout file, as JSON
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    layout: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    mappings: Vec<Mapping>,
+}
+
+/// The share code of a layout file's JSON, when a layout is shared, and of
+/// mappings.
+pub fn encode(layout: Option<&str>, mappings: &[Mapping]) -> String {
+    // Without the file's indentation
+    let layout = layout.map(
+        |json| match serde_json::from_str::<serde_json::Value>(json) {
+            Ok(value) => value.to_string(),
+            Err(_) => json.to_string(),
+        },
+    );
+    let shared = Shared {
+        layout,
+        mappings: mappings.to_vec(),
+    };
+    let json = serde_json::to_vec(&shared).expect("shared layouts serialize");
+    let mut deflate = DeflateEncoder::new(Vec::new(), Compression::best());
+    deflate.write_all(&json).expect("writing to memory");
+    let bytes = deflate.finish().expect("writing to memory");
+    format!("{}{}", PREFIX, URL_SAFE_NO_PAD.encode(bytes))
+}
+
+// Spaces and line breaks a chat app added are ignored
+fn decode(code: &str) -> Result<Shared, String> {
+    let code: String = code.chars().filter(|c| !c.is_whitespace()).collect();
+    let data = code
+        .strip_prefix(PREFIX)
+        .ok_or("This isn't a layout code")?;
+    let bytes = URL_SAFE_NO_PAD
+        .decode(data)
+        .map_err(|_| "The code is incomplete or mistyped".to_string())?;
+    let mut json = Vec::new();
+    DeflateDecoder::new(&bytes[..])
+        .take(MAX_JSON)
+        .read_to_end(&mut json)
+        .map_err(|_| "The code is incomplete or mistyped".to_string())?;
+    serde_json::from_slice(&json).map_err(|err| format!("The code can't be read: {}", err))
+}
+
+/// Installs the layout of a share code and puts its mappings over those in
+/// `mappings`, replacing any for the same English text. Nothing changes
+/// when the code or anything in it is invalid. Returns what was added; the
+/// caller saves the mappings and reloads the layouts.
+pub fn import(code: &str, mappings: &mut Vec<Mapping>) -> Result<String, String> {
+    let shared = decode(code)?;
+    if let Some(problem) = shared.mappings.iter().find_map(Mapping::problem) {
+        return Err(problem);
+    }
+    let layout = shared
+        .layout
+        .as_deref()
+        .map(layout_files::install)
+        .transpose()?;
+    let count = shared.mappings.len();
+    for mapping in shared.mappings {
+        match mappings.iter_mut().find(|m| m.roman == mapping.roman) {
+            Some(existing) => *existing = mapping,
+            None => mappings.push(mapping),
+        }
+    }
+    Ok(match layout {
+        Some(name) => format!("Added the {} layout and {} mappings", name, count),
+        None => format!("Added {} mappings", count),
+    })
+}
+
+/// Draws a share code as a QR code, or says it is too long for one.
+pub fn show_qr(ui: &mut egui::Ui, code: &str) {
+    let Ok(qr) = QrCode::encode_text(code, QrCodeEcc::Low) else {
+        ui.label(
+            RichText::new("Too long for a QR code; share the text instead.")
+                .weak()
+                .size(12.0),
+        );
+        return;
+    };
+    let modules = qr.size() + 2 * QUIET_ZONE;
+    let module = (300.0 / modules as f32).floor().max(2.0);
+    let (rect, _) = ui.allocate_exact_size(Vec2::splat(modules as f32 * module), Sense::hover());
+    let painter = ui.painter_at(rect);
+    painter.rect_filled(rect, 0.0, Color32::WHITE);
+    for y in 0..qr.size() {
+        for x in 0..qr.size() {
+            if qr.get_module(x, y) {
+                let min = rect.min
+                    + egui::vec2(
+                        (x + QUIET_ZONE) as f32 * module,
+                        (y + QUIET_ZONE) as f32 * module,
+                    );
+                painter.rect_filled(
+                    Rect::from_min_size(min, Vec2::splat(module)),
+                    0.0,
+                    Color32::BLACK,
+                );
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mapping(roman: &str, bangla: &str) -> Mapping {
+        Mapping {
+            roman: roman.to_string(),
+            bangla: bangla.to_string(),
+            ..Mapping::default()
+        }
+    }
+
+    #[test]
+    fn codes_carry_the_layout_and_mappings() {
+        let layout = "{\n  \"name\": \"Team\",\n  \"rules\": [{ \"roman\": \"q\", \"bangla\": \"ক\", \"category\": \"Consonants\" }]\n}";
+        let code = encode(Some(layout), &[mapping("x", "ক্স")]);
+        assert!(code.starts_with(PREFIX));
+        // Pasted back broken over lines
+        let (head, tail) = code.split_at(20);
+        let shared = decode(&format!("{}\n  {}", head, tail)).unwrap();
+        assert!(shared.layout.unwrap().starts_with("{\"name\":\"Team\""));
+        assert_eq!(shared.mappings, [mapping("x", "ক্স")]);
+    }
+
+    #[test]
+    fn imported_mappings_replace_the_users() {
+        let mut mine = vec![mapping("x", "এক্স"), mapping("w", "ও")];
+        let code = encode(None, &[mapping("x", "ক্স"), mapping("qq", "ঙ")]);
+        assert_eq!(import(&code, &mut mine).unwrap(), "Added 2 mappings");
+        assert_eq!(
+            mine,
+            [mapping("x", "ক্স"), mapping("w", "ও"), mapping("qq", "ঙ")]
+        );
+    }
+
+    #[test]
+    fn bad_codes_change_nothing() {
+        let mut mine = vec![mapping("x", "এক্স")];
+        assert!(import("hello", &mut mine).is_err());
+        assert!(import("RK1-@@@", &mut mine).is_err());
+        let broken = encode(
+            Some("{\"name\": \"\", \"rules\": []}"),
+            &[mapping("k", "খ")],
+        );
+        assert!(import(&broken, &mut mine).is_err());
+        let empty = encode(None, &[mapping("", "খ")]);
+        assert!(import(&empty, &mut mine).is_err());
+        assert_eq!(mine, [mapping("x", "এক্স")]);
+    }
+}
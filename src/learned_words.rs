@@ -0,0 +1,310 @@
+use crate::history::{script_of, Script};
+use crate::settings::{self, KeyboardSettings};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+
+// Words finished in Bangla mode, with how often each spelling was typed,
+// when the user lets the keyboard learn. A word's most typed spelling
+// comes first among suggestions and is what dictionary mode respells it
+// to, ahead of the bundled dictionary but behind words added on purpose.
+
+// Changes are written this long after they are made, at most
+const SAVE_INTERVAL: Duration = Duration::from_secs(5);
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq, Debug)]
+pub struct LearnedWord {
+    // As typed where the layout tells capitals apart, otherwise lowercase
+    // like the dictionary's
+    pub roman: String,
+    pub bangla: String,
+    pub count: u32,
+}
+
+#[derive(Default)]
+struct Learned {
+    words: Vec<LearnedWord>,
+}
+
+impl Learned {
+    /// Counts one more use of a spelling, under the word as typed when
+    /// `case_sensitive` (Dal ডাল is not dal দাল). Returns whether the
+    /// word's most typed spelling changed.
+    fn record(&mut self, roman: &str, bangla: &str, case_sensitive: bool) -> bool {
+        let roman = if case_sensitive {
+            roman.to_string()
+        } else {
+            roman.to_lowercase()
+        };
+        let before = self.top(&roman).map(str::to_string);
+        match self
+            .words
+            .iter_mut()
+            .find(|word| word.roman == roman && word.bangla == bangla)
+        {
+            Some(word) => word.count = word.count.saturating_add(1),
+            None => self.words.push(LearnedWord {
+                roman: roman.clone(),
+                bangla: bangla.to_string(),
+                count: 1,
+            }),
+        }
+        self.top(&roman) != before.as_deref()
+    }
+
+    /// The word's spellings, most typed first; ties go to the one learned
+    /// first. Those learned under the word as given, or else under it in
+    /// small letters.
+    fn spellings(&self, roman: &str) -> Vec<&LearnedWord> {
+        let lowercase = roman.to_lowercase();
+        let key = if self.words.iter().any(|word| word.roman == roman) {
+            roman
+        } else {
+            &lowercase
+        };
+        let mut spellings: Vec<&LearnedWord> =
+            self.words.iter().filter(|word| word.roman == key).collect();
+        // Stable, so earlier entries stay first among equals
+        spellings.sort_by_key(|word| std::cmp::Reverse(word.count));
+        spellings
+    }
+
+    fn top(&self, roman: &str) -> Option<&str> {
+        self.spellings(roman)
+            .first()
+            .map(|word| word.bangla.as_str())
+    }
+
+    fn remove(&mut self, roman: &str, bangla: &str) {
+        self.words
+            .retain(|word| !(word.roman == roman && word.bangla == bangla));
+    }
+}
+
+// Loaded at startup, saved by the saver thread once changed
+static LEARNED: Mutex<Option<Learned>> = Mutex::new(None);
+static CHANGED: AtomicBool = AtomicBool::new(false);
+// Set when a word's most typed spelling changed, so dictionary mode
+// respells to it
+static FAVOURITES_CHANGED: AtomicBool = AtomicBool::new(false);
+// Saves take turns, each writing the list as it is by then
+static SAVING: Mutex<()> = Mutex::new(());
+// Moves on when a spelling is added or removed
+static GENERATION: AtomicU64 = AtomicU64::new(0);
+
+/// `%APPDATA%\RestroKeyboard\learned.json`, next to the settings.
+pub fn path() -> Option<PathBuf> {
+    Some(KeyboardSettings::path()?.parent()?.join("learned.json"))
+}
+
+fn read() -> Learned {
+    let words = path()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .map(|json| {
+            serde_json::from_str(&json).unwrap_or_else(|err| {
+                eprintln!("Learned words file is corrupt, ignoring it: {}", err);
+                Vec::new()
+            })
+        })
+        .unwrap_or_default();
+    Learned { words }
+}
+
+/// Reads the learned words, so the hook thread never waits for the file.
+pub fn load() {
+    let learned = read();
+    *LEARNED.lock().unwrap() = Some(learned);
+}
+
+fn with_learned<T>(f: impl FnOnce(&mut Learned) -> T) -> T {
+    f(LEARNED.lock().unwrap().get_or_insert_with(read))
+}
+
+// The list is copied out so typing never waits for it to be written
+fn save() -> io::Result<()> {
+    let _turn = SAVING.lock().unwrap();
+    let path =
+        path().ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "APPDATA is not set"))?;
+    let words = with_learned(|learned| learned.words.clone());
+    settings::save_json_atomically(&path, &words)
+}
+
+/// Notes a finished word and how it was spelled, when learning is on.
+/// Words without Bangla and blocked words are never kept. Called from the
+/// hook thread, so the file is written by the saver.
+pub fn record(roman: &str, bangla: &str, settings: &KeyboardSettings) {
+    if !settings.learn_words
+        || roman.is_empty()
+        || !bangla.chars().any(|c| script_of(c) == Script::Bangla)
+        || crate::blocklist::is_blocked(&settings.blocked_words, roman)
+        || crate::blocklist::is_blocked(&settings.blocked_words, bangla)
+    {
+        return;
+    }
+    let case_sensitive =
+        crate::find_layout(&crate::layouts(), &settings.state.layout).is_case_sensitive();
+    let (favourite, added) = with_learned(|learned| {
+        let before = learned.words.len();
+        let favourite = learned.record(roman, bangla, case_sensitive);
+        (favourite, learned.words.len() != before)
+    });
+    if favourite {
+        FAVOURITES_CHANGED.store(true, Ordering::SeqCst);
+    }
+    if added {
+        GENERATION.fetch_add(1, Ordering::SeqCst);
+    }
+    CHANGED.store(true, Ordering::SeqCst);
+}
+
+/// Moves on whenever a spelling is added or removed, so what is made from
+/// the learned words knows when to be made again.
+pub fn generation() -> u64 {
+    GENERATION.load(Ordering::SeqCst)
+}
+
+/// Writes the learned words a few seconds after they change.
+pub fn spawn_saver() {
+    std::thread::spawn(|| loop {
+        std::thread::sleep(SAVE_INTERVAL);
+        if CHANGED.swap(false, Ordering::SeqCst) {
+            if let Err(err) = save() {
+                eprintln!("Could not save the learned words: {}", err);
+            }
+        }
+        // Dictionary mode respells to the new favourite from now on
+        if FAVOURITES_CHANGED.swap(false, Ordering::SeqCst) {
+            crate::reload_dictionary();
+        }
+    });
+}
+
+/// The word's learned spellings, most typed first.
+pub fn spellings(roman: &str) -> Vec<String> {
+    with_learned(|learned| {
+        learned
+            .spellings(roman)
+            .into_iter()
+            .map(|word| word.bangla.clone())
+            .collect()
+    })
+}
+
+/// Each word's most typed spelling, in the dictionary's `roman<TAB>bangla`
+/// format. Words learned with capitals are left out, as the dictionary
+/// doesn't tell them from the small ones.
+pub fn favourites() -> String {
+    with_learned(|learned| {
+        let mut text = String::new();
+        for word in &learned.words {
+            if word.roman.chars().any(|c| c.is_ascii_uppercase()) {
+                continue;
+            }
+            if learned.top(&word.roman) == Some(word.bangla.as_str()) {
+                text.push_str(&format!("{}\t{}\n", word.roman, word.bangla));
+            }
+        }
+        text
+    })
+}
+
+/// Every learned spelling, most typed first.
+pub fn words() -> Vec<LearnedWord> {
+    let mut words = with_learned(|learned| learned.words.clone());
+    words.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.roman.cmp(&b.roman)));
+    words
+}
+
+pub fn count() -> usize {
+    with_learned(|learned| learned.words.len())
+}
+
+/// Adds uses of spellings counted on other computers, see
+/// dictionary_sync.
+pub fn add_uses(words: &[LearnedWord]) {
+    if words.is_empty() {
+        return;
+    }
+    with_learned(|learned| {
+        for word in words {
+            match learned
+                .words
+                .iter_mut()
+                .find(|known| known.roman == word.roman && known.bangla == word.bangla)
+            {
+                Some(known) => known.count = known.count.saturating_add(word.count),
+                None => learned.words.push(word.clone()),
+            }
+        }
+    });
+    GENERATION.fetch_add(1, Ordering::SeqCst);
+    FAVOURITES_CHANGED.store(true, Ordering::SeqCst);
+    CHANGED.store(true, Ordering::SeqCst);
+}
+
+pub fn remove(roman: &str, bangla: &str) -> io::Result<()> {
+    with_learned(|learned| learned.remove(roman, bangla));
+    GENERATION.fetch_add(1, Ordering::SeqCst);
+    save()
+}
+
+pub fn delete() -> io::Result<()> {
+    let _turn = SAVING.lock().unwrap();
+    with_learned(|learned| learned.words.clear());
+    GENERATION.fetch_add(1, Ordering::SeqCst);
+    CHANGED.store(false, Ordering::SeqCst);
+    match path() {
+        Some(path) if path.exists() => fs::remove_file(path),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_most_typed_spelling_comes_first() {
+        let mut learned = Learned::default();
+        assert!(learned.record("Ami", "আমি", false));
+        // A tie goes to the spelling learned first
+        assert!(!learned.record("ami", "আমী", false));
+        assert_eq!(learned.top("ami"), Some("আমি"));
+        assert!(learned.record("ami", "আমী", false));
+        let spellings: Vec<&str> = learned
+            .spellings("AMI")
+            .iter()
+            .map(|word| word.bangla.as_str())
+            .collect();
+        assert_eq!(spellings, ["আমী", "আমি"]);
+    }
+
+    #[test]
+    fn removing_a_spelling_keeps_the_others() {
+        let mut learned = Learned::default();
+        learned.record("ami", "আমি", false);
+        learned.record("ami", "আমী", false);
+        learned.remove("ami", "আমি");
+        assert_eq!(learned.top("ami"), Some("আমী"));
+        assert_eq!(learned.words.len(), 1);
+    }
+
+    #[test]
+    fn case_sensitive_layouts_keep_capitals_apart() {
+        let mut learned = Learned::default();
+        learned.record("Dal", "ডাল", true);
+        learned.record("dal", "দাল", true);
+        assert_eq!(learned.top("Dal"), Some("ডাল"));
+        assert_eq!(learned.top("dal"), Some("দাল"));
+        // Capitals nothing was learned under find the small letters
+        assert_eq!(learned.top("DAL"), Some("দাল"));
+
+        let mut learned = Learned::default();
+        learned.record("Dal", "ডাল", false);
+        assert_eq!(learned.words[0].roman, "dal");
+    }
+}
@@ -0,0 +1,185 @@
+use crate::settings::KeyboardSettings;
+use std::sync::Mutex;
+use windows::Win32::UI::Input::KeyboardAndMouse::VIRTUAL_KEY;
+
+// Holding a key, as on a phone, types its alternate character instead:
+// hold t for ৎ. A held key repeats, and the hook sees the repeats as more
+// key downs; once they have come for longer than the threshold, the text
+// the first press typed is replaced by the alternate. Repeats of keys with
+// an alternate are swallowed, so holding never types tttt.
+
+/// Alternates for phonetic layouts the user hasn't changed, a key and its
+/// character per line. Fixed layouts start without any.
+const PHONETIC_ALTERNATES: &str = "t ৎ\nn ং\nh ঃ\ny য়\nk ক্ষ\nj জ্ঞ\nr ঋ\n";
+
+/// What the hook does with a key down.
+#[derive(PartialEq, Eq, Debug)]
+pub enum Action {
+    // Handled as usual
+    Type,
+    Swallow,
+    // The key was held long enough: the text typed since `before` becomes
+    // `alternate`
+    Replace { before: String, alternate: String },
+}
+
+struct Held {
+    vk: u16,
+    // Event time of the first press, in milliseconds
+    since: u32,
+    // The text before the first press and the alternate, for keys with one
+    alternate: Option<(String, String)>,
+    replaced: bool,
+}
+
+#[derive(Default)]
+struct Tracker {
+    held: Option<Held>,
+}
+
+impl Tracker {
+    fn down(
+        &mut self,
+        vk: u16,
+        time: u32,
+        threshold: u32,
+        start: impl FnOnce() -> Option<(String, String)>,
+    ) -> Action {
+        match &mut self.held {
+            Some(held) if held.vk == vk => {
+                // Keys without an alternate repeat as usual
+                let Some((before, alternate)) = &held.alternate else {
+                    return Action::Type;
+                };
+                if held.replaced || time.wrapping_sub(held.since) < threshold {
+                    return Action::Swallow;
+                }
+                held.replaced = true;
+                Action::Replace {
+                    before: before.clone(),
+                    alternate: alternate.clone(),
+                }
+            }
+            _ => {
+                self.held = Some(Held {
+                    vk,
+                    since: time,
+                    alternate: start(),
+                    replaced: false,
+                });
+                Action::Type
+            }
+        }
+    }
+
+    fn up(&mut self, vk: u16) {
+        if self.held.as_ref().is_some_and(|held| held.vk == vk) {
+            self.held = None;
+        }
+    }
+}
+
+static TRACKER: Mutex<Option<Tracker>> = Mutex::new(None);
+
+/// The alternates of the selected layout, as edited in the settings.
+pub fn table(settings: &KeyboardSettings) -> &str {
+    let layout = &settings.state.layout;
+    match settings.long_press_alternates.get(layout) {
+        Some(table) => table,
+        None if crate::fixed_layout(layout).is_some() => "",
+        None => PHONETIC_ALTERNATES,
+    }
+}
+
+// The alternate for `key` in a table; capitals are keys held with Shift
+fn alternate(table: &str, key: char) -> Option<String> {
+    table.lines().find_map(|line| {
+        let mut parts = line.split_whitespace();
+        let listed = parts.next()?;
+        let alternate = parts.next()?;
+        (listed.chars().eq([key])).then(|| alternate.to_string())
+    })
+}
+
+/// Notes a key down in Bangla mode, `time` being the hook's event time.
+pub fn key_down(vk: VIRTUAL_KEY, time: u32, settings: &KeyboardSettings) -> Action {
+    let Some(key) = crate::key_position(vk) else {
+        return Action::Type;
+    };
+    let key = if crate::shift_pressed() {
+        key.to_ascii_uppercase()
+    } else {
+        key
+    };
+    TRACKER
+        .lock()
+        .unwrap()
+        .get_or_insert_with(Tracker::default)
+        .down(vk.0, time, settings.long_press_ms, || {
+            let alternate = alternate(table(settings), key)?;
+            Some((crate::HISTORY.lock().unwrap().text().to_string(), alternate))
+        })
+}
+
+pub fn key_up(vk: VIRTUAL_KEY) {
+    if let Some(tracker) = TRACKER.lock().unwrap().as_mut() {
+        tracker.up(vk.0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const T: u16 = 0x54;
+    const THRESHOLD: u32 = 500;
+
+    fn with_alternate() -> Option<(String, String)> {
+        Some(("ভা".to_string(), "ৎ".to_string()))
+    }
+
+    #[test]
+    fn holding_past_the_threshold_replaces_once() {
+        let mut tracker = Tracker::default();
+        assert_eq!(
+            tracker.down(T, 1000, THRESHOLD, with_alternate),
+            Action::Type
+        );
+        assert_eq!(tracker.down(T, 1300, THRESHOLD, || None), Action::Swallow);
+        assert_eq!(
+            tracker.down(T, 1530, THRESHOLD, || None),
+            Action::Replace {
+                before: "ভা".to_string(),
+                alternate: "ৎ".to_string()
+            }
+        );
+        assert_eq!(tracker.down(T, 1560, THRESHOLD, || None), Action::Swallow);
+    }
+
+    #[test]
+    fn keys_without_an_alternate_repeat() {
+        let mut tracker = Tracker::default();
+        assert_eq!(tracker.down(T, 1000, THRESHOLD, || None), Action::Type);
+        assert_eq!(tracker.down(T, 1600, THRESHOLD, || None), Action::Type);
+    }
+
+    #[test]
+    fn releasing_starts_over() {
+        let mut tracker = Tracker::default();
+        tracker.down(T, 1000, THRESHOLD, with_alternate);
+        tracker.up(T);
+        assert_eq!(
+            tracker.down(T, 1600, THRESHOLD, with_alternate),
+            Action::Type
+        );
+    }
+
+    #[test]
+    fn tables_list_a_key_and_its_alternate_per_line() {
+        let table = "t ৎ\nT ঃ\n\nbad\n";
+        assert_eq!(alternate(table, 't'), Some("ৎ".to_string()));
+        assert_eq!(alternate(table, 'T'), Some("ঃ".to_string()));
+        assert_eq!(alternate(table, 'b'), None);
+        assert_eq!(alternate(PHONETIC_ALTERNATES, 'k'), Some("ক্ষ".to_string()));
+    }
+}
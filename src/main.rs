@@ -1,177 +1,248 @@
 use eframe::{self, App};
-use egui::{self, FontFamily, RichText, TextStyle, ViewportBuilder, ViewportCommand};
+use egui::{self, Key, RichText, TextStyle, ViewportBuilder, ViewportCommand};
 use lazy_static::lazy_static;
-use serde::{Deserialize, Serialize};
-use std::sync::atomic::Ordering;
-use std::{collections::HashMap, fs, sync::Mutex};
+use restro_keyboard_core::{
+    collation, reference, BanglaChar, Composer, Dictionary, FixedComposer, FixedLayout, Layer,
+    Layout, Transliterator,
+};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::sync::{Arc, Mutex, MutexGuard, RwLock};
 use windows::Win32::Foundation::{HMODULE, LPARAM, LRESULT, WPARAM};
+use windows::Win32::System::Threading::GetCurrentThreadId;
 use windows::Win32::UI::Input::KeyboardAndMouse::{
-    SendInput, INPUT, INPUT_0, INPUT_KEYBOARD, KEYBDINPUT, KEYEVENTF_KEYUP, KEYEVENTF_UNICODE,
-    VIRTUAL_KEY, VK_BACK, VK_CONTROL, VK_SPACE,
+    GetAsyncKeyState, GetKeyState, SendInput, INPUT, VIRTUAL_KEY, VK_1, VK_BACK, VK_CAPITAL,
+    VK_CONTROL, VK_DOWN, VK_ESCAPE, VK_LCONTROL, VK_LMENU, VK_LSHIFT, VK_LWIN, VK_MENU, VK_OEM_2,
+    VK_OEM_COMMA, VK_OEM_PERIOD, VK_RCONTROL, VK_RETURN, VK_RMENU, VK_RSHIFT, VK_RWIN, VK_SHIFT,
+    VK_SPACE, VK_UP,
 };
 use windows::Win32::UI::WindowsAndMessaging::{
-    CallNextHookEx, SetWindowsHookExA, UnhookWindowsHookEx, HHOOK, KBDLLHOOKSTRUCT,
-    KBDLLHOOKSTRUCT_FLAGS, WH_KEYBOARD_LL, WM_KEYDOWN, WM_KEYUP, WM_SYSKEYDOWN, WM_SYSKEYUP,
+    CallNextHookEx, GetMessageW, PostThreadMessageW, SetWindowsHookExA, UnhookWindowsHookEx, HHOOK,
+    KBDLLHOOKSTRUCT, KBDLLHOOKSTRUCT_FLAGS, MSG, WH_KEYBOARD_LL, WM_KEYDOWN, WM_KEYUP, WM_QUIT,
+    WM_SYSKEYDOWN, WM_SYSKEYUP,
 };
 
-#[derive(Serialize, Deserialize, Clone)]
-struct KeyboardSettings {
-    enabled: bool,
-    layout: String,
-    current_language: String,
-    use_suggestions: bool,
-    hotkey_enabled: bool,
-    font_size: f32,
-    theme: String,
-    intercept_all: bool,
-}
-
-#[derive(Clone)]
-enum BanglaChar {
-    Vowel(&'static str),
-    Consonant(&'static str),
-    VowelSign(&'static str),
-    Number(&'static str),
-    Special(&'static str),
-}
+mod autocorrect;
+mod avro_import;
+mod backup;
+mod bijoy;
+mod blocklist;
+mod candidate_popup;
+mod caret;
+mod cleanup;
+mod clipboard;
+mod converter;
+mod diagnostics;
+mod dictionary_sync;
+mod docs;
+#[cfg(all(test, feature = "e2e"))]
+mod e2e;
+mod evaluate;
+mod features;
+mod find_replace;
+mod focus_watch;
+mod fonts;
+mod full_dictionary;
+mod heatmap;
+mod history;
+mod hook_health;
+mod hotkey;
+mod injection;
+mod layout_files;
+mod layout_share;
+mod learned_words;
+mod long_press;
+mod mappings;
+mod mask;
+mod mouse_toggle;
+mod notification;
+mod numerals;
+mod on_screen_keyboard;
+mod overlay;
+mod power;
+mod preedit;
+mod problems;
+#[cfg(feature = "profiling")]
+mod profiling;
+mod provision;
+mod publisher;
+mod quick_insert;
+mod script_stats;
+mod self_test;
+mod sentence;
+mod settings;
+mod snapshot;
+mod spell_check;
+mod state;
+mod statistics;
+mod text_counts;
+mod transcript;
+mod tray;
+mod typography;
+mod unmatched;
+mod user_dictionary;
+mod web_copy;
+mod welcome;
+mod window_language;
+mod windows_spelling;
+
+use features::Feature;
+use history::{EmissionHistory, Script};
+use injection::EditPlan;
+use problems::Problem;
+use settings::{KeyboardSettings, SettingsService};
+use state::{Event, Language, SuspendReason};
+use typography::Mark;
 
 // Global state
-use std::sync::atomic;
 lazy_static! {
-    static ref CTRL_PRESSED: atomic::AtomicBool = atomic::AtomicBool::new(false);
     static ref KEYBOARD_HOOK: Mutex<Option<HHOOK>> = Mutex::new(None);
-    static ref BUFFER: Mutex<String> = Mutex::new(String::new());
-    static ref SETTINGS: Mutex<KeyboardSettings> = Mutex::new(KeyboardSettings {
-        enabled: true,
-        layout: "Phonetic".to_string(),
-        current_language: "Bangla".to_string(),
-        use_suggestions: true,
-        hotkey_enabled: true,
-        font_size: 14.0,
-        theme: "Light".to_string(),
-        intercept_all: true,
-    });
-
-    static ref PHONETIC_MAP: HashMap<&'static str, BanglaChar> = {
-        let mut m = HashMap::new();
-
-        // Vowels (স্বরবর্ণ)
-        m.insert("a", BanglaChar::Vowel("অ"));
-        m.insert("aa", BanglaChar::Vowel("আ"));
-        m.insert("A", BanglaChar::Vowel("আ"));
-        m.insert("i", BanglaChar::Vowel("ই"));
-        m.insert("ii", BanglaChar::Vowel("ঈ"));
-        m.insert("I", BanglaChar::Vowel("ঈ"));
-        m.insert("u", BanglaChar::Vowel("উ"));
-        m.insert("uu", BanglaChar::Vowel("ঊ"));
-        m.insert("U", BanglaChar::Vowel("ঊ"));
-        m.insert("rri", BanglaChar::Vowel("ঋ"));
-        m.insert("e", BanglaChar::Vowel("এ"));
-        m.insert("E", BanglaChar::VowelSign("ে"));
-        m.insert("oi", BanglaChar::Vowel("ঐ"));
-        m.insert("OI", BanglaChar::Vowel("ঐ"));
-        m.insert("o", BanglaChar::Vowel("ও"));
-        m.insert("O", BanglaChar::VowelSign("ো"));
-        m.insert("ou", BanglaChar::Vowel("ঔ"));
-        m.insert("OU", BanglaChar::Vowel("ঔ"));
-
-        // Consonants (ব্যঞ্জনবর্ণ)
-        m.insert("k", BanglaChar::Consonant("ক"));
-        m.insert("kh", BanglaChar::Consonant("খ"));
-        m.insert("g", BanglaChar::Consonant("গ"));
-        m.insert("gh", BanglaChar::Consonant("ঘ"));
-        m.insert("ng", BanglaChar::Consonant("ঙ"));
-        m.insert("c", BanglaChar::Consonant("চ"));
-        m.insert("ch", BanglaChar::Consonant("ছ"));
-        m.insert("j", BanglaChar::Consonant("জ"));
-        m.insert("jh", BanglaChar::Consonant("ঝ"));
-        m.insert("ny", BanglaChar::Consonant("ঞ"));
-        m.insert("t", BanglaChar::Consonant("ট"));
-        m.insert("th", BanglaChar::Consonant("ঠ"));
-        m.insert("d", BanglaChar::Consonant("ড"));
-        m.insert("dh", BanglaChar::Consonant("ঢ"));
-        m.insert("n", BanglaChar::Consonant("ন"));
-        m.insert("p", BanglaChar::Consonant("প"));
-        m.insert("ph", BanglaChar::Consonant("ফ"));
-        m.insert("f", BanglaChar::Consonant("ফ"));
-        m.insert("b", BanglaChar::Consonant("ব"));
-        m.insert("bh", BanglaChar::Consonant("ভ"));
-        m.insert("v", BanglaChar::Consonant("ভ"));
-        m.insert("m", BanglaChar::Consonant("ম"));
-        m.insert("z", BanglaChar::Consonant("য"));
-        m.insert("r", BanglaChar::Consonant("র"));
-        m.insert("l", BanglaChar::Consonant("ল"));
-        m.insert("sh", BanglaChar::Consonant("শ"));
-        m.insert("s", BanglaChar::Consonant("স"));
-        m.insert("h", BanglaChar::Consonant("হ"));
-        m.insert("y", BanglaChar::Consonant("য়"));
-        m.insert("kk", BanglaChar::Consonant("ক্ক"));
-        m.insert("tt", BanglaChar::Consonant("ত্ত"));
-        m.insert("nn", BanglaChar::Consonant("ন্ন"));
-
-        // Vowel Signs (কার)
-        m.insert("kar_aa", BanglaChar::VowelSign("া"));
-        m.insert("kar_i", BanglaChar::VowelSign("ি"));
-        m.insert("kar_ii", BanglaChar::VowelSign("ী"));
-        m.insert("kar_u", BanglaChar::VowelSign("ু"));
-        m.insert("kar_uu", BanglaChar::VowelSign("ূ"));
-        m.insert("kar_e", BanglaChar::VowelSign("ে"));
-        m.insert("kar_oi", BanglaChar::VowelSign("ৈ"));
-        m.insert("kar_o", BanglaChar::VowelSign("ো"));
-        m.insert("kar_ou", BanglaChar::VowelSign("ৌ"));
-
-        // Numbers
-        m.insert("0", BanglaChar::Number("০"));
-        m.insert("1", BanglaChar::Number("১"));
-        m.insert("2", BanglaChar::Number("২"));
-        m.insert("3", BanglaChar::Number("৩"));
-        m.insert("4", BanglaChar::Number("৪"));
-        m.insert("5", BanglaChar::Number("৫"));
-        m.insert("6", BanglaChar::Number("৬"));
-        m.insert("7", BanglaChar::Number("৭"));
-        m.insert("8", BanglaChar::Number("৮"));
-        m.insert("9", BanglaChar::Number("৯"));
-
-        // Special Characters
-        m.insert("chandrabindu", BanglaChar::Special("ঁ"));
-        m.insert("anusvar", BanglaChar::Special("ং"));
-        m.insert("bisarga", BanglaChar::Special("ঃ"));
-        m.insert("hasant", BanglaChar::Special("্"));
-        m.insert("dari", BanglaChar::Special("।"));
-
-        m
-    };
-
-    static ref CONVERSION_MAP: HashMap<&'static str, &'static str> = {
-        let mut m = HashMap::new();
-        // Convert PHONETIC_MAP to simple string mappings for display
-        for (k, v) in PHONETIC_MAP.iter() {
-            match v {
-                BanglaChar::Vowel(c) | BanglaChar::Consonant(c) |
-                BanglaChar::VowelSign(c) | BanglaChar::Number(c) |
-                BanglaChar::Special(c) => {
-                    m.insert(*k, *c);
-                }
-            }
-        }
-        m
-    };
+    static ref COMPOSER: Mutex<Composer> = Mutex::new(Composer::new());
+    static ref FIXED_COMPOSER: Mutex<FixedComposer> = Mutex::new(FixedComposer::new());
+    static ref HISTORY: Mutex<EmissionHistory> = Mutex::new(EmissionHistory::default());
+    static ref SETTINGS: SettingsService = SettingsService::new(KeyboardSettings::load());
+    // Phonetic layouts with the user's mappings, rebuilt when they change;
+    // the first is used when the selected one is unknown
+    static ref LAYOUTS: RwLock<Arc<Vec<Layout>>> =
+        RwLock::new(Arc::new(mappings::layouts(&mappings::load())));
+    static ref FIXED_LAYOUTS: Vec<FixedLayout> =
+        vec![FixedLayout::probhat(), FixedLayout::jatiya(), FixedLayout::bijoy()];
+    // Bundled whole-word spellings with the user's on top, reloaded when
+    // those change; empty when the files are missing
+    static ref DICTIONARY: RwLock<Arc<Dictionary>> = RwLock::new(Arc::new(load_dictionary()));
 }
 
+const DICTIONARY_FILE: &str = "assets/dictionary/words.tsv";
+
 struct KeyboardApp {
+    exiting: bool,
     show_settings: bool,
-    suggestions: Vec<String>,
+    settings_tab: String,
+    blocklist_path: String,
+    dictionary_path: String,
+    dictionary_status: Option<String>,
+    spelling_status: Option<String>,
+    blocklist_status: Option<String>,
+    docs_status: Option<String>,
+    // A behavior snapshot file, and what differs from it
+    snapshot_path: String,
+    snapshot_status: Option<String>,
+    snapshot_changes: Vec<snapshot::Change>,
+    // Edited here and only used once saved
+    mappings: Vec<mappings::Mapping>,
+    mappings_status: Option<String>,
+    // The layout file shared with the mappings, by name, empty for none
+    share_layout: String,
+    share_code: Option<String>,
+    share_qr: bool,
+    // A share code pasted to import
+    share_import: String,
+    share_status: Option<String>,
+    import_status: Option<String>,
+    // A Bijoy abbreviation list to import as autocorrections
+    abbreviations_path: String,
+    abbreviations_status: Option<String>,
+    privacy_status: Option<String>,
+    // The backups listed while the Restore window is open
+    backups: Option<Vec<backup::Backup>>,
+    backup_status: Option<String>,
+    sync_status: Option<String>,
+    // Removing everything the keyboard left, and what could not be removed
+    show_cleanup: bool,
+    keep_dictionary: bool,
+    cleanup_failures: Vec<String>,
+    show_diagnostics: bool,
+    show_statistics: bool,
+    heatmap: heatmap::Heatmap,
+    show_welcome: bool,
+    welcome_demo: welcome::TypingDemo,
+    converter: converter::Converter,
+    show_transcript: bool,
+    transcript_status: Option<String>,
+    transcript_view: transcript::View,
+    // Phonetic layouts shown side by side, and a word to try in both
+    show_compare: bool,
+    compare_left: String,
+    compare_right: String,
+    compare_only_differences: bool,
+    compare_word: String,
+    footprint: diagnostics::FootprintSampler,
+    // Keys the hook had seen when the hook check started, and the test text
+    hook_check: Option<u64>,
+    hook_check_text: String,
+    // A self-test running, and the last one's results
+    self_test: Option<Receiver<Vec<self_test::Check>>>,
+    self_test_results: Vec<self_test::Check>,
+    suggestions: Vec<(String, String)>,
+    selected_suggestion: Option<usize>,
+    copied_suggestion: Option<String>,
     search_text: String,
     selected_category: String,
+    fonts_loading: Option<Receiver<fonts::FontChain>>,
+    quick_insert: quick_insert::QuickInsert,
+    on_screen_keyboard: on_screen_keyboard::OnScreenKeyboard,
 }
 
 impl Default for KeyboardApp {
     fn default() -> Self {
         Self {
+            exiting: false,
             show_settings: false,
+            settings_tab: "General".to_string(),
+            blocklist_path: String::new(),
+            dictionary_path: String::new(),
+            dictionary_status: None,
+            spelling_status: None,
+            blocklist_status: None,
+            docs_status: None,
+            snapshot_path: String::new(),
+            snapshot_status: None,
+            snapshot_changes: Vec::new(),
+            mappings: mappings::load(),
+            mappings_status: None,
+            share_layout: String::new(),
+            share_code: None,
+            share_qr: false,
+            share_import: String::new(),
+            share_status: None,
+            import_status: None,
+            abbreviations_path: String::new(),
+            abbreviations_status: None,
+            privacy_status: None,
+            backups: None,
+            backup_status: None,
+            sync_status: None,
+            show_cleanup: false,
+            keep_dictionary: true,
+            cleanup_failures: Vec::new(),
+            show_diagnostics: false,
+            show_statistics: false,
+            heatmap: heatmap::Heatmap::default(),
+            show_welcome: SETTINGS.snapshot().show_welcome,
+            welcome_demo: welcome::TypingDemo::default(),
+            converter: converter::Converter::load(),
+            show_transcript: false,
+            transcript_status: None,
+            transcript_view: transcript::View::default(),
+            show_compare: false,
+            compare_left: "Phonetic".to_string(),
+            compare_right: "Avro".to_string(),
+            compare_only_differences: true,
+            compare_word: String::new(),
+            footprint: diagnostics::FootprintSampler::default(),
+            hook_check: None,
+            hook_check_text: String::new(),
+            self_test: None,
+            self_test_results: Vec::new(),
             suggestions: Vec::new(),
+            selected_suggestion: None,
+            copied_suggestion: None,
             search_text: String::new(),
             selected_category: "All".to_string(),
+            fonts_loading: None,
+            quick_insert: quick_insert::QuickInsert::default(),
+            on_screen_keyboard: on_screen_keyboard::OnScreenKeyboard::default(),
         }
     }
 }
@@ -179,45 +250,153 @@ impl Default for KeyboardApp {
 impl KeyboardApp {
     fn update_suggestions(&mut self) {
         self.suggestions.clear();
+        self.selected_suggestion = None;
+        self.copied_suggestion = None;
         if self.search_text.is_empty() {
             return;
         }
 
-        for (eng, bang) in CONVERSION_MAP.iter() {
-            if eng.contains(&self.search_text.to_lowercase()) {
-                self.suggestions.push(format!("{} → {}", eng, bang));
+        let settings = SETTINGS.snapshot();
+        let blocked = &settings.blocked_words;
+        let layouts = layouts();
+        // The user's own spellings of the whole word come first, then the
+        // dictionary's
+        let roman = self.search_text.to_lowercase();
+        let words = learned_words::spellings(&roman)
+            .into_iter()
+            .chain(dictionary().get(&roman))
+            .map(|bangla| (roman.clone(), bangla));
+        let mut seen = HashSet::new();
+        self.suggestions = words
+            .chain(
+                engine(&layouts, &settings)
+                    .candidates(&self.search_text)
+                    .into_iter()
+                    .map(|c| (c.roman, c.bangla)),
+            )
+            .filter(|(roman, bangla)| {
+                !blocklist::is_blocked(blocked, roman) && !blocklist::is_blocked(blocked, bangla)
+            })
+            .filter(|(_, bangla)| seen.insert(bangla.clone()))
+            .collect();
+        if !self.suggestions.is_empty() {
+            self.selected_suggestion = Some(0);
+        }
+    }
+
+    // Arrow keys move the selection, Enter copies the selected Bangla text
+    fn handle_suggestion_keys(&mut self, ctx: &egui::Context) -> bool {
+        if self.suggestions.is_empty() {
+            return false;
+        }
+
+        let (down, up, enter) = ctx.input(|i| {
+            (
+                i.key_pressed(Key::ArrowDown),
+                i.key_pressed(Key::ArrowUp),
+                i.key_pressed(Key::Enter),
+            )
+        });
+        let last = self.suggestions.len() - 1;
+
+        if down {
+            self.selected_suggestion =
+                Some(self.selected_suggestion.map_or(0, |i| (i + 1).min(last)));
+        }
+        if up {
+            self.selected_suggestion =
+                Some(self.selected_suggestion.map_or(0, |i| i.saturating_sub(1)));
+        }
+        if enter {
+            if let Some(index) = self.selected_suggestion {
+                self.copy_suggestion(ctx, index);
             }
         }
+
+        down || up
+    }
+
+    fn copy_suggestion(&mut self, ctx: &egui::Context, index: usize) {
+        if let Some((_, bang)) = self.suggestions.get(index) {
+            ctx.output_mut(|o| o.copied_text = bang.clone());
+            self.copied_suggestion = Some(bang.clone());
+        }
     }
 
-    fn matches_category(&self, key: &str) -> bool {
+    fn matches_category(&self, layout: &Layout, key: &str) -> bool {
+        let Some(bangla) = layout.get(key) else {
+            return self.selected_category == "All";
+        };
         match self.selected_category.as_str() {
             "All" => true,
-            "Vowels" => PHONETIC_MAP
-                .get(key)
-                .map_or(false, |c| matches!(c, BanglaChar::Vowel(_))),
-            "Consonants" => PHONETIC_MAP
-                .get(key)
-                .map_or(false, |c| matches!(c, BanglaChar::Consonant(_))),
-            "Numbers" => PHONETIC_MAP
-                .get(key)
-                .map_or(false, |c| matches!(c, BanglaChar::Number(_))),
-            "Special" => PHONETIC_MAP
-                .get(key)
-                .map_or(false, |c| matches!(c, BanglaChar::Special(_))),
+            "Vowels" => matches!(bangla, BanglaChar::Vowel(_)),
+            "Consonants" => matches!(bangla, BanglaChar::Consonant(_)),
+            "Numbers" => matches!(bangla, BanglaChar::Number(_)),
+            "Special" => matches!(bangla, BanglaChar::Special(_)),
             _ => false,
         }
     }
 
     fn get_font_size(&self) -> f32 {
-        SETTINGS.lock().unwrap().font_size
+        SETTINGS.snapshot().font_size
     }
 }
 
 impl App for KeyboardApp {
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
-        if ctx.input(|i| i.viewport().close_requested()) {
-            ctx.send_viewport_cmd(ViewportCommand::Close);
+        // With a tray icon, closing the window only hides it; File → Exit quits
+        if ctx.input(|i| i.viewport().close_requested()) && tray::is_running() && !self.exiting {
+            ctx.send_viewport_cmd(ViewportCommand::CancelClose);
+            ctx.send_viewport_cmd(ViewportCommand::Visible(false));
+        }
+        if tray::settings_requested() {
+            self.show_settings = true;
+        }
+        if tray::statistics_requested() {
+            self.show_statistics = true;
+        }
+
+        // Install the fonts once the background load has finished
+        if let Some(receiver) = &self.fonts_loading {
+            match receiver.try_recv() {
+                Ok(chain) => {
+                    if chain.is_empty() {
+                        eprintln!("No Bengali font found, Bangla text may not render");
+                    }
+                    ctx.set_fonts(fonts::font_definitions(&chain));
+                    self.fonts_loading = None;
+                }
+                Err(TryRecvError::Empty) => {}
+                Err(TryRecvError::Disconnected) => self.fonts_loading = None,
+            }
+        }
+
+        self.quick_insert.show(ctx, self.get_font_size());
+        self.on_screen_keyboard.show(ctx, self.get_font_size());
+        candidate_popup::show(ctx, self.get_font_size());
+        unmatched::show(ctx, self.get_font_size());
+        spell_check::show(
+            ctx,
+            self.get_font_size(),
+            &SETTINGS.snapshot().spelling_hotkey.label(),
+        );
+        preedit::show(ctx, self.get_font_size());
+
+        // Typing into our own window (search, settings) must not be converted
+        let focused = ctx.input(|i| i.viewport().focused.unwrap_or(false));
+        if focused
+            != SETTINGS
+                .snapshot()
+                .state
+                .is_suspended(SuspendReason::AppFocused)
+        {
+            SETTINGS.update(|settings| {
+                settings.state.apply(if focused {
+                    Event::Suspend(SuspendReason::AppFocused)
+                } else {
+                    Event::Resume(SuspendReason::AppFocused)
+                })
+            });
         }
         egui::TopBottomPanel::top("top_panel").show(ctx, |ui| {
             egui::menu::bar(ui, |ui| {
@@ -225,21 +404,88 @@ impl App for KeyboardApp {
                     if ui.button("Settings").clicked() {
                         self.show_settings = true;
                     }
+                    if ui.button("Converter").clicked() {
+                        self.converter.open = true;
+                    }
+                    if ui.button("Transcript").clicked() {
+                        self.show_transcript = true;
+                    }
+                    if ui.button("Statistics").clicked() {
+                        self.show_statistics = true;
+                    }
+                    if ui.button("On-screen keyboard").clicked() {
+                        self.on_screen_keyboard.open = true;
+                    }
                     if ui.button("Exit").clicked() {
+                        self.exiting = true;
                         ctx.send_viewport_cmd(ViewportCommand::Close);
                     }
                 });
                 ui.menu_button("Help", |ui| {
+                    if ui.button("Welcome").clicked() {
+                        self.show_welcome = true;
+                        self.welcome_demo.restart();
+                    }
+                    if ui.button("Compare layouts").clicked() {
+                        self.show_compare = true;
+                    }
+                    if ui.button("Diagnostics").clicked() {
+                        self.show_diagnostics = true;
+                    }
                     if ui.button("About").clicked() {
                         // Show about dialog
                     }
                 });
 
+                let recording = HISTORY.lock().unwrap().transcript().is_some();
+                if recording
+                    && ui
+                        .link(RichText::new("● Recording a transcript").size(12.0))
+                        .clicked()
+                {
+                    self.show_transcript = true;
+                }
+
+                if let Some(problem) = problems::unseen() {
+                    if ui
+                        .link(RichText::new(format!("⚠ {}", problem.summary())).size(12.0))
+                        .clicked()
+                    {
+                        self.show_diagnostics = true;
+                    }
+                }
+
+                if hook_health::is_suspected()
+                    && ui
+                        .link(RichText::new("⚠ Keys aren't reaching the keyboard").size(12.0))
+                        .clicked()
+                {
+                    self.show_diagnostics = true;
+                }
+
+                let reinstalls = hook_health::reinstalls();
+                if reinstalls > 0
+                    && ui
+                        .link(RichText::new("↻ Keyboard hook restarted").weak().size(12.0))
+                        .on_hover_text(format!(
+                            "Windows stopped sending keys {} time(s); the hook was installed again",
+                            reinstalls
+                        ))
+                        .clicked()
+                {
+                    self.show_diagnostics = true;
+                }
+
+                if self.fonts_loading.is_some() {
+                    ui.spinner();
+                    ui.label(RichText::new("Loading fonts…").weak().size(12.0));
+                }
+
                 // Keyboard status and language indicators
                 ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
-                    let settings = SETTINGS.lock().unwrap();
-                    let enabled = settings.enabled;
-                    let is_bangla = settings.current_language == "Bangla";
+                    let settings = SETTINGS.snapshot();
+                    let enabled = settings.state.enabled;
+                    let is_bangla = settings.state.is_bangla();
 
                     ui.horizontal(|ui| {
                         // Modern language indicator
@@ -253,8 +499,33 @@ impl App for KeyboardApp {
                                 }),
                         );
 
+                        // The window the language belongs to
+                        if settings.language_per_window {
+                            if let Some(app) = window_language::active_app() {
+                                ui.label(RichText::new(format!("in {}", app)).weak().size(12.0))
+                                    .on_hover_text(format!(
+                                        "Each window keeps its own language; {} remembered this session",
+                                        window_language::remembered()
+                                    ));
+                            }
+                        }
+
                         // Keyboard shortcut hint
-                        ui.label(RichText::new("(Ctrl+Space)").weak().size(12.0));
+                        if settings.hotkey_enabled {
+                            ui.label(
+                                RichText::new(format!("({})", settings.toggle_hotkey.label()))
+                                    .weak()
+                                    .size(12.0),
+                            );
+                        }
+
+                        if settings.monitors_clipboard() {
+                            ui.label(
+                                RichText::new("Clipboard → বাংলা")
+                                    .size(12.0)
+                                    .color(egui::Color32::from_rgb(0, 100, 150)),
+                            );
+                        }
                     });
 
                     ui.add_space(10.0);
@@ -262,77 +533,1574 @@ impl App for KeyboardApp {
             });
         });
 
-        // Settings window
-        if self.show_settings {
-            egui::Window::new("Settings")
-                .open(&mut self.show_settings)
+        // Settings window
+        if self.show_settings {
+            egui::Window::new("Settings")
+                .open(&mut self.show_settings)
+                .show(ctx, |ui| {
+                    let current = SETTINGS.snapshot();
+                    let mut settings = (*current).clone();
+                    ui.horizontal(|ui| {
+                        for tab in ["General", "Mappings", "Privacy", "Advanced"] {
+                            ui.selectable_value(&mut self.settings_tab, tab.to_string(), tab);
+                        }
+                    });
+                    ui.separator();
+
+                    if self.settings_tab == "Advanced" {
+                        // Experimental features, off until turned on here
+                        ui.label(RichText::new("Experimental features").strong());
+                        for feature in Feature::ALL {
+                            let mut enabled = settings.feature_enabled(feature);
+                            if ui.checkbox(&mut enabled, feature.label()).changed() {
+                                settings.set_feature(feature, enabled);
+                            }
+                            ui.label(RichText::new(feature.description()).weak().size(12.0));
+                            ui.add_space(6.0);
+                        }
+
+                        // Reference of every rule, regenerated from the loaded layout
+                        ui.add_space(10.0);
+                        ui.label(RichText::new("Layout reference").strong());
+                        if ui.button("Generate docs").clicked() {
+                            self.docs_status = Some(match docs::generate(&engine(&layouts(), &settings)) {
+                                Ok(dir) => format!("Written to {}", dir.display()),
+                                Err(err) => format!("Could not write the docs: {}", err),
+                            });
+                        }
+                        if let Some(status) = &self.docs_status {
+                            ui.label(RichText::new(status).weak().size(12.0));
+                        }
+
+                        // Everything that decides typing, to check an upgrade changed nothing
+                        ui.add_space(10.0);
+                        ui.label(RichText::new("Behavior snapshot").strong());
+                        ui.horizontal(|ui| {
+                            ui.label("File:");
+                            ui.text_edit_singleline(&mut self.snapshot_path);
+                        });
+                        ui.horizontal(|ui| {
+                            let path = PathBuf::from(self.snapshot_path.trim());
+                            let chosen = !self.snapshot_path.trim().is_empty();
+                            if ui.add_enabled(chosen, egui::Button::new("Export")).clicked() {
+                                self.snapshot_changes.clear();
+                                self.snapshot_status = Some(match snapshot::export(&path, &settings) {
+                                    Ok(()) => format!("Saved {}", path.display()),
+                                    Err(err) => format!("Could not save the snapshot: {}", err),
+                                });
+                            }
+                            if ui
+                                .add_enabled(chosen, egui::Button::new("Compare with current"))
+                                .clicked()
+                            {
+                                match snapshot::compare_with_current(&path, &settings) {
+                                    Ok(changes) => {
+                                        self.snapshot_status = Some(if changes.is_empty() {
+                                            "Typing behaves exactly as in the snapshot".to_string()
+                                        } else {
+                                            format!("{} change(s) since the snapshot", changes.len())
+                                        });
+                                        self.snapshot_changes = changes;
+                                    }
+                                    Err(err) => {
+                                        self.snapshot_changes.clear();
+                                        self.snapshot_status =
+                                            Some(format!("Could not read the snapshot: {}", err));
+                                    }
+                                }
+                            }
+                        });
+                        if let Some(status) = &self.snapshot_status {
+                            ui.label(RichText::new(status).weak().size(12.0));
+                        }
+                        if !self.snapshot_changes.is_empty() {
+                            egui::ScrollArea::vertical()
+                                .id_source("snapshot_changes")
+                                .max_height(160.0)
+                                .show(ui, |ui| {
+                                    for change in &self.snapshot_changes {
+                                        ui.label(RichText::new(change.to_string()).monospace());
+                                    }
+                                });
+                        }
+                    } else if self.settings_tab == "Privacy" {
+                        ui.label(RichText::new("What Restro Keyboard keeps").strong());
+                        ui.label(
+                            RichText::new(
+                                "Typed text is never sent anywhere, and only kept in a transcript you start. With \"Learn the words I type\" on, finished words and their spellings are kept in learned.json. With typing statistics on, the number of words typed each day, the time spent, how often each key and letter was typed and, with word timing on, each layout's time per word are kept in statistics.json, never the words. Text in the converter is kept in converter.json until cleared. With daily backups on, copies of the settings, dictionary, learned words and mappings are kept in the backups folder. Everything listed here stays on this computer, except that with a sync folder chosen, your dictionary words and learned spellings with how often each was typed are written to that folder.",
+                            )
+                            .weak()
+                            .size(12.0),
+                        );
+                        ui.add_space(6.0);
+
+                        egui::Grid::new("privacy")
+                            .striped(true)
+                            .spacing([12.0, 6.0])
+                            .show(ui, |ui| {
+                                ui.label("What");
+                                ui.label("Where");
+                                ui.label("Amount");
+                                ui.end_row();
+
+                                // Used to choose between a period and a dari
+                                let recent_text = HISTORY.lock().unwrap().text().chars().count();
+                                ui.label("Recent text near the caret");
+                                ui.label("Memory only");
+                                ui.label(format!("{} characters", recent_text));
+                                if ui.button("Clear").clicked() {
+                                    HISTORY.lock().unwrap().clear();
+                                    self.privacy_status = Some("Recent text cleared".to_string());
+                                }
+                                ui.end_row();
+
+                                ui.label("Recently inserted characters");
+                                ui.label("settings.json");
+                                ui.label(settings.recent_characters.len().to_string());
+                                if ui.button("Clear").clicked() {
+                                    settings.recent_characters.clear();
+                                    self.privacy_status =
+                                        Some("Recently inserted characters cleared".to_string());
+                                }
+                                ui.end_row();
+
+                                ui.label("Blocked words");
+                                ui.label("settings.json");
+                                ui.label(blocklist::count(&settings.blocked_words).to_string());
+                                if ui.button("Clear").clicked() {
+                                    settings.blocked_words.clear();
+                                    self.privacy_status = Some("Blocked words cleared".to_string());
+                                }
+                                ui.end_row();
+
+                                ui.label("Custom mappings");
+                                ui.label("mappings.json");
+                                ui.label(self.mappings.len().to_string());
+                                if ui.button("Delete").clicked() {
+                                    self.mappings.clear();
+                                    self.privacy_status = Some(match mappings::save(&[]) {
+                                        Ok(()) => {
+                                            reload_layouts();
+                                            "Custom mappings deleted".to_string()
+                                        }
+                                        Err(err) => {
+                                            format!("Could not delete the mappings: {}", err)
+                                        }
+                                    });
+                                }
+                                ui.end_row();
+
+                                ui.label("Words learned from typing");
+                                ui.label("learned.json");
+                                ui.label(format!("{} spellings", learned_words::count()));
+                                if ui.button("Delete").clicked() {
+                                    self.privacy_status = Some(match learned_words::delete() {
+                                        Ok(()) => {
+                                            reload_dictionary();
+                                            "Learned words deleted".to_string()
+                                        }
+                                        Err(err) => {
+                                            format!("Could not delete the learned words: {}", err)
+                                        }
+                                    });
+                                }
+                                ui.end_row();
+
+                                ui.label("Typing statistics");
+                                ui.label("statistics.json");
+                                ui.label(format!("{} days", statistics::day_count()));
+                                if ui.button("Delete").clicked() {
+                                    self.privacy_status = Some(match statistics::delete() {
+                                        Ok(()) => "Typing statistics deleted".to_string(),
+                                        Err(err) => {
+                                            format!("Could not delete the statistics: {}", err)
+                                        }
+                                    });
+                                }
+                                ui.end_row();
+
+                                ui.label("Text left in the converter");
+                                ui.label("converter.json");
+                                ui.label(format!(
+                                    "{} characters",
+                                    self.converter.saved_chars()
+                                ));
+                                if ui.button("Delete").clicked() {
+                                    self.privacy_status = Some(match self.converter.clear() {
+                                        Ok(()) => "Converter text deleted".to_string(),
+                                        Err(err) => {
+                                            format!("Could not delete the converter text: {}", err)
+                                        }
+                                    });
+                                }
+                                ui.end_row();
+
+                                ui.label("Your dictionary");
+                                ui.label("dictionary.tsv");
+                                ui.label(format!("{} words", user_dictionary::count()));
+                                if ui.button("Delete").clicked() {
+                                    self.privacy_status = Some(match user_dictionary::delete() {
+                                        Ok(()) => {
+                                            reload_dictionary();
+                                            "Your dictionary deleted".to_string()
+                                        }
+                                        Err(err) => {
+                                            format!("Could not delete the dictionary: {}", err)
+                                        }
+                                    });
+                                }
+                                ui.end_row();
+
+                                self.transcript_view.refresh(&HISTORY.lock().unwrap());
+                                let recording = self
+                                    .transcript_view
+                                    .transcript
+                                    .as_ref()
+                                    .map(transcript::Transcript::char_count);
+                                if let Some(count) = recording {
+                                    ui.label("Transcript being recorded");
+                                    ui.label("Memory only");
+                                    ui.label(format!("{} characters", count));
+                                    if ui.button("Discard").clicked() {
+                                        HISTORY.lock().unwrap().stop_transcript();
+                                        self.privacy_status =
+                                            Some("Transcript discarded".to_string());
+                                    }
+                                    ui.end_row();
+                                }
+
+                                ui.label("Saved transcripts");
+                                ui.label("transcripts folder");
+                                ui.label(format!("{} files", transcript::file_count()));
+                                if ui.button("Delete").clicked() {
+                                    self.privacy_status = Some(match transcript::delete() {
+                                        Ok(()) => "Transcripts deleted".to_string(),
+                                        Err(err) => {
+                                            format!("Could not delete the transcripts: {}", err)
+                                        }
+                                    });
+                                }
+                                ui.end_row();
+
+                                ui.label("Backups");
+                                ui.label("backups folder");
+                                ui.label(format!("{} backups", backup::count()));
+                                if ui.button("Delete").clicked() {
+                                    self.privacy_status = Some(match backup::delete() {
+                                        Ok(()) => "Backups deleted".to_string(),
+                                        Err(err) => {
+                                            format!("Could not delete the backups: {}", err)
+                                        }
+                                    });
+                                }
+                                ui.end_row();
+
+                                ui.label("Layout reference");
+                                ui.label("docs folder");
+                                ui.label(format!("{} files", docs::file_count()));
+                                if ui.button("Delete").clicked() {
+                                    self.privacy_status = Some(match docs::delete() {
+                                        Ok(()) => "Layout reference deleted".to_string(),
+                                        Err(err) => format!("Could not delete the docs: {}", err),
+                                    });
+                                }
+                                ui.end_row();
+                            });
+                        if let Some(dir) = KeyboardSettings::path()
+                            .as_deref()
+                            .and_then(std::path::Path::parent)
+                        {
+                            ui.label(
+                                RichText::new(format!("Files are in {}", dir.display()))
+                                    .weak()
+                                    .size(12.0),
+                            );
+                        }
+
+                        ui.add_space(10.0);
+                        ui.label(RichText::new("Retention").strong());
+                        if ui
+                            .checkbox(
+                                &mut settings.remember_recent_characters,
+                                "Remember characters inserted from the quick-insert panel",
+                            )
+                            .changed()
+                            && !settings.remember_recent_characters
+                        {
+                            settings.recent_characters.clear();
+                        }
+                        ui.checkbox(
+                            &mut settings.mask_buffer,
+                            "Mask typed text as ••• in previews, suggestions and the transcript",
+                        )
+                        .on_hover_text("For screen sharing and recording; suggestions still work by number");
+
+                        ui.add_space(10.0);
+                        ui.label(RichText::new("Backups").strong());
+                        ui.checkbox(
+                            &mut settings.automatic_backups,
+                            "Back up settings, dictionaries and mappings every day",
+                        );
+                        ui.add(
+                            egui::Slider::new(&mut settings.backups_kept, 1..=50)
+                                .text("backups kept"),
+                        );
+                        ui.horizontal(|ui| {
+                            if ui.button("Back up now").clicked() {
+                                self.backup_status = Some(match backup::create(settings.backups_kept) {
+                                    Ok(path) => format!("Saved {}", path.display()),
+                                    Err(err) => format!("Could not back up: {}", err),
+                                });
+                            }
+                            if ui.button("Restore…").clicked() {
+                                self.backups = Some(backup::list());
+                            }
+                        });
+                        if let Some(status) = &self.backup_status {
+                            ui.label(RichText::new(status).weak().size(12.0));
+                        }
+
+                        ui.add_space(10.0);
+                        if ui.button("Remove Restro Keyboard from this computer…").clicked() {
+                            self.show_cleanup = true;
+                            self.cleanup_failures.clear();
+                        }
+
+                        if let Some(status) = &self.privacy_status {
+                            ui.add_space(6.0);
+                            ui.label(RichText::new(status).weak().size(12.0));
+                        }
+                    } else if self.settings_tab == "Mappings" {
+                        ui.label(RichText::new("Custom mappings").strong());
+                        ui.label(
+                            RichText::new(
+                                "Added to the phonetic layouts. A mapping replaces the built-in rule for the same English text.",
+                            )
+                            .weak()
+                            .size(12.0),
+                        );
+                        ui.add_space(6.0);
+
+                        let mut removed = None;
+                        egui::ScrollArea::vertical().max_height(300.0).show(ui, |ui| {
+                            egui::Grid::new("custom_mappings")
+                                .striped(true)
+                                .show(ui, |ui| {
+                                    ui.label("English");
+                                    ui.label("Bangla");
+                                    ui.label("Category");
+                                    ui.end_row();
+                                    for (i, mapping) in self.mappings.iter_mut().enumerate() {
+                                        ui.add(
+                                            egui::TextEdit::singleline(&mut mapping.roman)
+                                                .desired_width(60.0),
+                                        );
+                                        ui.add(
+                                            egui::TextEdit::singleline(&mut mapping.bangla)
+                                                .desired_width(60.0),
+                                        );
+                                        egui::ComboBox::from_id_source(("mapping_category", i))
+                                            .selected_text(&mapping.category)
+                                            .show_ui(ui, |ui| {
+                                                for category in mappings::CATEGORIES {
+                                                    ui.selectable_value(
+                                                        &mut mapping.category,
+                                                        category.to_string(),
+                                                        category,
+                                                    );
+                                                }
+                                            });
+                                        if ui.button("Delete").clicked() {
+                                            removed = Some(i);
+                                        }
+                                        ui.end_row();
+                                    }
+                                });
+                        });
+                        if let Some(i) = removed {
+                            self.mappings.remove(i);
+                        }
+
+                        ui.horizontal(|ui| {
+                            if ui.button("Add").clicked() {
+                                self.mappings.push(mappings::Mapping::default());
+                            }
+                            if ui.button("Save and apply").clicked() {
+                                let problem = self.mappings.iter().find_map(mappings::Mapping::problem);
+                                self.mappings_status = Some(match problem {
+                                    Some(problem) => problem,
+                                    None => match mappings::save(&self.mappings) {
+                                        Ok(()) => {
+                                            reload_layouts();
+                                            "Saved and in use".to_string()
+                                        }
+                                        Err(err) => format!("Could not save the mappings: {}", err),
+                                    },
+                                });
+                            }
+                        });
+                        if let Some(status) = &self.mappings_status {
+                            ui.label(RichText::new(status).weak().size(12.0));
+                        }
+
+                        // Layout tweaks passed around as text
+                        ui.add_space(10.0);
+                        ui.label(RichText::new("Share").strong());
+                        ui.label(
+                            RichText::new(
+                                "A code with a layout file and the mappings above, to paste into a chat. Pasting a code below adds what it has.",
+                            )
+                            .weak()
+                            .size(12.0),
+                        );
+                        let sources = layout_files::sources();
+                        ui.horizontal(|ui| {
+                            let none = "Only my mappings";
+                            let selected = match self.share_layout.as_str() {
+                                "" => none,
+                                name => name,
+                            };
+                            egui::ComboBox::from_id_source("share_layout")
+                                .selected_text(selected)
+                                .show_ui(ui, |ui| {
+                                    ui.selectable_value(&mut self.share_layout, String::new(), none);
+                                    for (name, _) in &sources {
+                                        ui.selectable_value(&mut self.share_layout, name.clone(), name);
+                                    }
+                                });
+                            if ui.button("Make code").clicked() {
+                                let layout = sources
+                                    .iter()
+                                    .find(|(name, _)| *name == self.share_layout)
+                                    .map(|(_, json)| json.as_str());
+                                let valid: Vec<_> = self
+                                    .mappings
+                                    .iter()
+                                    .filter(|mapping| mapping.problem().is_none())
+                                    .cloned()
+                                    .collect();
+                                self.share_code = Some(layout_share::encode(layout, &valid));
+                            }
+                        });
+                        if let Some(code) = &self.share_code {
+                            ui.horizontal(|ui| {
+                                ui.add(egui::TextEdit::singleline(&mut code.as_str()).desired_width(300.0));
+                                if ui.button("Copy").clicked() {
+                                    ui.output_mut(|o| o.copied_text = code.clone());
+                                }
+                                ui.checkbox(&mut self.share_qr, "QR code");
+                            });
+                            if self.share_qr {
+                                layout_share::show_qr(ui, code);
+                            }
+                        }
+                        ui.horizontal(|ui| {
+                            ui.add(
+                                egui::TextEdit::singleline(&mut self.share_import)
+                                    .hint_text("Paste a layout code")
+                                    .desired_width(300.0),
+                            );
+                            let pasted = !self.share_import.trim().is_empty();
+                            if ui.add_enabled(pasted, egui::Button::new("Import")).clicked() {
+                                let mut imported = self.mappings.clone();
+                                self.share_status = Some(match layout_share::import(&self.share_import, &mut imported) {
+                                    Ok(added) => match mappings::save(&imported) {
+                                        Ok(()) => {
+                                            self.mappings = imported;
+                                            self.share_import.clear();
+                                            reload_layouts();
+                                            added
+                                        }
+                                        Err(err) => format!("Could not save the mappings: {}", err),
+                                    },
+                                    Err(err) => err,
+                                });
+                            }
+                        });
+                        if let Some(status) = &self.share_status {
+                            ui.label(RichText::new(status).weak().size(12.0));
+                        }
+
+                        ui.add_space(10.0);
+                        ui.label(RichText::new("Coming from Avro Keyboard").strong());
+                        ui.label(
+                            RichText::new(
+                                "Adds the user words of an Avro Keyboard installation on this computer to your dictionary, and its autocorrect list and macros to your corrections.",
+                            )
+                            .weak()
+                            .size(12.0),
+                        );
+                        if ui.button("Import from Avro Keyboard").clicked() {
+                            self.import_status = Some(match avro_import::import(&mut settings.autocorrect_additions) {
+                                Ok(report) => {
+                                    reload_dictionary();
+                                    report.summary()
+                                }
+                                Err(err) => err,
+                            });
+                        }
+                        if let Some(status) = &self.import_status {
+                            ui.label(RichText::new(status).weak().size(12.0));
+                        }
+
+                        // Shortened Banglish put right as the word ends
+                        ui.add_space(10.0);
+                        ui.label(RichText::new("Autocorrect").strong());
+                        ui.checkbox(
+                            &mut settings.autocorrect,
+                            "Correct shortened and misspelled words (amr → আমার, kmn → কেমন)",
+                        );
+                        ui.label(
+                            RichText::new(format!(
+                                "Your corrections, a word and its Bangla per line ({} added, bundled ones in {}):",
+                                autocorrect::addition_count(&settings),
+                                autocorrect::BUNDLED_FILE
+                            ))
+                            .weak()
+                            .size(12.0),
+                        );
+                        ui.add_enabled(
+                            settings.autocorrect,
+                            egui::TextEdit::multiline(&mut settings.autocorrect_additions)
+                                .hint_text("vlo ভালো")
+                                .desired_rows(3),
+                        );
+                        ui.horizontal(|ui| {
+                            ui.label("Bijoy abbreviation list:");
+                            ui.text_edit_singleline(&mut self.abbreviations_path);
+                            let chosen = !self.abbreviations_path.trim().is_empty();
+                            if ui.add_enabled(chosen, egui::Button::new("Import")).clicked() {
+                                let path = self.abbreviations_path.trim();
+                                let read = std::fs::read(path).and_then(|bytes| bijoy::read_text(&bytes, "Detect"));
+                                self.abbreviations_status = Some(match read {
+                                    Ok(text) => {
+                                        let abbreviations = bijoy::parse_abbreviations(&text);
+                                        let added = autocorrect::add(&mut settings.autocorrect_additions, &abbreviations);
+                                        format!("Found {} abbreviations, {} added.", abbreviations.len(), added)
+                                    }
+                                    Err(err) => format!("Could not read {}: {}", path, err),
+                                });
+                            }
+                        })
+                        .response
+                        .on_hover_text("A text file with a short=expansion pair per line, as exported from Bijoy. Corrections you already have are kept.");
+                        if let Some(status) = &self.abbreviations_status {
+                            ui.label(RichText::new(status).weak().size(12.0));
+                        }
+
+                        // Spellings counted as words are finished, each removable
+                        ui.add_space(10.0);
+                        ui.label(RichText::new("Words you type").strong());
+                        ui.checkbox(
+                            &mut settings.learn_words,
+                            "Learn the words I type, so my spellings come first",
+                        )
+                        .on_hover_text("Suggestions list them first, and dictionary mode respells to them. Kept on this computer, and in the sync folder if you choose one.");
+                        let learned = learned_words::words();
+                        if learned.is_empty() {
+                            ui.label(RichText::new("No words learned yet").weak().size(12.0));
+                        } else {
+                            let mut removed = None;
+                            egui::ScrollArea::vertical()
+                                .id_source("learned_words")
+                                .max_height(200.0)
+                                .show(ui, |ui| {
+                                    egui::Grid::new("learned_words").striped(true).show(ui, |ui| {
+                                        ui.label("English");
+                                        ui.label("Bangla");
+                                        ui.label("Typed");
+                                        ui.end_row();
+                                        for word in &learned {
+                                            ui.label(&word.roman);
+                                            ui.label(&word.bangla);
+                                            ui.label(format!("{}×", word.count));
+                                            if ui.button("Remove").clicked() {
+                                                removed = Some(word.clone());
+                                            }
+                                            ui.end_row();
+                                        }
+                                    });
+                                });
+                            if let Some(word) = removed {
+                                match learned_words::remove(&word.roman, &word.bangla) {
+                                    Ok(()) => reload_dictionary(),
+                                    Err(err) => {
+                                        self.import_status =
+                                            Some(format!("Could not remove {}: {}", word.roman, err));
+                                    }
+                                }
+                            }
+                        }
+
+                        // The same words on every computer the user types on
+                        ui.add_space(10.0);
+                        ui.label(RichText::new("Sync between computers").strong());
+                        ui.label(
+                            RichText::new(
+                                "Choose a folder your computers share, such as one in Dropbox or OneDrive. Your dictionary and learned words are merged through it every few minutes; words typed on several computers add up.",
+                            )
+                            .weak()
+                            .size(12.0),
+                        );
+                        ui.horizontal(|ui| {
+                            ui.label("Folder:");
+                            ui.add(
+                                egui::TextEdit::singleline(&mut settings.sync_folder)
+                                    .hint_text("Leave empty to not sync")
+                                    .desired_width(260.0),
+                            );
+                            let chosen = !settings.sync_folder.trim().is_empty();
+                            if ui.add_enabled(chosen, egui::Button::new("Sync now")).clicked() {
+                                self.sync_status = Some(match dictionary_sync::sync(&settings.sync_folder) {
+                                    Ok(report) => report.summary(),
+                                    Err(err) => format!("Could not sync: {}", err),
+                                });
+                            }
+                        });
+                        if let Some(status) = &self.sync_status {
+                            ui.label(RichText::new(status).weak().size(12.0));
+                        }
+                    } else {
+                        ui.vertical(|ui| {
+                            // Enable/Disable keyboard
+                            let mut enabled = settings.state.enabled;
+                            if ui.checkbox(&mut enabled, "Enable keyboard").changed() {
+                                settings.state.apply(if enabled {
+                                    Event::Enable
+                                } else {
+                                    Event::Disable
+                                });
+                            }
+
+                            ui.add_space(10.0);
+
+                            // Language selector
+                            ui.horizontal(|ui| {
+                                ui.label("Language:");
+                                for (language, label) in
+                                    [(Language::Bangla, "বাংলা"), (Language::English, "English")]
+                                {
+                                    if ui
+                                        .radio(settings.state.language == language, label)
+                                        .clicked()
+                                    {
+                                        settings.state.apply(Event::SelectLanguage(language));
+                                    }
+                                }
+                            });
+
+                            // Layout selector
+                            ui.horizontal_wrapped(|ui| {
+                                ui.label("Layout:");
+                                for name in layout_names() {
+                                    if ui.radio(settings.state.layout == name, &name).clicked() {
+                                        settings.state.apply(Event::SelectLayout(name));
+                                    }
+                                }
+                                if ui
+                                    .small_button("Reload")
+                                    .on_hover_text("Read the layout files in assets\\layouts again")
+                                    .clicked()
+                                {
+                                    reload_layouts();
+                                }
+                            });
+
+                            ui.add_space(10.0);
+
+                            // Startup language and layout
+                            ui.horizontal(|ui| {
+                                ui.label("Start in:");
+                                for choice in ["Bangla", "English", "Last used"] {
+                                    ui.radio_value(
+                                        &mut settings.startup_language,
+                                        choice.to_string(),
+                                        choice,
+                                    );
+                                }
+                            });
+                            ui.horizontal(|ui| {
+                                ui.label("Startup layout:");
+                                egui::ComboBox::from_id_source("startup_layout")
+                                    .selected_text(&settings.startup_layout)
+                                    .show_ui(ui, |ui| {
+                                        for choice in std::iter::once("Last used".to_string())
+                                            .chain(layout_names())
+                                        {
+                                            ui.selectable_value(
+                                                &mut settings.startup_layout,
+                                                choice.clone(),
+                                                choice,
+                                            );
+                                        }
+                                    });
+                            });
+
+                            ui.add_space(10.0);
+
+                            // Font size
+                            ui.horizontal(|ui| {
+                                ui.label("Font Size:");
+                                ui.add(
+                                    egui::Slider::new(&mut settings.font_size, 12.0..=24.0)
+                                        .step_by(1.0),
+                                );
+                            });
+
+                            ui.add_space(10.0);
+
+                            // Custom font, tried before the bundled and system fonts
+                            ui.horizontal(|ui| {
+                                ui.label("Custom font:");
+                                ui.text_edit_singleline(&mut settings.custom_font);
+                                if ui.button("Apply").clicked() {
+                                    let chain = fonts::load_font_chain(&settings.custom_font);
+                                    ctx.set_fonts(fonts::font_definitions(&chain));
+                                }
+                            });
+
+                            ui.add_space(10.0);
+
+                            // Theme
+                            ui.horizontal(|ui| {
+                                ui.label("Theme:");
+                                ui.radio_value(&mut settings.theme, "Light".to_string(), "Light");
+                                ui.radio_value(&mut settings.theme, "Dark".to_string(), "Dark");
+                            });
+
+                            ui.add_space(10.0);
+
+                            // Additional settings
+                            ui.checkbox(&mut settings.use_suggestions, "Show typing suggestions");
+                            ui.checkbox(
+                                &mut settings.pronunciation_hints,
+                                "Show how suggested words are said (shokal next to সকাল)",
+                            )
+                            .on_hover_text("From the dictionary, for words it has; helps tell apart words spelled alike");
+                            ui.checkbox(
+                                &mut settings.statistics,
+                                "Keep typing statistics (word counts only)",
+                            );
+                            ui.add_enabled(
+                                settings.statistics,
+                                egui::Checkbox::new(
+                                    &mut settings.layout_timing,
+                                    "Time each word, to compare layouts",
+                                ),
+                            );
+                            ui.horizontal(|ui| {
+                                ui.label("Typing summary notification:");
+                                egui::ComboBox::from_id_source("usage_summary")
+                                    .selected_text(&settings.usage_summary)
+                                    .show_ui(ui, |ui| {
+                                        for choice in statistics::SUMMARIES {
+                                            ui.selectable_value(
+                                                &mut settings.usage_summary,
+                                                choice.to_string(),
+                                                choice,
+                                            );
+                                        }
+                                    });
+                            });
+                            // Each hotkey is checked against the others
+                            ui.horizontal(|ui| {
+                                ui.checkbox(&mut settings.hotkey_enabled, "Switch language with");
+                                let others = [
+                                    ("quick insert", settings.quick_insert_hotkey),
+                                    ("converting the clipboard", settings.clipboard_hotkey),
+                                    ("converting the selection", settings.selection_hotkey),
+                                    ("correcting spelling", settings.spelling_hotkey),
+                                ];
+                                hotkey::recorder(
+                                    ui,
+                                    "toggle_hotkey",
+                                    &mut settings.toggle_hotkey,
+                                    &others,
+                                );
+                            });
+                            ui.checkbox(
+                                &mut settings.language_per_window,
+                                "Keep the language per window",
+                            )
+                            .on_hover_text("Switching to English in one window leaves the others as they were, until the keyboard is closed");
+                            ui.horizontal(|ui| {
+                                ui.label("Switch language with the mouse:");
+                                egui::ComboBox::from_id_source("mouse_toggle")
+                                    .selected_text(&settings.mouse_toggle)
+                                    .show_ui(ui, |ui| {
+                                        for choice in mouse_toggle::GESTURES {
+                                            ui.selectable_value(
+                                                &mut settings.mouse_toggle,
+                                                choice.to_string(),
+                                                choice,
+                                            );
+                                        }
+                                    });
+                            })
+                            .response
+                            .on_hover_text("For apps that keep every shortcut to themselves");
+                            ui.horizontal(|ui| {
+                                ui.label("Open quick insert with");
+                                let others = [
+                                    ("switching language", settings.toggle_hotkey),
+                                    ("converting the clipboard", settings.clipboard_hotkey),
+                                    ("converting the selection", settings.selection_hotkey),
+                                    ("correcting spelling", settings.spelling_hotkey),
+                                ];
+                                hotkey::recorder(
+                                    ui,
+                                    "quick_insert_hotkey",
+                                    &mut settings.quick_insert_hotkey,
+                                    &others,
+                                );
+                            });
+                            ui.horizontal(|ui| {
+                                ui.checkbox(
+                                    &mut settings.clipboard_hotkey_enabled,
+                                    "Convert the clipboard to Bangla with",
+                                );
+                                let others = [
+                                    ("switching language", settings.toggle_hotkey),
+                                    ("quick insert", settings.quick_insert_hotkey),
+                                    ("converting the selection", settings.selection_hotkey),
+                                    ("correcting spelling", settings.spelling_hotkey),
+                                ];
+                                hotkey::recorder(
+                                    ui,
+                                    "clipboard_hotkey",
+                                    &mut settings.clipboard_hotkey,
+                                    &others,
+                                );
+                            })
+                            .response
+                            .on_hover_text("Ctrl+Alt is AltGr on fixed layouts, so pick another combination if you use AltGr");
+                            ui.horizontal(|ui| {
+                                ui.checkbox(
+                                    &mut settings.selection_hotkey_enabled,
+                                    "Convert selected Banglish to Bangla with",
+                                );
+                                let others = [
+                                    ("switching language", settings.toggle_hotkey),
+                                    ("quick insert", settings.quick_insert_hotkey),
+                                    ("converting the clipboard", settings.clipboard_hotkey),
+                                    ("correcting spelling", settings.spelling_hotkey),
+                                ];
+                                hotkey::recorder(
+                                    ui,
+                                    "selection_hotkey",
+                                    &mut settings.selection_hotkey,
+                                    &others,
+                                );
+                            })
+                            .response
+                            .on_hover_text("Write a paragraph in Banglish in any app, select it and convert it at once. The clipboard is put back afterwards.");
+                            let spelling = spell_check::available();
+                            ui.horizontal(|ui| {
+                                ui.add_enabled(
+                                    spelling,
+                                    egui::Checkbox::new(
+                                        &mut settings.spell_check,
+                                        "Flag misspelled Bangla words, correct them with",
+                                    ),
+                                );
+                                let others = [
+                                    ("switching language", settings.toggle_hotkey),
+                                    ("quick insert", settings.quick_insert_hotkey),
+                                    ("converting the clipboard", settings.clipboard_hotkey),
+                                    ("converting the selection", settings.selection_hotkey),
+                                ];
+                                hotkey::recorder(
+                                    ui,
+                                    "spelling_hotkey",
+                                    &mut settings.spelling_hotkey,
+                                    &others,
+                                );
+                            })
+                            .response
+                            .on_hover_text(if spelling {
+                                "Words not in the installed dictionary or your words are flagged as they end. Each press puts the next correction in place, then the word as typed."
+                            } else {
+                                "Needs a full dictionary; install one below, under the dictionary spellings."
+                            });
+                            ui.horizontal(|ui| {
+                                let installed = windows_spelling::is_installed();
+                                ui.label(if installed {
+                                    "Other apps' Windows spell checking knows these words too."
+                                } else {
+                                    "Share these words with Windows spell checking:"
+                                });
+                                let clicked = match installed {
+                                    true => ui.button("Remove"),
+                                    false => ui.button("Add"),
+                                }
+                                .on_hover_text("As a Bangla (bn-BD) dictionary for Mail, Edge and other apps that check spelling through Windows; kept up to date as words change")
+                                .clicked();
+                                if clicked {
+                                    self.spelling_status = Some(match installed {
+                                        true => match windows_spelling::uninstall() {
+                                            Ok(()) => "Removed from Windows spelling".to_string(),
+                                            Err(err) => format!("Could not remove: {}", err),
+                                        },
+                                        false => match windows_spelling::install() {
+                                            Ok(count) => format!("{} words added to Windows spelling", count),
+                                            Err(err) => format!("Could not add: {}", err),
+                                        },
+                                    });
+                                }
+                            });
+                            if let Some(status) = &self.spelling_status {
+                                ui.label(RichText::new(status).weak().size(12.0));
+                            }
+                            ui.checkbox(
+                                &mut settings.dari_on_period,
+                                "Type । for a period after Bangla text",
+                            );
+                            ui.horizontal(|ui| {
+                                ui.label("Decimal point:");
+                                for (separator, label) in
+                                    [(".", "ASCII dot (৩.১৪)"), ("·", "Raised dot (৩·১৪)")]
+                                {
+                                    ui.radio_value(
+                                        &mut settings.decimal_separator,
+                                        separator.to_string(),
+                                        label,
+                                    );
+                                }
+                            });
+                            if settings.feature_enabled(Feature::ClipboardMonitor) {
+                                ui.checkbox(
+                                    &mut settings.clipboard_monitor,
+                                    "Convert copied Banglish or Bijoy text to Bangla (clipboard monitor)",
+                                );
+                            }
+                            ui.add_space(10.0);
+
+                            // Typography, applied when punctuation ends a word
+                            ui.label("Typography:");
+                            ui.checkbox(&mut settings.curly_quotes, "Curly quotes (“ ” ‘ ’)");
+                            ui.checkbox(&mut settings.smart_dashes, "-- for – and --- for —");
+                            ui.checkbox(
+                                &mut settings.nbsp_after_abbreviation,
+                                "Non-breaking space after abbreviations like ডঃ",
+                            );
+                            ui.checkbox(
+                                &mut settings.dari_spacing,
+                                "No space before । and only one after, in Bangla text",
+                            );
+                            ui.checkbox(
+                                &mut settings.capitalize_english,
+                                "Capitalize sentences and a lone i in English mode",
+                            );
+
+                            ui.add_space(10.0);
+                            // Only phonetic layouts have rules to double
+                            if fixed_layout(&settings.state.layout).is_none() {
+                                let layouts = layouts();
+                                let layout = find_layout(&layouts, &settings.state.layout);
+                                let mut gemination = settings.gemination(layout);
+                                if ui
+                                    .checkbox(
+                                        &mut gemination,
+                                        format!(
+                                            "Double consonants form conjuncts in {} (kk → ক্ক)",
+                                            layout.name()
+                                        ),
+                                    )
+                                    .changed()
+                                {
+                                    settings
+                                        .layout_gemination
+                                        .insert(layout.name().to_string(), gemination);
+                                }
+                            }
+                            ui.checkbox(
+                                &mut settings.dictionary_mode,
+                                "Dictionary spellings for whole words (ami → আমি)",
+                            );
+                            // Spell checking needs it too, so it shows either way
+                            full_dictionary::settings(
+                                ui,
+                                &mut self.dictionary_path,
+                                &mut self.dictionary_status,
+                            );
+                            ui.horizontal(|ui| {
+                                ui.checkbox(
+                                    &mut settings.long_press,
+                                    "Hold a key for its alternate character, after",
+                                );
+                                ui.add(
+                                    egui::DragValue::new(&mut settings.long_press_ms)
+                                        .clamp_range(250..=2000)
+                                        .suffix(" ms"),
+                                );
+                            })
+                            .response
+                            .on_hover_text("Holding t types ৎ, as on a phone");
+                            if settings.long_press {
+                                ui.label(format!(
+                                    "Alternates for {} (a key and its character per line, capitals with Shift):",
+                                    settings.state.layout
+                                ));
+                                let mut table = long_press::table(&settings).to_string();
+                                if ui
+                                    .add(egui::TextEdit::multiline(&mut table).desired_rows(3))
+                                    .changed()
+                                {
+                                    settings
+                                        .long_press_alternates
+                                        .insert(settings.state.layout.clone(), table);
+                                }
+                            }
+                            ui.horizontal(|ui| {
+                                ui.label("Keys not in the layout:");
+                                egui::ComboBox::from_id_source("unmatched_keys")
+                                    .selected_text(&settings.unmatched_keys)
+                                    .show_ui(ui, |ui| {
+                                        for choice in unmatched::POLICIES {
+                                            ui.selectable_value(
+                                                &mut settings.unmatched_keys,
+                                                choice.to_string(),
+                                                choice,
+                                            );
+                                        }
+                                    });
+                            })
+                            .response
+                            .on_hover_text("Applied when a word with such keys ends");
+                            ui.horizontal(|ui| {
+                                ui.label("Output encoding:");
+                                egui::ComboBox::from_id_source("output_encoding")
+                                    .selected_text(&settings.output_encoding)
+                                    .show_ui(ui, |ui| {
+                                        for choice in bijoy::ENCODINGS {
+                                            ui.selectable_value(
+                                                &mut settings.output_encoding,
+                                                choice.to_string(),
+                                                choice,
+                                            );
+                                        }
+                                    });
+                            })
+                            .response
+                            .on_hover_text("Bijoy text is for legacy fonts such as SutonnyMJ");
+                            if settings.output_encoding == "Bijoy (ANSI) in listed apps" {
+                                ui.label("Apps given Bijoy (one file name per line):");
+                                ui.add(
+                                    egui::TextEdit::multiline(&mut settings.bijoy_apps)
+                                        .hint_text("WINWORD.EXE")
+                                        .desired_rows(2),
+                                );
+                            }
+                            ui.add_space(10.0);
+
+                            // Language mode for RGB keyboards and scripts
+                            ui.label("Language state:");
+                            ui.horizontal(|ui| {
+                                ui.checkbox(&mut settings.state_api, "Serve as JSON on port");
+                                ui.add(egui::DragValue::new(&mut settings.state_api_port));
+                            });
+                            ui.horizontal(|ui| {
+                                ui.label("Run on Bangla:");
+                                ui.add(
+                                    egui::TextEdit::singleline(&mut settings.state_command_bangla)
+                                        .hint_text("OpenRGB.exe --profile Bangla"),
+                                );
+                            });
+                            ui.horizontal(|ui| {
+                                ui.label("Run on English:");
+                                ui.add(
+                                    egui::TextEdit::singleline(&mut settings.state_command_english)
+                                        .hint_text("OpenRGB.exe --profile English"),
+                                );
+                            });
+                            ui.add_space(10.0);
+
+                            // Battery use on laptops
+                            ui.label("Power:");
+                            ui.checkbox(
+                                &mut settings.efficiency_mode,
+                                "Efficiency mode for the window",
+                            );
+                            ui.checkbox(
+                                &mut settings.low_priority_ui,
+                                "Below-normal priority for the window",
+                            );
+                            ui.add_space(10.0);
+
+                            // Words that never appear as suggestions
+                            ui.label("Blocked words (one per line):");
+                            ui.add(
+                                egui::TextEdit::multiline(&mut settings.blocked_words)
+                                    .desired_rows(3),
+                            );
+                            ui.horizontal(|ui| {
+                                ui.label("Import list:");
+                                ui.text_edit_singleline(&mut self.blocklist_path);
+                                if ui.button("Import").clicked() {
+                                    self.blocklist_status = Some(
+                                        match blocklist::import(
+                                            &mut settings.blocked_words,
+                                            &self.blocklist_path,
+                                        ) {
+                                            Ok(added) => format!("{} words added", added),
+                                            Err(err) => format!("Could not import: {}", err),
+                                        },
+                                    );
+                                }
+                            });
+                            if let Some(status) = &self.blocklist_status {
+                                ui.label(RichText::new(status).weak().size(12.0));
+                            }
+                        });
+                    }
+
+                    if settings.efficiency_mode != current.efficiency_mode
+                        || settings.low_priority_ui != current.low_priority_ui
+                    {
+                        power::apply_to_ui_thread(&settings);
+                    }
+                    if settings != *current {
+                        SETTINGS.publish(settings);
+                    }
+                });
+        }
+
+        // Resource usage, so users can report regressions with numbers
+        if self.show_diagnostics {
+            let footprint = self.footprint.sample();
+            egui::Window::new("Diagnostics")
+                .open(&mut self.show_diagnostics)
+                .show(ctx, |ui| {
+                    egui::Grid::new("diagnostics").show(ui, |ui| {
+                        ui.label("Memory (working set):");
+                        ui.label(format!(
+                            "{:.1} MB",
+                            footprint.working_set_bytes as f64 / (1024.0 * 1024.0)
+                        ));
+                        ui.end_row();
+
+                        ui.label("CPU:");
+                        ui.label(format!("{:.1} %", footprint.cpu_percent));
+                        ui.end_row();
+
+                        ui.label("Handles:");
+                        ui.label(footprint.handles.to_string());
+                        ui.end_row();
+
+                        ui.label("Last key seen:");
+                        ui.label(format!(
+                            "{} s ago",
+                            hook_health::since_last_key().as_secs()
+                        ));
+                        ui.end_row();
+
+                        ui.label("Hook restarts:");
+                        ui.label(hook_health::reinstalls().to_string());
+                        ui.end_row();
+                    });
+
+                    // Failures, each with what to do about it
+                    problems::mark_seen();
+                    let reports = problems::reports();
+                    if !reports.is_empty() {
+                        ui.separator();
+                        ui.horizontal(|ui| {
+                            ui.label(RichText::new("Problems").strong());
+                            if ui.small_button("Clear").clicked() {
+                                problems::clear();
+                            }
+                        });
+                        for report in reports.iter().rev() {
+                            ui.label(format!(
+                                "{} ({}), {} time(s), last {} s ago",
+                                report.problem.summary(),
+                                report.detail,
+                                report.count,
+                                report.last.elapsed().as_secs()
+                            ));
+                            ui.label(RichText::new(report.problem.advice()).weak().size(12.0));
+                        }
+                    }
+
+                    // Guided check for another app taking the keys first
+                    ui.separator();
+                    ui.label(RichText::new("Keyboard hook check").strong());
+                    match self.hook_check {
+                        None => {
+                            if ui.button("Start check").clicked() {
+                                self.hook_check = Some(hook_health::keys_seen());
+                                self.hook_check_text.clear();
+                            }
+                        }
+                        Some(start) => {
+                            ui.label("Type a few letters here:");
+                            ui.text_edit_singleline(&mut self.hook_check_text);
+                            if self.hook_check_text.chars().count() >= 5 {
+                                if hook_health::keys_seen() > start {
+                                    ui.label("✔ Keys reach Restro Keyboard.");
+                                } else {
+                                    ui.label("✘ Restro Keyboard didn't see these keys. Another app is taking them first. Try:");
+                                    ui.label("1. Quit other keyboard tools, macro or remapping apps and hotkey managers, then type again.");
+                                    ui.label("2. Restart Restro Keyboard after those apps have started, so it is asked first.");
+                                    ui.label("3. Add Restro Keyboard to the allowed list of security or anti-cheat software.");
+                                }
+                                if ui.button("Check again").clicked() {
+                                    self.hook_check = None;
+                                }
+                            }
+                        }
+                    }
+
+                    // Everything typing depends on, as a report for bug reports
+                    ui.separator();
+                    ui.label(RichText::new("Self-test").strong());
+                    if let Some(running) = &self.self_test {
+                        match running.try_recv() {
+                            Ok(results) => {
+                                self.self_test_results = results;
+                                self.self_test = None;
+                            }
+                            Err(TryRecvError::Empty) => {
+                                ui.horizontal(|ui| {
+                                    ui.spinner();
+                                    ui.label("Running…");
+                                });
+                            }
+                            Err(TryRecvError::Disconnected) => self.self_test = None,
+                        }
+                    }
+                    if self.self_test.is_none() {
+                        ui.horizontal(|ui| {
+                            if ui.button("Run self-test").clicked() {
+                                let (done, results) = mpsc::channel();
+                                std::thread::spawn(move || {
+                                    let _ = done.send(self_test::run());
+                                });
+                                self.self_test = Some(results);
+                            }
+                            if !self.self_test_results.is_empty()
+                                && ui.button("Copy report").clicked()
+                            {
+                                let report = self_test::report(&self.self_test_results);
+                                ctx.output_mut(|o| o.copied_text = report);
+                            }
+                        });
+                        for check in &self.self_test_results {
+                            let mark = match check.outcome {
+                                self_test::Outcome::Pass => "✔",
+                                self_test::Outcome::Fail => "✘",
+                                self_test::Outcome::Skipped => "–",
+                            };
+                            ui.label(format!("{} {}: {}", mark, check.name, check.detail));
+                        }
+                    }
+                });
+            ctx.request_repaint_after(std::time::Duration::from_secs(1));
+        }
+
+        // Bangla words typed per day, and how fast
+        self.heatmap.screenshot_arrived(ctx);
+        if self.show_statistics {
+            let mut open = self.show_statistics;
+            egui::Window::new("Statistics")
+                .open(&mut open)
+                .show(ctx, |ui| {
+                    if !SETTINGS.snapshot().statistics {
+                        ui.label(
+                            RichText::new("Typing statistics are switched off in the settings.")
+                                .weak(),
+                        );
+                    }
+                    let week = statistics::recent(7);
+                    ui.label(format!(
+                        "Last 7 days: {} Bangla words, avg {:.0} WPM",
+                        statistics::grouped(week.bangla_words),
+                        week.wpm()
+                    ));
+                    ui.add_space(6.0);
+                    egui::ScrollArea::vertical()
+                        .max_height(240.0)
+                        .show(ui, |ui| {
+                            egui::Grid::new("statistics").striped(true).show(ui, |ui| {
+                                ui.label(RichText::new("Day").strong());
+                                ui.label(RichText::new("Bangla words").strong());
+                                ui.label(RichText::new("WPM").strong());
+                                ui.end_row();
+                                let today = statistics::today();
+                                for day in statistics::days().iter().rev() {
+                                    if day.day == today {
+                                        ui.label("Today");
+                                    } else {
+                                        ui.label(statistics::date(day.day));
+                                    }
+                                    ui.label(statistics::grouped(day.bangla_words));
+                                    ui.label(format!("{:.0}", day.wpm()));
+                                    ui.end_row();
+                                }
+                            });
+                        });
+                    ui.label(
+                        RichText::new(
+                            "Typing time counts the pauses between words shorter than 10 seconds.",
+                        )
+                        .weak()
+                        .size(12.0),
+                    );
+                    egui::CollapsingHeader::new("Layouts compared").show(ui, |ui| {
+                        layout_comparison(ui);
+                    });
+                    egui::CollapsingHeader::new("Heatmap").show(ui, |ui| {
+                        self.heatmap.show(ui);
+                    });
+                });
+            self.show_statistics = open;
+        }
+
+        // Backups to choose from, newest first
+        if let Some(backups) = &self.backups {
+            let mut open = true;
+            let mut restored = None;
+            egui::Window::new("Restore a backup")
+                .open(&mut open)
+                .show(ctx, |ui| {
+                    ui.label("The files in the backup replace yours. Yours are backed up first, so this can be undone.");
+                    ui.add_space(6.0);
+                    if backups.is_empty() {
+                        ui.label(RichText::new("No backups yet.").weak());
+                    }
+                    egui::ScrollArea::vertical()
+                        .max_height(240.0)
+                        .show(ui, |ui| {
+                            egui::Grid::new("backups").striped(true).show(ui, |ui| {
+                                for backup in backups {
+                                    ui.label(&backup.made);
+                                    ui.label(RichText::new(backup.files.join(", ")).weak());
+                                    if ui.button("Restore").clicked() {
+                                        restored = Some(backup.path.clone());
+                                    }
+                                    ui.end_row();
+                                }
+                            });
+                        });
+                });
+            if let Some(path) = restored {
+                let kept = SETTINGS.snapshot().backups_kept;
+                self.backup_status = Some(match backup::restore(&path, kept) {
+                    Ok(files) => {
+                        SETTINGS.publish(KeyboardSettings::load());
+                        self.mappings = mappings::load();
+                        reload_layouts();
+                        learned_words::load();
+                        reload_dictionary();
+                        format!("Restored {}", files.join(", "))
+                    }
+                    Err(err) => format!("Could not restore the backup: {}", err),
+                });
+                open = false;
+            }
+            if !open {
+                self.backups = None;
+            }
+        }
+
+        // Everything the keyboard left on the computer, removed before it exits
+        if self.show_cleanup {
+            let mut open = self.show_cleanup;
+            let mut remove = false;
+            egui::Window::new("Remove Restro Keyboard")
+                .open(&mut open)
+                .show(ctx, |ui| {
+                    ui.label("This removes the keyboard's startup entries, the words shared with Windows spelling, and everything in its app data folder: settings, learned words, statistics, transcripts and backups. Then the keyboard exits; delete its folder to finish.");
+                    ui.checkbox(
+                        &mut self.keep_dictionary,
+                        "Keep my dictionary and the installed full dictionary",
+                    );
+                    ui.add_space(6.0);
+                    if ui.button("Remove and exit").clicked() {
+                        remove = true;
+                    }
+                    for failure in &self.cleanup_failures {
+                        ui.label(RichText::new(failure).color(egui::Color32::RED).size(12.0));
+                    }
+                });
+            self.show_cleanup = open;
+            if remove {
+                self.cleanup_failures = cleanup::run(self.keep_dictionary);
+                // Exits at once, so no saver writes its file back
+                if self.cleanup_failures.is_empty() {
+                    std::process::exit(0);
+                }
+            }
+        }
+
+        // Shown on the first run: how typing works, with the real engine
+        if self.show_welcome {
+            let mut show_at_startup = SETTINGS.snapshot().show_welcome;
+            egui::Window::new("Welcome to Restro Keyboard")
+                .open(&mut self.show_welcome)
+                .show(ctx, |ui| {
+                    ui.label("Type Bangla the way it sounds. Each key converts the whole word again, so later keys can change what earlier ones typed:");
+                    ui.add_space(6.0);
+                    let settings = SETTINGS.snapshot();
+                    self.welcome_demo.show(
+                        ui,
+                        &engine(&layouts(), &settings),
+                        settings.font_size,
+                    );
+                    ui.add_space(6.0);
+                    ui.label(format!(
+                        "{} switches between Bangla and English.",
+                        settings.toggle_hotkey.label()
+                    ));
+                    ui.separator();
+                    ui.checkbox(&mut show_at_startup, "Show this at startup");
+                });
+            if show_at_startup != SETTINGS.snapshot().show_welcome {
+                SETTINGS.update(|settings| settings.show_welcome = show_at_startup);
+            }
+        }
+
+        // Opt-in copy of what is typed through the keyboard, across apps
+        if self.show_transcript {
+            egui::Window::new("Transcript")
+                .open(&mut self.show_transcript)
                 .show(ctx, |ui| {
-                    let mut settings = SETTINGS.lock().unwrap();
-                    ui.vertical(|ui| {
-                        // Enable/Disable keyboard
-                        if ui
-                            .checkbox(&mut settings.enabled, "Enable keyboard")
-                            .clicked()
-                        {
-                            // The state is already updated by the checkbox
+                    self.transcript_view.refresh(&HISTORY.lock().unwrap());
+                    let view = &self.transcript_view;
+                    let Some(transcript) = &view.transcript else {
+                        ui.label(
+                            "Keeps a copy of the text you type through Restro Keyboard, one paragraph per field, to save as one document. Nothing is kept unless you start it.",
+                        );
+                        if ui.button("Start transcript").clicked() {
+                            HISTORY.lock().unwrap().start_transcript();
+                            self.transcript_status = None;
                         }
+                        return;
+                    };
 
-                        ui.add_space(10.0);
-
-                        // Language selector
-                        ui.horizontal(|ui| {
-                            ui.label("Language:");
-                            if ui
-                                .radio_value(
-                                    &mut settings.current_language,
-                                    "Bangla".to_string(),
-                                    "বাংলা",
-                                )
-                                .clicked()
-                            {
-                                settings.enabled = true;
-                            }
-                            if ui
-                                .radio_value(
-                                    &mut settings.current_language,
-                                    "English".to_string(),
-                                    "English",
-                                )
-                                .clicked()
-                            {
-                                settings.enabled = false;
-                            }
-                        });
-
-                        ui.add_space(10.0);
-
-                        // Font size
-                        ui.horizontal(|ui| {
-                            ui.label("Font Size:");
+                    let settings = SETTINGS.snapshot();
+                    let text = &view.text;
+                    ui.label(
+                        RichText::new(format!("{} characters so far", transcript.char_count()))
+                            .weak()
+                            .size(12.0),
+                    );
+                    egui::ScrollArea::vertical()
+                        .max_height(300.0)
+                        .show(ui, |ui| {
                             ui.add(
-                                egui::Slider::new(&mut settings.font_size, 12.0..=24.0)
-                                    .step_by(1.0),
+                                egui::TextEdit::multiline(&mut mask::shown(&settings, text))
+                                    .desired_width(f32::INFINITY),
                             );
                         });
+                    ui.horizontal(|ui| {
+                        for (label, markdown) in [("Save as .txt", false), ("Save as .md", true)] {
+                            if ui.button(label).clicked() {
+                                self.transcript_status = Some(match transcript.save(markdown) {
+                                    Ok(path) => format!("Saved to {}", path.display()),
+                                    Err(err) => format!("Could not save the transcript: {}", err),
+                                });
+                            }
+                        }
+                        if ui.button("Copy").clicked() {
+                            ctx.output_mut(|o| o.copied_text = text.clone());
+                        }
+                        if ui
+                            .button("Stop")
+                            .on_hover_text("Text that wasn't saved is discarded")
+                            .clicked()
+                        {
+                            HISTORY.lock().unwrap().stop_transcript();
+                            self.transcript_status = None;
+                        }
+                    });
+                    if let Some(status) = &self.transcript_status {
+                        ui.label(RichText::new(status).weak().size(12.0));
+                    }
+                });
+        }
 
-                        ui.add_space(10.0);
-
-                        // Theme
-                        ui.horizontal(|ui| {
-                            ui.label("Theme:");
-                            ui.radio_value(&mut settings.theme, "Light".to_string(), "Light");
-                            ui.radio_value(&mut settings.theme, "Dark".to_string(), "Dark");
-                        });
+        if self.converter.open {
+            let font_size = self.get_font_size();
+            self.converter.show(ctx, font_size);
+        }
 
-                        ui.add_space(10.0);
+        // Two phonetic layouts side by side, before switching or after editing
+        if self.show_compare {
+            egui::Window::new("Compare layouts")
+                .open(&mut self.show_compare)
+                .show(ctx, |ui| {
+                    let layouts = layouts();
+                    ui.horizontal(|ui| {
+                        for (id, choice) in [
+                            ("compare_left", &mut self.compare_left),
+                            ("compare_right", &mut self.compare_right),
+                        ] {
+                            egui::ComboBox::from_id_source(id)
+                                .selected_text(choice.as_str())
+                                .show_ui(ui, |ui| {
+                                    for layout in layouts.iter() {
+                                        ui.selectable_value(
+                                            choice,
+                                            layout.name().to_string(),
+                                            layout.name(),
+                                        );
+                                    }
+                                });
+                        }
+                        ui.checkbox(&mut self.compare_only_differences, "Only differences");
+                    });
+                    let left = find_layout(&layouts, &self.compare_left);
+                    let right = find_layout(&layouts, &self.compare_right);
 
-                        // Additional settings
-                        ui.checkbox(&mut settings.use_suggestions, "Show typing suggestions");
-                        ui.checkbox(&mut settings.hotkey_enabled, "Enable Ctrl+Space shortcut");
+                    ui.horizontal(|ui| {
+                        ui.label("Try a word:");
+                        ui.text_edit_singleline(&mut self.compare_word);
                     });
+                    if !self.compare_word.is_empty() {
+                        let settings = SETTINGS.snapshot();
+                        for layout in [left, right] {
+                            let output = Transliterator::new(layout)
+                                .with_gemination(settings.gemination(layout))
+                                .transliterate(&self.compare_word);
+                            ui.label(format!("{}: {}", layout.name(), output));
+                        }
+                    }
+                    ui.separator();
+
+                    let rows: Vec<_> = reference::compare(left, right)
+                        .into_iter()
+                        .filter(|row| !self.compare_only_differences || row.differs())
+                        .collect();
+                    ui.label(
+                        RichText::new(format!("{} sequences", rows.len()))
+                            .weak()
+                            .size(12.0),
+                    );
+                    egui::ScrollArea::vertical()
+                        .max_height(400.0)
+                        .show(ui, |ui| {
+                            egui::Grid::new("compare_layouts")
+                                .striped(true)
+                                .spacing([20.0, 4.0])
+                                .show(ui, |ui| {
+                                    ui.label(RichText::new("Type").strong());
+                                    ui.label(RichText::new(left.name()).strong());
+                                    ui.label(RichText::new(right.name()).strong());
+                                    ui.end_row();
+                                    for row in rows {
+                                        ui.monospace(&row.roman);
+                                        ui.label(row.left.as_deref().unwrap_or("—"));
+                                        ui.label(row.right.as_deref().unwrap_or("—"));
+                                        ui.end_row();
+                                    }
+                                });
+                        });
                 });
         }
 
+        let selection_moved = self.handle_suggestion_keys(ctx);
+
         // Layout preview
         egui::CentralPanel::default().show(ctx, |ui| {
             ui.horizontal(|ui| {
@@ -388,19 +2156,29 @@ impl App for KeyboardApp {
                 columns[0].group(|ui| {
                     ui.set_min_height(400.0);
                     egui::ScrollArea::vertical().show(ui, |ui| {
+                        let layouts = layouts();
+                        let layout = find_layout(&layouts, &SETTINGS.snapshot().state.layout);
                         let mut col_counter = 0;
+                        // In Bangla dictionary order
+                        let mut rules: Vec<_> = layout
+                            .rules()
+                            .filter(|(k, _)| {
+                                self.search_text.is_empty()
+                                    || k.contains(&self.search_text.to_lowercase())
+                            })
+                            .collect();
+                        rules.sort_by(|a, b| {
+                            collation::compare(a.1.text(), b.1.text()).then_with(|| a.0.cmp(b.0))
+                        });
                         egui::Grid::new("keyboard_layout")
                             .spacing([10.0, 10.0])
                             .show(ui, |ui| {
-                                for (eng, bang) in CONVERSION_MAP.iter().filter(|(k, _)| {
-                                    self.search_text.is_empty()
-                                        || k.contains(&self.search_text.to_lowercase())
-                                }) {
-                                    if self.matches_category(eng) {
+                                for (eng, bang) in rules {
+                                    if self.matches_category(layout, eng) {
                                         ui.horizontal(|ui| {
                                             // English input text
                                             ui.label(
-                                                RichText::new(*eng)
+                                                RichText::new(eng)
                                                     .text_style(TextStyle::Body)
                                                     .monospace(),
                                             );
@@ -416,7 +2194,7 @@ impl App for KeyboardApp {
 
                                             // Bengali output text
                                             ui.label(
-                                                RichText::new(*bang)
+                                                RichText::new(bang.text())
                                                     .size(self.get_font_size())
                                                     .strong()
                                                     .color(egui::Color32::from_rgb(0, 100, 0)),
@@ -439,11 +2217,43 @@ impl App for KeyboardApp {
                 columns[1].group(|ui| {
                     ui.set_min_height(400.0);
                     ui.heading("Suggestions");
+                    ui.label(
+                        RichText::new("↑/↓ to select, Enter to copy")
+                            .weak()
+                            .size(12.0),
+                    );
+                    if let Some(copied) = &self.copied_suggestion {
+                        ui.label(RichText::new(format!("Copied {}", copied)).weak());
+                    }
+                    ui.add_space(5.0);
+
+                    let mut clicked = None;
                     egui::ScrollArea::vertical().show(ui, |ui| {
-                        for suggestion in &self.suggestions {
-                            ui.label(suggestion);
+                        for (index, (eng, bang)) in self.suggestions.iter().enumerate() {
+                            let selected = self.selected_suggestion == Some(index);
+                            let response = ui.selectable_label(
+                                selected,
+                                RichText::new(format!("{}  →  {}", eng, bang))
+                                    .size(self.get_font_size()),
+                            );
+                            let response = response.on_hover_ui(|ui| {
+                                conversion_preview(ui, eng);
+                            });
+                            if selected && selection_moved {
+                                response.scroll_to_me(None);
+                            }
+                            if response.clicked() {
+                                clicked = Some((index, response.double_clicked()));
+                            }
                         }
                     });
+
+                    if let Some((index, copy)) = clicked {
+                        self.selected_suggestion = Some(index);
+                        if copy {
+                            self.copy_suggestion(ui.ctx(), index);
+                        }
+                    }
                 });
             });
         });
@@ -451,6 +2261,9 @@ impl App for KeyboardApp {
 }
 
 unsafe extern "system" fn keyboard_hook_proc(code: i32, wparam: WPARAM, lparam: LPARAM) -> LRESULT {
+    #[cfg(feature = "profiling")]
+    let _span = tracing::trace_span!("keyboard_hook_proc").entered();
+
     let kbd_struct = unsafe { *(lparam.0 as *const KBDLLHOOKSTRUCT) };
     let vk_code = VIRTUAL_KEY(kbd_struct.vkCode as u16);
     let flags = kbd_struct.flags;
@@ -463,111 +2276,540 @@ unsafe extern "system" fn keyboard_hook_proc(code: i32, wparam: WPARAM, lparam:
     if (flags & KBDLLHOOKSTRUCT_FLAGS(0x10)).0 != 0 {
         return unsafe { CallNextHookEx(None, code, wparam, lparam) };
     }
+    hook_health::record_key();
 
     let msg_type = wparam.0 as u32;
+    if matches!(msg_type, WM_KEYUP | WM_SYSKEYUP) {
+        long_press::key_up(vk_code);
+    }
 
-    match msg_type {
-        WM_KEYDOWN | WM_SYSKEYDOWN => {
-            if vk_code == VK_CONTROL {
-                CTRL_PRESSED.store(true, Ordering::SeqCst);
+    if matches!(msg_type, WM_KEYDOWN | WM_SYSKEYDOWN) {
+        // Handle backspace
+        if vk_code == VK_BACK {
+            // Within a phonetic word the whole last letter goes, a conjunct
+            // included, along with the keys that made it. Otherwise the
+            // application erases one character and the word's keys would
+            // no longer match the text.
+            let settings = SETTINGS.snapshot();
+            let mut composer = COMPOSER.lock().unwrap();
+            let erased = composer.erase_letter(&engine(&layouts(), &settings));
+            let word = composer.buffer().to_string();
+            drop(composer);
+            composition_changed(&word, &settings);
+            if let Some((output, backspaces)) = erased {
+                retype(backspaces, &output);
+                return LRESULT(1);
             }
+            FIXED_COMPOSER.lock().unwrap().backspace();
+            HISTORY.lock().unwrap().erase(1);
+            return unsafe { CallNextHookEx(None, code, wparam, lparam) };
+        }
 
-            // Handle backspace
-            if vk_code == VK_BACK {
-                let mut buffer = BUFFER.lock().unwrap();
-                if !buffer.is_empty() {
-                    buffer.pop();
+        let settings = SETTINGS.snapshot();
+        if settings.state.enabled {
+            // Quick-insert panel next to the caret (Ctrl+Shift+. unless changed)
+            if settings.quick_insert_hotkey.matches(vk_code) {
+                if let Some(caret) = caret::locate() {
+                    quick_insert::summon(caret);
                 }
-                return unsafe { CallNextHookEx(None, code, wparam, lparam) };
+                return LRESULT(1);
             }
 
-            let settings = SETTINGS.lock().unwrap();
-            if settings.enabled {
-                // Handle language switching hotkey (Ctrl+Space)
-                if settings.hotkey_enabled {
-                    if vk_code == VK_SPACE && CTRL_PRESSED.load(Ordering::SeqCst) {
-                        drop(settings); // Release lock before modifying
-                        let mut settings = SETTINGS.lock().unwrap();
-                        let new_lang = if settings.current_language == "Bangla" {
-                            "English"
-                        } else {
-                            "Bangla"
-                        };
-                        settings.current_language = new_lang.to_string();
-                        return LRESULT(1);
-                    }
-                }
+            // Banglish on the clipboard to Bangla (Ctrl+Alt+B unless changed),
+            // off the hook thread since the clipboard can be slow
+            if settings.clipboard_hotkey_enabled && settings.clipboard_hotkey.matches(vk_code) {
+                std::thread::spawn(clipboard::convert);
+                return LRESULT(1);
+            }
 
-                // Process key input if in Bangla mode
-                if settings.current_language == "Bangla" && settings.intercept_all {
-                    let key_code = vk_code.0 as u32;
-                    let key = if (0x41..=0x5A).contains(&key_code) {
-                        // Convert A-Z to lowercase a-z
-                        Some(((key_code - 0x41 + 0x61) as u8 as char).to_string())
-                    } else if (0x30..=0x39).contains(&key_code) {
-                        // Numbers 0-9
-                        Some(((key_code - 0x30) as u8 as char).to_string())
-                    } else {
-                        None
-                    };
+            // Selected Banglish to Bangla in place (Ctrl+Shift+B unless
+            // changed), copied and pasted over
+            if settings.selection_hotkey_enabled && settings.selection_hotkey.matches(vk_code) {
+                std::thread::spawn(clipboard::convert_selection);
+                return LRESULT(1);
+            }
 
-                    if let Some(key) = key {
-                        let mut buffer = BUFFER.lock().unwrap();
+            // The flagged word's next correction (Ctrl+F7 unless changed);
+            // with no word flagged the app gets the keys
+            if settings.spell_check
+                && settings.spelling_hotkey.matches(vk_code)
+                && spell_check::correct()
+            {
+                return LRESULT(1);
+            }
 
-                        // If this is a vowel and the buffer is empty, handle it directly
-                        if buffer.is_empty() && matches!(key.as_str(), "a" | "e" | "i" | "o" | "u")
-                        {
-                            if let Some(bangla_char) = PHONETIC_MAP.get(key.as_str()) {
-                                if let BanglaChar::Vowel(c) = bangla_char {
-                                    simulate_unicode_input(c);
-                                    return LRESULT(1);
-                                }
-                            }
-                        }
+            // Language switching hotkey (Ctrl+Space unless changed)
+            if settings.hotkey_enabled && settings.toggle_hotkey.matches(vk_code) {
+                SETTINGS.update(|settings| settings.state.apply(Event::ToggleLanguage));
+                return LRESULT(1);
+            }
 
-                        if let Some((output, backspaces)) =
-                            process_keyboard_input(&key, &mut buffer)
-                        {
-                            drop(buffer); // Release lock before simulating input
+            // Process key input if in Bangla mode
+            if settings.converts_input() {
+                if let Some(key) = key_position(vk_code) {
+                    statistics::key_pressed(key, &settings);
+                }
 
-                            // First remove the typed English text
-                            for _ in 0..backspaces {
-                                simulate_backspace();
-                                std::thread::sleep(std::time::Duration::from_millis(5));
-                            }
+                // A key held long enough types its alternate instead
+                if settings.long_press {
+                    match long_press::key_down(vk_code, kbd_struct.time, &settings) {
+                        long_press::Action::Type => {}
+                        long_press::Action::Swallow => return LRESULT(1),
+                        long_press::Action::Replace { before, alternate } => {
+                            type_alternate(&before, &alternate);
+                            return LRESULT(1);
+                        }
+                    }
+                }
 
-                            // Then send the Bangla text
-                            if !output.is_empty() {
-                                std::thread::sleep(std::time::Duration::from_millis(5));
-                                simulate_unicode_input(&output);
-                            }
+                // Fixed layouts type straight from the key and its Shift and
+                // AltGr layers; Ctrl and Alt shortcuts pass through
+                let fixed = fixed_layout(&settings.state.layout);
+                if let Some(fixed) = fixed {
+                    let altgr = key_down(VK_RMENU);
+                    let shortcut = (ctrl_pressed() && !altgr) || key_down(VK_LMENU);
+                    if let Some(key) = key_position(vk_code).filter(|_| !shortcut) {
+                        let layer = Layer::new(shift_pressed(), altgr);
+                        let typed = FIXED_COMPOSER.lock().unwrap().push(fixed, key, layer);
+                        if let Some((output, backspaces)) = typed {
+                            retype(backspaces, &output);
                             return LRESULT(1);
                         }
                     }
                 }
+
+                // The candidate popup takes its keys while it shows
+                if fixed.is_none()
+                    && settings.feature_enabled(Feature::CandidatePopup)
+                    && popup_key(vk_code)
+                {
+                    return LRESULT(1);
+                }
+
+                let key_code = vk_code.0 as u32;
+                let key = if fixed.is_some() {
+                    None
+                } else if (0x41..=0x5A).contains(&key_code) {
+                    // A-Z as typed: the layout tells T (ত) from t (ট), and types
+                    // capitals it has no rules for like small letters
+                    let letter = key_code as u8 as char;
+                    Some(if shift_pressed() != caps_lock_on() {
+                        letter.to_string()
+                    } else {
+                        letter.to_ascii_lowercase().to_string()
+                    })
+                } else if (0x30..=0x39).contains(&key_code) && !shift_pressed() {
+                    // Numbers 0-9; shifted they are symbols and pass through
+                    Some((key_code as u8 as char).to_string())
+                } else {
+                    None
+                };
+
+                if let Some(key) = key {
+                    let mut composer = COMPOSER.lock().unwrap();
+                    let typed = composer.push(&engine(&layouts(), &settings), &key);
+                    let word = composer.buffer().to_string();
+                    drop(composer); // Release lock before simulating input
+
+                    composition_changed(&word, &settings);
+                    if let Some((output, backspaces)) = typed {
+                        retype(backspaces, &output);
+                        return LRESULT(1);
+                    }
+                    HISTORY.lock().unwrap().push(&key);
+                } else if vk_code == VK_OEM_PERIOD && !shift_pressed() {
+                    // A period after Bangla text becomes a dari, English stays as
+                    // is, and abbreviations such as ড. keep their period. After a
+                    // digit it is a decimal separator.
+                    let respelled = finish_word(&settings);
+                    clear_composition();
+                    let mut history = HISTORY.lock().unwrap();
+                    if sentence::ends_with_digit(history.text()) {
+                        let separator = settings.decimal_separator.clone();
+                        history.push(&separator);
+                        drop(history);
+                        if separator == "." && !respelled {
+                            return unsafe { CallNextHookEx(None, code, wparam, lparam) };
+                        }
+                        simulate_unicode_input(&separator);
+                        return LRESULT(1);
+                    }
+                    if settings.dari_on_period
+                        && history.last_script() == Some(Script::Bangla)
+                        && !sentence::is_abbreviation(sentence::last_word(history.text()))
+                    {
+                        let spaces = typography::spaces_before_dari(&settings, history.text());
+                        let plan = EditPlan::replace(history.text(), spaces, "।");
+                        send(&plan, history);
+                        return LRESULT(1);
+                    }
+                    history.push(".");
+                    if respelled {
+                        drop(history);
+                        simulate_unicode_input(".");
+                        return LRESULT(1);
+                    }
+                } else if let Some(mark) = Mark::from_key(vk_code, shift_pressed()) {
+                    // Quotes, dashes and spaces follow the typography settings
+                    let respelled = finish_word(&settings);
+                    clear_composition();
+                    let mut history = HISTORY.lock().unwrap();
+                    if let Some((backspaces, text)) =
+                        typography::replace(mark, &settings, history.text())
+                    {
+                        let plan = EditPlan::replace(history.text(), backspaces, &text);
+                        send(&plan, history);
+                        return LRESULT(1);
+                    }
+                    history.push(mark.as_str());
+                    if respelled {
+                        drop(history);
+                        simulate_unicode_input(mark.as_str());
+                        return LRESULT(1);
+                    }
+                } else if !is_modifier_key(vk_code) {
+                    // Other punctuation and navigation keys end the current word
+                    // and may move the caret somewhere we can't follow
+                    clear_composition();
+                    HISTORY.lock().unwrap().clear();
+                }
+            } else if settings.capitalizes_english() && capitalize_english(vk_code) {
+                return LRESULT(1);
             }
         }
-        WM_KEYUP | WM_SYSKEYUP => {
-            if vk_code == VK_CONTROL {
-                CTRL_PRESSED.store(false, Ordering::SeqCst);
+    }
+    unsafe { CallNextHookEx(None, code, wparam, lparam) }
+}
+
+// English mode with auto-capitalization: a lowercase letter starting a
+// sentence is typed as a capital, and a lone i becomes I when the word
+// ends. Returns whether the key was replaced; other keys are only noted in
+// the history, which the sentence checks read.
+fn capitalize_english(vk: VIRTUAL_KEY) -> bool {
+    let shift = shift_pressed();
+    let shortcut = ctrl_pressed() || key_down(VK_LMENU) || key_down(VK_RMENU);
+    let code = vk.0 as u32;
+    let mut history = HISTORY.lock().unwrap();
+
+    if (0x41..=0x5A).contains(&code) && !shortcut {
+        let letter = code as u8 as char;
+        let capital = shift != caps_lock_on();
+        if !capital && sentence::starts_sentence(history.text()) {
+            history.push(&letter.to_string());
+            drop(history);
+            simulate_unicode_input(&letter.to_string());
+            return true;
+        }
+        let typed = if capital {
+            letter
+        } else {
+            letter.to_ascii_lowercase()
+        };
+        history.push(&typed.to_string());
+        return false;
+    }
+
+    let ending = match vk {
+        VK_SPACE => " ",
+        VK_RETURN => "\n",
+        VK_OEM_PERIOD if !shift => ".",
+        VK_OEM_COMMA if !shift => ",",
+        VK_OEM_2 if shift => "?",
+        VK_1 if shift => "!",
+        _ => {
+            if (0x30..=0x39).contains(&code) && !shift {
+                history.push(&(code as u8 as char).to_string());
+            } else if !is_modifier_key(vk) {
+                history.clear();
             }
+            return false;
         }
-        _ => {}
+    };
+    if !shortcut && sentence::last_word(history.text()) == "i" {
+        let plan = EditPlan::replace(history.text(), 1, &format!("I{}", ending));
+        send(&plan, history);
+        return true;
     }
-    unsafe { CallNextHookEx(None, code, wparam, lparam) }
+    history.push(ending);
+    false
 }
 
-fn main() -> Result<(), Box<dyn std::error::Error>> {
-    // Set up keyboard hook first
+// Replaces the keyboard hook, if any, with a new one. Must run on the hook
+// thread, whose message loop calls it.
+fn install_keyboard_hook() -> windows::core::Result<()> {
     let hook = unsafe {
         SetWindowsHookExA(
             WH_KEYBOARD_LL,
             Some(keyboard_hook_proc),
             HMODULE::default(),
             0,
-        )?
+        )
+    }?;
+    if let Some(old) = KEYBOARD_HOOK.lock().unwrap().replace(hook) {
+        // Fails when Windows already removed it
+        let _ = unsafe { UnhookWindowsHookEx(old) };
+    }
+    Ok(())
+}
+
+// Erases the part of the word that changed, then types its new text
+fn retype(backspaces: usize, output: &str) {
+    let history = HISTORY.lock().unwrap();
+    let plan = EditPlan::replace(history.text(), backspaces, output);
+    send(&plan, history);
+}
+
+// Replaces what a held key typed after `before` with its alternate. The
+// alternate ends the word, as the keys typed so far no longer make it.
+fn type_alternate(before: &str, alternate: &str) {
+    let emitted = HISTORY.lock().unwrap().text().to_string();
+    let kept = emitted
+        .chars()
+        .zip(before.chars())
+        .take_while(|(emitted, before)| emitted == before)
+        .count();
+    let output: String = before.chars().skip(kept).chain(alternate.chars()).collect();
+    clear_composition();
+    retype(emitted.chars().count() - kept, &output);
+}
+
+// Mirrors a plan in the history, then sends it in the encoding the app in
+// front takes
+fn send(plan: &EditPlan, mut history: MutexGuard<EmissionHistory>) {
+    let before = history.text().to_string();
+    plan.apply(&mut history);
+    drop(history);
+    if bijoy::applies(&SETTINGS.snapshot()) {
+        execute(&bijoy::translate(&before, plan));
+    } else {
+        execute(plan);
+    }
+}
+
+// Sends a plan's keys to the application in one go
+fn execute(plan: &EditPlan) {
+    let inputs = plan.inputs();
+    if inputs.is_empty() {
+        return;
+    }
+    let sent = unsafe { SendInput(&inputs, std::mem::size_of::<INPUT>() as i32) };
+    // Fewer are sent when an elevated window has focus or input is blocked
+    if sent as usize != inputs.len() {
+        problems::report(Problem::InputBlocked, &problems::foreground_app());
+    }
+}
+
+// A word ending in a space, mark or period is respelled from the
+// autocorrect list, or in dictionary mode from the dictionary. Returns
+// whether it was, in which case the key that
+// ended the word has to be typed after the new spelling. Otherwise a word
+// with keys the layout doesn't know gets the unmatched-keys treatment.
+// Either way its spelling is checked and it is learned, except in a
+// password box.
+fn finish_word(settings: &KeyboardSettings) -> bool {
+    let layouts = layouts();
+    let engine = engine(&layouts, settings);
+    let corrections = autocorrect::corrections();
+    let mut composer = COMPOSER.lock().unwrap();
+    let word = composer.buffer().to_string();
+    let finished = if corrections.get(&word).is_some() {
+        composer.finish(&engine, &corrections)
+    } else if settings.dictionary_mode {
+        composer.finish(&engine, &dictionary())
+    } else {
+        None
+    };
+    drop(composer);
+    // As it stays on screen: respelled or as converted
+    let respelling = finished
+        .as_ref()
+        .and(corrections.get(&word).or_else(|| dictionary().get(&word)));
+    let committed = respelling.unwrap_or_else(|| engine.transliterate(&word));
+    let secret = caret::in_password_field();
+    if !secret {
+        learned_words::record(&word, &committed, settings);
+    }
+    if !word.is_empty() {
+        statistics::word_finished(&committed, settings);
+    }
+    if !word.is_empty() && !secret {
+        spell_check::word_finished(&committed, settings);
+    }
+    match finished {
+        Some((output, backspaces)) => {
+            retype(backspaces, &output);
+            true
+        }
+        None => {
+            if !secret {
+                unmatched::word_finished(&word, &engine.convert(&word), settings);
+            }
+            false
+        }
+    }
+}
+
+// Shows the word being typed, as roman keys, where it is turned on. Never
+// in a password box.
+fn composition_changed(word: &str, settings: &KeyboardSettings) {
+    if caret::in_password_field() {
+        candidate_popup::close();
+        preedit::close();
+        return;
+    }
+    if settings.feature_enabled(Feature::CandidatePopup) {
+        candidate_popup::update(word, settings);
+    }
+    if settings.feature_enabled(Feature::PreeditOverlay) {
+        preedit::update(word, settings);
+    }
+}
+
+// The word ended or the caret moved away
+fn clear_composition() {
+    COMPOSER.lock().unwrap().clear();
+    // Phonetic words are counted as they finish, fixed layout ones here
+    let mut fixed = FIXED_COMPOSER.lock().unwrap();
+    if !fixed.typed().is_empty() {
+        statistics::word_finished(fixed.typed(), &SETTINGS.snapshot());
+    }
+    fixed.clear();
+    drop(fixed);
+    statistics::word_abandoned();
+    candidate_popup::close();
+    preedit::close();
+}
+
+// Keys that work the candidate popup while it shows. Returns whether the
+// key was used; others go on as usual.
+fn popup_key(vk: VIRTUAL_KEY) -> bool {
+    let Some(selected) = candidate_popup::selected() else {
+        return false;
+    };
+    match vk {
+        VK_DOWN | VK_UP => candidate_popup::move_selection(vk == VK_DOWN),
+        // 1-9 type the candidate with that number
+        VIRTUAL_KEY(code @ 0x31..=0x39) if !shift_pressed() => {
+            candidate_popup::select(usize::from(code - 0x31)) && commit_candidate()
+        }
+        // Only once another candidate is highlighted, so typing on ends words
+        VK_SPACE | VK_RETURN if selected > 0 => commit_candidate(),
+        VK_ESCAPE => {
+            candidate_popup::close();
+            false
+        }
+        _ => false,
+    }
+}
+
+// Replaces the word on screen with the highlighted candidate and ends it
+fn commit_candidate() -> bool {
+    let Some((typed, chosen)) = candidate_popup::take() else {
+        return false;
     };
-    *KEYBOARD_HOOK.lock().unwrap() = Some(hook);
+    let word = COMPOSER.lock().unwrap().buffer().to_string();
+    let settings = SETTINGS.snapshot();
+    learned_words::record(&word, &chosen, &settings);
+    statistics::word_finished(&chosen, &settings);
+    clear_composition();
+    retype(typed.chars().count(), &chosen);
+    true
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    // Transliteration quality check against a parallel corpus
+    let args: Vec<String> = std::env::args().collect();
+    if let [_, flag, corpus] = args.as_slice() {
+        if flag == "--evaluate" {
+            return evaluate::run(corpus, &SETTINGS.snapshot());
+        }
+    }
+
+    // Behavior snapshots, to check an upgrade didn't change typing
+    if let [_, flag, file] = args.as_slice() {
+        if flag == "--snapshot" {
+            return snapshot::run_export(file, &SETTINGS.snapshot());
+        }
+    }
+    if let [_, flag, old, new] = args.as_slice() {
+        if flag == "--compare-snapshots" {
+            return snapshot::run_diff(old, new);
+        }
+    }
+
+    // Old Bijoy documents to Unicode or back, without opening the window
+    if let [_, flag, file] = args.as_slice() {
+        if flag == "--to-unicode" || flag == "--to-bijoy" {
+            let policy = SETTINGS.snapshot().reverse_numbers.clone();
+            let saved = bijoy::convert_file(
+                Path::new(file),
+                flag == "--to-unicode",
+                &policy,
+                bijoy::FILE_ENCODINGS[0],
+            )?;
+            println!("Saved {}", saved.display());
+            return Ok(());
+        }
+    }
+
+    // Admin deployment: apply a config for this user and exit, or preseed
+    // a first run. Both happen before the settings are first read.
+    if let [_, flag, config] = args.as_slice() {
+        if flag == "--provision" {
+            return provision::run(config);
+        }
+    }
+    provision::apply_preseeded();
+
+    SETTINGS.update(|settings| settings.apply_startup_preferences());
+    learned_words::load();
+    statistics::load();
+
+    #[cfg(feature = "profiling")]
+    let _flame_guard = profiling::init()?;
+
+    // Set up keyboard hook first, on its own thread with its own message
+    // loop so a busy or throttled UI thread never delays keystrokes
+    let (hook_ready, hook_started) = mpsc::channel();
+    let hook_thread = std::thread::spawn(move || {
+        power::boost_hook_thread();
+        if let Err(err) = install_keyboard_hook() {
+            let _ = hook_ready.send(Err(err));
+            return;
+        }
+        // Clicks and focus changes end the word being typed
+        let mut focus_watch = focus_watch::install();
+        let _ = hook_ready.send(Ok(unsafe { GetCurrentThreadId() }));
+
+        let mut msg = MSG::default();
+        while unsafe { GetMessageW(&mut msg, None, 0, 0) }.0 > 0 {
+            // The watchdog found the hooks removed by Windows
+            if msg.message == hook_health::WM_REINSTALL_HOOKS {
+                drop(focus_watch);
+                if let Err(err) = install_keyboard_hook() {
+                    problems::report(Problem::KeyboardHookFailed, &err.to_string());
+                }
+                focus_watch = focus_watch::install();
+            }
+        }
+
+        drop(focus_watch);
+        if let Some(hook) = KEYBOARD_HOOK.lock().unwrap().take() {
+            let _ = unsafe { UnhookWindowsHookEx(hook) };
+        }
+    });
+    let hook_thread_id = hook_started.recv()??;
+
+    settings::spawn_saver();
+    clipboard::spawn_monitor();
+    statistics::spawn_saver();
+    learned_words::spawn_saver();
+    dictionary_sync::spawn();
+    backup::spawn();
+    publisher::spawn();
+    tray::spawn();
+    hook_health::spawn_watch();
+    hook_health::spawn_watchdog(hook_thread_id);
 
     let options = eframe::NativeOptions {
         viewport: ViewportBuilder::default()
@@ -577,214 +2819,222 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         ..Default::default()
     };
 
-    // Try to load local Bengali font first, then fall back to system fonts
-    let bengali_font_path = if std::path::Path::new("assets/fonts/Nirmala.ttf").exists() {
-        "assets/fonts/Nirmala.ttf".to_string()
-    } else {
-        std::env::var("WINDIR")
-            .map(|windir| {
-                let font_paths = [
-                    format!("{}\\Fonts\\Nirmala.ttf", windir),
-                    format!("{}\\Fonts\\Vrinda.ttf", windir),
-                    format!("{}\\Fonts\\Shonar.ttf", windir),
-                ];
-                font_paths
-                    .into_iter()
-                    .find(|path| std::path::Path::new(path).exists())
-            })
-            .ok()
-            .flatten()
-            .ok_or_else(|| "No Bengali font found")?
-    };
-
-    // Load font data
-    let font_data = fs::read(&bengali_font_path)?;
-
     // Run UI in the main thread
     eframe::run_native(
         "Restro Keyboard",
         options,
-        Box::new(move |cc| {
-            let mut fonts = egui::FontDefinitions::default();
-            fonts.font_data.insert(
-                "bengali".to_owned(),
-                egui::FontData::from_owned(font_data.clone()),
+        Box::new(|cc| {
+            power::apply_to_ui_thread(&SETTINGS.snapshot());
+            quick_insert::attach(cc.egui_ctx.clone());
+            candidate_popup::attach(cc.egui_ctx.clone());
+            unmatched::attach(cc.egui_ctx.clone());
+            spell_check::attach(cc.egui_ctx.clone());
+            preedit::attach(cc.egui_ctx.clone());
+            problems::attach(cc.egui_ctx.clone());
+
+            // Font fallback chain (user font, bundled fonts, then system
+            // fonts) is read after the window shows
+            let fonts_loading = fonts::load_in_background(
+                SETTINGS.snapshot().custom_font.clone(),
+                cc.egui_ctx.clone(),
             );
-
-            for family in [FontFamily::Proportional, FontFamily::Monospace] {
-                fonts
-                    .families
-                    .entry(family)
-                    .or_default()
-                    .insert(0, "bengali".to_owned());
-            }
-
-            cc.egui_ctx.set_fonts(fonts);
-            Box::new(KeyboardApp::default())
+            Box::new(KeyboardApp {
+                fonts_loading: Some(fonts_loading),
+                ..Default::default()
+            })
         }),
     )?;
 
     // Clean up hook on exit
     unsafe {
-        if let Some(hook) = KEYBOARD_HOOK.lock().unwrap().take() {
-            let _ = UnhookWindowsHookEx(hook);
-        }
+        let _ = PostThreadMessageW(hook_thread_id, WM_QUIT, WPARAM(0), LPARAM(0));
     }
+    let _ = hook_thread.join();
 
     Ok(())
 }
 
-fn process_keyboard_input(key: &str, buffer: &mut String) -> Option<(String, usize)> {
-    buffer.push_str(key);
-    let buffer_str = buffer.as_str();
+// Step-by-step conversion of a roman sequence, shown when hovering a suggestion
+fn conversion_preview(ui: &mut egui::Ui, roman: &str) {
+    let settings = SETTINGS.snapshot();
+    egui::Grid::new("conversion_preview").show(ui, |ui| {
+        for step in engine(&layouts(), &settings).convert(roman).steps {
+            ui.monospace(&step.roman);
+            ui.label(RichText::new(&step.output).size(18.0));
+            ui.label(RichText::new(step.rule).weak());
+            ui.end_row();
+        }
+    });
+}
 
-    // Special case: if the buffer gets too long, clear it
-    if buffer_str.len() > 5 {
-        buffer.clear();
-        return None;
+// Each layout's time per word over the last 30 days, in the Statistics window
+fn layout_comparison(ui: &mut egui::Ui) {
+    if !SETTINGS.snapshot().layout_timing {
+        ui.label(RichText::new("Word timing is switched off in the settings.").weak());
+    }
+    let layouts = statistics::layout_timings(30);
+    if layouts.is_empty() {
+        ui.label("No timed words in the last 30 days.");
+        return;
     }
+    egui::Grid::new("layout_timings")
+        .striped(true)
+        .show(ui, |ui| {
+            ui.label(RichText::new("Layout").strong());
+            ui.label(RichText::new("Words").strong());
+            ui.label(RichText::new("Seconds per word").strong());
+            ui.end_row();
+            for timing in &layouts {
+                ui.label(&timing.layout);
+                ui.label(statistics::grouped(timing.words));
+                ui.label(format!("{:.2}", timing.seconds_per_word()));
+                ui.end_row();
+            }
+        });
+    ui.label(
+        RichText::new(
+            "From a word's first key to the key that ends it, over the last 30 days. Words that took 10 seconds or more are left out.",
+        )
+        .weak()
+        .size(12.0),
+    );
+}
 
-    // Try longer matches first (up to 3 characters)
-    for len in (1..=std::cmp::min(buffer_str.len(), 3)).rev() {
-        if let Some(substr) = buffer_str.get(buffer_str.len() - len..) {
-            // Handle vowel signs after consonants
-            if len == 1 {
-                if let Some(prev) = buffer_str.chars().nth(buffer_str.len() - 2) {
-                    if let Some(BanglaChar::Consonant(_)) =
-                        PHONETIC_MAP.get(prev.to_string().as_str())
-                    {
-                        let result = match substr {
-                            "a" => Some((String::new(), 1)), // Remove 'a' after consonant
-                            "i" => Some(("ি".to_string(), 1)),
-                            "e" => Some(("ে".to_string(), 1)),
-                            "u" => Some(("ু".to_string(), 1)),
-                            "o" => Some(("ো".to_string(), 1)),
-                            _ => None,
-                        };
+fn is_modifier_key(vk_code: VIRTUAL_KEY) -> bool {
+    matches!(
+        vk_code,
+        VK_SHIFT
+            | VK_LSHIFT
+            | VK_RSHIFT
+            | VK_CONTROL
+            | VK_LCONTROL
+            | VK_RCONTROL
+            | VK_MENU
+            | VK_LMENU
+            | VK_RMENU
+            | VK_LWIN
+            | VK_RWIN
+            | VK_CAPITAL
+    )
+}
 
-                        if result.is_some() {
-                            buffer.clear();
-                            return result;
-                        }
-                    }
-                }
-            }
+// The key by what it types on a US keyboard without Shift, as fixed
+// layouts name their keys
+fn key_position(vk_code: VIRTUAL_KEY) -> Option<char> {
+    Some(match vk_code.0 {
+        0x30..=0x39 => vk_code.0 as u8 as char,
+        0x41..=0x5A => (vk_code.0 as u8 as char).to_ascii_lowercase(),
+        0xBA => ';',
+        0xBB => '=',
+        0xBC => ',',
+        0xBD => '-',
+        0xBE => '.',
+        0xBF => '/',
+        0xC0 => '`',
+        0xDB => '[',
+        0xDC => '\\',
+        0xDD => ']',
+        0xDE => '\'',
+        _ => return None,
+    })
+}
 
-            // Try exact match for the current substring
-            if let Some(bangla_char) = PHONETIC_MAP.get(substr) {
-                let prev_was_consonant = if len < buffer_str.len() {
-                    buffer_str
-                        .chars()
-                        .nth(buffer_str.len() - len - 1)
-                        .map(|ch| {
-                            PHONETIC_MAP
-                                .get(ch.to_string().as_str())
-                                .map(|bc| matches!(bc, BanglaChar::Consonant(_)))
-                                .unwrap_or(false)
-                        })
-                        .unwrap_or(false)
-                } else {
-                    false
-                };
+fn key_down(vk_code: VIRTUAL_KEY) -> bool {
+    unsafe { GetAsyncKeyState(vk_code.0 as i32) < 0 }
+}
 
-                let output = match bangla_char {
-                    BanglaChar::Consonant(c) => {
-                        if prev_was_consonant {
-                            format!("্{}", c)
-                        } else {
-                            c.to_string()
-                        }
-                    }
-                    BanglaChar::VowelSign(c) => c.to_string(),
-                    BanglaChar::Vowel(c) => {
-                        if prev_was_consonant {
-                            match *c {
-                                "অ" => String::new(), // Remove 'a' after consonant
-                                "আ" => "া".to_string(),
-                                "ই" => "ি".to_string(),
-                                "ঈ" => "ী".to_string(),
-                                "উ" => "ু".to_string(),
-                                "ঊ" => "ূ".to_string(),
-                                "এ" => "ে".to_string(),
-                                "ঐ" => "ৈ".to_string(),
-                                "ও" => "ো".to_string(),
-                                "ঔ" => "ৌ".to_string(),
-                                _ => c.to_string(),
-                            }
-                        } else {
-                            c.to_string()
-                        }
-                    }
-                    BanglaChar::Number(c) | BanglaChar::Special(c) => c.to_string(),
-                };
+fn shift_pressed() -> bool {
+    unsafe { GetAsyncKeyState(VK_SHIFT.0 as i32) < 0 }
+}
 
-                buffer.clear();
-                return Some((output, len));
-            }
-        }
-    }
+fn caps_lock_on() -> bool {
+    unsafe { GetKeyState(VK_CAPITAL.0 as i32) & 1 != 0 }
+}
 
-    None
+fn ctrl_pressed() -> bool {
+    unsafe { GetAsyncKeyState(VK_CONTROL.0 as i32) < 0 }
 }
 
-fn simulate_backspace() {
-    unsafe {
-        let mut input = INPUT {
-            r#type: INPUT_KEYBOARD,
-            Anonymous: INPUT_0 {
-                ki: KEYBDINPUT {
-                    wVk: VK_BACK,
-                    wScan: 0,
-                    dwFlags: Default::default(),
-                    time: 0,
-                    dwExtraInfo: 0,
-                },
-            },
-        };
-        SendInput(&[input], std::mem::size_of::<INPUT>() as i32);
-
-        input.Anonymous.ki = KEYBDINPUT {
-            wVk: VK_BACK,
-            wScan: 0,
-            dwFlags: KEYEVENTF_KEYUP,
-            time: 0,
-            dwExtraInfo: 0,
-        };
-        SendInput(&[input], std::mem::size_of::<INPUT>() as i32);
+// The phonetic layouts as they are now. Like settings snapshots, callers
+// hold the `Arc` while they work and never block a rebuild.
+fn layouts() -> Arc<Vec<Layout>> {
+    LAYOUTS.read().unwrap().clone()
+}
+
+// The engine with the selected phonetic layout
+fn engine<'a>(layouts: &'a [Layout], settings: &KeyboardSettings) -> Transliterator<'a> {
+    let layout = find_layout(layouts, &settings.state.layout);
+    Transliterator::new(layout).with_gemination(settings.gemination(layout))
+}
+
+fn fixed_layout(name: &str) -> Option<&'static FixedLayout> {
+    FIXED_LAYOUTS.iter().find(|layout| layout.name() == name)
+}
+
+// Phonetic layouts first, then the fixed ones
+fn layout_names() -> Vec<String> {
+    layouts()
+        .iter()
+        .map(Layout::name)
+        .chain(FIXED_LAYOUTS.iter().map(FixedLayout::name))
+        .map(str::to_string)
+        .collect()
+}
+
+fn find_layout<'a>(layouts: &'a [Layout], name: &str) -> &'a Layout {
+    layouts
+        .iter()
+        .find(|layout| layout.name() == name)
+        .unwrap_or(&layouts[0])
+}
+
+// Rebuilds the phonetic layouts from the layout files and saved mappings,
+// so changes to either apply right away
+fn reload_layouts() {
+    *LAYOUTS.write().unwrap() = Arc::new(mappings::layouts(&mappings::load()));
+    clear_composition();
+}
+
+fn load_dictionary() -> Dictionary {
+    let bundled = std::fs::read_to_string(DICTIONARY_FILE).unwrap_or_default();
+    // Later lines win: the words the user types most over the bundled
+    // ones, and words the user added over both. All of them win over an
+    // installed dictionary, which is kept compact beneath them.
+    let dictionary = Dictionary::parse(&format!(
+        "{}\n{}\n{}",
+        bundled,
+        learned_words::favourites(),
+        user_dictionary::load()
+    ));
+    match full_dictionary::open() {
+        Some(full) => dictionary.with_base(full),
+        None => dictionary,
     }
 }
 
+fn dictionary() -> Arc<Dictionary> {
+    DICTIONARY.read().unwrap().clone()
+}
+
+// Picks up changes to the user's dictionary
+fn reload_dictionary() {
+    *DICTIONARY.write().unwrap() = Arc::new(load_dictionary());
+    windows_spelling::refresh();
+}
+
+#[cfg_attr(feature = "profiling", tracing::instrument(skip_all))]
 fn simulate_unicode_input(text: &str) {
-    // Small delay between characters to ensure reliable input
-    let delay = std::time::Duration::from_millis(1);
-
-    for c in text.chars() {
-        unsafe {
-            let mut input = INPUT {
-                r#type: INPUT_KEYBOARD,
-                Anonymous: INPUT_0 {
-                    ki: KEYBDINPUT {
-                        wVk: VIRTUAL_KEY(0),
-                        wScan: c as u16,
-                        dwFlags: KEYEVENTF_UNICODE,
-                        time: 0,
-                        dwExtraInfo: 0,
-                    },
-                },
-            };
-            SendInput(&[input], std::mem::size_of::<INPUT>() as i32);
-
-            input.Anonymous.ki = KEYBDINPUT {
-                wVk: VIRTUAL_KEY(0),
-                wScan: c as u16,
-                dwFlags: KEYEVENTF_UNICODE | KEYEVENTF_KEYUP,
-                time: 0,
-                dwExtraInfo: 0,
-            };
-            SendInput(&[input], std::mem::size_of::<INPUT>() as i32);
-
-            // Small delay to ensure characters are typed in the correct order
-            std::thread::sleep(delay);
-        }
+    let plan = EditPlan {
+        backspaces: 0,
+        text: text.to_string(),
+    };
+    if !bijoy::applies(&SETTINGS.snapshot()) {
+        return execute(&plan);
     }
+    // The text is in the history already; Bijoy may draw it around what
+    // came before
+    let history = HISTORY.lock().unwrap();
+    let before = history.text().strip_suffix(text).unwrap_or("").to_string();
+    drop(history);
+    execute(&bijoy::translate(&before, &plan));
 }
@@ -0,0 +1,110 @@
+use crate::layout_files;
+use crate::settings::{self, KeyboardSettings};
+use restro_keyboard_core::{BanglaChar, Layout, MAX_TYPED_LEN};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+// Categories a mapping can be added under, as named in the layout reference
+pub const CATEGORIES: [&str; 5] = ["Vowels", "Consonants", "Vowel signs", "Numbers", "Symbols"];
+
+/// A rule added or changed by the user, kept in its own file so the
+/// built-in layouts can change between versions.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct Mapping {
+    pub roman: String,
+    pub bangla: String,
+    pub category: String,
+}
+
+impl Default for Mapping {
+    fn default() -> Self {
+        Self {
+            roman: String::new(),
+            bangla: String::new(),
+            category: CATEGORIES[1].to_string(),
+        }
+    }
+}
+
+impl Mapping {
+    pub fn bangla_char(&self) -> BanglaChar {
+        let bangla = self.bangla.clone();
+        match self.category.as_str() {
+            "Vowels" => BanglaChar::Vowel(bangla),
+            "Vowel signs" => BanglaChar::VowelSign(bangla),
+            "Numbers" => BanglaChar::Number(bangla),
+            "Symbols" => BanglaChar::Special(bangla),
+            _ => BanglaChar::Consonant(bangla),
+        }
+    }
+
+    /// Why the mapping can't be used, if it can't.
+    pub fn problem(&self) -> Option<String> {
+        let len = self.roman.chars().count();
+        if len == 0 || len > MAX_TYPED_LEN {
+            Some(format!(
+                "\"{}\" must be 1 to {} characters",
+                self.roman, MAX_TYPED_LEN
+            ))
+        } else if !self.roman.chars().all(|c| c.is_ascii_graphic()) {
+            Some(format!(
+                "\"{}\" must be typed on an English keyboard",
+                self.roman
+            ))
+        } else if self.bangla.trim().is_empty() {
+            Some(format!("\"{}\" has no Bangla output", self.roman))
+        } else {
+            None
+        }
+    }
+}
+
+/// `%APPDATA%\RestroKeyboard\mappings.json`, next to the settings.
+pub fn path() -> Option<PathBuf> {
+    Some(KeyboardSettings::path()?.parent()?.join("mappings.json"))
+}
+
+/// The saved mappings; none when the file is missing or unreadable.
+pub fn load() -> Vec<Mapping> {
+    let Some(path) = path() else {
+        return Vec::new();
+    };
+    let Ok(json) = fs::read_to_string(&path) else {
+        return Vec::new();
+    };
+    serde_json::from_str(&json).unwrap_or_else(|err| {
+        eprintln!("Mappings file is corrupt, ignoring it: {}", err);
+        Vec::new()
+    })
+}
+
+pub fn save(mappings: &[Mapping]) -> io::Result<()> {
+    let path =
+        path().ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "APPDATA is not set"))?;
+    settings::save_json_atomically(&path, mappings)
+}
+
+/// The built-in phonetic layouts and those of layout files, with the user's
+/// mappings on top. Later mappings win over earlier ones and over the
+/// layouts' own rules.
+pub fn layouts(mappings: &[Mapping]) -> Vec<Layout> {
+    let mut layouts = vec![Layout::phonetic(), Layout::avro()];
+    for layout in layout_files::load_all() {
+        if layouts.iter().any(|l| l.name() == layout.name()) {
+            eprintln!(
+                "Skipping layout file for {}: the name is taken",
+                layout.name()
+            );
+            continue;
+        }
+        layouts.push(layout);
+    }
+    for layout in &mut layouts {
+        for mapping in mappings.iter().filter(|m| m.problem().is_none()) {
+            layout.insert(&mapping.roman, mapping.bangla_char());
+        }
+    }
+    layouts
+}
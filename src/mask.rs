@@ -0,0 +1,40 @@
+use crate::settings::KeyboardSettings;
+
+// With "Mask typed text" on, nothing that came from the keyboard is shown
+// as typed: the word preview, the candidates, the unmatched-keys notice,
+// spelling flags and the transcript all show ••• instead. Each of them, and
+// any log line that would include typed text, goes through `shown`, so the
+// setting is enforced here and nowhere else.
+
+pub const MASK: &str = "•••";
+
+/// Whether typed text is hidden, for views that style pieces of it.
+pub fn is_on(settings: &KeyboardSettings) -> bool {
+    settings.mask_buffer
+}
+
+/// `text` as a window, overlay or log may show it.
+pub fn shown<'a>(settings: &KeyboardSettings, text: &'a str) -> &'a str {
+    if is_on(settings) && !text.is_empty() {
+        MASK
+    } else {
+        text
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn typed_text_is_masked_only_when_asked() {
+        let masked = KeyboardSettings {
+            mask_buffer: true,
+            ..KeyboardSettings::default()
+        };
+        assert_eq!(shown(&masked, "ami"), MASK);
+        assert_eq!(shown(&masked, "আমি"), MASK);
+        assert_eq!(shown(&masked, ""), "");
+        assert_eq!(shown(&KeyboardSettings::default(), "ami"), "ami");
+    }
+}
@@ -0,0 +1,104 @@
+use crate::state::Event;
+use std::sync::atomic::{AtomicBool, Ordering};
+use windows::core::w;
+use windows::Win32::Foundation::{LPARAM, POINT, RECT, WPARAM};
+use windows::Win32::Graphics::Gdi::PtInRect;
+use windows::Win32::UI::WindowsAndMessaging::{
+    FindWindowExW, FindWindowW, GetSystemMetrics, GetWindowRect, MSLLHOOKSTRUCT,
+    SM_CXVIRTUALSCREEN, SM_CYVIRTUALSCREEN, SM_XVIRTUALSCREEN, SM_YVIRTUALSCREEN, WM_MBUTTONDOWN,
+    WM_MBUTTONUP, WM_MOUSEMOVE,
+};
+
+// Switching language with the mouse, for apps that take every keyboard
+// shortcut for themselves. Seen by the mouse hook in focus_watch.
+
+/// Choices for `KeyboardSettings::mouse_toggle`, the first is the default.
+pub const GESTURES: [&str; 6] = [
+    "Off",
+    "Top-left corner",
+    "Top-right corner",
+    "Bottom-left corner",
+    "Bottom-right corner",
+    "Middle-click the notification area",
+];
+
+// How close to the corner, in pixels, the pointer has to be
+const CORNER_SIZE: i32 = 4;
+
+// The pointer has to leave the corner before it switches again
+static IN_CORNER: AtomicBool = AtomicBool::new(false);
+// A middle click that switched; its button release is kept from the taskbar
+static SWALLOW_RELEASE: AtomicBool = AtomicBool::new(false);
+
+// Whether the point is in the named corner of the desktop, all monitors
+// together, since that is where the pointer stops
+fn in_corner(corner: &str, point: POINT) -> bool {
+    let (left, top, width, height) = unsafe {
+        (
+            GetSystemMetrics(SM_XVIRTUALSCREEN),
+            GetSystemMetrics(SM_YVIRTUALSCREEN),
+            GetSystemMetrics(SM_CXVIRTUALSCREEN),
+            GetSystemMetrics(SM_CYVIRTUALSCREEN),
+        )
+    };
+    let at_left = point.x < left + CORNER_SIZE;
+    let at_right = point.x >= left + width - CORNER_SIZE;
+    let at_top = point.y < top + CORNER_SIZE;
+    let at_bottom = point.y >= top + height - CORNER_SIZE;
+    match corner {
+        "Top-left corner" => at_top && at_left,
+        "Top-right corner" => at_top && at_right,
+        "Bottom-left corner" => at_bottom && at_left,
+        "Bottom-right corner" => at_bottom && at_right,
+        _ => false,
+    }
+}
+
+// Whether the point is on the taskbar's notification area, where the
+// tray icon is
+fn in_notification_area(point: POINT) -> bool {
+    unsafe {
+        let taskbar = FindWindowW(w!("Shell_TrayWnd"), None);
+        if taskbar.0 == 0 {
+            return false;
+        }
+        let area = FindWindowExW(taskbar, None, w!("TrayNotifyWnd"), None);
+        let mut rect = RECT::default();
+        area.0 != 0 && GetWindowRect(area, &mut rect).is_ok() && PtInRect(&rect, point).as_bool()
+    }
+}
+
+fn toggle() {
+    crate::SETTINGS.update(|settings| settings.state.apply(Event::ToggleLanguage));
+}
+
+/// Switches language when the mouse event makes the chosen gesture.
+/// Returns whether the event was used up and should go no further.
+pub fn handle(wparam: WPARAM, lparam: LPARAM) -> bool {
+    let message = wparam.0 as u32;
+    if message == WM_MBUTTONUP && SWALLOW_RELEASE.swap(false, Ordering::Relaxed) {
+        return true;
+    }
+    let settings = crate::SETTINGS.snapshot();
+    if !settings.state.enabled || settings.mouse_toggle == GESTURES[0] {
+        return false;
+    }
+    let point = unsafe { (*(lparam.0 as *const MSLLHOOKSTRUCT)).pt };
+    match message {
+        WM_MOUSEMOVE => {
+            let inside = in_corner(&settings.mouse_toggle, point);
+            if inside && !IN_CORNER.swap(true, Ordering::Relaxed) {
+                toggle();
+            } else if !inside {
+                IN_CORNER.store(false, Ordering::Relaxed);
+            }
+            false
+        }
+        WM_MBUTTONDOWN if settings.mouse_toggle == GESTURES[5] && in_notification_area(point) => {
+            toggle();
+            SWALLOW_RELEASE.store(true, Ordering::Relaxed);
+            true
+        }
+        _ => false,
+    }
+}
@@ -0,0 +1,152 @@
+use std::cell::Cell;
+use std::time::{Duration, Instant};
+use windows::core::{w, PCWSTR};
+use windows::Win32::Foundation::{HWND, LPARAM, LRESULT, WPARAM};
+use windows::Win32::UI::Shell::{
+    Shell_NotifyIconW, NIF_ICON, NIF_INFO, NIF_MESSAGE, NIIF_INFO, NIIF_WARNING, NIM_ADD,
+    NIM_DELETE, NIN_BALLOONHIDE, NIN_BALLOONTIMEOUT, NIN_BALLOONUSERCLICK, NOTIFYICONDATAW,
+};
+use windows::Win32::UI::WindowsAndMessaging::{
+    CreateWindowExW, DefWindowProcW, DestroyWindow, DispatchMessageW, FindWindowW, LoadIconW,
+    PeekMessageW, RegisterClassW, TranslateMessage, HWND_MESSAGE, IDI_INFORMATION, IDI_WARNING,
+    MSG, PM_REMOVE, WINDOW_EX_STYLE, WINDOW_STYLE, WM_APP, WNDCLASSW,
+};
+
+// Our own id for the balloon's icon, apart from the tray icon's
+const BALLOON_ICON_ID: u32 = 0x5246;
+// What the balloon's icon tells its window
+const WM_BALLOON: u32 = WM_APP + 2;
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Icon {
+    // A confirmation, gone after a few seconds
+    Info,
+    Warning,
+}
+
+impl Icon {
+    // How long the balloon stays before its icon is removed
+    fn duration(self) -> Duration {
+        match self {
+            Icon::Info => Duration::from_secs(4),
+            Icon::Warning => Duration::from_secs(10),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Balloon {
+    Showing,
+    Clicked,
+    Closed,
+}
+
+thread_local! {
+    // The balloon of this thread's window, which gets its messages
+    static BALLOON: Cell<Balloon> = const { Cell::new(Balloon::Showing) };
+}
+
+unsafe extern "system" fn balloon_proc(
+    window: HWND,
+    message: u32,
+    wparam: WPARAM,
+    lparam: LPARAM,
+) -> LRESULT {
+    if message == WM_BALLOON {
+        match lparam.0 as u32 {
+            NIN_BALLOONUSERCLICK => BALLOON.with(|balloon| balloon.set(Balloon::Clicked)),
+            NIN_BALLOONTIMEOUT | NIN_BALLOONHIDE => {
+                BALLOON.with(|balloon| balloon.set(Balloon::Closed))
+            }
+            _ => {}
+        }
+    }
+    DefWindowProcW(window, message, wparam, lparam)
+}
+
+// A message-only window to hear about clicks; registering the class again
+// fails harmlessly
+fn message_window() -> HWND {
+    let class = w!("RestroKeyboardBalloon");
+    unsafe {
+        RegisterClassW(&WNDCLASSW {
+            lpfnWndProc: Some(balloon_proc),
+            lpszClassName: class,
+            ..Default::default()
+        });
+        CreateWindowExW(
+            WINDOW_EX_STYLE::default(),
+            class,
+            w!(""),
+            WINDOW_STYLE::default(),
+            0,
+            0,
+            0,
+            0,
+            HWND_MESSAGE,
+            None,
+            None,
+            None,
+        )
+    }
+}
+
+fn copy_wide(text: &str, buffer: &mut [u16]) {
+    // Leave room for the terminating NUL
+    let room = buffer.len() - 1;
+    for (slot, unit) in buffer.iter_mut().zip(text.encode_utf16().take(room)) {
+        *slot = unit;
+    }
+}
+
+/// A balloon from a short-lived icon of our own, since the tray icon's
+/// library can't show one. Blocks while the balloon shows, so callers run
+/// it on a thread of its own. Returns whether the user clicked it.
+pub fn show(title: &str, message: &str, icon: Icon) -> bool {
+    // Nothing to show it for without our window
+    let app = unsafe { FindWindowW(PCWSTR::null(), w!("Restro Keyboard")) };
+    if app.0 == 0 {
+        return false;
+    }
+    let window = message_window();
+    let (info_flags, icon_id) = match icon {
+        Icon::Info => (NIIF_INFO, IDI_INFORMATION),
+        Icon::Warning => (NIIF_WARNING, IDI_WARNING),
+    };
+    let mut data = NOTIFYICONDATAW {
+        cbSize: std::mem::size_of::<NOTIFYICONDATAW>() as u32,
+        hWnd: if window.0 == 0 { app } else { window },
+        uID: BALLOON_ICON_ID,
+        uFlags: NIF_ICON | NIF_INFO,
+        dwInfoFlags: info_flags,
+        ..Default::default()
+    };
+    if window.0 != 0 {
+        data.uFlags |= NIF_MESSAGE;
+        data.uCallbackMessage = WM_BALLOON;
+    }
+    data.hIcon = unsafe { LoadIconW(None, icon_id) }.unwrap_or_default();
+    copy_wide(title, &mut data.szInfoTitle);
+    copy_wide(message, &mut data.szInfo);
+
+    BALLOON.with(|balloon| balloon.set(Balloon::Showing));
+    unsafe {
+        if Shell_NotifyIconW(NIM_ADD, &data).as_bool() {
+            let start = Instant::now();
+            let mut msg = MSG::default();
+            while start.elapsed() < icon.duration() && BALLOON.with(Cell::get) == Balloon::Showing {
+                while PeekMessageW(&mut msg, window, 0, 0, PM_REMOVE).as_bool() {
+                    let _ = TranslateMessage(&msg);
+                    DispatchMessageW(&msg);
+                }
+                std::thread::sleep(POLL_INTERVAL);
+            }
+            let _ = Shell_NotifyIconW(NIM_DELETE, &data);
+        }
+        if window.0 != 0 {
+            let _ = DestroyWindow(window);
+        }
+    }
+    BALLOON.with(Cell::get) == Balloon::Clicked
+}
@@ -0,0 +1,158 @@
+use once_cell::sync::Lazy;
+use regex::{Captures, Regex};
+use restro_keyboard_core::Transliterator;
+
+// What converting Bangla to Banglish or to Bijoy does with Bangla digits,
+// dates and the dari. Banglish readers mostly want 12.03.2024 and a period;
+// others want the numbers left as written. Bijoy fonts draw ASCII digits as
+// Bangla ones, so in Bijoy output only the dari follows the policy.
+
+/// Choices for `KeyboardSettings::reverse_numbers`, the first is the default.
+pub const POLICIES: [&str; 3] = [
+    "English digits and periods",
+    "English digits, keep the dari",
+    "Keep as written",
+];
+
+// Gregorian months as written in Bangla, with the common spellings
+const MONTHS: [(&str, &str); 12] = [
+    ("জানুয়ারি|জানুয়ারী", "January"),
+    ("ফেব্রুয়ারি|ফেব্রুয়ারী", "February"),
+    ("মার্চ", "March"),
+    ("এপ্রিল", "April"),
+    ("মে", "May"),
+    ("জুন", "June"),
+    ("জুলাই", "July"),
+    ("আগস্ট|আগষ্ট", "August"),
+    ("সেপ্টেম্বর", "September"),
+    ("অক্টোবর", "October"),
+    ("নভেম্বর", "November"),
+    ("ডিসেম্বর", "December"),
+];
+
+// A month name next to a number, as in ১২ মার্চ ২০২৪ or মার্চ ১২. য় may be
+// one code point or য and a nukta.
+static DATE_MONTH: Lazy<Regex> = Lazy::new(|| {
+    let names: Vec<&str> = MONTHS.iter().map(|(names, _)| *names).collect();
+    let names = names
+        .join("|")
+        .replace('\u{09DF}', "(?:\u{09DF}|\u{09AF}\u{09BC})");
+    Regex::new(&format!(
+        r"(\d[,\s]*)\b({})\b|\b({})\b([,\s]*\d)",
+        names, names
+    ))
+    .expect("month pattern")
+});
+
+fn english_digits(policy: &str) -> bool {
+    policy != POLICIES[2]
+}
+
+fn period(policy: &str) -> bool {
+    policy == POLICIES[0]
+}
+
+fn is_bangla_digit(c: char) -> bool {
+    ('০'..='৯').contains(&c)
+}
+
+fn month(name: &str) -> &'static str {
+    let name = name.replace("\u{09AF}\u{09BC}", "\u{09DF}");
+    MONTHS
+        .iter()
+        .find(|(names, _)| names.split('|').any(|spelling| spelling == name))
+        .map_or("", |(_, english)| english)
+}
+
+/// The text with Bangla digits as ASCII, month names in dates in English
+/// and the dari as a period, as far as the policy says.
+pub fn prepare(text: &str, policy: &str) -> String {
+    let mut text = text.to_string();
+    if english_digits(policy) {
+        text = DATE_MONTH
+            .replace_all(&text, |captures: &Captures| match captures.get(2) {
+                Some(name) => format!("{}{}", &captures[1], month(name.as_str())),
+                None => format!("{}{}", month(&captures[3]), &captures[4]),
+            })
+            .into_owned();
+        text = text
+            .chars()
+            .map(|c| match is_bangla_digit(c) {
+                true => char::from(b'0' + (c as u32 - '০' as u32) as u8),
+                false => c,
+            })
+            .collect();
+    }
+    if period(policy) {
+        text = text.replace('।', ".");
+    }
+    text
+}
+
+/// Bangla text as Banglish. Digits and the dari kept by the policy are
+/// left as they are rather than romanized.
+pub fn romanize(engine: &Transliterator, text: &str, policy: &str) -> String {
+    let text = prepare(text, policy);
+    let mut output = String::new();
+    let mut run = String::new();
+    for c in text.chars() {
+        if is_bangla_digit(c) || c == '।' {
+            output.push_str(&engine.romanize_text(&run));
+            run.clear();
+            output.push(c);
+        } else {
+            run.push(c);
+        }
+    }
+    output.push_str(&engine.romanize_text(&run));
+    output
+}
+
+/// Unicode Bangla as Bijoy text, with the dari as the policy says.
+pub fn to_bijoy(text: &str, policy: &str) -> String {
+    crate::bijoy::encode(&prepare(text, policy))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use restro_keyboard_core::Layout;
+
+    #[test]
+    fn dates_become_english() {
+        let text = "১২ মার্চ ২০২৪ থেকে জানুয়ারি ৫ পর্যন্ত।";
+        assert_eq!(
+            prepare(text, POLICIES[0]),
+            "12 March 2024 থেকে January 5 পর্যন্ত."
+        );
+        // The nukta spelling of য় too
+        assert_eq!(prepare("৫ জানুয\u{09BC}ারি", POLICIES[1]), "5 January");
+        // মে on its own is a word, not May; মেঘ has মে in it
+        assert_eq!(prepare("সে মেঘ দেখে", POLICIES[0]), "সে মেঘ দেখে");
+        assert_eq!(prepare("৩ মেঘ", POLICIES[0]), "3 মেঘ");
+    }
+
+    #[test]
+    fn the_policy_decides_what_is_kept() {
+        let text = "দাম ১,২০০ টাকা।";
+        assert_eq!(prepare(text, POLICIES[0]), "দাম 1,200 টাকা.");
+        assert_eq!(prepare(text, POLICIES[1]), "দাম 1,200 টাকা।");
+        assert_eq!(prepare(text, POLICIES[2]), text);
+    }
+
+    #[test]
+    fn kept_digits_are_not_romanized() {
+        let layout = Layout::phonetic();
+        let engine = Transliterator::new(&layout);
+        let kept = romanize(&engine, "আমি ১২টা।", POLICIES[2]);
+        assert!(kept.contains("১২") && kept.ends_with('।'), "{}", kept);
+        let english = romanize(&engine, "আমি ১২টা।", POLICIES[0]);
+        assert!(
+            english.contains("12") && english.ends_with('.'),
+            "{}",
+            english
+        );
+        assert_eq!(to_bijoy("১।", POLICIES[0]), "1.");
+        assert_eq!(to_bijoy("১।", POLICIES[2]), "1|");
+    }
+}
@@ -0,0 +1,126 @@
+use egui::{RichText, ViewportBuilder, ViewportId};
+use restro_keyboard_core::Layer;
+use windows::core::HSTRING;
+use windows::Win32::UI::WindowsAndMessaging::{
+    FindWindowW, GetWindowLongPtrW, SetWindowLongPtrW, GWL_EXSTYLE, WS_EX_NOACTIVATE,
+};
+
+const TITLE: &str = "On-screen keyboard";
+
+// Keys by what they type on a US keyboard without Shift, as fixed layouts
+// name them
+const ROWS: [&str; 4] = [
+    "`1234567890-=",
+    "qwertyuiop[]\\",
+    "asdfghjkl;'",
+    "zxcvbnm,./",
+];
+
+// Each row starts a little further right, as on a keyboard
+const ROW_INDENT: f32 = 14.0;
+
+/// The selected layout drawn as a keyboard; a click types the key's text
+/// into the app in front. The window never takes focus, so that app keeps
+/// its caret. Shift and AltGr apply to the next key only.
+#[derive(Default)]
+pub struct OnScreenKeyboard {
+    pub open: bool,
+    shift: bool,
+    altgr: bool,
+    // Whether the window was kept from taking focus since it opened
+    focus_blocked: bool,
+}
+
+// What a key types on the layer, or nothing
+fn key_text(key: char, layer: Layer) -> String {
+    let settings = crate::SETTINGS.snapshot();
+    if let Some(fixed) = crate::fixed_layout(&settings.state.layout) {
+        return fixed.get(key, layer).unwrap_or_default().to_string();
+    }
+    // Phonetic layouts convert letters, either case, and unshifted digits
+    let roman = match layer {
+        Layer::Base if key.is_ascii_alphanumeric() => key,
+        Layer::Shift if key.is_ascii_alphabetic() => key.to_ascii_uppercase(),
+        _ => return String::new(),
+    };
+    let layouts = crate::layouts();
+    crate::engine(&layouts, &settings).transliterate(&roman.to_string())
+}
+
+// A click on a WS_EX_NOACTIVATE window leaves the foreground app active
+fn block_focus() -> bool {
+    unsafe {
+        let window = FindWindowW(None, &HSTRING::from(TITLE));
+        if window.0 == 0 {
+            return false;
+        }
+        let style = GetWindowLongPtrW(window, GWL_EXSTYLE);
+        SetWindowLongPtrW(window, GWL_EXSTYLE, style | WS_EX_NOACTIVATE.0 as isize);
+    }
+    true
+}
+
+fn type_text(text: &str) {
+    crate::clear_composition();
+    crate::HISTORY.lock().unwrap().push(text);
+    crate::simulate_unicode_input(text);
+}
+
+impl OnScreenKeyboard {
+    pub fn show(&mut self, ctx: &egui::Context, font_size: f32) {
+        if !self.open {
+            self.focus_blocked = false;
+            return;
+        }
+        let layer = Layer::new(self.shift, self.altgr);
+        let mut typed = None;
+
+        ctx.show_viewport_immediate(
+            ViewportId::from_hash_of("on_screen_keyboard"),
+            ViewportBuilder::default()
+                .with_title(TITLE)
+                .with_inner_size([600.0, 260.0])
+                .with_always_on_top()
+                .with_active(false),
+            |ctx, _class| {
+                egui::CentralPanel::default().show(ctx, |ui| {
+                    for (index, row) in ROWS.iter().enumerate() {
+                        ui.horizontal(|ui| {
+                            ui.add_space(ROW_INDENT * index as f32);
+                            for key in row.chars() {
+                                let text = key_text(key, layer);
+                                let label = format!("{}\n{}", text, key);
+                                let button =
+                                    egui::Button::new(RichText::new(label).size(font_size + 2.0))
+                                        .min_size(egui::vec2(36.0, 44.0));
+                                if ui.add_enabled(!text.is_empty(), button).clicked() {
+                                    typed = Some(text);
+                                }
+                            }
+                        });
+                    }
+                    ui.horizontal(|ui| {
+                        ui.toggle_value(&mut self.shift, "Shift");
+                        ui.toggle_value(&mut self.altgr, "AltGr");
+                        let space = egui::Button::new("Space").min_size(egui::vec2(280.0, 36.0));
+                        if ui.add(space).clicked() {
+                            typed = Some(" ".to_string());
+                        }
+                    });
+                });
+                if ctx.input(|i| i.viewport().close_requested()) {
+                    self.open = false;
+                }
+            },
+        );
+
+        if !self.focus_blocked {
+            self.focus_blocked = block_focus();
+        }
+        if let Some(text) = typed {
+            self.shift = false;
+            self.altgr = false;
+            type_text(&text);
+        }
+    }
+}
@@ -0,0 +1,200 @@
+use crate::caret::Caret;
+use egui::{Pos2, Rect, Vec2, ViewportBuilder};
+use windows::Win32::Graphics::Gdi::{
+    GetMonitorInfoW, MonitorFromPoint, MONITORINFO, MONITOR_DEFAULTTONEAREST,
+};
+
+// The small windows drawn next to the caret of another app: the candidate
+// popup, the unmatched-keys notice and the word preview. They float above
+// everything, never take focus and let clicks through to the app. Each
+// keeps what it shows in a static the hook sets; the UI copies it out before
+// drawing, so the hook never waits for a frame to be drawn.
+
+/// Which side of the caret's line an overlay goes on. Either flips to the
+/// other side when the screen ends.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Side {
+    Below,
+    Above,
+}
+
+// Space between the line and an overlay above it, in points
+const GAP: f32 = 8.0;
+
+/// Where an overlay of `size` goes for a caret at `caret`, both the caret
+/// and the work area in physical pixels and the rest in points. The caret
+/// is the bottom of a line about `line_height` tall. The overlay is kept
+/// inside the work area when one is known.
+pub fn place(
+    caret: Pos2,
+    scale: f32,
+    line_height: f32,
+    size: Vec2,
+    side: Side,
+    work_area: Option<Rect>,
+) -> Pos2 {
+    let caret = caret / scale;
+    let below = caret.y;
+    let above = caret.y - line_height - size.y - GAP;
+    let Some(area) = work_area.map(|area| Rect::from_min_max(area.min / scale, area.max / scale))
+    else {
+        return egui::pos2(caret.x, if side == Side::Below { below } else { above });
+    };
+
+    let fits_below = below + size.y <= area.max.y;
+    let fits_above = above >= area.min.y;
+    let y = match side {
+        Side::Below if fits_below || !fits_above => below,
+        Side::Above if fits_above || !fits_below => above,
+        Side::Below => above,
+        Side::Above => below,
+    };
+    // Wider or taller than the area, it starts at the area's edge
+    egui::pos2(
+        caret.x.min(area.max.x - size.x).max(area.min.x),
+        y.min(area.max.y - size.y).max(area.min.y),
+    )
+}
+
+// The work area of the monitor the caret is on, without the taskbar
+fn work_area(caret: &Caret) -> Option<Rect> {
+    unsafe {
+        let monitor = MonitorFromPoint(caret.position, MONITOR_DEFAULTTONEAREST);
+        let mut info = MONITORINFO {
+            cbSize: std::mem::size_of::<MONITORINFO>() as u32,
+            ..Default::default()
+        };
+        if !GetMonitorInfoW(monitor, &mut info).as_bool() {
+            return None;
+        }
+        let area = info.rcWork;
+        Some(Rect::from_min_max(
+            egui::pos2(area.left as f32, area.top as f32),
+            egui::pos2(area.right as f32, area.bottom as f32),
+        ))
+    }
+}
+
+/// Where an overlay goes next to `caret`, in points for egui.
+pub fn position(
+    ctx: &egui::Context,
+    caret: &Caret,
+    line_height: f32,
+    size: Vec2,
+    side: Side,
+) -> Pos2 {
+    let physical = egui::pos2(caret.position.x as f32, caret.position.y as f32);
+    place(
+        physical,
+        ctx.pixels_per_point(),
+        line_height,
+        size,
+        side,
+        work_area(caret),
+    )
+}
+
+/// A borderless window at `position` that stays on top, stays off the
+/// taskbar, never takes focus and lets clicks through.
+pub fn builder(title: &str, position: Pos2, size: Vec2) -> ViewportBuilder {
+    ViewportBuilder::default()
+        .with_title(title)
+        .with_position(position)
+        .with_inner_size(size)
+        .with_decorations(false)
+        .with_resizable(false)
+        .with_always_on_top()
+        .with_taskbar(false)
+        .with_active(false)
+        .with_mouse_passthrough(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SIZE: Vec2 = egui::vec2(100.0, 50.0);
+
+    fn screen() -> Option<Rect> {
+        Some(Rect::from_min_max(
+            egui::pos2(0.0, 0.0),
+            egui::pos2(1000.0, 700.0),
+        ))
+    }
+
+    #[test]
+    fn goes_below_or_above_the_line() {
+        let caret = egui::pos2(200.0, 300.0);
+        assert_eq!(
+            place(caret, 1.0, 20.0, SIZE, Side::Below, screen()),
+            egui::pos2(200.0, 300.0)
+        );
+        assert_eq!(
+            place(caret, 1.0, 20.0, SIZE, Side::Above, screen()),
+            egui::pos2(200.0, 300.0 - 20.0 - 50.0 - GAP)
+        );
+    }
+
+    #[test]
+    fn flips_at_the_screen_edges() {
+        let near_bottom = egui::pos2(200.0, 680.0);
+        assert_eq!(
+            place(near_bottom, 1.0, 20.0, SIZE, Side::Below, screen()).y,
+            680.0 - 20.0 - 50.0 - GAP
+        );
+        let near_top = egui::pos2(200.0, 40.0);
+        assert_eq!(
+            place(near_top, 1.0, 20.0, SIZE, Side::Above, screen()).y,
+            40.0
+        );
+    }
+
+    #[test]
+    fn stays_inside_the_work_area() {
+        let near_right = egui::pos2(990.0, 300.0);
+        assert_eq!(
+            place(near_right, 1.0, 20.0, SIZE, Side::Below, screen()).x,
+            900.0
+        );
+        // A second monitor left of the primary one has negative coordinates
+        let left_monitor = Some(Rect::from_min_max(
+            egui::pos2(-1920.0, 0.0),
+            egui::pos2(0.0, 1080.0),
+        ));
+        assert_eq!(
+            place(
+                egui::pos2(-2000.0, 300.0),
+                1.0,
+                20.0,
+                SIZE,
+                Side::Below,
+                left_monitor
+            )
+            .x,
+            -1920.0
+        );
+    }
+
+    #[test]
+    fn converts_physical_pixels_to_points() {
+        // At 150% a caret at 300 physical pixels is 200 points in
+        let placed = place(
+            egui::pos2(300.0, 300.0),
+            1.5,
+            20.0,
+            SIZE,
+            Side::Below,
+            screen(),
+        );
+        assert_eq!(placed, egui::pos2(200.0, 200.0));
+    }
+
+    #[test]
+    fn without_a_work_area_nothing_is_clamped() {
+        let caret = egui::pos2(5000.0, -10.0);
+        assert_eq!(
+            place(caret, 1.0, 20.0, SIZE, Side::Below, None),
+            egui::pos2(5000.0, -10.0)
+        );
+    }
+}
@@ -0,0 +1,52 @@
+use crate::settings::KeyboardSettings;
+use std::ffi::c_void;
+use windows::Win32::System::Threading::{
+    GetCurrentThread, SetThreadInformation, SetThreadPriority, ThreadPowerThrottling,
+    THREAD_POWER_THROTTLING_CURRENT_VERSION, THREAD_POWER_THROTTLING_EXECUTION_SPEED,
+    THREAD_POWER_THROTTLING_STATE, THREAD_PRIORITY_BELOW_NORMAL, THREAD_PRIORITY_HIGHEST,
+    THREAD_PRIORITY_NORMAL,
+};
+
+/// Applies the battery settings to the calling thread, which must be the UI
+/// thread. The hook thread keeps its own priority either way.
+pub fn apply_to_ui_thread(settings: &KeyboardSettings) {
+    let priority = if settings.low_priority_ui {
+        THREAD_PRIORITY_BELOW_NORMAL
+    } else {
+        THREAD_PRIORITY_NORMAL
+    };
+
+    // Efficiency mode (EcoQoS) lets Windows run the thread on efficient
+    // cores at lower clock speeds; with an empty mask Windows decides again
+    let throttling = THREAD_POWER_THROTTLING_STATE {
+        Version: THREAD_POWER_THROTTLING_CURRENT_VERSION,
+        ControlMask: if settings.efficiency_mode {
+            THREAD_POWER_THROTTLING_EXECUTION_SPEED
+        } else {
+            0
+        },
+        StateMask: if settings.efficiency_mode {
+            THREAD_POWER_THROTTLING_EXECUTION_SPEED
+        } else {
+            0
+        },
+    };
+
+    unsafe {
+        let thread = GetCurrentThread();
+        let _ = SetThreadPriority(thread, priority);
+        let _ = SetThreadInformation(
+            thread,
+            ThreadPowerThrottling,
+            &throttling as *const _ as *const c_void,
+            std::mem::size_of::<THREAD_POWER_THROTTLING_STATE>() as u32,
+        );
+    }
+}
+
+// Keystrokes wait on the hook, so its thread runs ahead of everything else
+pub fn boost_hook_thread() {
+    unsafe {
+        let _ = SetThreadPriority(GetCurrentThread(), THREAD_PRIORITY_HIGHEST);
+    }
+}
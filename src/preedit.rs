@@ -0,0 +1,93 @@
+use crate::caret::{self, Caret};
+use crate::mask;
+use crate::overlay::{self, Side};
+use crate::settings::KeyboardSettings;
+use egui::{Color32, RichText, ViewportId};
+use once_cell::sync::OnceCell;
+use std::sync::Mutex;
+
+#[derive(Clone)]
+struct Preedit {
+    // Where the word started, so the overlay stays put while it grows
+    caret: Caret,
+    roman: String,
+    bangla: String,
+}
+
+// Updated by the hook thread, drawn by the UI on its next frame
+static CURRENT: Mutex<Option<Preedit>> = Mutex::new(None);
+static UI_CONTEXT: OnceCell<egui::Context> = OnceCell::new();
+
+/// Lets the hook wake the UI when the word changes.
+pub fn attach(ctx: egui::Context) {
+    let _ = UI_CONTEXT.set(ctx);
+}
+
+fn repaint() {
+    if let Some(ctx) = UI_CONTEXT.get() {
+        ctx.request_repaint();
+    }
+}
+
+/// Shows the keys of the word being typed and what they convert to, or
+/// hides the overlay when there is no word. Called from the hook thread.
+pub fn update(roman: &str, settings: &KeyboardSettings) {
+    if roman.is_empty() {
+        close();
+        return;
+    }
+    let layouts = crate::layouts();
+    let bangla = crate::engine(&layouts, settings).transliterate(roman);
+    let mut current = CURRENT.lock().unwrap();
+    let caret = match current.as_ref() {
+        Some(preedit) => preedit.caret,
+        None => match caret::locate() {
+            Some(caret) => caret,
+            None => return,
+        },
+    };
+    *current = Some(Preedit {
+        caret,
+        roman: roman.to_string(),
+        bangla,
+    });
+    drop(current);
+    repaint();
+}
+
+pub fn close() {
+    if CURRENT.lock().unwrap().take().is_some() {
+        repaint();
+    }
+}
+
+/// Draws the overlay above the caret line, clear of the candidate popup
+/// below it. Like the popup it never takes focus or clicks.
+pub fn show(ctx: &egui::Context, font_size: f32) {
+    let Some(preedit) = CURRENT.lock().unwrap().clone() else {
+        return;
+    };
+    let settings = crate::SETTINGS.snapshot();
+
+    let size = egui::vec2(220.0, font_size * 2.0 + 16.0);
+    let position = overlay::position(ctx, &preedit.caret, font_size, size, Side::Above);
+
+    ctx.show_viewport_immediate(
+        ViewportId::from_hash_of("preedit"),
+        overlay::builder("Word being typed", position, size),
+        |ctx, _class| {
+            egui::CentralPanel::default().show(ctx, |ui| {
+                ui.label(
+                    RichText::new(mask::shown(&settings, &preedit.roman))
+                        .monospace()
+                        .weak(),
+                );
+                ui.label(
+                    RichText::new(mask::shown(&settings, &preedit.bangla))
+                        .size(font_size + 2.0)
+                        .color(Color32::from_rgb(0, 100, 0)),
+                );
+            });
+        },
+    );
+}
@@ -0,0 +1,227 @@
+use once_cell::sync::OnceCell;
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use windows::core::PWSTR;
+use windows::Win32::Foundation::CloseHandle;
+use windows::Win32::System::Threading::{
+    OpenProcess, QueryFullProcessImageNameW, PROCESS_NAME_WIN32, PROCESS_QUERY_LIMITED_INFORMATION,
+};
+use windows::Win32::UI::WindowsAndMessaging::{GetForegroundWindow, GetWindowThreadProcessId};
+
+// Failures the user should hear about once, not a line per keystroke.
+// Repeats of the same problem in the same place are counted, the console
+// gets a line per problem at most once a minute, and the main window shows
+// one link until the problem is looked at in Diagnostics.
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Problem {
+    // SendInput sent fewer events than asked
+    InputBlocked,
+    KeyboardHookFailed,
+    FocusWatchFailed,
+    MouseWatchFailed,
+}
+
+impl Problem {
+    pub fn summary(self) -> &'static str {
+        match self {
+            Problem::InputBlocked => "Typed text didn't reach the app",
+            Problem::KeyboardHookFailed => "The keyboard hook couldn't be installed",
+            Problem::FocusWatchFailed => "Focus changes can't be watched",
+            Problem::MouseWatchFailed => "Mouse clicks can't be watched",
+        }
+    }
+
+    pub fn advice(self) -> &'static str {
+        match self {
+            Problem::InputBlocked => {
+                "Windows keeps programs from typing into apps run as administrator. Run Restro Keyboard as administrator too, or type into that app in English."
+            }
+            Problem::KeyboardHookFailed => {
+                "Restart Restro Keyboard. If it keeps happening, security software may be blocking keyboard hooks."
+            }
+            Problem::FocusWatchFailed | Problem::MouseWatchFailed => {
+                "Typing still works, but a word may continue in the wrong place after switching fields. Restart Restro Keyboard to try again."
+            }
+        }
+    }
+}
+
+// Console lines for one problem are at least this far apart
+const PRINT_INTERVAL: Duration = Duration::from_secs(60);
+// Oldest reports are dropped beyond this
+const MAX_REPORTS: usize = 20;
+
+/// One problem in one place, however often it happened.
+#[derive(Clone, Debug)]
+pub struct Report {
+    pub problem: Problem,
+    // Where it happened, such as the app in front, or what failed
+    pub detail: String,
+    pub count: u32,
+    pub last: Instant,
+}
+
+#[derive(Default)]
+struct Log {
+    reports: Vec<Report>,
+    // When each problem was last written to the console
+    printed: Vec<(Problem, Instant)>,
+    // The latest problem nobody has looked at
+    unseen: Option<Problem>,
+}
+
+impl Log {
+    /// Records a problem, returning whether it is due a console line.
+    fn record(&mut self, problem: Problem, detail: &str, now: Instant) -> bool {
+        match self
+            .reports
+            .iter_mut()
+            .find(|report| report.problem == problem && report.detail == detail)
+        {
+            Some(report) => {
+                report.count += 1;
+                report.last = now;
+            }
+            None => {
+                if self.reports.len() == MAX_REPORTS {
+                    self.reports.remove(0);
+                }
+                self.reports.push(Report {
+                    problem,
+                    detail: detail.to_string(),
+                    count: 1,
+                    last: now,
+                });
+            }
+        }
+        self.unseen = Some(problem);
+
+        match self.printed.iter_mut().find(|(p, _)| *p == problem) {
+            Some((_, at)) if now.duration_since(*at) < PRINT_INTERVAL => false,
+            Some((_, at)) => {
+                *at = now;
+                true
+            }
+            None => {
+                self.printed.push((problem, now));
+                true
+            }
+        }
+    }
+}
+
+static LOG: Mutex<Option<Log>> = Mutex::new(None);
+static UI_CONTEXT: OnceCell<egui::Context> = OnceCell::new();
+
+/// Lets problems from other threads show up without waiting for a frame.
+pub fn attach(ctx: egui::Context) {
+    let _ = UI_CONTEXT.set(ctx);
+}
+
+/// Records a problem. Safe to call on every keystroke.
+pub fn report(problem: Problem, detail: &str) {
+    let due = LOG.lock().unwrap().get_or_insert_with(Log::default).record(
+        problem,
+        detail,
+        Instant::now(),
+    );
+    if due {
+        eprintln!("{} ({}): {}", problem.summary(), detail, problem.advice());
+    }
+    if let Some(ctx) = UI_CONTEXT.get() {
+        ctx.request_repaint();
+    }
+}
+
+/// Most recent last.
+pub fn reports() -> Vec<Report> {
+    LOG.lock()
+        .unwrap()
+        .as_ref()
+        .map_or_else(Vec::new, |log| log.reports.clone())
+}
+
+/// The latest problem not looked at yet, for the main window to point at.
+pub fn unseen() -> Option<Problem> {
+    LOG.lock().unwrap().as_ref().and_then(|log| log.unseen)
+}
+
+pub fn mark_seen() {
+    if let Some(log) = LOG.lock().unwrap().as_mut() {
+        log.unseen = None;
+    }
+}
+
+pub fn clear() {
+    *LOG.lock().unwrap() = None;
+}
+
+/// The file name of the app in front, such as notepad.exe.
+pub fn foreground_app() -> String {
+    let name = unsafe {
+        let mut process_id = 0;
+        GetWindowThreadProcessId(GetForegroundWindow(), Some(&mut process_id));
+        OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, false, process_id)
+            .ok()
+            .and_then(|process| {
+                let mut buffer = [0u16; 260];
+                let mut len = buffer.len() as u32;
+                let queried = QueryFullProcessImageNameW(
+                    process,
+                    PROCESS_NAME_WIN32,
+                    PWSTR(buffer.as_mut_ptr()),
+                    &mut len,
+                );
+                let _ = CloseHandle(process);
+                queried.ok()?;
+                let path = String::from_utf16_lossy(&buffer[..len as usize]);
+                Path::new(&path)
+                    .file_name()
+                    .map(|name| name.to_string_lossy().into_owned())
+            })
+    };
+    name.unwrap_or_else(|| "an unknown app".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn repeats_are_counted_once() {
+        let mut log = Log::default();
+        let start = Instant::now();
+        log.record(Problem::InputBlocked, "app.exe", start);
+        log.record(Problem::InputBlocked, "app.exe", start);
+        log.record(Problem::InputBlocked, "other.exe", start);
+        let counts: Vec<_> = log
+            .reports
+            .iter()
+            .map(|report| (report.detail.as_str(), report.count))
+            .collect();
+        assert_eq!(counts, [("app.exe", 2), ("other.exe", 1)]);
+    }
+
+    #[test]
+    fn console_lines_are_rate_limited_per_problem() {
+        let mut log = Log::default();
+        let start = Instant::now();
+        assert!(log.record(Problem::InputBlocked, "app.exe", start));
+        assert!(!log.record(Problem::InputBlocked, "other.exe", start));
+        assert!(log.record(Problem::MouseWatchFailed, "", start));
+        assert!(log.record(Problem::InputBlocked, "app.exe", start + PRINT_INTERVAL));
+    }
+
+    #[test]
+    fn old_reports_make_room() {
+        let mut log = Log::default();
+        let start = Instant::now();
+        for index in 0..=MAX_REPORTS {
+            log.record(Problem::InputBlocked, &index.to_string(), start);
+        }
+        assert_eq!(log.reports.len(), MAX_REPORTS);
+        assert_eq!(log.reports[0].detail, "1");
+    }
+}
@@ -0,0 +1,14 @@
+use std::fs::File;
+use std::io::BufWriter;
+use tracing_flame::{FlameLayer, FlushGuard};
+use tracing_subscriber::prelude::*;
+
+// Folded stacks are written here; render them with inferno-flamegraph
+const FOLDED_STACKS_PATH: &str = "tracing.folded";
+
+/// Installs the flame layer. Spans are flushed when the guard is dropped.
+pub fn init() -> Result<FlushGuard<BufWriter<File>>, Box<dyn std::error::Error>> {
+    let (flame_layer, guard) = FlameLayer::with_file(FOLDED_STACKS_PATH)?;
+    tracing_subscriber::registry().with(flame_layer).init();
+    Ok(guard)
+}
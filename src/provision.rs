@@ -0,0 +1,241 @@
+use crate::mappings::{self, Mapping};
+use crate::settings::KeyboardSettings;
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use std::fs;
+use std::path::PathBuf;
+use windows::core::w;
+use windows::Win32::System::Registry::{RegGetValueW, HKEY_LOCAL_MACHINE, RRF_RT_REG_SZ};
+
+// Unattended setup for many desks. An admin writes a config like
+// assets/deploy/provision.json:
+//
+// {
+//   "settings": { "startup_language": "Bangla", "custom_font": "%ProgramData%\\Fonts\\Kalpurush.ttf" },
+//   "mappings": [{ "roman": "rri", "bangla": "ঋ", "category": "Vowels" }]
+// }
+//
+// Settings left out keep the user's value; mappings, when given, replace
+// the user's. %NAME% in any text is replaced by that environment variable,
+// so one file serves every user. It is applied by `--provision <file>`, or
+// on a user's first run from the file that the ConfigTemplate value of
+// HKLM\SOFTWARE\Policies\RestroKeyboard names (set by Group Policy or an
+// installer), else from %ProgramData%\RestroKeyboard\provision.json.
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Config {
+    #[serde(default)]
+    settings: Map<String, Value>,
+    #[serde(default)]
+    mappings: Option<Vec<Mapping>>,
+}
+
+// Replaces %NAME% with the variable's value; %% is a literal percent sign
+fn expand(text: &str) -> Result<String, String> {
+    let mut expanded = String::new();
+    let mut rest = text;
+    while let Some(start) = rest.find('%') {
+        expanded.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        let end = after
+            .find('%')
+            .ok_or_else(|| format!("unclosed placeholder in \"{}\"", text))?;
+        let name = &after[..end];
+        if name.is_empty() {
+            expanded.push('%');
+        } else {
+            let value = std::env::var(name)
+                .map_err(|_| format!("%{}% is not set on this computer", name))?;
+            expanded.push_str(&value);
+        }
+        rest = &after[end + 1..];
+    }
+    expanded.push_str(rest);
+    Ok(expanded)
+}
+
+fn expand_all(value: &mut Value) -> Result<(), String> {
+    match value {
+        Value::String(text) => *text = expand(text)?,
+        Value::Array(items) => items.iter_mut().try_for_each(expand_all)?,
+        Value::Object(fields) => fields.values_mut().try_for_each(expand_all)?,
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Lays a config over the current settings. Returns the new settings and
+/// the mappings to save, if the config has any. Unknown settings are an
+/// error, so a typo isn't silently ignored.
+fn apply(
+    config: &str,
+    current: &KeyboardSettings,
+) -> Result<(KeyboardSettings, Option<Vec<Mapping>>), String> {
+    let mut config: Value = serde_json::from_str(config).map_err(|err| err.to_string())?;
+    expand_all(&mut config)?;
+    let config: Config = serde_json::from_value(config).map_err(|err| err.to_string())?;
+
+    let Value::Object(mut settings) =
+        serde_json::to_value(current).map_err(|err| err.to_string())?
+    else {
+        unreachable!("settings serialize to an object");
+    };
+    for (key, value) in config.settings {
+        if !settings.contains_key(&key) {
+            return Err(format!("unknown setting \"{}\"", key));
+        }
+        settings.insert(key, value);
+    }
+    let settings =
+        serde_json::from_value(Value::Object(settings)).map_err(|err| err.to_string())?;
+
+    if let Some(problem) = config.mappings.iter().flatten().find_map(Mapping::problem) {
+        return Err(problem);
+    }
+    Ok((settings, config.mappings))
+}
+
+fn apply_file(path: &str) -> Result<(), String> {
+    let config = fs::read_to_string(path).map_err(|err| format!("{}: {}", path, err))?;
+    let (settings, mappings) = apply(&config, &KeyboardSettings::load())?;
+    settings
+        .save()
+        .map_err(|err| format!("could not save the settings: {}", err))?;
+    if let Some(mappings) = mappings {
+        mappings::save(&mappings).map_err(|err| format!("could not save the mappings: {}", err))?;
+    }
+    Ok(())
+}
+
+/// `--provision <config>`: applies an admin's config to this user's
+/// settings and exits.
+pub fn run(path: &str) -> Result<(), Box<dyn std::error::Error>> {
+    apply_file(path)?;
+    println!(
+        "Applied {}. Restart Restro Keyboard if it is running.",
+        path
+    );
+    Ok(())
+}
+
+// The file named by the policy value, if it is set
+fn policy_template() -> Option<PathBuf> {
+    let mut buffer = [0u16; 260];
+    let mut size = std::mem::size_of_val(&buffer) as u32;
+    unsafe {
+        RegGetValueW(
+            HKEY_LOCAL_MACHINE,
+            w!("SOFTWARE\\Policies\\RestroKeyboard"),
+            w!("ConfigTemplate"),
+            RRF_RT_REG_SZ,
+            None,
+            Some(buffer.as_mut_ptr().cast()),
+            Some(&mut size),
+        )
+        .ok()?;
+    }
+    // The size includes the terminating null
+    let len = (size as usize / 2).saturating_sub(1);
+    Some(PathBuf::from(String::from_utf16_lossy(&buffer[..len])))
+}
+
+/// On a user's first run, before the settings are read, applies the
+/// machine's preseeded config if there is one. A broken config is reported
+/// and the defaults are used.
+pub fn apply_preseeded() {
+    let first_run = KeyboardSettings::path().is_some_and(|path| !path.exists());
+    if !first_run {
+        return;
+    }
+    let template = policy_template().or_else(|| {
+        let data = std::env::var_os("ProgramData")?;
+        Some(
+            PathBuf::from(data)
+                .join("RestroKeyboard")
+                .join("provision.json"),
+        )
+    });
+    let Some(template) = template.filter(|path| path.is_file()) else {
+        return;
+    };
+    if let Err(err) = apply_file(&template.to_string_lossy()) {
+        eprintln!("Could not apply {}: {}", template.display(), err);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn placeholders_are_replaced_by_variables() {
+        std::env::set_var("RESTRO_PROVISION_TEST", "C:\\Shared");
+        assert_eq!(
+            expand("%RESTRO_PROVISION_TEST%\\Kalpurush.ttf").as_deref(),
+            Ok("C:\\Shared\\Kalpurush.ttf")
+        );
+        assert_eq!(expand("100%%").as_deref(), Ok("100%"));
+        assert_eq!(expand("no placeholders").as_deref(), Ok("no placeholders"));
+        assert!(expand("%RESTRO_PROVISION_TEST").is_err());
+        assert!(expand("%RESTRO_PROVISION_UNSET%").is_err());
+    }
+
+    #[test]
+    fn settings_left_out_keep_the_users_values() {
+        let current = KeyboardSettings {
+            custom_font: "mine.ttf".to_string(),
+            ..KeyboardSettings::default()
+        };
+        let (settings, mappings) = apply(
+            r#"{ "settings": { "startup_language": "Bangla" } }"#,
+            &current,
+        )
+        .unwrap();
+        assert_eq!(settings.startup_language, "Bangla");
+        assert_eq!(settings.custom_font, "mine.ttf");
+        assert_eq!(mappings, None);
+    }
+
+    #[test]
+    fn unknown_keys_are_rejected() {
+        let current = KeyboardSettings::default();
+        let err = apply(
+            r#"{ "settings": { "startup_langauge": "Bangla" } }"#,
+            &current,
+        )
+        .err()
+        .unwrap();
+        assert!(err.contains("startup_langauge"), "{}", err);
+        assert!(apply(r#"{ "setting": {} }"#, &current).is_err());
+    }
+
+    #[test]
+    fn invalid_mappings_are_rejected() {
+        let current = KeyboardSettings::default();
+        for mapping in [
+            r#"{ "roman": "", "bangla": "ক", "category": "Consonants" }"#,
+            r#"{ "roman": "kkkk", "bangla": "ক", "category": "Consonants" }"#,
+            r#"{ "roman": "ক", "bangla": "ক", "category": "Consonants" }"#,
+            r#"{ "roman": "k", "bangla": " ", "category": "Consonants" }"#,
+        ] {
+            let config = format!(r#"{{ "mappings": [{}] }}"#, mapping);
+            assert!(apply(&config, &current).is_err(), "{}", mapping);
+        }
+    }
+
+    #[test]
+    fn the_sample_config_applies_cleanly() {
+        if std::env::var_os("ProgramData").is_none() {
+            std::env::set_var("ProgramData", "C:\\ProgramData");
+        }
+        let config = include_str!("../assets/deploy/provision.json");
+        let (settings, mappings) = apply(config, &KeyboardSettings::default()).unwrap();
+        assert_eq!(settings.startup_language, "Bangla");
+        assert!(settings
+            .custom_font
+            .ends_with("RestroKeyboard\\Kalpurush.ttf"));
+        assert!(!settings.custom_font.contains('%'));
+        assert_eq!(mappings.map(|mappings| mappings.len()), Some(1));
+    }
+}
@@ -0,0 +1,168 @@
+use crate::settings::KeyboardSettings;
+use crate::state::Language;
+use std::io::{Read, Write};
+use std::net::TcpListener;
+use std::os::windows::process::CommandExt;
+use std::process::Command;
+use std::time::Duration;
+
+// Language changes reach RGB tools within a quarter second
+const PUBLISH_INTERVAL: Duration = Duration::from_millis(250);
+
+// Keeps `cmd /C` from flashing a console window
+const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+
+/// The mode shown to the outside: English whenever the keyboard is off.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Mode {
+    pub enabled: bool,
+    pub language: Language,
+}
+
+impl Mode {
+    fn of(settings: &KeyboardSettings) -> Self {
+        Self {
+            enabled: settings.state.enabled,
+            language: if settings.state.enabled {
+                settings.state.language
+            } else {
+                Language::English
+            },
+        }
+    }
+
+    fn to_json(self) -> String {
+        serde_json::json!({ "enabled": self.enabled, "language": self.language }).to_string()
+    }
+}
+
+/// Somewhere the language mode is sent, such as a local API or an RGB tool.
+pub trait Sink {
+    // Called once when the sink is set up and then on every change
+    fn publish(&mut self, mode: Mode);
+
+    // Called on every tick, for sinks that answer requests
+    fn poll(&mut self) {}
+}
+
+/// Answers any connection on localhost with the mode as JSON, e.g.
+/// `curl http://127.0.0.1:48100`.
+pub struct LocalApi {
+    listener: TcpListener,
+    body: String,
+}
+
+impl LocalApi {
+    pub fn bind(port: u16) -> std::io::Result<Self> {
+        let listener = TcpListener::bind(("127.0.0.1", port))?;
+        listener.set_nonblocking(true)?;
+        Ok(Self {
+            listener,
+            body: String::new(),
+        })
+    }
+}
+
+impl Sink for LocalApi {
+    fn publish(&mut self, mode: Mode) {
+        self.body = mode.to_json();
+    }
+
+    fn poll(&mut self) {
+        while let Ok((mut stream, _)) = self.listener.accept() {
+            let _ = stream.set_nonblocking(false);
+            let _ = stream.set_read_timeout(Some(PUBLISH_INTERVAL));
+            // The request itself doesn't matter, every path gets the mode
+            let _ = stream.read(&mut [0; 1024]);
+            let _ = write!(
+                stream,
+                "HTTP/1.0 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
+                self.body.len(),
+                self.body
+            );
+        }
+    }
+}
+
+/// Runs a user command for each language, e.g.
+/// `OpenRGB.exe --profile Bangla` to recolor the keyboard.
+pub struct LanguageCommand {
+    pub bangla: String,
+    pub english: String,
+}
+
+impl Sink for LanguageCommand {
+    fn publish(&mut self, mode: Mode) {
+        let command = match mode.language {
+            Language::Bangla => &self.bangla,
+            Language::English => &self.english,
+        };
+        if command.trim().is_empty() {
+            return;
+        }
+        let _ = Command::new("cmd")
+            .args(["/C", command.trim()])
+            .creation_flags(CREATE_NO_WINDOW)
+            .spawn();
+    }
+}
+
+// The local API when it is switched on in the settings
+fn local_api(settings: &KeyboardSettings) -> Option<LocalApi> {
+    if !settings.state_api {
+        return None;
+    }
+    LocalApi::bind(settings.state_api_port)
+        .map_err(|err| {
+            eprintln!(
+                "Could not serve the language state on port {}: {}",
+                settings.state_api_port, err
+            )
+        })
+        .ok()
+}
+
+/// Watches the settings and sends the language mode to every sink when it
+/// changes. The local API is set up again when its settings change; edits
+/// to the commands only take effect on the next change of mode, so a
+/// half-typed command is never run.
+pub fn spawn() {
+    std::thread::spawn(|| {
+        let mut api_config = None;
+        let mut api = None;
+        let mut command = LanguageCommand {
+            bangla: String::new(),
+            english: String::new(),
+        };
+        let mut last_mode = None;
+        loop {
+            let settings = crate::SETTINGS.snapshot();
+            let mode = Mode::of(&settings);
+            let current = (settings.state_api, settings.state_api_port);
+            if api_config != Some(current) {
+                // Drop the old listener first so the port is free to bind again
+                drop(api.take());
+                api = local_api(&settings);
+                if let Some(api) = &mut api {
+                    api.publish(mode);
+                }
+                api_config = Some(current);
+            }
+            command.bangla.clone_from(&settings.state_command_bangla);
+            command.english.clone_from(&settings.state_command_english);
+
+            if last_mode != Some(mode) {
+                if let Some(api) = &mut api {
+                    api.publish(mode);
+                }
+                command.publish(mode);
+                last_mode = Some(mode);
+            }
+            if let Some(api) = &mut api {
+                api.poll();
+            }
+
+            std::thread::sleep(PUBLISH_INTERVAL);
+        }
+    });
+}
@@ -0,0 +1,174 @@
+use crate::caret::Caret;
+use egui::{Key, RichText, ViewportBuilder, ViewportId};
+use once_cell::sync::OnceCell;
+use std::sync::Mutex;
+use std::time::Duration;
+use windows::Win32::UI::WindowsAndMessaging::SetForegroundWindow;
+
+// Characters offered by category, for what the phonetic layout makes awkward
+const CATEGORIES: [(&str, &[&str]); 5] = [
+    (
+        "Vowels",
+        &["অ", "আ", "ই", "ঈ", "উ", "ঊ", "ঋ", "এ", "ঐ", "ও", "ঔ"],
+    ),
+    (
+        "Vowel signs",
+        &["া", "ি", "ী", "ু", "ূ", "ৃ", "ে", "ৈ", "ো", "ৌ"],
+    ),
+    (
+        "Conjuncts",
+        &[
+            "ক্ষ",
+            "জ্ঞ",
+            "ঙ্গ",
+            "ঞ্জ",
+            "ন্ত",
+            "ন্দ",
+            "ন্ধ",
+            "স্ত",
+            "ষ্ট",
+            "ক্ত",
+            "ত্র",
+            "প্র",
+            "ক্র",
+            "শ্র",
+        ],
+    ),
+    ("Signs", &["ং", "ঃ", "ঁ", "্", "ৎ", "য়", "ড়", "ঢ়", "।", "৳"]),
+    (
+        "Digits",
+        &["০", "১", "২", "৩", "৪", "৫", "৬", "৭", "৮", "৯"],
+    ),
+];
+
+// Most recent first
+const MAX_RECENT: usize = 12;
+
+// Set by the hook thread, picked up by the UI on its next frame
+static PENDING: Mutex<Option<Caret>> = Mutex::new(None);
+static UI_CONTEXT: OnceCell<egui::Context> = OnceCell::new();
+
+/// Lets the hook wake the UI when the panel is summoned.
+pub fn attach(ctx: egui::Context) {
+    let _ = UI_CONTEXT.set(ctx);
+}
+
+/// Opens the panel next to `caret`. Called from the hook thread.
+pub fn summon(caret: Caret) {
+    *PENDING.lock().unwrap() = Some(caret);
+    if let Some(ctx) = UI_CONTEXT.get() {
+        ctx.request_repaint();
+    }
+}
+
+/// Popup with recently used and categorized Bangla characters. A click
+/// hands focus back to the app the panel was summoned from and types there.
+#[derive(Default)]
+pub struct QuickInsert {
+    target: Option<Caret>,
+    category: usize,
+}
+
+impl QuickInsert {
+    pub fn show(&mut self, ctx: &egui::Context, font_size: f32) {
+        if let Some(caret) = PENDING.lock().unwrap().take() {
+            self.target = Some(caret);
+        }
+        let Some(target) = self.target else {
+            return;
+        };
+
+        // The caret is in physical pixels, egui places windows in points
+        let scale = ctx.pixels_per_point();
+        let position = egui::pos2(
+            target.position.x as f32 / scale,
+            target.position.y as f32 / scale,
+        );
+
+        let recent = crate::SETTINGS.snapshot().recent_characters.clone();
+        let mut chosen = None;
+        let mut dismissed = false;
+
+        ctx.show_viewport_immediate(
+            ViewportId::from_hash_of("quick_insert"),
+            ViewportBuilder::default()
+                .with_title("Quick insert")
+                .with_position(position)
+                .with_inner_size([360.0, 240.0])
+                .with_decorations(false)
+                .with_resizable(false)
+                .with_always_on_top()
+                .with_taskbar(false),
+            |ctx, _class| {
+                egui::CentralPanel::default().show(ctx, |ui| {
+                    let mut character_button = |ui: &mut egui::Ui, text: &str| {
+                        if ui
+                            .button(RichText::new(text).size(font_size + 4.0))
+                            .clicked()
+                        {
+                            chosen = Some(text.to_string());
+                        }
+                    };
+
+                    if !recent.is_empty() {
+                        ui.label(RichText::new("Recent").weak());
+                        ui.horizontal_wrapped(|ui| {
+                            for text in &recent {
+                                character_button(ui, text);
+                            }
+                        });
+                        ui.separator();
+                    }
+
+                    ui.horizontal(|ui| {
+                        for (index, (name, _)) in CATEGORIES.iter().enumerate() {
+                            ui.selectable_value(&mut self.category, index, *name);
+                        }
+                    });
+                    ui.horizontal_wrapped(|ui| {
+                        for text in CATEGORIES[self.category].1 {
+                            character_button(ui, text);
+                        }
+                    });
+                });
+
+                dismissed =
+                    ctx.input(|i| i.key_pressed(Key::Escape) || i.viewport().close_requested());
+            },
+        );
+
+        if let Some(text) = chosen {
+            self.target = None;
+            insert(target, text);
+        } else if dismissed {
+            self.target = None;
+            unsafe {
+                let _ = SetForegroundWindow(target.window);
+            }
+        }
+    }
+}
+
+fn insert(target: Caret, text: String) {
+    crate::SETTINGS.update(|settings| {
+        if !settings.remember_recent_characters {
+            return;
+        }
+        let recent = &mut settings.recent_characters;
+        recent.retain(|t| *t != text);
+        recent.insert(0, text.clone());
+        recent.truncate(MAX_RECENT);
+    });
+
+    // The panel closes first, then the app gets focus back before typing
+    std::thread::spawn(move || {
+        unsafe {
+            let _ = SetForegroundWindow(target.window);
+        }
+        std::thread::sleep(Duration::from_millis(50));
+
+        crate::clear_composition();
+        crate::HISTORY.lock().unwrap().push(&text);
+        crate::simulate_unicode_input(&text);
+    });
+}
@@ -0,0 +1,271 @@
+use crate::bijoy;
+use crate::history::{script_of, Script};
+use egui::{Color32, Sense};
+use std::ops::Range;
+
+// How much of a document is in which script, paragraph by paragraph, so
+// editors can spot English left in Bangla text. Spaces and punctuation
+// belong to no script and aren't counted. Lines that mix scripts or carry
+// leftovers of old encodings are listed as issues to clean up.
+
+/// Characters by script. Digits of either script count as digits.
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub struct Counts {
+    pub bangla: usize,
+    pub latin: usize,
+    pub digits: usize,
+    pub unknown: usize,
+}
+
+// Bars and legends name the scripts in this order
+const COLORS: [(&str, Color32); 4] = [
+    ("Bangla", Color32::from_rgb(46, 139, 87)),
+    ("Latin", Color32::from_rgb(65, 105, 225)),
+    ("Digits", Color32::from_rgb(160, 160, 160)),
+    ("Unknown", Color32::from_rgb(205, 92, 92)),
+];
+
+const BAR_HEIGHT: f32 = 10.0;
+
+fn is_punctuation(c: char) -> bool {
+    c.is_ascii_punctuation()
+        || matches!(c, '।' | '॥' | '\u{2000}'..='\u{206F}' | '\u{00A0}'..='\u{00BF}')
+}
+
+impl Counts {
+    pub fn of(text: &str) -> Self {
+        let mut counts = Self::default();
+        for c in text.chars() {
+            if c.is_whitespace() || is_punctuation(c) {
+                continue;
+            }
+            if c.is_ascii_digit() || matches!(c, '০'..='৯') {
+                counts.digits += 1;
+                continue;
+            }
+            match script_of(c) {
+                Script::Bangla => counts.bangla += 1,
+                Script::Latin => counts.latin += 1,
+                // Joiners go with the Bangla around them
+                Script::Other if matches!(c, '\u{200C}' | '\u{200D}') => counts.bangla += 1,
+                Script::Other => counts.unknown += 1,
+            }
+        }
+        counts
+    }
+
+    pub fn total(&self) -> usize {
+        self.bangla + self.latin + self.digits + self.unknown
+    }
+
+    /// Each script's share in percent, in the order of [`COLORS`].
+    pub fn percentages(&self) -> [f32; 4] {
+        let total = self.total().max(1) as f32;
+        [self.bangla, self.latin, self.digits, self.unknown].map(|n| n as f32 * 100.0 / total)
+    }
+}
+
+/// Paragraphs with text, as separated by blank lines, and their counts.
+pub fn paragraphs(text: &str) -> Vec<(&str, Counts)> {
+    // Byte ranges of the paragraphs, and of the one being read
+    let mut ranges = Vec::new();
+    let mut current: Option<(usize, usize)> = None;
+    let mut offset = 0;
+    for line in text.split_inclusive('\n') {
+        if line.trim().is_empty() {
+            ranges.extend(current.take());
+        } else {
+            let start = current.map_or(offset, |(start, _)| start);
+            current = Some((start, offset + line.len()));
+        }
+        offset += line.len();
+    }
+    ranges.extend(current);
+    ranges
+        .into_iter()
+        .map(|(start, end)| {
+            let paragraph = text[start..end].trim();
+            (paragraph, Counts::of(paragraph))
+        })
+        .collect()
+}
+
+/// Something to clean up on a line before the text is published.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Issue {
+    // Bangla and English words on one line
+    Mixed,
+    // Bijoy text never converted, alone or next to Unicode
+    Bijoy,
+    // Unicode Bangla once read as Windows-1252, like à¦†à¦®à¦¿
+    Mojibake,
+    // U+FFFD, where a converter gave up on a character
+    Replacement,
+    // Characters old Bangla fonts kept in the private use area
+    PrivateUse,
+}
+
+impl Issue {
+    pub fn describe(self) -> &'static str {
+        match self {
+            Issue::Mixed => "Bangla and Latin mixed",
+            Issue::Bijoy => "Bijoy text not converted",
+            Issue::Mojibake => "Garbled Unicode (à¦...)",
+            Issue::Replacement => "Lost characters (\u{FFFD})",
+            Issue::PrivateUse => "Characters from an old font",
+        }
+    }
+}
+
+/// The issues of each line, in order, with the line's byte range in `text`
+/// and its number from 1. Bijoy is looked for only when `bijoy` is set, as
+/// text being converted from Bijoy is all Bijoy.
+pub fn issues(text: &str, bijoy: bool) -> Vec<(usize, Range<usize>, Issue)> {
+    let mut issues = Vec::new();
+    let mut offset = 0;
+    for (index, line) in text.split_inclusive('\n').enumerate() {
+        let range = offset..offset + line.trim_end().len();
+        offset += line.len();
+        let mut found = Vec::new();
+        if line.contains("à¦") || line.contains("à§") {
+            found.push(Issue::Mojibake);
+        }
+        if line.contains('\u{FFFD}') {
+            found.push(Issue::Replacement);
+        }
+        if line.chars().any(|c| ('\u{E000}'..='\u{F8FF}').contains(&c)) {
+            found.push(Issue::PrivateUse);
+        }
+        // The line without its Unicode Bangla, which may be Bijoy or English
+        let rest: String = line
+            .chars()
+            .map(|c| {
+                if script_of(c) == Script::Bangla {
+                    ' '
+                } else {
+                    c
+                }
+            })
+            .collect();
+        let has_bangla = rest != line;
+        if bijoy && !found.contains(&Issue::Mojibake) && bijoy::looks_like_bijoy(&rest) {
+            found.push(Issue::Bijoy);
+        } else if has_bangla && rest.chars().any(|c| script_of(c) == Script::Latin) {
+            found.push(Issue::Mixed);
+        }
+        issues.extend(
+            found
+                .into_iter()
+                .map(|issue| (index + 1, range.clone(), issue)),
+        );
+    }
+    issues
+}
+
+/// One bar split by script share, `width` points long.
+pub fn bar(ui: &mut egui::Ui, counts: &Counts, width: f32) -> egui::Response {
+    let (rect, response) = ui.allocate_exact_size(egui::vec2(width, BAR_HEIGHT), Sense::hover());
+    let painter = ui.painter();
+    if counts.total() == 0 {
+        painter.rect_filled(rect, 2.0, ui.visuals().faint_bg_color);
+        return response;
+    }
+    let mut left = rect.left();
+    for ((_, color), percent) in COLORS.iter().zip(counts.percentages()) {
+        let right = left + rect.width() * percent / 100.0;
+        painter.rect_filled(
+            egui::Rect::from_x_y_ranges(left..=right, rect.y_range()),
+            0.0,
+            *color,
+        );
+        left = right;
+    }
+    response
+}
+
+/// The scripts with any share, as "82% Bangla · 18% Latin".
+pub fn summary(counts: &Counts) -> String {
+    COLORS
+        .iter()
+        .zip(counts.percentages())
+        .filter(|(_, percent)| *percent > 0.0)
+        .map(|((name, _), percent)| format!("{:.0}% {}", percent, name))
+        .collect::<Vec<_>>()
+        .join(" · ")
+}
+
+/// The colors of the bars and what they stand for.
+pub fn legend(ui: &mut egui::Ui) {
+    ui.horizontal(|ui| {
+        for (name, color) in COLORS {
+            let (rect, _) =
+                ui.allocate_exact_size(egui::vec2(BAR_HEIGHT, BAR_HEIGHT), Sense::hover());
+            ui.painter().rect_filled(rect, 2.0, color);
+            ui.label(name);
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_letters_and_digits_not_spaces_or_punctuation() {
+        let counts = Counts::of("আমি OK, ১২3!");
+        assert_eq!(
+            counts,
+            Counts {
+                bangla: 3,
+                latin: 2,
+                digits: 3,
+                unknown: 0,
+            }
+        );
+        assert_eq!(Counts::of("Привет").unknown, 6);
+    }
+
+    #[test]
+    fn percentages_share_the_counted_characters() {
+        let counts = Counts::of("আমি a");
+        assert_eq!(counts.percentages(), [75.0, 25.0, 0.0, 0.0]);
+        assert_eq!(summary(&counts), "75% Bangla · 25% Latin");
+        assert_eq!(Counts::default().percentages(), [0.0; 4]);
+    }
+
+    #[test]
+    fn blank_lines_separate_paragraphs() {
+        let text = "আমি\nযাব\n\n  \nhello\n\n";
+        let found: Vec<&str> = paragraphs(text).iter().map(|(p, _)| *p).collect();
+        assert_eq!(found, ["আমি\nযাব", "hello"]);
+    }
+
+    #[test]
+    fn issues_name_their_lines() {
+        let text = "আমি যাব\nআমি OK বলেছি\nAvwg evsjvq wjwL|\nà¦†à¦®à¦¿\nক\u{FFFD}খ \u{E0A5}\n";
+        let found: Vec<(usize, Issue)> = issues(text, true)
+            .into_iter()
+            .map(|(line, _, issue)| (line, issue))
+            .collect();
+        assert_eq!(
+            found,
+            [
+                (2, Issue::Mixed),
+                (3, Issue::Bijoy),
+                (4, Issue::Mojibake),
+                (5, Issue::Replacement),
+                (5, Issue::PrivateUse),
+            ]
+        );
+        let (_, range, _) = &issues(text, true)[0];
+        assert_eq!(&text[range.clone()], "আমি OK বলেছি");
+    }
+
+    #[test]
+    fn bijoy_is_looked_for_only_when_asked() {
+        assert_eq!(issues("Avwg evsjvq wjwL|", false), []);
+        // Bijoy pasted after Unicode Bangla is Bijoy, not English
+        let mixed = issues("আমি evsjvq wjwL|", true);
+        assert_eq!(mixed[0].2, Issue::Bijoy);
+    }
+}
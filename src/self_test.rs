@@ -0,0 +1,289 @@
+use crate::injection::EditPlan;
+use crate::settings::KeyboardSettings;
+use std::fs;
+use std::io::{Read, Write};
+use std::net::TcpStream;
+use std::time::{Duration, Instant};
+use windows::core::w;
+use windows::Win32::UI::Input::KeyboardAndMouse::{SendInput, SetFocus, INPUT};
+use windows::Win32::UI::WindowsAndMessaging::{
+    CreateWindowExW, DestroyWindow, DispatchMessageW, GetWindowTextW, PeekMessageW,
+    SetForegroundWindow, TranslateMessage, MSG, PM_REMOVE, WINDOW_EX_STYLE, WS_OVERLAPPEDWINDOW,
+    WS_VISIBLE,
+};
+
+// Checks of everything typing depends on, run from Diagnostics, with a
+// report to paste into a bug report. Each check says what it found, so a
+// failure in the report explains itself.
+
+// Typed into the scratch window and read back
+const INJECTION_SAMPLE: &str = "কখগ";
+// Long enough for the scratch window to handle the injected input
+const SETTLE_TIME: Duration = Duration::from_millis(200);
+const IPC_TIMEOUT: Duration = Duration::from_secs(2);
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Outcome {
+    Pass,
+    Fail,
+    // Not switched on, so nothing to check
+    Skipped,
+}
+
+impl Outcome {
+    pub fn label(self) -> &'static str {
+        match self {
+            Outcome::Pass => "PASS",
+            Outcome::Fail => "FAIL",
+            Outcome::Skipped => "SKIP",
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Check {
+    pub name: &'static str,
+    pub outcome: Outcome,
+    pub detail: String,
+}
+
+impl Check {
+    fn new(name: &'static str, result: Result<String, String>) -> Self {
+        let (outcome, detail) = match result {
+            Ok(detail) => (Outcome::Pass, detail),
+            Err(detail) => (Outcome::Fail, detail),
+        };
+        Self {
+            name,
+            outcome,
+            detail,
+        }
+    }
+}
+
+fn hook() -> Result<String, String> {
+    if crate::KEYBOARD_HOOK.lock().unwrap().is_none() {
+        return Err("not installed; keys can't be converted".to_string());
+    }
+    Ok(format!(
+        "installed, {} restart(s), last key {} s ago",
+        crate::hook_health::reinstalls(),
+        crate::hook_health::since_last_key().as_secs()
+    ))
+}
+
+// Lets the scratch window handle what was sent to it
+fn settle() {
+    let start = Instant::now();
+    let mut msg = MSG::default();
+    while start.elapsed() < SETTLE_TIME {
+        unsafe {
+            while PeekMessageW(&mut msg, None, 0, 0, PM_REMOVE).as_bool() {
+                let _ = TranslateMessage(&msg);
+                DispatchMessageW(&msg);
+            }
+        }
+        std::thread::sleep(Duration::from_millis(1));
+    }
+}
+
+// Types into a plain Edit control of our own, the way text reaches apps.
+// Injected keys are ignored by the hook, so this bypasses conversion.
+fn injection() -> Result<String, String> {
+    let window = unsafe {
+        CreateWindowExW(
+            WINDOW_EX_STYLE::default(),
+            w!("EDIT"),
+            w!(""),
+            WS_OVERLAPPEDWINDOW | WS_VISIBLE,
+            100,
+            100,
+            300,
+            80,
+            None,
+            None,
+            None,
+            None,
+        )
+    };
+    if window.0 == 0 {
+        return Err("couldn't create a scratch window".to_string());
+    }
+    unsafe {
+        let _ = SetForegroundWindow(window);
+        let _ = SetFocus(window);
+    }
+    settle();
+    let plan = EditPlan {
+        backspaces: 0,
+        text: INJECTION_SAMPLE.to_string(),
+    };
+    let inputs = plan.inputs();
+    let sent = unsafe { SendInput(&inputs, std::mem::size_of::<INPUT>() as i32) };
+    settle();
+    let mut buffer = [0u16; 64];
+    let len = unsafe { GetWindowTextW(window, &mut buffer) };
+    unsafe {
+        let _ = DestroyWindow(window);
+    }
+    let received = String::from_utf16_lossy(&buffer[..len as usize]);
+    if sent as usize != inputs.len() {
+        return Err(format!(
+            "Windows took {} of {} key events",
+            sent,
+            inputs.len()
+        ));
+    }
+    if received != INJECTION_SAMPLE {
+        return Err(format!(
+            "typed {} but the window got {:?}",
+            INJECTION_SAMPLE, received
+        ));
+    }
+    Ok(format!("typed {} into a scratch window", INJECTION_SAMPLE))
+}
+
+fn fonts(settings: &KeyboardSettings) -> Result<String, String> {
+    let paths = crate::fonts::font_chain_paths(&settings.custom_font);
+    if paths.is_empty() {
+        return Err("no Bangla font found; Bangla shows as boxes in this window".to_string());
+    }
+    let names: Vec<_> = paths
+        .iter()
+        .filter_map(|path| path.file_name())
+        .map(|name| name.to_string_lossy())
+        .collect();
+    Ok(names.join(", "))
+}
+
+// The settings file parses, and its folder takes a file written and read back
+fn config() -> Result<String, String> {
+    let path = KeyboardSettings::path().ok_or("APPDATA is not set")?;
+    let read = match fs::read_to_string(&path) {
+        Ok(json) => {
+            serde_json::from_str::<KeyboardSettings>(&json)
+                .map_err(|err| format!("{} is corrupt: {}", path.display(), err))?;
+            "read"
+        }
+        Err(_) => "not saved yet",
+    };
+    let dir = path.parent().ok_or("the settings path has no folder")?;
+    let probe = dir.join("self-test.tmp");
+    let written = fs::create_dir_all(dir)
+        .and_then(|_| fs::write(&probe, "self-test"))
+        .and_then(|_| fs::read_to_string(&probe));
+    let _ = fs::remove_file(&probe);
+    match written {
+        Ok(text) if text == "self-test" => Ok(format!("{}, {} writable", read, dir.display())),
+        Ok(_) => Err(format!("{} gave back different text", dir.display())),
+        Err(err) => Err(format!("{} isn't writable: {}", dir.display(), err)),
+    }
+}
+
+fn dictionary() -> Result<String, String> {
+    let bundled = fs::read_to_string(crate::DICTIONARY_FILE)
+        .map_err(|err| format!("{} can't be read: {}", crate::DICTIONARY_FILE, err))?;
+    let words = crate::dictionary().len();
+    if bundled.trim().is_empty() || words == 0 {
+        return Err(format!("{} has no words", crate::DICTIONARY_FILE));
+    }
+    Ok(format!("{} words", words))
+}
+
+// The language state answers on localhost, as RGB tools ask for it
+fn ipc(settings: &KeyboardSettings) -> Option<Result<String, String>> {
+    if !settings.state_api {
+        return None;
+    }
+    let port = settings.state_api_port;
+    let answer = (|| {
+        let mut stream = TcpStream::connect(("127.0.0.1", port))?;
+        stream.set_read_timeout(Some(IPC_TIMEOUT))?;
+        stream.write_all(b"GET / HTTP/1.0\r\n\r\n")?;
+        let mut response = String::new();
+        stream.read_to_string(&mut response)?;
+        Ok::<_, std::io::Error>(response)
+    })();
+    Some(match answer {
+        Ok(response) if response.contains("\"language\"") => Ok(format!("port {} answers", port)),
+        Ok(_) => Err(format!("port {} answered something else", port)),
+        Err(err) => Err(format!("port {}: {}", port, err)),
+    })
+}
+
+/// Runs every check. Takes a moment and briefly shows a window, so it runs
+/// off the UI thread.
+pub fn run() -> Vec<Check> {
+    let settings = crate::SETTINGS.snapshot();
+    let mut checks = vec![
+        Check::new("Keyboard hook", hook()),
+        Check::new("Typing into apps", injection()),
+        Check::new("Fonts", fonts(&settings)),
+        Check::new("Settings", config()),
+        Check::new("Dictionary", dictionary()),
+    ];
+    checks.push(match ipc(&settings) {
+        Some(result) => Check::new("Language state API", result),
+        None => Check {
+            name: "Language state API",
+            outcome: Outcome::Skipped,
+            detail: "switched off".to_string(),
+        },
+    });
+    checks
+}
+
+/// The checks as plain text, one per line.
+pub fn report(checks: &[Check]) -> String {
+    let failed = checks
+        .iter()
+        .filter(|check| check.outcome == Outcome::Fail)
+        .count();
+    let mut report = format!(
+        "Restro Keyboard {} self-test: {}\n",
+        env!("CARGO_PKG_VERSION"),
+        if failed == 0 {
+            "all passed".to_string()
+        } else {
+            format!("{} failed", failed)
+        }
+    );
+    for check in checks {
+        report.push_str(&format!(
+            "[{}] {}: {}\n",
+            check.outcome.label(),
+            check.name,
+            check.detail
+        ));
+    }
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn report_lists_each_check_with_its_outcome() {
+        let checks = [
+            Check::new("Fonts", Ok("Nirmala.ttf".to_string())),
+            Check::new("Dictionary", Err("no words".to_string())),
+            Check {
+                name: "Language state API",
+                outcome: Outcome::Skipped,
+                detail: "switched off".to_string(),
+            },
+        ];
+        let report = report(&checks);
+        let lines: Vec<&str> = report.lines().collect();
+        assert!(lines[0].ends_with("self-test: 1 failed"));
+        assert_eq!(
+            &lines[1..],
+            [
+                "[PASS] Fonts: Nirmala.ttf",
+                "[FAIL] Dictionary: no words",
+                "[SKIP] Language state API: switched off",
+            ]
+        );
+    }
+}
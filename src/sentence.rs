@@ -0,0 +1,133 @@
+use crate::history::{script_of, Script};
+
+// Bangla words commonly abbreviated with a period (ড. = ডক্টর, মো. = মোহাম্মদ)
+const ABBREVIATIONS: [&str; 10] = [
+    "ড",
+    "ডা",
+    "মো",
+    "মোসা",
+    "মোছা",
+    "মি",
+    "মিসেস",
+    "লি",
+    "কো",
+    "সং",
+];
+
+/// The word right before the caret.
+pub fn last_word(before: &str) -> &str {
+    before
+        .rsplit(|c: char| c.is_whitespace() || "(\"'“‘".contains(c))
+        .next()
+        .unwrap_or_default()
+}
+
+/// Whether a period after `word` marks an abbreviation rather than the end
+/// of a sentence. Lone letters count as initials (এ. ফজলুল).
+pub fn is_abbreviation(word: &str) -> bool {
+    if word.is_empty() || !word.chars().all(|c| script_of(c) == Script::Bangla) {
+        return false;
+    }
+    ABBREVIATIONS.contains(&word) || is_initial(word)
+}
+
+// A lone letter (এ, ক). Letters with a vowel sign are left out, since
+// words like না and কি often end a sentence.
+fn is_initial(word: &str) -> bool {
+    let mut chars = word.chars();
+    let first_is_letter = chars
+        .next()
+        .is_some_and(|c| matches!(c, '\u{0985}'..='\u{09B9}' | '\u{09DC}'..='\u{09DF}'));
+    first_is_letter && chars.next().is_none()
+}
+
+/// Whether the caret follows a digit, so a period is a decimal point (৩.১৪)
+/// rather than the end of a sentence.
+pub fn ends_with_digit(before: &str) -> bool {
+    before
+        .chars()
+        .last()
+        .is_some_and(|c| c.is_ascii_digit() || matches!(c, '০'..='৯'))
+}
+
+/// Whether the caret starts a new sentence: after a line break, or after
+/// the end of a sentence and a space. Unknown text (nothing before the
+/// caret) doesn't count.
+pub fn starts_sentence(before: &str) -> bool {
+    if before.ends_with('\n') {
+        return true;
+    }
+    let trimmed = before.trim_end_matches(' ');
+    trimmed.len() < before.len() && trimmed.ends_with(['.', '!', '?', '।'])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn known_abbreviations_and_initials_are_not_sentence_ends() {
+        for (word, abbreviation) in [
+            ("ড", true),
+            ("মো", true),
+            ("মিসেস", true),
+            ("এ", true),
+            ("ক", true),
+            // Short words that often end a sentence
+            ("না", false),
+            ("কি", false),
+            ("যাব", false),
+            ("", false),
+            // Latin text is left to English rules
+            ("Dr", false),
+            ("A", false),
+            ("ডr", false),
+        ] {
+            assert_eq!(is_abbreviation(word), abbreviation, "{:?}", word);
+        }
+    }
+
+    #[test]
+    fn initials_are_lone_letters() {
+        for (word, initial) in [
+            ("এ", true),
+            ("\u{09DC}", true),
+            ("কি", false),
+            ("ং", false),
+            ("১", false),
+            ("a", false),
+        ] {
+            assert_eq!(is_initial(word), initial, "{:?}", word);
+        }
+    }
+
+    #[test]
+    fn the_last_word_starts_after_a_space_or_opening_mark() {
+        for (before, word) in [
+            ("আমি যাব", "যাব"),
+            ("দেখা হল ড.", "ড."),
+            ("সে বলল “মো.", "মো."),
+            ("(এ.", "এ."),
+            ("Hello world", "world"),
+            ("যাব ", ""),
+            ("", ""),
+        ] {
+            assert_eq!(last_word(before), word, "{:?}", before);
+        }
+    }
+
+    #[test]
+    fn sentences_start_after_an_end_mark_and_a_space() {
+        for (before, starts) in [
+            ("আমি যাব। ", true),
+            ("Really? ", true),
+            ("ok.\n", true),
+            ("line\n", true),
+            ("আমি যাব।", false),
+            ("আমি যাব ", false),
+            ("", false),
+        ] {
+            assert_eq!(starts_sentence(before), starts, "{:?}", before);
+        }
+    }
+}
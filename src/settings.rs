@@ -0,0 +1,445 @@
+use crate::features::Feature;
+use crate::hotkey::Hotkey;
+use crate::state::{Event, KeyboardState, Language};
+use restro_keyboard_core::Layout;
+use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+// Changes are written out at most once a second
+const SAVE_INTERVAL: Duration = Duration::from_secs(1);
+
+// Fields missing from an older settings file take their default
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct KeyboardSettings {
+    pub state: KeyboardState,
+    pub use_suggestions: bool,
+    // The dictionary's roman spelling of each candidate, as a guide to
+    // saying it
+    pub pronunciation_hints: bool,
+    pub hotkey_enabled: bool,
+    pub toggle_hotkey: Hotkey,
+    pub quick_insert_hotkey: Hotkey,
+    // Each top-level window keeps the language it was left in, for the
+    // session
+    pub language_per_window: bool,
+    // Converts Banglish on the clipboard to Bangla; off by default, since
+    // Ctrl+Alt is AltGr for fixed layouts
+    pub clipboard_hotkey_enabled: bool,
+    pub clipboard_hotkey: Hotkey,
+    // Converts the selected text in place, by copying and pasting over it
+    pub selection_hotkey_enabled: bool,
+    pub selection_hotkey: Hotkey,
+    // Finished Bangla words not in the full dictionary are flagged, and the
+    // hotkey swaps in the nearest known words
+    pub spell_check: bool,
+    pub spelling_hotkey: Hotkey,
+    // A hot corner or middle click that also switches language, one of
+    // mouse_toggle::GESTURES
+    pub mouse_toggle: String,
+    // Holding a key types its alternate character, such as ৎ for t, once
+    // it repeats for long_press_ms; alternates by layout, one "key
+    // character" pair per line, long_press::table when missing
+    pub long_press: bool,
+    pub long_press_ms: u32,
+    pub long_press_alternates: BTreeMap<String, String>,
+    pub font_size: f32,
+    pub theme: String,
+    pub intercept_all: bool,
+    pub custom_font: String,
+    // Whether doubled consonants join, by layout name where the user chose
+    // differently from the layout; see gemination()
+    pub layout_gemination: BTreeMap<String, bool>,
+    // Finished words are respelled from the bundled dictionary
+    pub dictionary_mode: bool,
+    // Shortened Banglish is corrected as words end, from the bundled list
+    // and the user's additions, "roman bangla" per line
+    pub autocorrect: bool,
+    pub autocorrect_additions: String,
+    // Finished words are counted so the user's own spellings rank first;
+    // off unless the user opts in
+    pub learn_words: bool,
+    // Bangla words typed per day, counts only, and a summary notification,
+    // one of statistics::SUMMARIES
+    pub statistics: bool,
+    pub usage_summary: String,
+    // With statistics, how long each word takes to type in each layout,
+    // for comparing them; off unless the user opts in
+    pub layout_timing: bool,
+    // A daily backup of the user's files, and how many are kept; see
+    // backup.rs
+    pub automatic_backups: bool,
+    pub backups_kept: usize,
+    // A folder shared between computers, such as one in Dropbox, that the
+    // dictionary and learned words sync through; empty when not syncing.
+    // See dictionary_sync.rs
+    pub sync_folder: String,
+    // What happens when a word with keys the layout doesn't know ends, one
+    // of unmatched::POLICIES
+    pub unmatched_keys: String,
+    // How typed text is encoded, one of bijoy::ENCODINGS, and the apps that
+    // get Bijoy when only listed ones do, one file name per line
+    pub output_encoding: String,
+    pub bijoy_apps: String,
+    pub dari_on_period: bool,
+    // Typed for a period right after a digit
+    pub decimal_separator: String,
+    pub clipboard_monitor: bool,
+    pub curly_quotes: bool,
+    pub smart_dashes: bool,
+    pub nbsp_after_abbreviation: bool,
+    // No space before a dari and one after, where the text is Bangla
+    pub dari_spacing: bool,
+    // English typed in English mode gets capital sentence starts and I
+    pub capitalize_english: bool,
+    pub startup_language: String,
+    pub startup_layout: String,
+    // Battery savers for the UI thread; the hook thread is never throttled
+    pub efficiency_mode: bool,
+    pub low_priority_ui: bool,
+    // Language mode for RGB keyboards: a localhost JSON endpoint and
+    // commands run when the language changes
+    pub state_api: bool,
+    pub state_api_port: u16,
+    pub state_command_bangla: String,
+    pub state_command_english: String,
+    // Last characters inserted from the quick-insert panel, newest first;
+    // none are kept while remember_recent_characters is off
+    pub recent_characters: Vec<String>,
+    pub remember_recent_characters: bool,
+    // Words kept out of suggestions, one per line
+    pub blocked_words: String,
+    // What the converter's "Copy for web" cleans text for, one of
+    // web_copy::PROFILES
+    pub web_profile: String,
+    // Bangla digits, dates and the dari in Banglish and Bijoy output, one
+    // of numerals::POLICIES
+    pub reverse_numbers: String,
+    // Typed text is shown as ••• in every overlay, window and log; see mask
+    pub mask_buffer: bool,
+    // The welcome window opens at startup until turned off there
+    pub show_welcome: bool,
+    // Experimental features by key; a missing entry means off
+    pub feature_flags: BTreeMap<String, bool>,
+}
+
+impl Default for KeyboardSettings {
+    fn default() -> Self {
+        Self {
+            state: KeyboardState::default(),
+            use_suggestions: true,
+            pronunciation_hints: false,
+            hotkey_enabled: true,
+            toggle_hotkey: Hotkey::default(),
+            quick_insert_hotkey: Hotkey::quick_insert(),
+            language_per_window: false,
+            clipboard_hotkey_enabled: false,
+            clipboard_hotkey: Hotkey::convert_clipboard(),
+            selection_hotkey_enabled: false,
+            selection_hotkey: Hotkey::convert_selection(),
+            spell_check: false,
+            spelling_hotkey: Hotkey::correct_spelling(),
+            mouse_toggle: "Off".to_string(),
+            long_press: false,
+            long_press_ms: 500,
+            long_press_alternates: BTreeMap::new(),
+            font_size: 14.0,
+            theme: "Light".to_string(),
+            intercept_all: true,
+            custom_font: String::new(),
+            layout_gemination: BTreeMap::new(),
+            dictionary_mode: false,
+            autocorrect: true,
+            autocorrect_additions: String::new(),
+            learn_words: false,
+            statistics: false,
+            usage_summary: "Off".to_string(),
+            layout_timing: false,
+            automatic_backups: true,
+            backups_kept: 10,
+            sync_folder: String::new(),
+            unmatched_keys: "Pass through".to_string(),
+            output_encoding: "Unicode".to_string(),
+            bijoy_apps: String::new(),
+            dari_on_period: true,
+            decimal_separator: ".".to_string(),
+            clipboard_monitor: false,
+            curly_quotes: false,
+            smart_dashes: false,
+            nbsp_after_abbreviation: false,
+            dari_spacing: false,
+            capitalize_english: false,
+            startup_language: "Last used".to_string(),
+            startup_layout: "Last used".to_string(),
+            efficiency_mode: false,
+            low_priority_ui: false,
+            state_api: false,
+            state_api_port: 48100,
+            state_command_bangla: String::new(),
+            state_command_english: String::new(),
+            recent_characters: Vec::new(),
+            remember_recent_characters: true,
+            blocked_words: String::new(),
+            web_profile: "Facebook".to_string(),
+            reverse_numbers: "English digits and periods".to_string(),
+            mask_buffer: false,
+            show_welcome: true,
+            feature_flags: BTreeMap::new(),
+        }
+    }
+}
+
+impl KeyboardSettings {
+    /// `%APPDATA%\RestroKeyboard\settings.json`, if APPDATA is set.
+    pub fn path() -> Option<PathBuf> {
+        let appdata = std::env::var_os("APPDATA")?;
+        Some(
+            PathBuf::from(appdata)
+                .join("RestroKeyboard")
+                .join("settings.json"),
+        )
+    }
+
+    /// Settings saved by the last run. A missing file gives the defaults; a
+    /// corrupt one is kept aside as settings.json.bak and the defaults are used.
+    pub fn load() -> Self {
+        let Some(path) = Self::path() else {
+            return Self::default();
+        };
+        let Ok(json) = fs::read_to_string(&path) else {
+            return Self::default();
+        };
+        match serde_json::from_str(&json) {
+            Ok(settings) => settings,
+            Err(err) => {
+                eprintln!("Settings file is corrupt, using defaults: {}", err);
+                let _ = fs::rename(&path, path.with_extension("json.bak"));
+                Self::default()
+            }
+        }
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        let Some(path) = Self::path() else {
+            return Ok(());
+        };
+        save_json_atomically(&path, self)
+    }
+
+    // Keys are only converted in Bangla mode and while intercepting
+    pub fn converts_input(&self) -> bool {
+        self.state.converts_input() && self.intercept_all
+    }
+
+    // Like conversion, capitalization needs the keys to be intercepted
+    pub fn capitalizes_english(&self) -> bool {
+        self.capitalize_english && self.state.types_english() && self.intercept_all
+    }
+
+    // The monitor is experimental, so its own switch only counts once the flag is on
+    pub fn monitors_clipboard(&self) -> bool {
+        self.feature_enabled(Feature::ClipboardMonitor) && self.clipboard_monitor
+    }
+
+    /// Whether doubled consonants join in `layout` (kk → ক্ক): the user's
+    /// choice for it, or the layout's own.
+    pub fn gemination(&self, layout: &Layout) -> bool {
+        self.layout_gemination
+            .get(layout.name())
+            .copied()
+            .unwrap_or_else(|| layout.gemination())
+    }
+
+    pub fn feature_enabled(&self, feature: Feature) -> bool {
+        self.feature_flags
+            .get(feature.key())
+            .copied()
+            .unwrap_or(false)
+    }
+
+    pub fn set_feature(&mut self, feature: Feature, enabled: bool) {
+        self.feature_flags
+            .insert(feature.key().to_string(), enabled);
+    }
+
+    // Shared machines boot into a fixed mode unless "Last used" is chosen
+    pub fn apply_startup_preferences(&mut self) {
+        match self.startup_language.as_str() {
+            "Bangla" => self.state.apply(Event::SelectLanguage(Language::Bangla)),
+            "English" => self.state.apply(Event::SelectLanguage(Language::English)),
+            _ => {}
+        }
+        if self.startup_layout != "Last used" {
+            self.state
+                .apply(Event::SelectLayout(self.startup_layout.clone()));
+        }
+    }
+}
+
+/// Writes `contents` through a temporary file next to `path`, so a crash
+/// never leaves half a file. Creates the folder when needed.
+pub fn write_atomically(path: &Path, contents: impl AsRef<[u8]>) -> io::Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let mut temp = path.as_os_str().to_owned();
+    temp.push(".tmp");
+    fs::write(&temp, contents)?;
+    fs::rename(&temp, path)
+}
+
+/// Saves `value` as JSON with `write_atomically`.
+pub fn save_json_atomically<T: Serialize + ?Sized>(path: &Path, value: &T) -> io::Result<()> {
+    write_atomically(path, &serde_json::to_string_pretty(value)?)
+}
+
+/// Holds the current settings as an immutable snapshot. Readers clone the
+/// `Arc` and never hold a lock while working; writers publish a new snapshot.
+///
+/// Each thread keeps the last snapshot it read along with the version it
+/// had, so reading is an atomic load and an `Arc` clone until something is
+/// published. The version only moves once a write is done, so the keyboard
+/// hook never waits on a writer.
+pub struct SettingsService {
+    current: RwLock<Arc<KeyboardSettings>>,
+    // Bumped after every publish
+    version: AtomicU64,
+    // Tells services apart in the thread caches, unlike an address that a
+    // later service may reuse
+    id: u64,
+}
+
+static NEXT_SERVICE_ID: AtomicU64 = AtomicU64::new(0);
+
+thread_local! {
+    // The service it came from, its version and the snapshot
+    static CACHED: RefCell<Option<(u64, u64, Arc<KeyboardSettings>)>> =
+        const { RefCell::new(None) };
+}
+
+impl SettingsService {
+    pub fn new(settings: KeyboardSettings) -> Self {
+        Self {
+            current: RwLock::new(Arc::new(settings)),
+            version: AtomicU64::new(0),
+            id: NEXT_SERVICE_ID.fetch_add(1, Ordering::Relaxed),
+        }
+    }
+
+    pub fn snapshot(&self) -> Arc<KeyboardSettings> {
+        let version = self.version.load(Ordering::Acquire);
+        CACHED.with(|cached| {
+            let mut cached = cached.borrow_mut();
+            match cached.as_ref() {
+                Some((cached_id, cached_version, settings))
+                    if *cached_id == self.id && *cached_version == version =>
+                {
+                    settings.clone()
+                }
+                _ => {
+                    // At least as new as `version`; a later publish bumps it again
+                    let settings = self.current.read().unwrap().clone();
+                    *cached = Some((self.id, version, settings.clone()));
+                    settings
+                }
+            }
+        })
+    }
+
+    /// Moves on with every publish, so data worked out from the settings
+    /// knows when to be worked out again.
+    pub fn version(&self) -> u64 {
+        self.version.load(Ordering::Acquire)
+    }
+
+    pub fn publish(&self, settings: KeyboardSettings) {
+        *self.current.write().unwrap() = Arc::new(settings);
+        self.version.fetch_add(1, Ordering::Release);
+    }
+
+    // Copy, modify and publish in one step so concurrent updates aren't lost
+    pub fn update(&self, f: impl FnOnce(&mut KeyboardSettings)) {
+        let mut current = self.current.write().unwrap();
+        let mut settings = (**current).clone();
+        f(&mut settings);
+        *current = Arc::new(settings);
+        drop(current);
+        self.version.fetch_add(1, Ordering::Release);
+    }
+}
+
+/// Saves the settings whenever a new snapshot is published. Runs on its own
+/// thread so the hook never waits on the disk.
+pub fn spawn_saver() {
+    std::thread::spawn(|| {
+        let mut saved = crate::SETTINGS.snapshot();
+        loop {
+            std::thread::sleep(SAVE_INTERVAL);
+
+            let current = crate::SETTINGS.snapshot();
+            if Arc::ptr_eq(&current, &saved) || *current == *saved {
+                saved = current;
+                continue;
+            }
+            if let Err(err) = current.save() {
+                eprintln!("Could not save settings: {}", err);
+            }
+            saved = current;
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn snapshot_follows_publish_and_update() {
+        let service = SettingsService::new(KeyboardSettings::default());
+        let before = service.snapshot();
+        assert!(Arc::ptr_eq(&before, &service.snapshot()));
+
+        service.update(|settings| settings.font_size = 20.0);
+        assert_eq!(service.snapshot().font_size, 20.0);
+
+        service.publish(KeyboardSettings::default());
+        assert_eq!(service.snapshot().font_size, 14.0);
+        assert_eq!(before.font_size, 14.0);
+    }
+
+    #[test]
+    fn other_threads_see_updates() {
+        let service = Arc::new(SettingsService::new(KeyboardSettings::default()));
+        let (updated, wait_for_update) = std::sync::mpsc::channel();
+        let reader = Arc::clone(&service);
+        let reader = std::thread::spawn(move || {
+            // Cached before the update, unless the update came first
+            reader.snapshot();
+            wait_for_update.recv().unwrap();
+            reader.snapshot().dictionary_mode
+        });
+
+        service.update(|settings| settings.dictionary_mode = true);
+        updated.send(()).unwrap();
+        assert!(reader.join().unwrap());
+    }
+
+    #[test]
+    fn services_keep_their_own_snapshots() {
+        let first = SettingsService::new(KeyboardSettings::default());
+        first.snapshot();
+        drop(first);
+        let second = SettingsService::new(KeyboardSettings {
+            font_size: 18.0,
+            ..Default::default()
+        });
+        assert_eq!(second.snapshot().font_size, 18.0);
+    }
+}
@@ -0,0 +1,294 @@
+use crate::settings::KeyboardSettings;
+use restro_keyboard_core::{Condition, FixedLayout, Layer, Layout, Scope, Side, Transliterator};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+// Everything that decides what typing produces, written out so two
+// versions or two machines can be compared: the settings, every rule of
+// every layout, the dictionary and what sample words convert to. Lines are
+// sorted and free of anything that changes between runs, so an unchanged
+// keyboard gives the same file byte for byte.
+//
+// Sections start with "[name]" and hold key<TAB>value lines, tabs since
+// keys and values can hold any other character; keys are unique within
+// their section. Lines starting with '#' are comments.
+
+// Words that go through most kinds of rules: vowel signs, conjuncts,
+// phala, reph, khanda ta and numbers
+const SAMPLES: [&str; 24] = [
+    "ami", "tumi", "bangla", "bhalo", "kichu", "kotha", "shikkha", "bidya", "kormo", "durgo",
+    "sroddha", "rri", "hriday", "ongko", "shoktt", "bAngla", "ut`pol", "kyamera", "priyo",
+    "dukkho", "songsar", "agun", "2024", "o.k.",
+];
+
+// Every key a fixed layout can have, by what it types on a US keyboard
+const KEYS: &str = "`1234567890-=qwertyuiop[]\\asdfghjkl;'zxcvbnm,./";
+
+/// A line that differs between two snapshots.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Change {
+    pub section: String,
+    pub key: String,
+    pub old: Option<String>,
+    pub new: Option<String>,
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match (&self.old, &self.new) {
+            (Some(old), Some(new)) => {
+                write!(f, "~ [{}] {}: {} → {}", self.section, self.key, old, new)
+            }
+            (None, Some(new)) => write!(f, "+ [{}] {} = {}", self.section, self.key, new),
+            (old, _) => write!(
+                f,
+                "- [{}] {} = {}",
+                self.section,
+                self.key,
+                old.as_deref().unwrap_or_default()
+            ),
+        }
+    }
+}
+
+// Sections by name, each with its lines by key
+type Sections = BTreeMap<String, BTreeMap<String, String>>;
+
+// Nested settings become dotted keys, like state.layout
+fn flatten(prefix: &str, value: &serde_json::Value, lines: &mut BTreeMap<String, String>) {
+    match value {
+        serde_json::Value::Object(fields) => {
+            for (name, value) in fields {
+                let key = if prefix.is_empty() {
+                    name.clone()
+                } else {
+                    format!("{}.{}", prefix, name)
+                };
+                flatten(&key, value, lines);
+            }
+        }
+        value => {
+            lines.insert(prefix.to_string(), value.to_string());
+        }
+    }
+}
+
+fn condition(condition: &Condition) -> String {
+    let side = match condition.side {
+        Side::Prefix => "after",
+        Side::Suffix => "before",
+    };
+    let scope = match &condition.scope {
+        Scope::Vowel => "vowel".to_string(),
+        Scope::Consonant => "consonant".to_string(),
+        Scope::Punctuation => "punctuation".to_string(),
+        Scope::Exact(text) => format!("{:?}", text),
+    };
+    let not = if condition.negated { "not " } else { "" };
+    format!("{}{} {}", not, side, scope)
+}
+
+fn phonetic(layout: &Layout, gemination: bool) -> BTreeMap<String, String> {
+    let mut lines = BTreeMap::new();
+    for (roman, bangla) in layout.rules() {
+        lines.insert(format!("rule {}", roman), format!("{:?}", bangla));
+        for (index, rule) in layout.context_rules(roman).iter().enumerate() {
+            let conditions: Vec<String> = rule.conditions.iter().map(condition).collect();
+            lines.insert(
+                format!("context {} {}", roman, index + 1),
+                format!("{} if {}", rule.output, conditions.join(" and ")),
+            );
+        }
+    }
+    for (first, second) in layout.conjuncts() {
+        lines.insert(
+            format!("conjunct {}+{}", first, second),
+            "joins".to_string(),
+        );
+    }
+    let engine = Transliterator::new(layout).with_gemination(gemination);
+    for sample in SAMPLES {
+        lines.insert(format!("sample {}", sample), engine.transliterate(sample));
+    }
+    lines
+}
+
+fn fixed(layout: &FixedLayout) -> BTreeMap<String, String> {
+    let mut lines = BTreeMap::new();
+    for key in KEYS.chars() {
+        for layer in [Layer::Base, Layer::Shift, Layer::AltGr, Layer::ShiftAltGr] {
+            if let Some(text) = layout.get(key, layer) {
+                lines.insert(format!("{} {:?}", key, layer), text.to_string());
+            }
+        }
+    }
+    lines
+}
+
+fn capture_sections(settings: &KeyboardSettings) -> Sections {
+    let mut sections = Sections::new();
+    let mut lines = BTreeMap::new();
+    if let Ok(value) = serde_json::to_value(settings) {
+        flatten("", &value, &mut lines);
+    }
+    sections.insert("settings".to_string(), lines);
+    for layout in crate::layouts().iter() {
+        sections.insert(
+            format!("layout {}", layout.name()),
+            phonetic(layout, settings.gemination(layout)),
+        );
+    }
+    for layout in crate::FIXED_LAYOUTS.iter() {
+        sections.insert(format!("layout {}", layout.name()), fixed(layout));
+    }
+    let words = crate::dictionary()
+        .words()
+        .map(|(roman, bangla)| (roman.to_string(), bangla.to_string()))
+        .collect();
+    sections.insert("dictionary".to_string(), words);
+    sections
+}
+
+fn write_sections(sections: &Sections) -> String {
+    let mut text = format!(
+        "# Restro Keyboard {} behavior snapshot\n",
+        env!("CARGO_PKG_VERSION")
+    );
+    for (name, lines) in sections {
+        text.push_str(&format!("\n[{}]\n", name));
+        for (key, value) in lines {
+            text.push_str(&format!("{}\t{}\n", key, value));
+        }
+    }
+    text
+}
+
+fn parse(text: &str) -> Sections {
+    let mut sections = Sections::new();
+    let mut current = String::new();
+    for line in text.lines() {
+        if line.trim().is_empty() || line.starts_with('#') {
+            continue;
+        }
+        // Keys of fixed layouts can be brackets, so lines go first
+        if let Some((key, value)) = line.split_once('\t') {
+            sections
+                .entry(current.clone())
+                .or_default()
+                .insert(key.to_string(), value.to_string());
+        } else if let Some(name) = line
+            .strip_prefix('[')
+            .and_then(|line| line.strip_suffix(']'))
+        {
+            current = name.to_string();
+            sections.entry(current.clone()).or_default();
+        }
+    }
+    sections
+}
+
+/// The snapshot of the keyboard as it types now.
+pub fn capture(settings: &KeyboardSettings) -> String {
+    write_sections(&capture_sections(settings))
+}
+
+/// Lines added, removed or changed from `old` to `new`, by section and key.
+/// Comments, like the version line, aren't compared.
+pub fn diff(old: &str, new: &str) -> Vec<Change> {
+    let (old, new) = (parse(old), parse(new));
+    let mut keys: Vec<(&String, &String)> = old
+        .iter()
+        .chain(&new)
+        .flat_map(|(section, lines)| lines.keys().map(move |key| (section, key)))
+        .collect();
+    keys.sort();
+    keys.dedup();
+    keys.into_iter()
+        .filter_map(|(section, key)| {
+            let old = old.get(section).and_then(|lines| lines.get(key));
+            let new = new.get(section).and_then(|lines| lines.get(key));
+            (old != new).then(|| Change {
+                section: section.clone(),
+                key: key.clone(),
+                old: old.cloned(),
+                new: new.cloned(),
+            })
+        })
+        .collect()
+}
+
+pub fn export(path: &Path, settings: &KeyboardSettings) -> io::Result<()> {
+    fs::write(path, capture(settings))
+}
+
+/// How typing now differs from a snapshot saved earlier.
+pub fn compare_with_current(path: &Path, settings: &KeyboardSettings) -> io::Result<Vec<Change>> {
+    Ok(diff(&fs::read_to_string(path)?, &capture(settings)))
+}
+
+/// `--snapshot <file>`: saves the snapshot of the keyboard as it is now.
+pub fn run_export(
+    path: &str,
+    settings: &KeyboardSettings,
+) -> Result<(), Box<dyn std::error::Error>> {
+    export(Path::new(path), settings)?;
+    println!("Saved {}", path);
+    Ok(())
+}
+
+/// `--compare-snapshots <old> <new>`: prints what changed between two
+/// snapshots, one line each.
+pub fn run_diff(old: &str, new: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let changes = diff(&fs::read_to_string(old)?, &fs::read_to_string(new)?);
+    for change in &changes {
+        println!("{}", change);
+    }
+    println!("{} change(s)", changes.len());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn snapshots_read_back_as_written() {
+        let mut sections = Sections::new();
+        let mut lines = BTreeMap::new();
+        lines.insert("rule =".to_string(), "Special(\"= \")".to_string());
+        lines.insert("[ Shift".to_string(), "]".to_string());
+        lines.insert("sample ami".to_string(), "অমি".to_string());
+        sections.insert("layout Phonetic".to_string(), lines);
+        sections.insert("dictionary".to_string(), BTreeMap::new());
+        assert_eq!(parse(&write_sections(&sections)), sections);
+    }
+
+    #[test]
+    fn settings_keys_are_sorted_and_dotted() {
+        let value = serde_json::json!({"b": 1, "a": {"y": "x", "b": [1, 2]}});
+        let mut lines = BTreeMap::new();
+        flatten("", &value, &mut lines);
+        let keys: Vec<&str> = lines.keys().map(String::as_str).collect();
+        assert_eq!(keys, ["a.b", "a.y", "b"]);
+        assert_eq!(lines["a.y"], "\"x\"");
+    }
+
+    #[test]
+    fn diff_reports_changed_added_and_removed_lines() {
+        let old = "# version 1\n[settings]\ngemination\ttrue\nfont_size\t14.0\n\n[dictionary]\nami\tআমি\n";
+        let new = "# version 2\n[settings]\ngemination\tfalse\nfont_size\t14.0\n\n[dictionary]\ntumi\tতুমি\n";
+        let changes: Vec<String> = diff(old, new).iter().map(Change::to_string).collect();
+        assert_eq!(
+            changes,
+            [
+                "- [dictionary] ami = আমি",
+                "+ [dictionary] tumi = তুমি",
+                "~ [settings] gemination: true → false",
+            ]
+        );
+        assert!(diff(old, old).is_empty());
+    }
+}
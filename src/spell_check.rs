@@ -0,0 +1,307 @@
+use crate::caret::{self, Caret};
+use crate::history::{script_of, Script};
+use crate::injection::EditPlan;
+use crate::mask;
+use crate::overlay::{self, Side};
+use crate::settings::KeyboardSettings;
+use egui::{Color32, RichText, ViewportId};
+use once_cell::sync::OnceCell;
+use restro_keyboard_core::{Dictionary, WordList};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+// Finished Bangla words checked against an installed full dictionary and
+// every word the keyboard otherwise knows: the dictionary's, the
+// autocorrect list's and the user's own. An unknown word is flagged next
+// to the caret with the nearest known words, and the spelling hotkey swaps
+// them in turn. No wordlist ships with the keyboard, and a few hundred
+// words would flag most of what is typed, so without a full dictionary
+// spell checking is off.
+
+// Known words further than this from the finished word aren't offered
+const MAX_CORRECTION_DISTANCE: usize = 2;
+// One line each in the flag
+const MAX_CORRECTIONS: usize = 4;
+// Shorter words are mostly particles the list can't judge
+const MIN_CHECKED_LEN: usize = 2;
+// How long a flag stays next to the caret, from the last correction
+const FLAG_TIME: Duration = Duration::from_secs(8);
+const RELEASE_TIMEOUT: Duration = Duration::from_secs(1);
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+// The known words and what they were made from
+struct Known {
+    settings: u64,
+    learned: u64,
+    dictionary: Arc<Dictionary>,
+    words: Arc<WordList>,
+}
+
+#[derive(Clone)]
+struct Flag {
+    caret: Caret,
+    // As it was finished
+    word: String,
+    corrections: Vec<String>,
+    // The correction on screen instead of the word, if any
+    chosen: Option<usize>,
+    shown: Instant,
+}
+
+impl Flag {
+    fn on_screen(&self) -> &str {
+        self.chosen
+            .and_then(|index| self.corrections.get(index))
+            .unwrap_or(&self.word)
+    }
+}
+
+// Set by the hook thread, drawn by the UI until it expires
+static CURRENT: Mutex<Option<Flag>> = Mutex::new(None);
+static KNOWN: Mutex<Option<Known>> = Mutex::new(None);
+static UI_CONTEXT: OnceCell<egui::Context> = OnceCell::new();
+
+/// Lets the hook wake the UI when a word is flagged.
+pub fn attach(ctx: egui::Context) {
+    let _ = UI_CONTEXT.set(ctx);
+}
+
+fn repaint() {
+    if let Some(ctx) = UI_CONTEXT.get() {
+        ctx.request_repaint();
+    }
+}
+
+/// The words taken as spelled right besides the full dictionary's: the
+/// Bangla of the other dictionary words, including the user's, of the
+/// autocorrect list and of the learned words. Made again only when one of those changes.
+fn known_words() -> Arc<WordList> {
+    let settings = crate::SETTINGS.version();
+    let learned = crate::learned_words::generation();
+    let dictionary = crate::dictionary();
+    let mut known = KNOWN.lock().unwrap();
+    if let Some(known) = known.as_ref() {
+        if known.settings == settings
+            && known.learned == learned
+            && Arc::ptr_eq(&known.dictionary, &dictionary)
+        {
+            return known.words.clone();
+        }
+    }
+
+    let mut words = WordList::new();
+    for (_, bangla) in dictionary.words() {
+        words.insert(bangla);
+    }
+    for (_, bangla) in crate::autocorrect::corrections().words() {
+        words.insert(bangla);
+    }
+    for word in crate::learned_words::words() {
+        words.insert(&word.bangla);
+    }
+    let words = Arc::new(words);
+    *known = Some(Known {
+        settings,
+        learned,
+        dictionary,
+        words: words.clone(),
+    });
+    words
+}
+
+// Words of Bangla letters only; numbers and words with unmatched keys are
+// left alone
+fn is_checked(word: &str) -> bool {
+    word.chars().count() >= MIN_CHECKED_LEN
+        && word
+            .chars()
+            .all(|c| script_of(c) == Script::Bangla && !c.is_numeric())
+}
+
+/// Whether spelling can be checked: a full dictionary is installed.
+pub fn available() -> bool {
+    crate::full_dictionary::open().is_some()
+}
+
+/// Checks a word that just ended, as it stays on screen, and flags it when
+/// it isn't known. Any earlier flag goes, as its word is no longer the last
+/// one. Called from the hook thread.
+pub fn word_finished(bangla: &str, settings: &KeyboardSettings) {
+    close();
+    if !settings.spell_check || !is_checked(bangla) {
+        return;
+    }
+    let Some(full) = crate::full_dictionary::open() else {
+        return;
+    };
+    // The full dictionary is too large to copy into the list
+    let known = known_words();
+    if known.contains(bangla) || full.has_spelling(bangla) {
+        return;
+    }
+    // The nearest of either, closest first
+    let mut near = WordList::new();
+    for word in known.nearest(bangla, MAX_CORRECTION_DISTANCE, MAX_CORRECTIONS) {
+        near.insert(word);
+    }
+    for word in full.nearest_spellings(bangla, MAX_CORRECTION_DISTANCE, MAX_CORRECTIONS) {
+        near.insert(&word);
+    }
+    let corrections: Vec<String> = near
+        .nearest(bangla, MAX_CORRECTION_DISTANCE, MAX_CORRECTIONS)
+        .into_iter()
+        .map(str::to_string)
+        .collect();
+    let Some(caret) = caret::locate() else {
+        return;
+    };
+    *CURRENT.lock().unwrap() = Some(Flag {
+        caret,
+        word: bangla.to_string(),
+        corrections,
+        chosen: None,
+        shown: Instant::now(),
+    });
+    repaint();
+}
+
+pub fn close() {
+    if CURRENT.lock().unwrap().take().is_some() {
+        repaint();
+    }
+}
+
+/// The keys that put `new` in place of `old`, the last word of the text
+/// typed so far, keeping what ended it. None when something else was
+/// typed after it.
+fn respell(emitted: &str, old: &str, new: &str) -> Option<(usize, String)> {
+    let start = emitted.rfind(old)?;
+    let after = &emitted[start + old.len()..];
+    if after.chars().any(char::is_alphanumeric) {
+        return None;
+    }
+    Some((
+        old.chars().count() + after.chars().count(),
+        format!("{}{}", new, after),
+    ))
+}
+
+/// Puts the flagged word's next correction on screen, and after the last
+/// one the word as it was typed. Returns false when no word is flagged or
+/// it has no corrections, so the hotkey goes to the app. Waits for the
+/// hotkey's modifiers on a thread of its own, as Ctrl+Backspace would
+/// erase a whole word.
+pub fn correct() -> bool {
+    let mut current = CURRENT.lock().unwrap();
+    let Some(flag) = current.as_mut().filter(|flag| !flag.corrections.is_empty()) else {
+        return false;
+    };
+    let old = flag.on_screen().to_string();
+    flag.chosen = match flag.chosen {
+        Some(index) if index + 1 >= flag.corrections.len() => None,
+        Some(index) => Some(index + 1),
+        None => Some(0),
+    };
+    flag.shown = Instant::now();
+    let new = flag.on_screen().to_string();
+    drop(current);
+    repaint();
+
+    std::thread::spawn(move || {
+        let start = Instant::now();
+        while !crate::hotkey::modifiers_released() {
+            if start.elapsed() > RELEASE_TIMEOUT {
+                return;
+            }
+            std::thread::sleep(POLL_INTERVAL);
+        }
+        let history = crate::HISTORY.lock().unwrap();
+        match respell(history.text(), &old, &new) {
+            Some((backspaces, output)) => {
+                let plan = EditPlan::replace(history.text(), backspaces, &output);
+                crate::send(&plan, history);
+            }
+            // Typing went on, so the flag is out of date
+            None => {
+                drop(history);
+                close();
+            }
+        }
+    });
+    true
+}
+
+/// Draws the flagged word and its corrections below the caret until it
+/// expires, the one on screen highlighted. Like the candidate popup it
+/// never takes focus or clicks.
+pub fn show(ctx: &egui::Context, font_size: f32, hotkey: &str) {
+    let mut current = CURRENT.lock().unwrap();
+    let Some(flag) = current.clone() else {
+        return;
+    };
+    let settings = crate::SETTINGS.snapshot();
+    let Some(remaining) = FLAG_TIME.checked_sub(flag.shown.elapsed()) else {
+        *current = None;
+        return;
+    };
+    drop(current);
+    ctx.request_repaint_after(remaining);
+
+    let lines = flag.corrections.len().max(1) + 2;
+    let size = egui::vec2(220.0, (font_size + 12.0) * lines as f32 + 8.0);
+    let position = overlay::position(ctx, &flag.caret, font_size, size, Side::Below);
+
+    ctx.show_viewport_immediate(
+        ViewportId::from_hash_of("spelling_flag"),
+        overlay::builder("Spelling", position, size),
+        |ctx, _class| {
+            egui::CentralPanel::default().show(ctx, |ui| {
+                ui.add(egui::SelectableLabel::new(
+                    flag.chosen.is_none(),
+                    RichText::new(format!("✘ {}", mask::shown(&settings, &flag.word)))
+                        .size(font_size + 2.0)
+                        .color(Color32::RED)
+                        .underline(),
+                ));
+                if flag.corrections.is_empty() {
+                    ui.label(RichText::new("Not a known word").weak());
+                    return;
+                }
+                for (index, correction) in flag.corrections.iter().enumerate() {
+                    let correction = mask::shown(&settings, correction);
+                    let text = RichText::new(format!("{} {}", index + 1, correction))
+                        .size(font_size + 2.0);
+                    ui.add(egui::SelectableLabel::new(flag.chosen == Some(index), text));
+                }
+                ui.label(
+                    RichText::new(format!("{} for the next", hotkey))
+                        .weak()
+                        .size(12.0),
+                );
+            });
+        },
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_bangla_words_are_checked() {
+        assert!(is_checked("ভালবাসা"));
+        assert!(!is_checked("বিশw"));
+        assert!(!is_checked("২০২৪"));
+        assert!(!is_checked("ও"));
+    }
+
+    #[test]
+    fn the_last_word_is_respelled_with_what_ended_it() {
+        assert_eq!(
+            respell("আমি ভালবাসা, ", "ভালবাসা", "ভালোবাসা"),
+            Some((9, "ভালোবাসা, ".to_string()))
+        );
+        assert_eq!(respell("ভালবাসা তুমি", "ভালবাসা", "ভালোবাসা"), None);
+        assert_eq!(respell("আমি", "ভালবাসা", "ভালোবাসা"), None);
+    }
+}
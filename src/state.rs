@@ -0,0 +1,168 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Language {
+    Bangla,
+    English,
+}
+
+/// Temporary reasons to stop converting without changing what the user chose.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SuspendReason {
+    // Our own window has focus, so its text fields receive plain keys
+    AppFocused,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum Event {
+    Enable,
+    Disable,
+    SelectLanguage(Language),
+    ToggleLanguage,
+    SelectLayout(String),
+    Suspend(SuspendReason),
+    Resume(SuspendReason),
+}
+
+/// The keyboard's mode. Every change goes through `apply`, so enabling,
+/// language and suspension stay independent of each other.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+#[serde(default)]
+pub struct KeyboardState {
+    pub enabled: bool,
+    pub language: Language,
+    pub layout: String,
+    #[serde(skip)]
+    suspended: Vec<SuspendReason>,
+}
+
+impl Default for KeyboardState {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            language: Language::Bangla,
+            layout: "Phonetic".to_string(),
+            suspended: Vec::new(),
+        }
+    }
+}
+
+impl KeyboardState {
+    pub fn apply(&mut self, event: Event) {
+        match event {
+            Event::Enable => self.enabled = true,
+            Event::Disable => self.enabled = false,
+            Event::SelectLanguage(language) => self.language = language,
+            Event::ToggleLanguage => {
+                self.language = match self.language {
+                    Language::Bangla => Language::English,
+                    Language::English => Language::Bangla,
+                }
+            }
+            Event::SelectLayout(layout) => self.layout = layout,
+            Event::Suspend(reason) => {
+                if !self.suspended.contains(&reason) {
+                    self.suspended.push(reason);
+                }
+            }
+            Event::Resume(reason) => self.suspended.retain(|r| *r != reason),
+        }
+    }
+
+    pub fn is_bangla(&self) -> bool {
+        self.language == Language::Bangla
+    }
+
+    pub fn is_suspended(&self, reason: SuspendReason) -> bool {
+        self.suspended.contains(&reason)
+    }
+
+    pub fn converts_input(&self) -> bool {
+        self.enabled && self.is_bangla() && self.suspended.is_empty()
+    }
+
+    // English mode while not suspended
+    pub fn types_english(&self) -> bool {
+        self.enabled && !self.is_bangla() && self.suspended.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn state_after(events: Vec<Event>) -> KeyboardState {
+        let mut state = KeyboardState::default();
+        for event in events {
+            state.apply(event);
+        }
+        state
+    }
+
+    #[test]
+    fn default_state_converts() {
+        assert!(KeyboardState::default().converts_input());
+    }
+
+    #[test]
+    fn selecting_english_keeps_keyboard_enabled() {
+        let state = state_after(vec![Event::SelectLanguage(Language::English)]);
+        assert!(state.enabled);
+        assert!(!state.converts_input());
+
+        let state = state_after(vec![
+            Event::SelectLanguage(Language::English),
+            Event::SelectLanguage(Language::Bangla),
+        ]);
+        assert!(state.converts_input());
+    }
+
+    #[test]
+    fn selecting_bangla_does_not_enable() {
+        let state = state_after(vec![
+            Event::Disable,
+            Event::SelectLanguage(Language::Bangla),
+        ]);
+        assert!(!state.enabled);
+        assert!(!state.converts_input());
+    }
+
+    #[test]
+    fn toggle_language_round_trips() {
+        let state = state_after(vec![Event::ToggleLanguage]);
+        assert_eq!(state.language, Language::English);
+
+        let state = state_after(vec![Event::ToggleLanguage, Event::ToggleLanguage]);
+        assert_eq!(state.language, Language::Bangla);
+    }
+
+    #[test]
+    fn disabling_keeps_language() {
+        let state = state_after(vec![Event::Disable, Event::Enable]);
+        assert!(state.is_bangla());
+        assert!(state.converts_input());
+    }
+
+    #[test]
+    fn suspension_pauses_without_changing_choices() {
+        let state = state_after(vec![Event::Suspend(SuspendReason::AppFocused)]);
+        assert!(state.enabled);
+        assert!(state.is_bangla());
+        assert!(state.is_suspended(SuspendReason::AppFocused));
+        assert!(!state.converts_input());
+
+        let state = state_after(vec![
+            Event::Suspend(SuspendReason::AppFocused),
+            Event::Suspend(SuspendReason::AppFocused),
+            Event::Resume(SuspendReason::AppFocused),
+        ]);
+        assert!(state.converts_input());
+    }
+
+    #[test]
+    fn select_layout_only_changes_layout() {
+        let state = state_after(vec![Event::SelectLayout("Custom".to_string())]);
+        assert_eq!(state.layout, "Custom");
+        assert!(state.converts_input());
+    }
+}
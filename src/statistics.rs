@@ -0,0 +1,472 @@
+use crate::notification::{self, Icon};
+use crate::settings::{self, KeyboardSettings};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use windows::Win32::System::SystemInformation::GetLocalTime;
+
+// Bangla words typed per day and the time spent typing them, for the
+// Statistics window and an optional summary notification, and how often
+// each key and Bangla character was typed, for its heatmap. Optionally each
+// word is timed from its first key to the one ending it, to compare layouts.
+// Only counts and times are kept, never the words.
+
+/// Choices for `KeyboardSettings::usage_summary`, the first is the default.
+pub const SUMMARIES: [&str; 3] = ["Off", "Daily", "Weekly"];
+
+// Days older than this are dropped
+const KEPT_DAYS: i64 = 90;
+// A longer pause between words isn't typing time
+const IDLE_GAP: Duration = Duration::from_secs(10);
+const SAVE_INTERVAL: Duration = Duration::from_secs(60);
+
+/// One day's typing.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Day {
+    // Days since 1970-01-01, local time
+    pub day: i64,
+    pub bangla_words: u64,
+    pub typing_ms: u64,
+}
+
+impl Day {
+    /// Words per minute of typing time; 0 without any.
+    pub fn wpm(&self) -> f64 {
+        if self.typing_ms == 0 {
+            return 0.0;
+        }
+        self.bangla_words as f64 * 60_000.0 / self.typing_ms as f64
+    }
+}
+
+/// The words typed in a layout, and the time they took, on a day or added
+/// up over several.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq, Debug)]
+pub struct LayoutTiming {
+    pub day: i64,
+    pub layout: String,
+    pub words: u64,
+    pub word_ms: u64,
+}
+
+impl LayoutTiming {
+    /// Seconds from a word's first key to its end, on average.
+    pub fn seconds_per_word(&self) -> f64 {
+        if self.words == 0 {
+            return 0.0;
+        }
+        self.word_ms as f64 / 1000.0 / self.words as f64
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Default, PartialEq)]
+struct Store {
+    days: Vec<Day>,
+    // The day the last summary was shown, or counting started
+    last_summary: Option<i64>,
+    // Keys pressed in Bangla mode by their place on a US keyboard, and the
+    // Bangla characters typed, since counting started
+    #[serde(default)]
+    keys: BTreeMap<char, u64>,
+    #[serde(default)]
+    characters: BTreeMap<char, u64>,
+    #[serde(default)]
+    timings: Vec<LayoutTiming>,
+}
+
+impl Store {
+    fn add_word(&mut self, today: i64, typing: Duration) {
+        if self.days.last().is_none_or(|day| day.day != today) {
+            self.days.push(Day {
+                day: today,
+                bangla_words: 0,
+                typing_ms: 0,
+            });
+        }
+        self.days.retain(|day| day.day > today - KEPT_DAYS);
+        if let Some(day) = self.days.last_mut() {
+            day.bangla_words += 1;
+            day.typing_ms += typing.as_millis() as u64;
+        }
+    }
+
+    fn add_characters(&mut self, word: &str) {
+        for c in word
+            .chars()
+            .filter(|c| ('\u{0980}'..='\u{09FF}').contains(c))
+        {
+            *self.characters.entry(c).or_default() += 1;
+        }
+    }
+
+    fn add_timing(&mut self, today: i64, layout: &str, took: Duration) {
+        self.timings.retain(|timing| timing.day > today - KEPT_DAYS);
+        let index = match self
+            .timings
+            .iter()
+            .position(|timing| timing.day == today && timing.layout == layout)
+        {
+            Some(index) => index,
+            None => {
+                self.timings.push(LayoutTiming {
+                    day: today,
+                    layout: layout.to_string(),
+                    words: 0,
+                    word_ms: 0,
+                });
+                self.timings.len() - 1
+            }
+        };
+        self.timings[index].words += 1;
+        self.timings[index].word_ms += took.as_millis() as u64;
+    }
+
+    /// Each layout's words in the `count` days up to and including `today`,
+    /// most typed first.
+    fn layouts(&self, today: i64, count: i64) -> Vec<LayoutTiming> {
+        let mut layouts: Vec<LayoutTiming> = Vec::new();
+        for timing in &self.timings {
+            if timing.day <= today - count || timing.day > today {
+                continue;
+            }
+            match layouts
+                .iter_mut()
+                .find(|total| total.layout == timing.layout)
+            {
+                Some(total) => {
+                    total.words += timing.words;
+                    total.word_ms += timing.word_ms;
+                }
+                None => layouts.push(LayoutTiming {
+                    day: today - count + 1,
+                    ..timing.clone()
+                }),
+            }
+        }
+        layouts.sort_by(|a, b| b.words.cmp(&a.words).then(a.layout.cmp(&b.layout)));
+        layouts
+    }
+
+    /// The `count` days before `today`, added together.
+    fn total(&self, today: i64, count: i64) -> Day {
+        let mut total = Day {
+            day: today - count,
+            bangla_words: 0,
+            typing_ms: 0,
+        };
+        for day in &self.days {
+            if (today - count..today).contains(&day.day) {
+                total.bangla_words += day.bangla_words;
+                total.typing_ms += day.typing_ms;
+            }
+        }
+        total
+    }
+
+    /// The summary due today, if any: the period's name and its totals.
+    /// Starts the count the first time, so the first summary covers a whole
+    /// period.
+    fn due_summary(&mut self, today: i64, choice: &str) -> Option<(&'static str, Day)> {
+        let (period, name) = match choice {
+            "Daily" => (1, "yesterday"),
+            "Weekly" => (7, "in the last 7 days"),
+            _ => return None,
+        };
+        let last = *self.last_summary.get_or_insert(today);
+        if today - last < period {
+            return None;
+        }
+        self.last_summary = Some(today);
+        let total = self.total(today, period);
+        (total.bangla_words > 0).then_some((name, total))
+    }
+}
+
+static STORE: Mutex<Option<Store>> = Mutex::new(None);
+static LAST_WORD: Mutex<Option<Instant>> = Mutex::new(None);
+// The first key of the word being typed, when words are timed
+static WORD_START: Mutex<Option<Instant>> = Mutex::new(None);
+
+/// `%APPDATA%\RestroKeyboard\statistics.json`, next to the settings.
+pub fn path() -> Option<PathBuf> {
+    Some(KeyboardSettings::path()?.parent()?.join("statistics.json"))
+}
+
+fn read() -> Store {
+    path()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default()
+}
+
+/// Reads the statistics at startup, so the hook thread never waits for the
+/// file when the first word is finished.
+pub fn load() {
+    let store = read();
+    *STORE.lock().unwrap() = Some(store);
+}
+
+fn with_store<T>(f: impl FnOnce(&mut Store) -> T) -> T {
+    f(STORE.lock().unwrap().get_or_insert_with(read))
+}
+
+fn save() -> io::Result<()> {
+    let path =
+        path().ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "APPDATA is not set"))?;
+    let store = with_store(|store| store.clone());
+    settings::save_json_atomically(&path, &store)
+}
+
+// Days since 1970-01-01 of a date in the Gregorian calendar
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let month_index = (month + 9) % 12;
+    let day_of_year = (153 * month_index + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// The date of a day number, as 2024-03-01.
+pub fn date(day: i64) -> String {
+    let shifted = day + 719_468;
+    let era = shifted.div_euclid(146_097);
+    let day_of_era = shifted - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+pub fn today() -> i64 {
+    let now = unsafe { GetLocalTime() };
+    days_from_civil(now.wYear.into(), now.wMonth.into(), now.wDay.into())
+}
+
+/// Counts a key pressed in Bangla mode, by its place on a US keyboard.
+/// Called from the hook thread.
+pub fn key_pressed(key: char, settings: &KeyboardSettings) {
+    if !settings.statistics {
+        return;
+    }
+    with_store(|store| *store.keys.entry(key).or_default() += 1);
+    if settings.layout_timing {
+        WORD_START.lock().unwrap().get_or_insert_with(Instant::now);
+    }
+}
+
+/// Counts a Bangla word that just ended and its characters, with the time
+/// since the one before as typing time unless the user paused. With layout
+/// timing the word's own time is added to its layout's, unless it took so
+/// long the user must have paused. Called from the hook thread; the file is
+/// written later by the saver.
+pub fn word_finished(word: &str, settings: &KeyboardSettings) {
+    let started = WORD_START.lock().unwrap().take();
+    if !settings.statistics {
+        return;
+    }
+    let now = Instant::now();
+    let took = started
+        .map(|started| now - started)
+        .filter(|took| *took < IDLE_GAP && settings.layout_timing);
+    let typing = LAST_WORD
+        .lock()
+        .unwrap()
+        .replace(now)
+        .map(|last| now - last)
+        .filter(|gap| *gap < IDLE_GAP)
+        .unwrap_or_default();
+    with_store(|store| {
+        store.add_word(today(), typing);
+        store.add_characters(word);
+        if let Some(took) = took {
+            store.add_timing(today(), &settings.state.layout, took);
+        }
+    });
+}
+
+/// Forgets when the word being typed started, for words that end without
+/// being finished.
+pub fn word_abandoned() {
+    WORD_START.lock().unwrap().take();
+}
+
+/// The days with typing, oldest first.
+pub fn days() -> Vec<Day> {
+    with_store(|store| store.days.clone())
+}
+
+/// The `count` days before today, added together.
+pub fn recent(count: i64) -> Day {
+    with_store(|store| store.total(today(), count))
+}
+
+/// How often each key was pressed in Bangla mode.
+pub fn key_counts() -> BTreeMap<char, u64> {
+    with_store(|store| store.keys.clone())
+}
+
+/// How often each Bangla character was typed.
+pub fn character_counts() -> BTreeMap<char, u64> {
+    with_store(|store| store.characters.clone())
+}
+
+/// Each layout's words and their time over the last `count` days, today
+/// included, most typed first.
+pub fn layout_timings(count: i64) -> Vec<LayoutTiming> {
+    with_store(|store| store.layouts(today(), count))
+}
+
+pub fn day_count() -> usize {
+    with_store(|store| store.days.len())
+}
+
+pub fn delete() -> io::Result<()> {
+    with_store(|store| {
+        store.days.clear();
+        store.keys.clear();
+        store.characters.clear();
+        store.timings.clear();
+    });
+    match path() {
+        Some(path) if path.exists() => fs::remove_file(path),
+        _ => Ok(()),
+    }
+}
+
+/// 12400 as 12,400.
+pub fn grouped(number: u64) -> String {
+    let digits = number.to_string();
+    let mut grouped = String::new();
+    for (index, digit) in digits.chars().enumerate() {
+        if index > 0 && (digits.len() - index).is_multiple_of(3) {
+            grouped.push(',');
+        }
+        grouped.push(digit);
+    }
+    grouped
+}
+
+/// Saves the counts every minute, and shows the summary when one is due.
+/// Clicking it opens the Statistics window.
+pub fn spawn_saver() {
+    std::thread::spawn(|| {
+        let mut saved = None;
+        loop {
+            std::thread::sleep(SAVE_INTERVAL);
+            let settings = crate::SETTINGS.snapshot();
+            let summary = with_store(|store| store.due_summary(today(), &settings.usage_summary));
+            let store = with_store(|store| store.clone());
+            if saved.as_ref() != Some(&store) || summary.is_some() {
+                if let Err(err) = save() {
+                    eprintln!("Could not save the statistics: {}", err);
+                }
+                saved = Some(store);
+            }
+            if let Some((period, total)) = summary {
+                let message = format!(
+                    "You typed {} Bangla words {}, avg {:.0} WPM. Click for your statistics.",
+                    grouped(total.bangla_words),
+                    period,
+                    total.wpm()
+                );
+                if notification::show("Your typing", &message, Icon::Info) {
+                    crate::tray::open_statistics();
+                }
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn day_numbers_round_trip_through_dates() {
+        assert_eq!(days_from_civil(1970, 1, 1), 0);
+        assert_eq!(days_from_civil(2024, 3, 1), 19_783);
+        assert_eq!(date(19_783), "2024-03-01");
+        assert_eq!(date(days_from_civil(2000, 2, 29)), "2000-02-29");
+    }
+
+    #[test]
+    fn words_are_counted_per_day() {
+        let mut store = Store::default();
+        store.add_word(100, Duration::ZERO);
+        store.add_word(100, Duration::from_secs(2));
+        store.add_word(101, Duration::from_secs(1));
+        assert_eq!(store.days.len(), 2);
+        let total = store.total(102, 7);
+        assert_eq!((total.bangla_words, total.typing_ms), (3, 3000));
+        assert_eq!(total.wpm(), 60.0);
+        // Today isn't over, so it isn't in the summary
+        assert_eq!(store.total(101, 7).bangla_words, 2);
+    }
+
+    #[test]
+    fn keys_and_characters_survive_saving() {
+        let mut store = Store::default();
+        store.keys.insert('k', 2);
+        store.add_characters("আমি a");
+        let json = serde_json::to_string(&store).unwrap();
+        let read: Store = serde_json::from_str(&json).unwrap();
+        assert_eq!(read.keys[&'k'], 2);
+        assert_eq!(read.characters.len(), 3);
+        // Counts from before there were any
+        let old: Store = serde_json::from_str(r#"{"days":[],"last_summary":null}"#).unwrap();
+        assert!(old.keys.is_empty());
+    }
+
+    #[test]
+    fn layouts_are_compared_by_time_per_word() {
+        let mut store = Store::default();
+        store.add_timing(100, "Avro", Duration::from_millis(800));
+        store.add_timing(101, "Avro", Duration::from_millis(1200));
+        store.add_timing(101, "Bijoy", Duration::from_millis(1500));
+        store.add_timing(50, "Bijoy", Duration::from_millis(900));
+        let layouts = store.layouts(101, 7);
+        assert_eq!(layouts.len(), 2);
+        assert_eq!((layouts[0].layout.as_str(), layouts[0].words), ("Avro", 2));
+        assert_eq!(layouts[0].seconds_per_word(), 1.0);
+        assert_eq!((layouts[1].layout.as_str(), layouts[1].words), ("Bijoy", 1));
+        // Only today
+        assert_eq!(store.layouts(101, 1)[0].words, 1);
+        // Days past keeping are dropped
+        store.add_timing(150, "Avro", Duration::ZERO);
+        assert!(store.timings.iter().all(|timing| timing.day > 60));
+    }
+
+    #[test]
+    fn summaries_come_once_a_period() {
+        let mut store = Store::default();
+        store.add_word(100, Duration::from_secs(1));
+        // The first check only starts the count
+        assert_eq!(store.due_summary(100, "Weekly"), None);
+        assert_eq!(store.due_summary(106, "Weekly"), None);
+        let (period, total) = store.due_summary(107, "Weekly").unwrap();
+        assert_eq!((period, total.bangla_words), ("in the last 7 days", 1));
+        assert_eq!(store.due_summary(108, "Weekly"), None);
+        assert_eq!(store.due_summary(200, "Off"), None);
+    }
+
+    #[test]
+    fn numbers_are_grouped_by_thousands() {
+        assert_eq!(grouped(12_400), "12,400");
+        assert_eq!(grouped(999), "999");
+        assert_eq!(grouped(1_000_000), "1,000,000");
+    }
+}
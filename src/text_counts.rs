@@ -0,0 +1,95 @@
+use unicode_segmentation::UnicodeSegmentation;
+
+// Word and character counts for writers with a word limit. A character is
+// what a reader sees as one, a grapheme cluster: ক্ষ is one character, not
+// the three code points it is stored as. An akshara is a character with a
+// Bangla letter in it, a consonant or cluster with its vowel sign (ক্ষ, মা).
+
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub struct TextCounts {
+    pub words: usize,
+    pub characters: usize,
+    pub characters_without_spaces: usize,
+    pub aksharas: usize,
+}
+
+// "1 word", "2 words"
+fn count(n: usize, noun: &str) -> String {
+    format!("{} {}{}", n, noun, if n == 1 { "" } else { "s" })
+}
+
+fn is_letter(c: char) -> bool {
+    matches!(c, '\u{0985}'..='\u{09B9}' | 'ৎ' | '\u{09DC}'..='\u{09DF}')
+}
+
+impl TextCounts {
+    pub fn of(text: &str) -> Self {
+        let mut counts = Self {
+            words: text.unicode_words().count(),
+            ..Self::default()
+        };
+        for cluster in text.graphemes(true) {
+            counts.characters += 1;
+            if !cluster.chars().all(char::is_whitespace) {
+                counts.characters_without_spaces += 1;
+            }
+            if cluster.chars().any(is_letter) {
+                counts.aksharas += 1;
+            }
+        }
+        counts
+    }
+
+    /// As "12 words · 40 characters (34 without spaces) · 30 aksharas",
+    /// aksharas left out when there are none.
+    pub fn summary(&self) -> String {
+        let mut summary = format!(
+            "{} · {} ({} without spaces)",
+            count(self.words, "word"),
+            count(self.characters, "character"),
+            self.characters_without_spaces
+        );
+        if self.aksharas > 0 {
+            summary.push_str(&format!(" · {}", count(self.aksharas, "akshara")));
+        }
+        summary
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn conjuncts_and_vowel_signs_are_one_character() {
+        let counts = TextCounts::of("ক্ষমা");
+        assert_eq!(counts.characters, 2);
+        assert_eq!(counts.aksharas, 2);
+        assert_eq!("ক্ষমা".chars().count(), 5);
+        assert_eq!(TextCounts::of("স্ত্রী").characters, 1);
+        assert_eq!(TextCounts::of("বাংলা").aksharas, 2);
+    }
+
+    #[test]
+    fn words_are_split_on_spaces_and_punctuation() {
+        let counts = TextCounts::of("আমি বাংলায় লিখি। Hello, world!");
+        assert_eq!(counts.words, 5);
+        assert_eq!(counts.aksharas, 7);
+        assert_eq!(TextCounts::of("  \n").words, 0);
+    }
+
+    #[test]
+    fn spaces_count_as_characters_unless_left_out() {
+        let counts = TextCounts::of("ক খ\n");
+        assert_eq!(counts.characters, 4);
+        assert_eq!(counts.characters_without_spaces, 2);
+        assert_eq!(
+            counts.summary(),
+            "2 words · 4 characters (2 without spaces) · 2 aksharas"
+        );
+        assert_eq!(
+            TextCounts::of("ok").summary(),
+            "1 word · 2 characters (2 without spaces)"
+        );
+    }
+}
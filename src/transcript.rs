@@ -0,0 +1,177 @@
+use crate::history::EmissionHistory;
+use crate::settings::KeyboardSettings;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use windows::Win32::System::SystemInformation::GetLocalTime;
+
+// An opt-in record of the text typed through the keyboard, for people who
+// type into many small fields and want one copy of their work. It holds
+// only what the history sees, in memory, until it is saved.
+
+/// Text typed while recording, one paragraph per place it was typed in.
+#[derive(Clone, Default)]
+pub struct Transcript {
+    paragraphs: Vec<String>,
+    // Local time recording started, as shown in file names and headings
+    started: String,
+}
+
+// Local time as 2025-03-01 14.05, which file names can hold
+fn now() -> String {
+    let time = unsafe { GetLocalTime() };
+    format!(
+        "{}-{:02}-{:02} {:02}.{:02}",
+        time.wYear, time.wMonth, time.wDay, time.wHour, time.wMinute
+    )
+}
+
+impl Transcript {
+    pub fn start() -> Self {
+        Self {
+            paragraphs: Vec::new(),
+            started: now(),
+        }
+    }
+
+    pub fn push(&mut self, text: &str) {
+        match self.paragraphs.last_mut() {
+            Some(paragraph) => paragraph.push_str(text),
+            None => self.paragraphs.push(text.to_string()),
+        }
+    }
+
+    // Backspaces reach only into the paragraph being typed
+    pub fn erase(&mut self, count: usize) {
+        if let Some(paragraph) = self.paragraphs.last_mut() {
+            for _ in 0..count {
+                paragraph.pop();
+            }
+        }
+    }
+
+    /// The caret moved to another field or place; later text starts a new
+    /// paragraph.
+    pub fn break_paragraph(&mut self) {
+        if self.paragraphs.last().is_some_and(|p| !p.trim().is_empty()) {
+            self.paragraphs.push(String::new());
+        }
+    }
+
+    /// The paragraphs with text, separated by blank lines.
+    pub fn text(&self) -> String {
+        self.paragraphs
+            .iter()
+            .map(|paragraph| paragraph.trim())
+            .filter(|paragraph| !paragraph.is_empty())
+            .collect::<Vec<_>>()
+            .join("\n\n")
+    }
+
+    pub fn markdown(&self) -> String {
+        format!("# Transcript of {}\n\n{}\n", self.started, self.text())
+    }
+
+    pub fn char_count(&self) -> usize {
+        self.paragraphs.iter().map(|p| p.chars().count()).sum()
+    }
+
+    /// Writes the transcript as `.md` or `.txt` to the transcripts folder,
+    /// returning the file.
+    pub fn save(&self, markdown: bool) -> io::Result<PathBuf> {
+        let dir =
+            dir().ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "APPDATA is not set"))?;
+        fs::create_dir_all(&dir)?;
+        let (extension, contents) = if markdown {
+            ("md", self.markdown())
+        } else {
+            ("txt", self.text() + "\n")
+        };
+        let path = dir.join(format!("Transcript {}.{}", self.started, extension));
+        fs::write(&path, contents)?;
+        Ok(path)
+    }
+}
+
+/// The transcript window's copy of the recording and its text. It is taken
+/// again only when the recording changed, so a long one isn't copied under
+/// the history lock on every frame.
+#[derive(Default)]
+pub struct View {
+    generation: Option<u64>,
+    pub transcript: Option<Transcript>,
+    pub text: String,
+}
+
+impl View {
+    pub fn refresh(&mut self, history: &EmissionHistory) {
+        let generation = history.transcript_generation();
+        if self.generation == Some(generation) {
+            return;
+        }
+        self.transcript = history.transcript().cloned();
+        self.text = self
+            .transcript
+            .as_ref()
+            .map(Transcript::text)
+            .unwrap_or_default();
+        self.generation = Some(generation);
+    }
+}
+
+/// `%APPDATA%\RestroKeyboard\transcripts`, if APPDATA is set.
+pub fn dir() -> Option<PathBuf> {
+    Some(KeyboardSettings::path()?.parent()?.join("transcripts"))
+}
+
+/// How many transcripts were saved; none when the folder doesn't exist.
+pub fn file_count() -> usize {
+    dir()
+        .and_then(|dir| fs::read_dir(dir).ok())
+        .map_or(0, |entries| entries.count())
+}
+
+pub fn delete() -> io::Result<()> {
+    match dir() {
+        Some(dir) if dir.exists() => fs::remove_dir_all(dir),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn each_place_typed_in_is_a_paragraph() {
+        let mut transcript = Transcript::default();
+        transcript.push("আমি ");
+        transcript.push("যাব");
+        transcript.break_paragraph();
+        transcript.push("তুমি");
+        assert_eq!(transcript.text(), "আমি যাব\n\nতুমি");
+    }
+
+    #[test]
+    fn backspaces_stay_in_the_current_paragraph() {
+        let mut transcript = Transcript::default();
+        transcript.push("আমি");
+        transcript.break_paragraph();
+        transcript.push("ক");
+        transcript.erase(3);
+        assert_eq!(transcript.text(), "আমি");
+    }
+
+    #[test]
+    fn empty_places_leave_no_blank_paragraphs() {
+        let mut transcript = Transcript::default();
+        transcript.break_paragraph();
+        transcript.push("এক");
+        transcript.break_paragraph();
+        transcript.break_paragraph();
+        transcript.push(" ");
+        transcript.break_paragraph();
+        transcript.push("দুই");
+        assert_eq!(transcript.text(), "এক\n\nদুই");
+    }
+}
@@ -0,0 +1,181 @@
+use crate::features::Feature;
+use crate::state::{Event, Language};
+use crate::{layout_names, SETTINGS};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use tray_item::{IconSource, TIError, TrayItem};
+use windows::core::{w, PCWSTR};
+use windows::Win32::UI::WindowsAndMessaging::{
+    FindWindowW, SetForegroundWindow, ShowWindow, SW_SHOW,
+};
+
+// The icon follows language changes within a quarter second
+const ICON_INTERVAL: Duration = Duration::from_millis(250);
+
+// Set once the icon is in the notification area, so closing the window
+// only hides it
+static RUNNING: AtomicBool = AtomicBool::new(false);
+static SETTINGS_REQUESTED: AtomicBool = AtomicBool::new(false);
+static STATISTICS_REQUESTED: AtomicBool = AtomicBool::new(false);
+
+pub fn is_running() -> bool {
+    RUNNING.load(Ordering::SeqCst)
+}
+
+/// Whether "Settings" was picked from the tray menu since the last call.
+pub fn settings_requested() -> bool {
+    SETTINGS_REQUESTED.swap(false, Ordering::SeqCst)
+}
+
+/// Whether the Statistics window was asked for since the last call.
+pub fn statistics_requested() -> bool {
+    STATISTICS_REQUESTED.swap(false, Ordering::SeqCst)
+}
+
+/// Brings the window up with Statistics open, as the summary's link does.
+pub fn open_statistics() {
+    STATISTICS_REQUESTED.store(true, Ordering::SeqCst);
+    show_window();
+}
+
+// Icons from manifest.rc: green B for Bangla, grey E for English or off
+fn icon(bangla: bool) -> IconSource {
+    IconSource::Resource(if bangla {
+        "bangla-icon"
+    } else {
+        "english-icon"
+    })
+}
+
+fn shows_bangla() -> bool {
+    let settings = SETTINGS.snapshot();
+    settings.state.enabled && settings.state.is_bangla()
+}
+
+fn apply(event: Event) {
+    SETTINGS.update(|settings| settings.state.apply(event));
+}
+
+fn show_window() {
+    unsafe {
+        let window = FindWindowW(PCWSTR::null(), w!("Restro Keyboard"));
+        if window.0 != 0 {
+            let _ = ShowWindow(window, SW_SHOW);
+            let _ = SetForegroundWindow(window);
+        }
+    }
+}
+
+// What the menu lists besides the fixed items; the menu is built again
+// when it changes
+#[derive(PartialEq)]
+struct Menu {
+    layouts: Vec<String>,
+    clipboard_monitor: bool,
+}
+
+impl Menu {
+    fn current() -> Self {
+        Self {
+            layouts: layout_names(),
+            clipboard_monitor: SETTINGS
+                .snapshot()
+                .feature_enabled(Feature::ClipboardMonitor),
+        }
+    }
+}
+
+fn build(menu: &Menu) -> Result<TrayItem, TIError> {
+    let mut tray = TrayItem::new("Restro Keyboard", icon(shows_bangla()))?;
+    tray.add_label("Restro Keyboard")?;
+    tray.inner_mut().add_separator()?;
+
+    tray.add_menu_item("Enable / disable", || {
+        SETTINGS.update(|settings| {
+            let event = if settings.state.enabled {
+                Event::Disable
+            } else {
+                Event::Enable
+            };
+            settings.state.apply(event);
+        })
+    })?;
+    tray.add_menu_item("বাংলা", || {
+        apply(Event::SelectLanguage(Language::Bangla))
+    })?;
+    tray.add_menu_item("English", || {
+        apply(Event::SelectLanguage(Language::English))
+    })?;
+    tray.inner_mut().add_separator()?;
+
+    for name in menu.layouts.clone() {
+        tray.add_menu_item(&format!("{} layout", name), move || {
+            apply(Event::SelectLayout(name.clone()))
+        })?;
+    }
+    tray.inner_mut().add_separator()?;
+
+    if menu.clipboard_monitor {
+        tray.add_menu_item("Clipboard monitor on / off", || {
+            SETTINGS.update(|settings| settings.clipboard_monitor = !settings.clipboard_monitor)
+        })?;
+        tray.inner_mut().add_separator()?;
+    }
+
+    tray.add_menu_item("Open", show_window)?;
+    tray.add_menu_item("Settings", || {
+        SETTINGS_REQUESTED.store(true, Ordering::SeqCst);
+        show_window();
+    })?;
+    tray.add_menu_item("Statistics", open_statistics)?;
+    tray.add_menu_item("Exit", || {
+        // The saver thread won't get another turn
+        if let Err(err) = SETTINGS.snapshot().save() {
+            eprintln!("Could not save settings: {}", err);
+        }
+        std::process::exit(0);
+    })?;
+
+    Ok(tray)
+}
+
+/// Puts the icon in the notification area and keeps it in step with the
+/// language and the layouts. Without a tray icon the app behaves as before.
+pub fn spawn() {
+    std::thread::spawn(|| {
+        let mut menu = Menu::current();
+        let mut tray = match build(&menu) {
+            Ok(tray) => tray,
+            Err(err) => {
+                eprintln!("Could not create the tray icon: {:?}", err);
+                return;
+            }
+        };
+        RUNNING.store(true, Ordering::SeqCst);
+
+        let mut shown = shows_bangla();
+        loop {
+            std::thread::sleep(ICON_INTERVAL);
+            // A layout or the clipboard monitor came or went: the old icon
+            // goes before the new one comes, so only one is ever in the tray
+            let current = Menu::current();
+            if current != menu {
+                drop(tray);
+                menu = current;
+                tray = match build(&menu) {
+                    Ok(tray) => tray,
+                    Err(err) => {
+                        eprintln!("Could not rebuild the tray icon: {:?}", err);
+                        RUNNING.store(false, Ordering::SeqCst);
+                        return;
+                    }
+                };
+                shown = shows_bangla();
+            }
+            let bangla = shows_bangla();
+            if bangla != shown && tray.set_icon(icon(bangla)).is_ok() {
+                shown = bangla;
+            }
+        }
+    });
+}
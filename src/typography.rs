@@ -0,0 +1,203 @@
+use crate::history::{script_of, Script};
+use crate::sentence;
+use crate::settings::KeyboardSettings;
+use windows::Win32::UI::Input::KeyboardAndMouse::{VIRTUAL_KEY, VK_OEM_7, VK_OEM_MINUS, VK_SPACE};
+
+/// Punctuation keys that typography preferences may rewrite when they
+/// commit a word.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Mark {
+    Quote,
+    DoubleQuote,
+    Dash,
+    Space,
+}
+
+impl Mark {
+    pub fn from_key(vk_code: VIRTUAL_KEY, shift: bool) -> Option<Self> {
+        match vk_code {
+            VK_OEM_7 if shift => Some(Mark::DoubleQuote),
+            VK_OEM_7 => Some(Mark::Quote),
+            VK_OEM_MINUS if !shift => Some(Mark::Dash),
+            VK_SPACE => Some(Mark::Space),
+            _ => None,
+        }
+    }
+
+    // What the key types when left alone
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Mark::Quote => "'",
+            Mark::DoubleQuote => "\"",
+            Mark::Dash => "-",
+            Mark::Space => " ",
+        }
+    }
+}
+
+/// Replacement for `mark` given the text before the caret, as the number of
+/// characters to erase and the text to type. None lets the key through.
+pub fn replace(mark: Mark, settings: &KeyboardSettings, before: &str) -> Option<(usize, String)> {
+    match mark {
+        Mark::Quote | Mark::DoubleQuote if settings.curly_quotes => {
+            let opening = before
+                .chars()
+                .last()
+                .is_none_or(|c| c.is_whitespace() || "([{‘“".contains(c));
+            let quote = match (mark, opening) {
+                (Mark::Quote, true) => "‘",
+                (Mark::Quote, false) => "’",
+                (_, true) => "“",
+                (_, false) => "”",
+            };
+            Some((0, quote.to_string()))
+        }
+        // "--" becomes an en dash, a third "-" turns it into an em dash
+        Mark::Dash if settings.smart_dashes => {
+            if before.ends_with('–') {
+                Some((1, "—".to_string()))
+            } else if before.ends_with('-') {
+                Some((1, "–".to_string()))
+            } else {
+                None
+            }
+        }
+        Mark::Space if settings.nbsp_after_abbreviation && ends_with_abbreviation(before) => {
+            Some((0, "\u{00A0}".to_string()))
+        }
+        // A second space after a dari is swallowed
+        Mark::Space if settings.dari_spacing && before.ends_with("। ") => {
+            Some((0, String::new()))
+        }
+        _ => None,
+    }
+}
+
+/// How many spaces before the caret a dari typed now should erase: with
+/// dari spacing on, those right after Bangla text (কি । becomes কি।).
+/// Spaces after English are left alone.
+pub fn spaces_before_dari(settings: &KeyboardSettings, before: &str) -> usize {
+    if !settings.dari_spacing {
+        return 0;
+    }
+    let trimmed = before.trim_end_matches(' ');
+    let after_bangla = trimmed
+        .chars()
+        .last()
+        .is_some_and(|c| script_of(c) == Script::Bangla);
+    if after_bangla {
+        before.len() - trimmed.len()
+    } else {
+        0
+    }
+}
+
+// A short Bangla stem closed by ঃ (ডঃ) or a known abbreviation closed by a
+// period (ড.)
+fn ends_with_abbreviation(before: &str) -> bool {
+    let word = sentence::last_word(before);
+    if let Some(stem) = word.strip_suffix('.') {
+        return sentence::is_abbreviation(stem);
+    }
+    let Some(stem) = word.strip_suffix('ঃ') else {
+        return false;
+    };
+    let len = stem.chars().count();
+    (1..=3).contains(&len) && stem.chars().all(|c| script_of(c) == Script::Bangla)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dari_spacing() -> KeyboardSettings {
+        KeyboardSettings {
+            dari_spacing: true,
+            ..KeyboardSettings::default()
+        }
+    }
+
+    #[test]
+    fn spaces_before_a_dari_are_erased_after_bangla() {
+        let settings = dari_spacing();
+        assert_eq!(spaces_before_dari(&settings, "আমি যাব  "), 2);
+        assert_eq!(spaces_before_dari(&settings, "আমি যাব"), 0);
+        assert_eq!(spaces_before_dari(&settings, "OK "), 0);
+        assert_eq!(spaces_before_dari(&KeyboardSettings::default(), "যাব "), 0);
+    }
+
+    #[test]
+    fn one_space_follows_a_dari() {
+        let settings = dari_spacing();
+        assert_eq!(replace(Mark::Space, &settings, "যাব।"), None);
+        assert_eq!(
+            replace(Mark::Space, &settings, "যাব। "),
+            Some((0, String::new()))
+        );
+        assert_eq!(
+            replace(Mark::Space, &KeyboardSettings::default(), "যাব। "),
+            None
+        );
+    }
+
+    #[test]
+    fn quotes_open_after_a_space_or_bracket_and_close_after_a_letter() {
+        let settings = KeyboardSettings {
+            curly_quotes: true,
+            ..KeyboardSettings::default()
+        };
+        let quote = |mark, before| replace(mark, &settings, before).map(|(_, text)| text);
+        assert_eq!(quote(Mark::DoubleQuote, "").as_deref(), Some("“"));
+        assert_eq!(quote(Mark::DoubleQuote, "বলল ").as_deref(), Some("“"));
+        assert_eq!(quote(Mark::DoubleQuote, "(").as_deref(), Some("“"));
+        assert_eq!(quote(Mark::DoubleQuote, "আমি").as_deref(), Some("”"));
+        assert_eq!(quote(Mark::Quote, "“").as_deref(), Some("‘"));
+        assert_eq!(quote(Mark::Quote, "যাব").as_deref(), Some("’"));
+        assert_eq!(quote(Mark::Quote, "it").as_deref(), Some("’"));
+        assert_eq!(
+            replace(Mark::Quote, &KeyboardSettings::default(), "যাব"),
+            None
+        );
+    }
+
+    #[test]
+    fn hyphens_build_up_to_dashes() {
+        let settings = KeyboardSettings {
+            smart_dashes: true,
+            ..KeyboardSettings::default()
+        };
+        assert_eq!(replace(Mark::Dash, &settings, "ঢাকা"), None);
+        assert_eq!(
+            replace(Mark::Dash, &settings, "ঢাকা-"),
+            Some((1, "–".to_string()))
+        );
+        assert_eq!(
+            replace(Mark::Dash, &settings, "ঢাকা–"),
+            Some((1, "—".to_string()))
+        );
+        assert_eq!(
+            replace(Mark::Dash, &KeyboardSettings::default(), "ঢাকা-"),
+            None
+        );
+    }
+
+    #[test]
+    fn abbreviations_keep_the_next_word_on_their_line() {
+        let settings = KeyboardSettings {
+            nbsp_after_abbreviation: true,
+            ..KeyboardSettings::default()
+        };
+        let nbsp = Some((0, "\u{00A0}".to_string()));
+        assert_eq!(replace(Mark::Space, &settings, "ডঃ"), nbsp);
+        assert_eq!(replace(Mark::Space, &settings, "দেখা করলাম ড."), nbsp);
+        assert_eq!(replace(Mark::Space, &settings, "এ."), nbsp);
+        // Words that end in ঃ or a sentence that ends in a period
+        assert_eq!(replace(Mark::Space, &settings, "দুঃখিতঃ"), None);
+        assert_eq!(replace(Mark::Space, &settings, "যাব."), None);
+        assert_eq!(replace(Mark::Space, &settings, "Dr."), None);
+        assert_eq!(
+            replace(Mark::Space, &KeyboardSettings::default(), "ডঃ"),
+            None
+        );
+    }
+}
@@ -0,0 +1,133 @@
+use crate::caret::{self, Caret};
+use crate::mask;
+use crate::overlay::{self, Side};
+use crate::settings::KeyboardSettings;
+use egui::text::{LayoutJob, TextFormat};
+use egui::{Color32, FontId, Stroke, ViewportId};
+use once_cell::sync::OnceCell;
+use restro_keyboard_core::Conversion;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use windows::Win32::System::Diagnostics::Debug::MessageBeep;
+use windows::Win32::UI::WindowsAndMessaging::MB_ICONWARNING;
+
+// Letters the layout has no rule for are typed as they are (bishw types
+// বিশw). What else happens when such a word ends is up to the user.
+
+/// Choices for `KeyboardSettings::unmatched_keys`, the first is the default.
+pub const POLICIES: [&str; 4] = ["Pass through", "Highlight", "Beep", "Suggest a word"];
+
+// How long a notice stays next to the caret
+const NOTICE_TIME: Duration = Duration::from_secs(3);
+
+// Dictionary words further than this from the typed word aren't suggested
+const MAX_SUGGESTION_DISTANCE: usize = 2;
+
+#[derive(Clone)]
+struct Notice {
+    caret: Caret,
+    // Pieces of text, and whether each one is unmatched input
+    parts: Vec<(String, bool)>,
+    shown: Instant,
+}
+
+// Set by the hook thread, drawn by the UI until it expires
+static CURRENT: Mutex<Option<Notice>> = Mutex::new(None);
+static UI_CONTEXT: OnceCell<egui::Context> = OnceCell::new();
+
+/// Lets the hook wake the UI when a notice is posted.
+pub fn attach(ctx: egui::Context) {
+    let _ = UI_CONTEXT.set(ctx);
+}
+
+fn post(parts: Vec<(String, bool)>) {
+    let Some(caret) = caret::locate() else {
+        return;
+    };
+    *CURRENT.lock().unwrap() = Some(Notice {
+        caret,
+        parts,
+        shown: Instant::now(),
+    });
+    if let Some(ctx) = UI_CONTEXT.get() {
+        ctx.request_repaint();
+    }
+}
+
+/// Applies the policy to a word that just ended, given as typed and as
+/// converted. Does nothing when every letter matched a rule. Called from the
+/// hook thread.
+pub fn word_finished(roman: &str, conversion: &Conversion, settings: &KeyboardSettings) {
+    if conversion.unmatched().next().is_none() {
+        return;
+    }
+    match settings.unmatched_keys.as_str() {
+        "Highlight" => post(
+            conversion
+                .steps
+                .iter()
+                .map(|step| (step.output.clone(), step.is_unmatched()))
+                .collect(),
+        ),
+        "Beep" => unsafe {
+            let _ = MessageBeep(MB_ICONWARNING);
+        },
+        "Suggest a word" => {
+            if let Some((word, bangla)) =
+                crate::dictionary().nearest(roman, MAX_SUGGESTION_DISTANCE)
+            {
+                post(vec![(format!("{} ({})", bangla, word), false)]);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Draws the latest notice below the caret until it expires. Like the
+/// candidate popup it never takes focus or clicks.
+pub fn show(ctx: &egui::Context, font_size: f32) {
+    let mut current = CURRENT.lock().unwrap();
+    let Some(mut notice) = current.clone() else {
+        return;
+    };
+    let Some(remaining) = NOTICE_TIME.checked_sub(notice.shown.elapsed()) else {
+        *current = None;
+        return;
+    };
+    drop(current);
+    ctx.request_repaint_after(remaining);
+
+    // Masked, the notice only says that some keys weren't matched
+    if mask::is_on(&crate::SETTINGS.snapshot()) {
+        notice.parts = vec![(mask::MASK.to_string(), true)];
+    }
+
+    let font = FontId::proportional(font_size + 2.0);
+    let mut job = LayoutJob::default();
+    for (text, unmatched) in &notice.parts {
+        let format = if *unmatched {
+            TextFormat {
+                font_id: font.clone(),
+                color: Color32::RED,
+                underline: Stroke::new(1.0, Color32::RED),
+                ..Default::default()
+            }
+        } else {
+            TextFormat::simple(font.clone(), ctx.style().visuals.text_color())
+        };
+        job.append(text, 0.0, format);
+    }
+
+    let size = egui::vec2(220.0, font_size + 20.0);
+    let position = overlay::position(ctx, &notice.caret, font_size, size, Side::Below);
+
+    ctx.show_viewport_immediate(
+        ViewportId::from_hash_of("unmatched_notice"),
+        overlay::builder("Unmatched keys", position, size),
+        |ctx, _class| {
+            egui::CentralPanel::default().show(ctx, |ui| {
+                ui.label(job);
+            });
+        },
+    );
+}
@@ -0,0 +1,53 @@
+use crate::settings::{self, KeyboardSettings};
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+// Whole-word spellings added by the user, in the bundled dictionary's
+// `roman<TAB>bangla` format. Read after the bundled words, so they win.
+
+/// `%APPDATA%\RestroKeyboard\dictionary.tsv`, if APPDATA is set.
+pub fn path() -> Option<PathBuf> {
+    Some(KeyboardSettings::path()?.parent()?.join("dictionary.tsv"))
+}
+
+/// The file's text; empty when it is missing or unreadable.
+pub fn load() -> String {
+    path()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .unwrap_or_default()
+}
+
+pub fn count() -> usize {
+    restro_keyboard_core::Dictionary::parse(&load()).len()
+}
+
+/// Adds words, replacing earlier spellings of the same roman word. Returns
+/// how many were new or changed.
+pub fn add(words: &[(String, String)]) -> io::Result<usize> {
+    let path =
+        path().ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "APPDATA is not set"))?;
+    let mut text = load();
+    let known = restro_keyboard_core::Dictionary::parse(&text);
+    let mut added = 0;
+    for (roman, bangla) in words {
+        if known.get(roman).as_ref() == Some(bangla) {
+            continue;
+        }
+        if !text.is_empty() && !text.ends_with('\n') {
+            text.push('\n');
+        }
+        text.push_str(&format!("{}\t{}\n", roman, bangla));
+        added += 1;
+    }
+
+    settings::write_atomically(&path, &text)?;
+    Ok(added)
+}
+
+pub fn delete() -> io::Result<()> {
+    match path() {
+        Some(path) if path.exists() => fs::remove_file(path),
+        _ => Ok(()),
+    }
+}
@@ -0,0 +1,82 @@
+use unicode_normalization::UnicodeNormalization;
+
+// Bangla pasted into web pages often comes out broken: sites store it
+// decomposed or composed as they like, drop or double invisible characters
+// and restyle punctuation. "Copy for web" cleans the converter's output for
+// the site it goes to first, one profile per kind of site.
+
+/// Where the text is going, the first is the default.
+pub const PROFILES: [&str; 3] = ["Facebook", "Blogger", "Old phones (SMS)"];
+
+// Invisible characters no site needs: zero width space, word joiner, byte
+// order mark, soft hyphen and the direction marks
+const INVISIBLE: [char; 6] = [
+    '\u{200B}', '\u{2060}', '\u{FEFF}', '\u{00AD}', '\u{200E}', '\u{200F}',
+];
+const ZWNJ: char = '\u{200C}';
+const ZWJ: char = '\u{200D}';
+const HASANTA: char = '\u{09CD}';
+
+/// `text` cleaned for `profile`. Every profile composes the text (NFC) and
+/// drops invisible characters. Facebook also loses joiners that don't shape
+/// a conjunct, curly quotes and no-break spaces, which its editor mangles;
+/// old phones get a period for the dari and ASCII punctuation too. Blogger
+/// keeps the rest, as web pages show it faithfully.
+pub fn prepare(text: &str, profile: &str) -> String {
+    let strict = profile != "Blogger";
+    let ascii = profile == "Old phones (SMS)";
+    let chars: Vec<char> = text.nfc().filter(|c| !INVISIBLE.contains(c)).collect();
+    let mut prepared = String::with_capacity(text.len());
+    for (index, &c) in chars.iter().enumerate() {
+        match c {
+            // র‍্য and ক্‌ষ need theirs, next to a hasanta
+            ZWJ | ZWNJ if strict => {
+                let before = index.checked_sub(1).map(|i| chars[i]);
+                let after = chars.get(index + 1).copied();
+                if before == Some(HASANTA) || after == Some(HASANTA) {
+                    prepared.push(c);
+                }
+            }
+            '‘' | '’' if strict => prepared.push('\''),
+            '“' | '”' if strict => prepared.push('"'),
+            '\u{00A0}' | '\u{202F}' if strict => prepared.push(' '),
+            '।' if ascii => prepared.push('.'),
+            '–' | '—' if ascii => prepared.push('-'),
+            _ => prepared.push(c),
+        }
+    }
+    prepared
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_profile_composes_and_drops_invisible_characters() {
+        // য় written as য and a nukta, a zero width space and a BOM
+        let text = "\u{FEFF}বিয\u{09BC}ে\u{200B}বাড়ি";
+        for profile in PROFILES {
+            assert_eq!(prepare(text, profile), "বিয়েবাড়ি");
+        }
+    }
+
+    #[test]
+    fn facebook_keeps_only_joiners_that_shape_conjuncts() {
+        assert_eq!(prepare("র\u{200D}্যাব", "Facebook"), "র\u{200D}্যাব");
+        assert_eq!(prepare("ক্\u{200C}ষ", "Facebook"), "ক্\u{200C}ষ");
+        assert_eq!(prepare("আমি\u{200C} যাব", "Facebook"), "আমি যাব");
+        assert_eq!(prepare("আমি\u{200C} যাব", "Blogger"), "আমি\u{200C} যাব");
+    }
+
+    #[test]
+    fn punctuation_follows_the_profile() {
+        let text = "“ডঃ\u{00A0}রহমান” বললেন – ‘হ্যাঁ’।";
+        assert_eq!(prepare(text, "Blogger"), text);
+        assert_eq!(prepare(text, "Facebook"), "\"ডঃ রহমান\" বললেন – 'হ্যাঁ'।");
+        assert_eq!(
+            prepare(text, "Old phones (SMS)"),
+            "\"ডঃ রহমান\" বললেন - 'হ্যাঁ'."
+        );
+    }
+}
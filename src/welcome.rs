@@ -0,0 +1,62 @@
+use egui::{Color32, RichText};
+use restro_keyboard_core::Transliterator;
+use std::time::Duration;
+
+// Typed by the demo one key at a time, then shown whole for a moment
+const SAMPLE: &str = "ami banglay kotha boli";
+const KEY_INTERVAL: Duration = Duration::from_millis(300);
+const END_PAUSE: Duration = Duration::from_secs(2);
+
+/// Types the sample sentence over and over, showing each key, the Bangla
+/// it makes so far and the rule the engine used for the latest key.
+#[derive(Default)]
+pub struct TypingDemo {
+    // egui time when the current run started
+    started: Option<f64>,
+}
+
+impl TypingDemo {
+    pub fn restart(&mut self) {
+        self.started = None;
+    }
+
+    // Keys typed at `now`; runs start over after the pause
+    fn keys_typed(&mut self, now: f64) -> usize {
+        let total = SAMPLE.len();
+        let run = KEY_INTERVAL.as_secs_f64() * total as f64 + END_PAUSE.as_secs_f64();
+        let started = *self.started.get_or_insert(now);
+        let elapsed = (now - started) % run;
+        ((elapsed / KEY_INTERVAL.as_secs_f64()) as usize + 1).min(total)
+    }
+
+    pub fn show(&mut self, ui: &mut egui::Ui, engine: &Transliterator, font_size: f32) {
+        let now = ui.input(|i| i.time);
+        let typed = &SAMPLE[..self.keys_typed(now)];
+        ui.ctx().request_repaint_after(KEY_INTERVAL);
+
+        ui.horizontal(|ui| {
+            ui.label("Keys:");
+            let (earlier, last) = typed.split_at(typed.len() - 1);
+            ui.monospace(earlier);
+            ui.label(
+                RichText::new(if last == " " { "␣" } else { last })
+                    .monospace()
+                    .strong()
+                    .background_color(Color32::from_rgb(255, 230, 150)),
+            );
+        });
+        ui.label(
+            RichText::new(engine.transliterate_text(typed))
+                .size(font_size + 10.0)
+                .color(Color32::from_rgb(0, 100, 0)),
+        );
+
+        // What the latest key did to the word being typed
+        let word = typed.rsplit(' ').next().unwrap_or_default();
+        let explanation = match engine.convert(word).steps.last() {
+            Some(step) => format!("{} → {}: {}", step.roman, step.output, step.rule),
+            None => "Space ends the word".to_string(),
+        };
+        ui.label(RichText::new(explanation).weak().size(12.0));
+    }
+}
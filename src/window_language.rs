@@ -0,0 +1,117 @@
+use crate::state::{Event, Language};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use windows::Win32::Foundation::HWND;
+use windows::Win32::UI::WindowsAndMessaging::{
+    GetAncestor, GetWindowThreadProcessId, IsWindow, GA_ROOT,
+};
+
+// With the language kept per window, each top-level window gets back the
+// language it was left in, so switching to English in the browser leaves
+// the document in Bangla. Kept for the session only, as Windows reuses the
+// handles of closed windows. Our own windows don't count: choosing a
+// language in them or the tray menu sets it for the window before.
+
+#[derive(Default)]
+struct Windows {
+    // The window typing goes to, by handle, and its app
+    active: Option<(isize, String)>,
+    languages: HashMap<isize, Language>,
+}
+
+impl Windows {
+    /// Leaves the active window in `current` and makes `window` active.
+    /// Returns the language `window` was left in, if it was seen before.
+    fn switch(&mut self, window: isize, app: String, current: Language) -> Option<Language> {
+        if let Some((active, _)) = &self.active {
+            self.languages.insert(*active, current);
+        }
+        self.active = Some((window, app));
+        self.languages.get(&window).copied()
+    }
+}
+
+static WINDOWS: Mutex<Option<Windows>> = Mutex::new(None);
+
+fn with_windows<T>(f: impl FnOnce(&mut Windows) -> T) -> T {
+    f(WINDOWS.lock().unwrap().get_or_insert_with(Windows::default))
+}
+
+fn is_ours(window: HWND) -> bool {
+    let mut process_id = 0;
+    unsafe { GetWindowThreadProcessId(window, Some(&mut process_id)) };
+    process_id == std::process::id()
+}
+
+/// Gives the new foreground window the language it was left in, when the
+/// setting is on. Called from the focus watch on the hook thread.
+pub fn foreground_changed(window: HWND) {
+    let settings = crate::SETTINGS.snapshot();
+    if !settings.language_per_window {
+        *WINDOWS.lock().unwrap() = None;
+        return;
+    }
+    let window = unsafe { GetAncestor(window, GA_ROOT) };
+    if window.0 == 0 || is_ours(window) {
+        return;
+    }
+    let app = crate::problems::foreground_app();
+    let language = with_windows(|windows| {
+        windows
+            .languages
+            .retain(|handle, _| unsafe { IsWindow(HWND(*handle)) }.as_bool());
+        windows.switch(window.0, app, settings.state.language)
+    });
+    if let Some(language) = language.filter(|language| *language != settings.state.language) {
+        crate::SETTINGS.update(|settings| settings.state.apply(Event::SelectLanguage(language)));
+    }
+}
+
+/// The app of the window the current language belongs to, while the
+/// language is kept per window.
+pub fn active_app() -> Option<String> {
+    WINDOWS
+        .lock()
+        .unwrap()
+        .as_ref()
+        .and_then(|windows| windows.active.as_ref())
+        .map(|(_, app)| app.clone())
+}
+
+/// How many other windows have a language of their own.
+pub fn remembered() -> usize {
+    WINDOWS
+        .lock()
+        .unwrap()
+        .as_ref()
+        .map_or(0, |windows| windows.languages.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn each_window_gets_back_its_language() {
+        let mut windows = Windows::default();
+        // New windows keep the language in use
+        assert_eq!(
+            windows.switch(1, "WINWORD.EXE".to_string(), Language::Bangla),
+            None
+        );
+        assert_eq!(
+            windows.switch(2, "chrome.exe".to_string(), Language::Bangla),
+            None
+        );
+        // English was chosen in the browser
+        assert_eq!(
+            windows.switch(1, "WINWORD.EXE".to_string(), Language::English),
+            Some(Language::Bangla)
+        );
+        assert_eq!(
+            windows.switch(2, "chrome.exe".to_string(), Language::Bangla),
+            Some(Language::English)
+        );
+        assert_eq!(windows.active, Some((2, "chrome.exe".to_string())));
+    }
+}
@@ -0,0 +1,105 @@
+use crate::settings;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+// The keyboard's words shared with the Windows spell checker, so apps that
+// check spelling through it, such as Mail or Edge set to use the Windows
+// spellchecker, know the same Bangla words. Windows 8 and later read
+// Hunspell dictionaries from %APPDATA%\Microsoft\Spelling\<language>, so
+// no COM provider needs registering machine-wide: writing the files there
+// installs it, deleting them uninstalls it. They hold every spelling the
+// keyboard knows, an installed full dictionary's and the user's, and are
+// written again whenever those change.
+
+const LANGUAGE: &str = "bn-BD";
+
+// Writes take turns, each with the dictionary as it is by then
+static WRITING: Mutex<()> = Mutex::new(());
+
+/// `%APPDATA%\Microsoft\Spelling\bn-BD`, if APPDATA is set.
+pub fn dir() -> Option<PathBuf> {
+    let appdata = std::env::var_os("APPDATA")?;
+    Some(
+        PathBuf::from(appdata)
+            .join("Microsoft")
+            .join("Spelling")
+            .join(LANGUAGE),
+    )
+}
+
+fn files() -> Option<(PathBuf, PathBuf)> {
+    let dir = dir()?;
+    Some((
+        dir.join(format!("{}.dic", LANGUAGE)),
+        dir.join(format!("{}.aff", LANGUAGE)),
+    ))
+}
+
+pub fn is_installed() -> bool {
+    files().is_some_and(|(dic, _)| dic.exists())
+}
+
+// A Hunspell word list: the count, then a word per line
+fn dic(words: &[String]) -> String {
+    let mut text = format!("{}\n", words.len());
+    for word in words {
+        text.push_str(word);
+        text.push('\n');
+    }
+    text
+}
+
+fn write() -> io::Result<usize> {
+    let _turn = WRITING.lock().unwrap();
+    let (dic_path, aff_path) =
+        files().ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "APPDATA is not set"))?;
+    let words = crate::dictionary().spellings();
+    // No affixes: every form is a word of its own
+    settings::write_atomically(&aff_path, "SET UTF-8\n")?;
+    settings::write_atomically(&dic_path, dic(&words))?;
+    Ok(words.len())
+}
+
+/// Writes the dictionary files, returning how many words they hold.
+pub fn install() -> io::Result<usize> {
+    write()
+}
+
+/// Writes the files again with the current words, when installed. The
+/// dictionary may be large, so they are written by a thread of their own.
+pub fn refresh() {
+    if is_installed() {
+        std::thread::spawn(|| {
+            if let Err(err) = write() {
+                eprintln!("Could not update the Windows spelling dictionary: {}", err);
+            }
+        });
+    }
+}
+
+pub fn uninstall() -> io::Result<()> {
+    let _turn = WRITING.lock().unwrap();
+    let Some((dic, aff)) = files() else {
+        return Ok(());
+    };
+    for path in [dic, aff] {
+        if path.exists() {
+            fs::remove_file(path)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_word_list_starts_with_its_count() {
+        let words = ["আমি".to_string(), "তুমি".to_string()];
+        assert_eq!(dic(&words), "2\nআমি\nতুমি\n");
+        assert_eq!(dic(&[]), "0\n");
+    }
+}